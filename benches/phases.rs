@@ -0,0 +1,116 @@
+//! Where single-threaded transcoding time goes, phase by phase.
+//!
+//! Requires the `timing` feature: `cargo bench --bench phases --features timing`.
+//! For each compression level this prints each phase's share of the wall time,
+//! then benchmarks the total and each phase separately, so criterion reports
+//! the time per transcode spent parsing, resolving, and encoding.
+
+use std::io::{Cursor, Write};
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rebgzf::{
+    take_phase_times, CompressionLevel, PhaseTimes, SingleThreadedTranscoder, TranscodeConfig,
+    Transcoder,
+};
+
+/// Generate FASTQ-like data with pseudo-random bases and qualities
+fn generate_fastq_data(num_reads: usize, read_length: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut state = 42u32;
+    let mut next = |alphabet: &[u8]| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        alphabet[(state >> 16) as usize % alphabet.len()]
+    };
+    for read_num in 0..num_reads {
+        data.extend_from_slice(format!("@READ_{}\n", read_num).as_bytes());
+        data.extend((0..read_length).map(|_| next(b"ACGT")));
+        data.extend_from_slice(b"\n+\n");
+        data.extend((0..read_length).map(|_| next(b"#-7<AFJ")));
+        data.push(b'\n');
+    }
+    data
+}
+
+/// Picks one measurement out of a transcode's wall time and phase totals
+type Pick = fn(Duration, PhaseTimes) -> Duration;
+
+/// Transcode once, returning the wall time and the phase totals within it
+fn transcode_timed(gzip_data: &[u8], config: &TranscodeConfig) -> (Duration, PhaseTimes) {
+    take_phase_times();
+    let start = Instant::now();
+    let mut output = Vec::new();
+    SingleThreadedTranscoder::new(config.clone())
+        .transcode(Cursor::new(gzip_data), &mut output)
+        .unwrap();
+    let wall = start.elapsed();
+    (wall, take_phase_times())
+}
+
+fn print_breakdown(level: CompressionLevel, gzip_data: &[u8], config: &TranscodeConfig) {
+    let (mut wall, mut phases) = (Duration::ZERO, PhaseTimes::default());
+    for _ in 0..5 {
+        let (w, p) = transcode_timed(gzip_data, config);
+        wall += w;
+        phases.parse += p.parse;
+        phases.resolve += p.resolve;
+        phases.encode += p.encode;
+    }
+    let share = |d: Duration| 100.0 * d.as_secs_f64() / wall.as_secs_f64();
+    let other = wall.saturating_sub(phases.parse + phases.resolve + phases.encode);
+    eprintln!(
+        "{:?}: parse {:.1}%, resolve {:.1}%, encode {:.1}%, other {:.1}%",
+        level,
+        share(phases.parse),
+        share(phases.resolve),
+        share(phases.encode),
+        share(other)
+    );
+}
+
+fn bench_phases(c: &mut Criterion) {
+    let data = generate_fastq_data(20_000, 150);
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let gzip_data = encoder.finish().unwrap();
+
+    let mut group = c.benchmark_group("phases");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+
+    // Fixed Huffman (resolve is fused into encode) and dynamic Huffman
+    for level in [CompressionLevel::Level1, CompressionLevel::Level6] {
+        let config = TranscodeConfig { compression_level: level, ..Default::default() };
+        print_breakdown(level, &gzip_data, &config);
+
+        let phases: [(&str, Pick); 4] = [
+            ("total", |wall, _| wall),
+            ("parse", |_, p| p.parse),
+            ("resolve", |_, p| p.resolve),
+            ("encode", |_, p| p.encode),
+        ];
+        for (name, pick) in phases {
+            // Fused into encode, so there is nothing to measure
+            if name == "resolve" && level.use_fixed_huffman() {
+                continue;
+            }
+            let id = BenchmarkId::new(name, format!("{:?}", level));
+            group.bench_function(id, |b| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            let (wall, phases) = transcode_timed(&gzip_data, &config);
+                            pick(wall, phases)
+                        })
+                        .sum()
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_phases);
+criterion_main!(benches);
@@ -5,7 +5,9 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use rebgzf::{ParallelTranscoder, SingleThreadedTranscoder, TranscodeConfig, Transcoder};
+use rebgzf::{
+    CompressionLevel, ParallelTranscoder, SingleThreadedTranscoder, TranscodeConfig, Transcoder,
+};
 use std::io::{Cursor, Write};
 
 /// Generate random (incompressible) data
@@ -154,6 +156,71 @@ fn bench_parallel(c: &mut Criterion) {
     group.finish();
 }
 
+/// Parallel transcoding of small (4KB) blocks, one vs several blocks per worker job
+fn bench_blocks_per_job(c: &mut Criterion) {
+    let mut group = c.benchmark_group("blocks_per_job");
+
+    let size = 4 * 1024 * 1024;
+    let data = generate_dna_data(size);
+    let gzip_data = compress_to_gzip(&data, Compression::default());
+
+    group.throughput(Throughput::Bytes(size as u64));
+
+    for blocks_per_job in [1, 8] {
+        let config = TranscodeConfig {
+            block_size: 4096,
+            num_threads: 4,
+            blocks_per_job,
+            ..Default::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::new("batch", blocks_per_job),
+            &gzip_data,
+            |b, gzip_data| {
+                b.iter(|| {
+                    let mut transcoder = ParallelTranscoder::new(config.clone());
+                    let mut output = Vec::new();
+                    transcoder.transcode(Cursor::new(gzip_data), &mut output).unwrap();
+                    output
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Parallel transcoding of highly compressible data, where parsing outpaces
+/// encoding and the dispatcher spends most of its time on full job channels
+fn bench_backpressure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backpressure");
+
+    let size = 8 * 1024 * 1024;
+    let data = generate_repetitive_data(size);
+    let gzip_data = compress_to_gzip(&data, Compression::best());
+
+    group.throughput(Throughput::Bytes(size as u64));
+
+    for threads in [4, 16] {
+        let config = TranscodeConfig {
+            block_size: 4096,
+            compression_level: CompressionLevel::Level6,
+            num_threads: threads,
+            ..Default::default()
+        };
+        group.bench_with_input(BenchmarkId::new("threads", threads), &gzip_data, |b, gzip_data| {
+            b.iter(|| {
+                let mut transcoder = ParallelTranscoder::new(config.clone());
+                let mut output = Vec::new();
+                transcoder.transcode(Cursor::new(gzip_data), &mut output).unwrap();
+                output
+            });
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_data_patterns(c: &mut Criterion) {
     let mut group = c.benchmark_group("data_patterns");
     let size = 256 * 1024; // 256KB
@@ -230,6 +297,35 @@ fn bench_compression_levels(c: &mut Criterion) {
     group.finish();
 }
 
+/// Block size against level, backing `CompressionLevel::default_block_size`.
+fn bench_block_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_sizes");
+    let data = generate_fastq_data(5000, 150);
+    let gzip_data = compress_to_gzip(&data, Compression::default());
+
+    group.throughput(Throughput::Bytes(data.len() as u64));
+
+    for level in [1, 6, 9] {
+        for block_size in [16384, 32768, 65280] {
+            let config = TranscodeConfig {
+                block_size,
+                compression_level: CompressionLevel::from_level(level),
+                ..Default::default()
+            };
+            let transcode = || {
+                let mut transcoder = SingleThreadedTranscoder::new(config.clone());
+                let mut output = Vec::new();
+                transcoder.transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+                output
+            };
+            let id = BenchmarkId::new(format!("level{}", level), block_size);
+            group.bench_function(id, |b| b.iter(transcode));
+        }
+    }
+
+    group.finish();
+}
+
 fn bench_fastq_realistic(c: &mut Criterion) {
     let mut group = c.benchmark_group("fastq_realistic");
 
@@ -303,8 +399,11 @@ criterion_group!(
     benches,
     bench_single_threaded,
     bench_parallel,
+    bench_blocks_per_job,
+    bench_backpressure,
     bench_data_patterns,
     bench_compression_levels,
+    bench_block_sizes,
     bench_fastq_realistic,
     bench_bgzf_detection,
 );
@@ -0,0 +1,503 @@
+//! Block-level access to an existing BGZF stream.
+//!
+//! [`iter_blocks`] walks block headers and footers by following BSIZE, without
+//! inflating any DEFLATE data; useful for statistics, indexing, and structural
+//! checks. [`count_blocks`] does the same by seeking, reading only headers.
+//! [`inflate_block`] and [`repair_footers`] work on individual blocks.
+
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+use super::constants::{BGZF_EOF_SIZE, BGZF_FOOTER_SIZE, BGZF_HEADER_SIZE, BGZF_MAX_BLOCK_SIZE};
+use super::detector::{complete_bgzf_header, is_eof_block};
+use crate::error::{Error, Result};
+
+/// Location and sizes of a single BGZF block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// Byte offset of the block's gzip header in the stream
+    pub compressed_offset: u64,
+    /// Total block size including header and footer (BSIZE + 1)
+    pub compressed_size: u32,
+    /// Uncompressed size from the footer (ISIZE)
+    pub uncompressed_size: u32,
+}
+
+/// Iterator over the blocks of a BGZF stream.
+///
+/// Reads each header (18 bytes, plus any further extra subfields), skips the
+/// compressed payload, and reads the footer; no seeking is required so it
+/// works on pipes. Iteration stops at the end of the stream or after the
+/// first error.
+pub struct BgzfBlocks<R: Read> {
+    reader: R,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read> BgzfBlocks<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, offset: 0, done: false }
+    }
+
+    /// Byte offset just past the last block read
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn read_block(&mut self) -> Result<Option<BlockInfo>> {
+        let mut header = Vec::with_capacity(BGZF_HEADER_SIZE);
+        let block_size = match read_header(&mut self.reader, &mut header, self.offset)? {
+            Some(size) => size,
+            None => return Ok(None),
+        };
+
+        let payload = (block_size - header.len() - BGZF_FOOTER_SIZE) as u64;
+        let skipped = std::io::copy(&mut (&mut self.reader).take(payload), &mut std::io::sink())?;
+        if skipped < payload {
+            return Err(Error::UnexpectedEof);
+        }
+
+        let mut footer = [0u8; BGZF_FOOTER_SIZE];
+        read_exact(&mut self.reader, &mut footer)?;
+
+        let info = BlockInfo {
+            compressed_offset: self.offset,
+            compressed_size: block_size as u32,
+            uncompressed_size: u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]),
+        };
+        self.offset += block_size as u64;
+        Ok(Some(info))
+    }
+}
+
+impl<R: Read> Iterator for BgzfBlocks<R> {
+    type Item = Result<BlockInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_block() {
+            Ok(Some(info)) => Some(Ok(info)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterate over the blocks of a BGZF stream without decompressing them.
+pub fn iter_blocks<R: Read>(reader: R) -> BgzfBlocks<R> {
+    BgzfBlocks::new(reader)
+}
+
+/// Count the blocks of a BGZF stream by following BSIZE from header to header.
+///
+/// Only the headers are read; payloads and footers are skipped with a
+/// seek, so this is much cheaper than [`validate_bgzf_strict`](super::validate_bgzf_strict).
+/// A malformed header, or a final block that runs past the end of the stream,
+/// is an error.
+pub fn count_blocks<R: Read + Seek>(mut reader: R) -> Result<u64> {
+    let mut offset = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut count = 0u64;
+    let mut header = Vec::with_capacity(BGZF_HEADER_SIZE);
+    while let Some(block_size) = read_header(&mut reader, &mut header, offset)? {
+        offset += block_size as u64;
+        if offset > end {
+            return Err(Error::UnexpectedEof);
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Block structure of a BGZF stream, as checked by [`verify_structure`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StructureReport {
+    /// Number of complete blocks, including the EOF block
+    pub block_count: u64,
+    /// Total size of the complete blocks
+    pub compressed_size: u64,
+    /// Sum of the complete blocks' ISIZE fields
+    pub uncompressed_size: u64,
+    /// Whether the last complete block is the BGZF EOF block
+    pub has_eof: bool,
+    /// Whether the blocks chain exactly to the end of the stream, with no
+    /// partial block or trailing bytes left over
+    pub reached_end: bool,
+}
+
+impl StructureReport {
+    /// The stream is whole: its blocks end exactly at an EOF block
+    pub fn is_complete(&self) -> bool {
+        self.has_eof && self.reached_end
+    }
+}
+
+/// Check that a BGZF stream's blocks chain from header to header to the end
+/// of the stream, without decompressing anything.
+///
+/// Sits between [`is_bgzf`](super::is_bgzf), which looks only at the first
+/// header, and [`verify_bgzf`](super::verify_bgzf), which inflates every block
+/// and checks CRCs: each block costs a header read and a footer read, so this
+/// is a cheap way to tell whether a file was truncated. A block that runs past
+/// the end of the stream is not counted and leaves `reached_end` false; a
+/// malformed header is an error.
+pub fn verify_structure<R: Read + Seek>(mut reader: R) -> Result<StructureReport> {
+    let mut offset = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut report = StructureReport::default();
+    let mut header = Vec::with_capacity(BGZF_HEADER_SIZE);
+    loop {
+        let block_size = match read_header(&mut reader, &mut header, offset) {
+            Ok(Some(size)) => size as u64,
+            Ok(None) => {
+                report.reached_end = true;
+                break;
+            }
+            Err(Error::UnexpectedEof) => break,
+            Err(e) => return Err(e),
+        };
+        if offset + block_size > end {
+            break;
+        }
+
+        // ISIZE is the last field of the block; the EOF block is small enough to read whole
+        let isize = if block_size == BGZF_EOF_SIZE as u64 {
+            let mut block = [0u8; BGZF_EOF_SIZE];
+            reader.seek(SeekFrom::Start(offset))?;
+            read_exact(&mut reader, &mut block)?;
+            report.has_eof = is_eof_block(&block);
+            u32::from_le_bytes([block[24], block[25], block[26], block[27]])
+        } else {
+            let mut isize = [0u8; 4];
+            reader.seek(SeekFrom::Start(offset + block_size - 4))?;
+            read_exact(&mut reader, &mut isize)?;
+            report.has_eof = false;
+            u32::from_le_bytes(isize)
+        };
+
+        report.block_count += 1;
+        report.compressed_size += block_size;
+        report.uncompressed_size += isize as u64;
+        offset += block_size;
+    }
+    Ok(report)
+}
+
+/// Seek to a block located by [`iter_blocks`] and return its uncompressed bytes.
+///
+/// The CRC32 and ISIZE from the footer are checked against the inflated data.
+pub fn inflate_block<R: Read + Seek>(reader: &mut R, block: &BlockInfo) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(block.compressed_offset))?;
+    let mut bytes = vec![0u8; block.compressed_size as usize];
+    read_exact(reader, &mut bytes)?;
+    let mut decompressor = libdeflater::Decompressor::new();
+    inflate_block_bytes(&mut decompressor, &bytes, block.compressed_offset, block.uncompressed_size)
+}
+
+/// Inflate one whole block held in `bytes`, checking it against its footer.
+///
+/// `uncompressed_size` comes from the block's untrusted ISIZE, so anything over
+/// the 64 KiB a BGZF block can hold is an [`Error::BgzfBlockTooLarge`] before the
+/// output is allocated. `offset` is only used in error messages.
+pub(super) fn inflate_block_bytes(
+    decompressor: &mut libdeflater::Decompressor,
+    bytes: &[u8],
+    offset: u64,
+    uncompressed_size: u32,
+) -> Result<Vec<u8>> {
+    if uncompressed_size as usize > BGZF_MAX_BLOCK_SIZE {
+        return Err(Error::BgzfBlockTooLarge {
+            size: uncompressed_size as usize,
+            max: BGZF_MAX_BLOCK_SIZE,
+        });
+    }
+    // DEFLATE data follows the extra field and precedes the 8-byte footer
+    if bytes.len() < 12 + BGZF_FOOTER_SIZE {
+        return Err(Error::InvalidBgzfHeader { offset });
+    }
+    let xlen = u16::from_le_bytes([bytes[10], bytes[11]]) as usize;
+    let data_start = 12 + xlen;
+    let data_end = bytes.len() - BGZF_FOOTER_SIZE;
+    if data_start > data_end {
+        return Err(Error::InvalidBgzfHeader { offset });
+    }
+    let footer = &bytes[data_end..];
+    let expected_crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+
+    let mut output = vec![0u8; uncompressed_size as usize];
+    let size = decompressor
+        .deflate_decompress(&bytes[data_start..data_end], &mut output)
+        .map_err(|_| Error::BlockInflateFailed { offset })?;
+    if size != output.len() {
+        return Err(Error::SizeMismatch { expected: uncompressed_size, found: size as u32 });
+    }
+
+    let found = crc32fast::hash(&output);
+    if found != expected_crc {
+        return Err(Error::Crc32Mismatch { expected: expected_crc, found });
+    }
+    Ok(output)
+}
+
+/// Copy a BGZF stream, rewriting any block footer whose CRC32 or ISIZE is wrong.
+///
+/// Each block is inflated and its footer recomputed from the data; headers and
+/// compressed payloads are copied byte-for-byte. Returns the number of footers fixed.
+/// A block whose payload cannot be inflated is an error, since there is nothing
+/// trustworthy to recompute the footer from.
+pub fn repair_footers<R: Read, W: Write>(mut reader: R, mut writer: W) -> Result<u64> {
+    let mut decompressor = libdeflater::Decompressor::new();
+    let mut inflated = vec![0u8; BGZF_MAX_BLOCK_SIZE];
+    let mut block = Vec::new();
+    let mut offset = 0u64;
+    let mut fixed = 0u64;
+
+    loop {
+        let mut header = Vec::with_capacity(BGZF_HEADER_SIZE);
+        let block_size = match read_header(&mut reader, &mut header, offset)? {
+            Some(size) => size,
+            None => break,
+        };
+        block.clear();
+        block.extend_from_slice(&header);
+        block.resize(block_size, 0);
+        read_exact(&mut reader, &mut block[header.len()..])?;
+
+        let data_end = block_size - BGZF_FOOTER_SIZE;
+        let size = decompressor
+            .deflate_decompress(&block[header.len()..data_end], &mut inflated)
+            .map_err(|_| Error::BlockInflateFailed { offset })?;
+
+        let mut footer = [0u8; BGZF_FOOTER_SIZE];
+        footer[..4].copy_from_slice(&crc32fast::hash(&inflated[..size]).to_le_bytes());
+        footer[4..].copy_from_slice(&(size as u32).to_le_bytes());
+        if block[data_end..] != footer {
+            block[data_end..].copy_from_slice(&footer);
+            fixed += 1;
+        }
+
+        writer.write_all(&block)?;
+        offset += block_size as u64;
+    }
+
+    writer.flush()?;
+    Ok(fixed)
+}
+
+/// Read and validate a BGZF header into `header` (18 bytes, plus any extra
+/// subfields beyond them), returning the total block size (BSIZE + 1), or
+/// `None` at a clean end of stream.
+pub(super) fn read_header<R: Read>(
+    reader: &mut R,
+    header: &mut Vec<u8>,
+    offset: u64,
+) -> Result<Option<usize>> {
+    header.clear();
+    header.resize(BGZF_HEADER_SIZE, 0);
+    loop {
+        match reader.read(&mut header[..1]) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
+    read_exact(reader, &mut header[1..])?;
+
+    let parsed = complete_bgzf_header(reader, header).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+        _ => Error::Io(e),
+    })?;
+    let Some(parsed) = parsed else {
+        return Err(Error::InvalidBgzfHeader { offset });
+    };
+
+    let block_size = parsed.block_size;
+    if block_size < parsed.header_size + BGZF_FOOTER_SIZE {
+        return Err(Error::InvalidBgzfHeader { offset });
+    }
+    Ok(Some(block_size))
+}
+
+pub(super) fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+        _ => Error::Io(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::{BgzfBlockWriter, BGZF_EOF};
+    use std::io::Cursor;
+
+    fn two_block_stream() -> Vec<u8> {
+        let mut writer = BgzfBlockWriter::new(Vec::new());
+        let data = b"hello bgzf blocks";
+        // Single final stored block: BFINAL=1, BTYPE=00, then LEN/NLEN
+        let len = data.len() as u16;
+        let mut deflate = vec![0x01];
+        deflate.extend_from_slice(&len.to_le_bytes());
+        deflate.extend_from_slice(&(!len).to_le_bytes());
+        deflate.extend_from_slice(data);
+        writer.write_block(&deflate, data).unwrap();
+        writer.write_eof().unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_iter_blocks() {
+        let stream = two_block_stream();
+        let blocks: Vec<BlockInfo> =
+            iter_blocks(Cursor::new(&stream)).map(|b| b.unwrap()).collect();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].compressed_offset, 0);
+        assert_eq!(blocks[0].uncompressed_size, 17);
+        assert_eq!(blocks[1].compressed_offset, blocks[0].compressed_size as u64);
+        assert_eq!(blocks[1].compressed_size as usize, BGZF_EOF.len());
+        assert_eq!(blocks[1].uncompressed_size, 0);
+    }
+
+    #[test]
+    fn test_inflate_block() {
+        let stream = two_block_stream();
+        let blocks: Vec<BlockInfo> =
+            iter_blocks(Cursor::new(&stream)).map(|b| b.unwrap()).collect();
+        let mut reader = Cursor::new(&stream);
+
+        // Read out of order to exercise the seek
+        assert!(inflate_block(&mut reader, &blocks[1]).unwrap().is_empty());
+        assert_eq!(inflate_block(&mut reader, &blocks[0]).unwrap(), b"hello bgzf blocks");
+    }
+
+    #[test]
+    fn test_inflate_block_crc_mismatch() {
+        let mut stream = two_block_stream();
+        let first = iter_blocks(Cursor::new(&stream)).next().unwrap().unwrap();
+        stream[first.compressed_size as usize - 8] ^= 0xFF;
+
+        let result = inflate_block(&mut Cursor::new(&stream), &first);
+        assert!(matches!(result, Err(Error::Crc32Mismatch { .. })));
+    }
+
+    #[test]
+    fn test_inflate_block_rejects_oversized_isize() {
+        let mut stream = two_block_stream();
+        let mut first = iter_blocks(Cursor::new(&stream)).next().unwrap().unwrap();
+        let end = first.compressed_size as usize;
+        stream[end - 4..end].copy_from_slice(&(BGZF_MAX_BLOCK_SIZE as u32 + 1).to_le_bytes());
+        first.uncompressed_size = BGZF_MAX_BLOCK_SIZE as u32 + 1;
+
+        let result = inflate_block(&mut Cursor::new(&stream), &first);
+        assert!(matches!(result, Err(Error::BgzfBlockTooLarge { .. })), "{:?}", result);
+    }
+
+    #[test]
+    fn test_repair_footers() {
+        use crate::bgzf::verify_bgzf;
+
+        let original = two_block_stream();
+        let mut broken = original.clone();
+        let first = iter_blocks(Cursor::new(&broken)).next().unwrap().unwrap();
+        let footer = first.compressed_size as usize - BGZF_FOOTER_SIZE;
+        broken[footer..footer + 8].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef, 99, 0, 0, 0]);
+        assert!(!verify_bgzf(&mut Cursor::new(&broken)).unwrap().crc_valid);
+
+        let mut repaired = Vec::new();
+        assert_eq!(repair_footers(Cursor::new(&broken), &mut repaired).unwrap(), 1);
+        assert_eq!(repaired, original);
+        let verification = verify_bgzf(&mut Cursor::new(&repaired)).unwrap();
+        assert!(verification.crc_valid && verification.isize_valid);
+
+        // Nothing to fix on a valid stream
+        let mut copy = Vec::new();
+        assert_eq!(repair_footers(Cursor::new(&original), &mut copy).unwrap(), 0);
+        assert_eq!(copy, original);
+    }
+
+    #[test]
+    fn test_count_blocks() {
+        let stream = two_block_stream();
+        assert_eq!(count_blocks(Cursor::new(&stream)).unwrap(), 2);
+        assert_eq!(count_blocks(Cursor::new(&BGZF_EOF)).unwrap(), 1);
+        assert_eq!(count_blocks(Cursor::new(Vec::new())).unwrap(), 0);
+
+        // A block cut short is an error rather than being counted
+        let truncated = &stream[..stream.len() - 1];
+        assert!(matches!(count_blocks(Cursor::new(truncated)), Err(Error::UnexpectedEof)));
+
+        let mut corrupt = stream.clone();
+        corrupt[12] = b'X'; // BC subfield ID
+        assert!(matches!(
+            count_blocks(Cursor::new(&corrupt)),
+            Err(Error::InvalidBgzfHeader { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_iter_blocks_truncated() {
+        let stream = two_block_stream();
+        let truncated = &stream[..stream.len() - 10];
+        let results: Vec<Result<BlockInfo>> = iter_blocks(Cursor::new(truncated)).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::UnexpectedEof)));
+    }
+
+    #[test]
+    fn test_iter_blocks_invalid_header() {
+        let mut stream = two_block_stream();
+        let second = iter_blocks(Cursor::new(&stream)).next().unwrap().unwrap().compressed_size;
+        stream[second as usize + 12] = b'X'; // corrupt the BC subfield ID
+
+        let results: Vec<Result<BlockInfo>> = iter_blocks(Cursor::new(&stream)).collect();
+        assert_eq!(results.len(), 2);
+        assert!(
+            matches!(results[1], Err(Error::InvalidBgzfHeader { offset }) if offset == second as u64)
+        );
+    }
+
+    #[test]
+    fn test_verify_structure() {
+        let stream = two_block_stream();
+        let report = verify_structure(Cursor::new(&stream)).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.block_count, 2);
+        assert_eq!(report.compressed_size, stream.len() as u64);
+        assert_eq!(report.uncompressed_size, 17);
+
+        // Cut inside the EOF block: the data block is intact, the chain is not
+        let report = verify_structure(Cursor::new(&stream[..stream.len() - 5])).unwrap();
+        assert_eq!(report.block_count, 1);
+        assert!(!report.has_eof && !report.reached_end);
+
+        // Cut exactly between blocks: the chain ends cleanly, but without an EOF block
+        let first_len = stream.len() - BGZF_EOF_SIZE;
+        let report = verify_structure(Cursor::new(&stream[..first_len])).unwrap();
+        assert!(report.reached_end && !report.has_eof && !report.is_complete());
+
+        // Cut inside the first header
+        let report = verify_structure(Cursor::new(&stream[..10])).unwrap();
+        assert_eq!(report, StructureReport::default());
+
+        // Empty input has no blocks but nothing is cut short either
+        let report = verify_structure(Cursor::new(Vec::new())).unwrap();
+        assert!(report.reached_end && !report.is_complete());
+    }
+}
@@ -0,0 +1,61 @@
+//! Conversions between a BGZF block's total size and its `BSIZE` field.
+//!
+//! `BSIZE` stores the total block size (header, compressed data and footer)
+//! minus one, so the largest block, 65536 bytes, fits in 16 bits. Keep all
+//! of that arithmetic here rather than open-coding `+ 1` and `- 1`.
+
+use super::constants::MAX_BGZF_BLOCK_SIZE;
+use crate::error::{Error, Result};
+
+/// `BSIZE` for a block of `block_size` total bytes.
+///
+/// Fails for blocks over [`MAX_BGZF_BLOCK_SIZE`], whose `BSIZE` would not fit
+/// in 16 bits, and for an empty block.
+pub fn bsize_from_block_size(block_size: usize) -> Result<u16> {
+    match block_size {
+        0 => Err(Error::Internal("BGZF block size must be at least 1".into())),
+        _ => u16::try_from(block_size - 1)
+            .map_err(|_| Error::BgzfBlockTooLarge { size: block_size, max: MAX_BGZF_BLOCK_SIZE }),
+    }
+}
+
+/// Total size in bytes of a block whose header holds `bsize`
+pub fn block_size_from_bsize(bsize: u16) -> usize {
+    bsize as usize + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::{BGZF_EOF, BGZF_EOF_SIZE};
+
+    #[test]
+    fn test_bsize_round_trip() {
+        for bsize in 0..=u16::MAX {
+            let block_size = block_size_from_bsize(bsize);
+            assert_eq!(bsize_from_block_size(block_size).unwrap(), bsize);
+        }
+    }
+
+    #[test]
+    fn test_bsize_limits() {
+        assert_eq!(bsize_from_block_size(65535).unwrap(), 65534);
+        assert_eq!(bsize_from_block_size(MAX_BGZF_BLOCK_SIZE).unwrap(), u16::MAX);
+        assert_eq!(block_size_from_bsize(u16::MAX), MAX_BGZF_BLOCK_SIZE);
+        assert!(matches!(
+            bsize_from_block_size(MAX_BGZF_BLOCK_SIZE + 1),
+            Err(Error::BgzfBlockTooLarge { size: 65537, max: 65536 })
+        ));
+        assert!(bsize_from_block_size(usize::MAX).is_err());
+        assert!(bsize_from_block_size(0).is_err());
+        assert_eq!(bsize_from_block_size(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_eof_block_bsize() {
+        let bsize = u16::from_le_bytes([BGZF_EOF[16], BGZF_EOF[17]]);
+        assert_eq!(bsize, 27);
+        assert_eq!(block_size_from_bsize(bsize), BGZF_EOF_SIZE);
+        assert_eq!(bsize_from_block_size(BGZF_EOF_SIZE).unwrap(), bsize);
+    }
+}
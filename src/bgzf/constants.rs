@@ -13,8 +13,11 @@ pub const BGZF_FOOTER_SIZE: usize = 8;
 /// Maximum total BGZF block size
 pub const MAX_BGZF_BLOCK_SIZE: usize = 65536;
 
+/// Size of the BGZF EOF block
+pub const BGZF_EOF_SIZE: usize = 28;
+
 /// BGZF EOF block (28 bytes)
-pub const BGZF_EOF: [u8; 28] = [
+pub const BGZF_EOF: [u8; BGZF_EOF_SIZE] = [
     0x1f, 0x8b, 0x08, 0x04, // gzip magic, method, flags (FEXTRA)
     0x00, 0x00, 0x00, 0x00, // mtime
     0x00, 0xff, // xfl, os
@@ -0,0 +1,220 @@
+//! Coarse coordinate index for sorted VCF, built alongside the GZI.
+//!
+//! For each BGZF block in which a data line starts, the index records the
+//! chromosome and position (the first two tab-delimited columns) of the first
+//! such line. That is enough to seek near a region of a sorted VCF: find the
+//! last block starting at or before the region, then read forward. Full
+//! TBI/CSI binning is not attempted.
+//!
+//! The index is written as text, one block per line:
+//! `chrom<TAB>pos<TAB>compressed_offset<TAB>uncompressed_offset`.
+
+use std::io::{self, Write};
+
+use super::index::GziEntry;
+
+/// First data line starting in a BGZF block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockCoordinate {
+    /// Byte offset of the block in the BGZF file
+    pub compressed_offset: u64,
+    /// Uncompressed offset of the block's first byte
+    pub uncompressed_offset: u64,
+    /// CHROM column of the first data line starting in the block
+    pub chrom: String,
+    /// POS column of that line
+    pub pos: u64,
+}
+
+/// Write a coordinate index (e.g. `TranscodeStats::coordinate_index`) as text.
+pub fn write_coordinate_index<W: Write>(
+    coordinates: &[BlockCoordinate],
+    mut writer: W,
+) -> io::Result<()> {
+    for c in coordinates {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            c.chrom, c.pos, c.compressed_offset, c.uncompressed_offset
+        )?;
+    }
+    Ok(())
+}
+
+/// Finds VCF data line starts in resolved blocks as the transcoder produces them.
+///
+/// Only the first data line starting in each resolved block is kept; a block
+/// that the encoder later splits to fit the BGZF limit may therefore leave a
+/// BGZF block without a coordinate.
+#[derive(Debug)]
+pub(crate) struct CoordinateTracker {
+    /// Uncompressed bytes seen so far
+    offset: u64,
+    at_line_start: bool,
+    /// Data line being read: its start offset and its first two columns so far
+    line: Option<(u64, Vec<u8>)>,
+    /// Whether the current block already has a line start
+    block_has_start: bool,
+    /// Offset, CHROM, and POS of the line starts found
+    starts: Vec<(u64, String, u64)>,
+}
+
+impl CoordinateTracker {
+    pub fn new() -> Self {
+        Self {
+            offset: 0,
+            at_line_start: true,
+            line: None,
+            block_has_start: false,
+            starts: Vec::new(),
+        }
+    }
+
+    /// Scan the uncompressed bytes of the next block in the stream
+    pub fn add_block(&mut self, bytes: &[u8]) {
+        self.block_has_start = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            if self.at_line_start {
+                self.at_line_start = false;
+                // Header lines start with '#'; empty lines have no columns
+                if bytes[i] != b'#' && bytes[i] != b'\n' && !self.block_has_start {
+                    self.line = Some((self.offset + i as u64, Vec::new()));
+                    self.block_has_start = true;
+                }
+            }
+
+            let end = memchr::memchr(b'\n', &bytes[i..]).map(|n| i + n);
+            if let Some((_, columns)) = &mut self.line {
+                extend_columns(columns, &bytes[i..end.unwrap_or(bytes.len())]);
+            }
+            match end {
+                Some(end) => {
+                    self.end_line();
+                    self.at_line_start = true;
+                    i = end + 1;
+                }
+                None => i = bytes.len(),
+            }
+        }
+        self.offset += bytes.len() as u64;
+    }
+
+    /// Assign the line starts found to the blocks in `entries` (one per BGZF
+    /// block, as built with the GZI index).
+    pub fn finish(mut self, entries: &[GziEntry]) -> Vec<BlockCoordinate> {
+        // The last line may lack a newline
+        self.end_line();
+
+        let mut coordinates = Vec::new();
+        let mut starts = self.starts.into_iter().peekable();
+        for (i, entry) in entries.iter().enumerate() {
+            let block_end = entries.get(i + 1).map_or(u64::MAX, |e| e.uncompressed_offset);
+            // Skip starts before this block (only possible for malformed entries)
+            while starts.next_if(|s| s.0 < entry.uncompressed_offset).is_some() {}
+            if let Some((_, chrom, pos)) = starts.next_if(|s| s.0 < block_end) {
+                coordinates.push(BlockCoordinate {
+                    compressed_offset: entry.compressed_offset,
+                    uncompressed_offset: entry.uncompressed_offset,
+                    chrom,
+                    pos,
+                });
+            }
+            // Later starts in the same block came from a block the encoder split
+            while starts.next_if(|s| s.0 < block_end).is_some() {}
+        }
+        coordinates
+    }
+
+    /// Record the data line being read, if its first two columns parse
+    fn end_line(&mut self) {
+        let Some((start, columns)) = self.line.take() else {
+            return;
+        };
+        let mut fields = columns.split(|&b| b == b'\t');
+        let chrom = fields.next().and_then(|f| std::str::from_utf8(f).ok());
+        let pos = fields.next().and_then(|f| std::str::from_utf8(f).ok()?.trim_end().parse().ok());
+        if let (Some(chrom), Some(pos)) = (chrom, pos) {
+            if !chrom.is_empty() {
+                self.starts.push((start, chrom.to_string(), pos));
+            }
+        }
+    }
+}
+
+/// Append `segment` to a line's leading columns, keeping only CHROM and POS
+fn extend_columns(columns: &mut Vec<u8>, segment: &[u8]) {
+    let tabs = columns.iter().filter(|&&b| b == b'\t').count();
+    if tabs >= 2 {
+        return;
+    }
+    let mut seen = tabs;
+    let keep = segment
+        .iter()
+        .position(|&b| {
+            seen += (b == b'\t') as usize;
+            seen == 2
+        })
+        .unwrap_or(segment.len());
+    columns.extend_from_slice(&segment[..keep]);
+    if keep < segment.len() {
+        // Mark the columns as complete
+        columns.push(b'\t');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(offsets: &[u64]) -> Vec<GziEntry> {
+        offsets
+            .iter()
+            .map(|&u| GziEntry { compressed_offset: u * 10, uncompressed_offset: u })
+            .collect()
+    }
+
+    #[test]
+    fn test_tracker_lines_across_blocks() {
+        let vcf = b"##fileformat=VCFv4.2\n#CHROM\tPOS\tID\nchr1\t100\t.\tA\tC\nchr1\t250\t.\tG\tT\nchr2\t75\t.\tT\tA";
+        // Split mid-header, then inside each data line's CHROM or POS
+        let splits = [10, 38, 56, 71, vcf.len()];
+        let mut tracker = CoordinateTracker::new();
+        let mut start = 0;
+        let mut block_starts = Vec::new();
+        for &end in &splits {
+            block_starts.push(start as u64);
+            tracker.add_block(&vcf[start..end]);
+            start = end;
+        }
+        let coordinates = tracker.finish(&entries(&block_starts));
+
+        let found: Vec<(&str, u64, u64)> =
+            coordinates.iter().map(|c| (c.chrom.as_str(), c.pos, c.uncompressed_offset)).collect();
+        // Lines belong to the block they start in; the first and last blocks have none
+        assert_eq!(found, [("chr1", 100, 10), ("chr1", 250, 38), ("chr2", 75, 56)]);
+    }
+
+    #[test]
+    fn test_tracker_skips_malformed_lines() {
+        let mut tracker = CoordinateTracker::new();
+        tracker.add_block(b"\nchr1\tnot_a_number\nchr1\t5\n");
+        tracker.add_block(b"no tabs here\n");
+        // A block whose first data line is malformed gets no coordinate
+        let coordinates = tracker.finish(&entries(&[0, 27]));
+        assert!(coordinates.is_empty());
+    }
+
+    #[test]
+    fn test_write_coordinate_index() {
+        let coordinates = [BlockCoordinate {
+            compressed_offset: 0,
+            uncompressed_offset: 0,
+            chrom: "chr1".to_string(),
+            pos: 100,
+        }];
+        let mut out = Vec::new();
+        write_coordinate_index(&coordinates, &mut out).unwrap();
+        assert_eq!(out, b"chr1\t100\t0\t0\n");
+    }
+}
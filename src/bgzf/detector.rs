@@ -3,8 +3,12 @@
 //! Provides both quick detection (first block only) and strict validation
 //! (all blocks) for BGZF files.
 
+use crate::bgzf::blocks::BlockInfo;
+use crate::bgzf::bsize::block_size_from_bsize;
+use crate::bgzf::constants::{BGZF_EOF, BGZF_EOF_SIZE, BGZF_MAX_BLOCK_SIZE};
 use crate::error::{Error, Result};
-use std::io::{Read, Seek, SeekFrom};
+use crate::logging::debug;
+use std::io::{Read, Seek, SeekFrom, Write};
 
 /// Result of BGZF validation
 #[derive(Clone, Debug, Default)]
@@ -15,6 +19,12 @@ pub struct BgzfValidation {
     pub block_count: Option<u64>,
     /// Total uncompressed size across all blocks (only populated in strict mode)
     pub total_uncompressed_size: Option<u64>,
+    /// Whether validation ended at the canonical BGZF EOF block; a file without
+    /// one may be truncated (only meaningful in strict mode)
+    pub has_eof_block: bool,
+    /// Offset and sizes of each block read (only populated by
+    /// [`validate_bgzf_detailed`]); on invalid input, the blocks before the bad one
+    pub blocks: Option<Vec<BlockInfo>>,
 }
 
 /// Result of BGZF verification (deep validation with decompression)
@@ -34,8 +44,68 @@ pub struct BgzfVerification {
     pub uncompressed_size: u64,
     /// Block number where first error was found (if any)
     pub first_error_block: Option<u64>,
+    /// Compressed byte offset of the header of the block where the first error was found
+    pub first_error_offset: Option<u64>,
     /// Description of first error (if any)
     pub first_error: Option<String>,
+    /// Whether verification ended at a BGZF EOF block
+    pub has_eof_block: bool,
+    /// Every failing block, in stream order (only populated when
+    /// [`VerifyOptions::collect_all_errors`] is set)
+    pub errors: Vec<BlockError>,
+}
+
+impl BgzfVerification {
+    /// Record a failure in `block_index`, keeping the first one's description
+    fn record_error(
+        &mut self,
+        options: &VerifyOptions,
+        block_index: u64,
+        offset: u64,
+        kind: BlockErrorKind,
+        message: String,
+    ) {
+        if self.first_error.is_none() {
+            self.first_error_block = Some(block_index);
+            self.first_error_offset = Some(offset);
+            self.first_error = Some(message);
+        }
+        if options.collect_all_errors {
+            self.errors.push(BlockError { block_index, offset, kind });
+        }
+    }
+}
+
+/// Options for [`verify_bgzf_with_options`]
+#[derive(Clone, Debug, Default)]
+pub struct VerifyOptions {
+    /// List every failing block in [`BgzfVerification::errors`] (default: false,
+    /// only the first failure is described)
+    pub collect_all_errors: bool,
+}
+
+/// A block that failed verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockError {
+    /// Zero-based index of the block
+    pub block_index: u64,
+    /// Compressed byte offset of the block's header
+    pub offset: u64,
+    /// What was wrong with it
+    pub kind: BlockErrorKind,
+}
+
+/// Why a block failed verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockErrorKind {
+    /// Malformed header, impossible size, or data cut short; verification stops here
+    Structure,
+    /// The DEFLATE data could not be decompressed
+    Inflate,
+    /// Stored CRC32 does not match the decompressed data
+    Crc,
+    /// Stored ISIZE does not match the decompressed length
+    Isize,
 }
 
 /// BGZF header constants
@@ -44,26 +114,89 @@ const BGZF_SUBFIELD_ID: [u8; 2] = [b'B', b'C'];
 const FEXTRA_FLAG: u8 = 0x04;
 const MIN_HEADER_SIZE: usize = 18;
 
+/// Check whether `bytes` is exactly the canonical 28-byte BGZF EOF block.
+pub fn is_eof_block(bytes: &[u8]) -> bool {
+    bytes == BGZF_EOF
+}
+
 /// Quick check - only validates first block header.
 ///
 /// This is a fast O(1) check that reads the first 18+ bytes to verify
 /// the BGZF header signature is present.
 pub fn is_bgzf<R: Read>(reader: &mut R) -> Result<bool> {
-    let mut header = [0u8; MIN_HEADER_SIZE];
+    let mut header = vec![0u8; MIN_HEADER_SIZE];
 
-    match reader.read_exact(&mut header) {
-        Ok(()) => {}
-        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-            return Ok(false);
-        }
-        Err(e) => return Err(Error::Io(e)),
+    match reader.read_exact(&mut header).and_then(|()| complete_bgzf_header(reader, &mut header)) {
+        Ok(parsed) => Ok(parsed.is_some()),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(Error::Io(e)),
     }
+}
 
-    Ok(validate_bgzf_header(&header))
+/// Quick check on an in-memory buffer, such as a memory-mapped file: whether
+/// `data` starts with a BGZF block header.
+///
+/// The slice counterpart of [`is_bgzf`]; input too short to hold a whole header
+/// is not BGZF.
+pub fn is_bgzf_bytes(data: &[u8]) -> bool {
+    validate_bgzf_header_bytes(data)
+}
+
+/// Check that `header` begins with a complete BGZF block header: the gzip magic,
+/// DEFLATE method, FEXTRA flag, and an extra field holding a `BC` subfield.
+///
+/// Bytes after the header are ignored. Returns `false` rather than an error
+/// when `header` is too short.
+pub fn validate_bgzf_header_bytes(header: &[u8]) -> bool {
+    parse_bgzf_header(header).is_some()
+}
+
+/// Sizes taken from a BGZF block header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct BgzfHeader {
+    /// Header length: the fixed gzip fields plus the whole extra field (12 + XLEN)
+    pub header_size: usize,
+    /// Total block size including header and footer (BSIZE + 1)
+    pub block_size: usize,
+}
+
+/// Parse a complete BGZF header (at least 12 + XLEN bytes).
+///
+/// The `BC` subfield may appear anywhere among the extra subfields; returns
+/// `None` if the gzip markers are wrong, the extra field is malformed, or it
+/// has no `BC` subfield.
+pub(super) fn parse_bgzf_header(header: &[u8]) -> Option<BgzfHeader> {
+    if !has_bgzf_prefix(header) {
+        return None;
+    }
+    let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
+    let bsize = find_bsize(header.get(12..12 + xlen)?)?;
+    Some(BgzfHeader { header_size: 12 + xlen, block_size: block_size_from_bsize(bsize) })
+}
+
+/// Read the rest of a BGZF header whose first `MIN_HEADER_SIZE` bytes are in
+/// `header`, appending any extra-field bytes beyond them.
+///
+/// Returns `None`, without reading further, if those first bytes cannot start
+/// a BGZF header.
+pub(super) fn complete_bgzf_header<R: Read>(
+    reader: &mut R,
+    header: &mut Vec<u8>,
+) -> std::io::Result<Option<BgzfHeader>> {
+    if !has_bgzf_prefix(header) {
+        return Ok(None);
+    }
+    let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
+    let start = header.len();
+    if 12 + xlen > start {
+        header.resize(12 + xlen, 0);
+        reader.read_exact(&mut header[start..])?;
+    }
+    Ok(parse_bgzf_header(header))
 }
 
-/// Check if a header buffer contains valid BGZF header markers.
-fn validate_bgzf_header(header: &[u8]) -> bool {
+/// Check the fixed gzip fields: magic, DEFLATE, FEXTRA, and room for `BC`.
+fn has_bgzf_prefix(header: &[u8]) -> bool {
     if header.len() < MIN_HEADER_SIZE {
         return false;
     }
@@ -83,26 +216,24 @@ fn validate_bgzf_header(header: &[u8]) -> bool {
         return false;
     }
 
-    // Get XLEN (extra field length) at bytes 10-11
-    let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
-
     // We need at least 6 bytes for the BC subfield (2 ID + 2 LEN + 2 BSIZE)
-    if xlen < 6 {
-        return false;
-    }
-
-    // Check for BC subfield at bytes 12-13
-    if header[12..14] != BGZF_SUBFIELD_ID {
-        return false;
-    }
+    let xlen = u16::from_le_bytes([header[10], header[11]]) as usize;
+    xlen >= 6
+}
 
-    // Check BC subfield length is 2 (bytes 14-15)
-    let bc_len = u16::from_le_bytes([header[14], header[15]]);
-    if bc_len != 2 {
-        return false;
+/// Find the `BC` subfield in a gzip extra field and return its BSIZE.
+fn find_bsize(extra: &[u8]) -> Option<u16> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let id = &extra[pos..pos + 2];
+        let len = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        let data = extra.get(pos + 4..pos + 4 + len)?;
+        if id == BGZF_SUBFIELD_ID && len == 2 {
+            return Some(u16::from_le_bytes([data[0], data[1]]));
+        }
+        pos += 4 + len;
     }
-
-    true
+    None
 }
 
 /// Streaming validation - iterates all blocks without seeking.
@@ -112,7 +243,15 @@ fn validate_bgzf_header(header: &[u8]) -> bool {
 /// on non-seekable streams (stdin, pipes). It reads and discards block
 /// data rather than seeking.
 pub fn validate_bgzf_streaming<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
-    validate_bgzf_impl(reader)
+    validate_bgzf_impl(reader, false)
+}
+
+/// Streaming validation that also lists every block in [`BgzfValidation::blocks`].
+///
+/// The seek-free counterpart of [`validate_bgzf_detailed`], for pipes: block
+/// bodies are read and discarded, following each header's BSIZE.
+pub fn validate_bgzf_streaming_detailed<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
+    validate_bgzf_impl(reader, true)
 }
 
 /// Full validation - iterates all blocks (requires Seek).
@@ -121,10 +260,28 @@ pub fn validate_bgzf_streaming<R: Read>(reader: &mut R) -> Result<BgzfValidation
 /// and verifying the structure. It also counts blocks and accumulates
 /// uncompressed sizes. Seeks back to start when done.
 pub fn validate_bgzf_strict<R: Read + Seek>(reader: &mut R) -> Result<BgzfValidation> {
+    validate_seekable(reader, false)
+}
+
+/// Full validation that also lists every block in [`BgzfValidation::blocks`].
+///
+/// Like [`validate_bgzf_strict`], but records each block's offset and sizes,
+/// so a malformed file's bad block can be located (it starts where the last
+/// listed block ends). Seeks back to start when done.
+pub fn validate_bgzf_detailed<R: Read + Seek>(reader: &mut R) -> Result<BgzfValidation> {
+    validate_seekable(reader, true)
+}
+
+/// Validate from the start of `reader`, then seek back to it.
+fn validate_seekable<R: Read + Seek>(reader: &mut R, detailed: bool) -> Result<BgzfValidation> {
     // Start from beginning
     reader.seek(SeekFrom::Start(0))?;
 
-    let result = validate_bgzf_impl(reader)?;
+    let result = validate_bgzf_impl(reader, detailed)?;
+    debug!(
+        "strict BGZF validation: valid={} blocks={:?}",
+        result.is_valid_bgzf, result.block_count
+    );
 
     // Seek back to start for potential fast-path copy
     reader.seek(SeekFrom::Start(0))?;
@@ -132,13 +289,17 @@ pub fn validate_bgzf_strict<R: Read + Seek>(reader: &mut R) -> Result<BgzfValida
     Ok(result)
 }
 
-/// Internal validation implementation that works on any Read.
-fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
+/// Internal validation implementation that works on any Read, listing the
+/// blocks when `detailed`.
+fn validate_bgzf_impl<R: Read>(reader: &mut R, detailed: bool) -> Result<BgzfValidation> {
     let mut block_count: u64 = 0;
     let mut total_uncompressed_size: u64 = 0;
+    let mut offset: u64 = 0;
+    let mut blocks = detailed.then(Vec::new);
+    let mut has_eof_block = false;
 
     loop {
-        let mut header = [0u8; MIN_HEADER_SIZE];
+        let mut header = vec![0u8; MIN_HEADER_SIZE];
 
         match reader.read_exact(&mut header) {
             Ok(()) => {}
@@ -149,6 +310,8 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
                         is_valid_bgzf: false,
                         block_count: None,
                         total_uncompressed_size: None,
+                        has_eof_block: false,
+                        blocks: None,
                     });
                 }
                 break;
@@ -157,21 +320,20 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
         }
 
         // Validate this block's header
-        if !validate_bgzf_header(&header) {
+        let Some(parsed) = complete_bgzf_header(reader, &mut header)? else {
             return Ok(BgzfValidation {
                 is_valid_bgzf: false,
                 block_count: Some(block_count),
                 total_uncompressed_size: Some(total_uncompressed_size),
+                has_eof_block: false,
+                blocks,
             });
-        }
-
-        // Get BSIZE (total block size - 1) from bytes 16-17
-        let bsize = u16::from_le_bytes([header[16], header[17]]) as u64;
-        let block_size = bsize + 1;
+        };
+        let block_size = parsed.block_size as u64;
 
         // Calculate remaining bytes in this block
-        // We've read 18 bytes, need to read to end of block
-        let remaining = block_size.saturating_sub(MIN_HEADER_SIZE as u64);
+        // We've read the whole header, need to read to end of block
+        let remaining = block_size.saturating_sub(parsed.header_size as u64);
 
         // Footer is last 8 bytes: 4 bytes CRC32 + 4 bytes ISIZE
         if remaining < 8 {
@@ -180,12 +342,17 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
                 is_valid_bgzf: false,
                 block_count: Some(block_count),
                 total_uncompressed_size: Some(total_uncompressed_size),
+                has_eof_block: false,
+                blocks,
             });
         }
 
-        // Read and discard data until footer
+        // Read and discard data until footer, keeping it for a possible EOF block
         let skip_to_footer = remaining - 8;
-        if skip_to_footer > 0 {
+        let mut payload = Vec::new();
+        if block_size == BGZF_EOF_SIZE as u64 {
+            reader.take(skip_to_footer).read_to_end(&mut payload)?;
+        } else if skip_to_footer > 0 {
             std::io::copy(&mut reader.take(skip_to_footer), &mut std::io::sink())?;
         }
 
@@ -196,12 +363,21 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
         // Get ISIZE (uncompressed size) from last 4 bytes
         let isize = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
         total_uncompressed_size += isize as u64;
+        if let Some(blocks) = &mut blocks {
+            blocks.push(BlockInfo {
+                compressed_offset: offset,
+                compressed_size: block_size as u32,
+                uncompressed_size: isize,
+            });
+        }
+        offset += block_size;
 
         block_count += 1;
 
         // Check for EOF block (ISIZE = 0 and block_size = 28)
-        if isize == 0 && block_size == 28 {
+        if isize == 0 && block_size == BGZF_EOF_SIZE as u64 {
             // This is likely the EOF block, we're done
+            has_eof_block = is_eof_block(&[&header[..], &payload, &footer].concat());
             break;
         }
     }
@@ -210,6 +386,8 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
         is_valid_bgzf: true,
         block_count: Some(block_count),
         total_uncompressed_size: Some(total_uncompressed_size),
+        has_eof_block,
+        blocks,
     })
 }
 
@@ -224,6 +402,33 @@ fn validate_bgzf_impl<R: Read>(reader: &mut R) -> Result<BgzfValidation> {
 ///
 /// This is slower than `validate_bgzf_strict` but catches data corruption.
 pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
+    verify_bgzf_with(reader, |_| true)
+}
+
+/// Deep verification that reports each block to `on_block` as it is checked.
+///
+/// Behaves like [`verify_bgzf`], calling `on_block` after each block (including
+/// the EOF block) has been decompressed and its CRC32 checked. Return `false`
+/// from the callback to stop early; the result then covers the blocks seen so far.
+pub fn verify_bgzf_with<R: Read, F: FnMut(&BlockInfo) -> bool>(
+    reader: &mut R,
+    on_block: F,
+) -> Result<BgzfVerification> {
+    verify_bgzf_with_options(reader, &VerifyOptions::default(), on_block)
+}
+
+/// Deep verification with [`VerifyOptions`], reporting each block to `on_block`.
+///
+/// With `collect_all_errors`, every failing block is listed in
+/// [`BgzfVerification::errors`] rather than only the first being described.
+/// Checking continues past CRC32, ISIZE, and decompression failures either way;
+/// a structural error still ends verification, since the following block
+/// cannot be located.
+pub fn verify_bgzf_with_options<R: Read, F: FnMut(&BlockInfo) -> bool>(
+    reader: &mut R,
+    options: &VerifyOptions,
+    mut on_block: F,
+) -> Result<BgzfVerification> {
     let mut result = BgzfVerification {
         is_valid_bgzf: true,
         crc_valid: true,
@@ -231,8 +436,15 @@ pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
         ..Default::default()
     };
 
+    let mut decompressor = libdeflater::Decompressor::new();
     loop {
-        let mut header = [0u8; MIN_HEADER_SIZE];
+        // Blocks are contiguous, so this block starts where the previous ones ended
+        let block_offset = result.compressed_size;
+        let block_index = result.block_count;
+        let fail = |result: &mut BgzfVerification, kind: BlockErrorKind, message: String| {
+            result.record_error(options, block_index, block_offset, kind, message)
+        };
+        let mut header = vec![0u8; MIN_HEADER_SIZE];
 
         match reader.read_exact(&mut header) {
             Ok(()) => {}
@@ -247,41 +459,40 @@ pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
             Err(e) => return Err(Error::Io(e)),
         }
 
-        // Validate header
-        if !validate_bgzf_header(&header) {
-            result.is_valid_bgzf = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some("Invalid BGZF header".to_string());
+        // Validate header, reading any extra subfields beyond the first 18 bytes
+        let parsed = match complete_bgzf_header(reader, &mut header) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => {
+                result.is_valid_bgzf = false;
+                fail(&mut result, BlockErrorKind::Structure, "Invalid BGZF header".to_string());
+                break;
             }
-            break;
-        }
-
-        // Get BSIZE (total block size - 1) from bytes 16-17
-        let bsize = u16::from_le_bytes([header[16], header[17]]) as usize;
-        let block_size = bsize + 1;
+            Err(e) => {
+                result.is_valid_bgzf = false;
+                let message = format!("Failed to read block data: {}", e);
+                fail(&mut result, BlockErrorKind::Structure, message);
+                break;
+            }
+        };
+        let block_size = parsed.block_size;
+        let header_size = parsed.header_size;
         result.compressed_size += block_size as u64;
 
         // Calculate compressed data size (block_size - header - footer)
-        let compressed_data_size = block_size.saturating_sub(MIN_HEADER_SIZE + 8);
+        let compressed_data_size = block_size.saturating_sub(header_size + 8);
 
-        if compressed_data_size == 0 && block_size < MIN_HEADER_SIZE + 8 {
+        if compressed_data_size == 0 && block_size < header_size + 8 {
             result.is_valid_bgzf = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some("Block too small".to_string());
-            }
+            fail(&mut result, BlockErrorKind::Structure, "Block too small".to_string());
             break;
         }
 
-        // Read compressed data
+        // Read the compressed data
         let mut compressed_data = vec![0u8; compressed_data_size];
         if let Err(e) = reader.read_exact(&mut compressed_data) {
             result.is_valid_bgzf = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some(format!("Failed to read block data: {}", e));
-            }
+            let message = format!("Failed to read block data: {}", e);
+            fail(&mut result, BlockErrorKind::Structure, message);
             break;
         }
 
@@ -289,18 +500,34 @@ pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
         let mut footer = [0u8; 8];
         if let Err(e) = reader.read_exact(&mut footer) {
             result.is_valid_bgzf = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some(format!("Failed to read footer: {}", e));
-            }
+            fail(&mut result, BlockErrorKind::Structure, format!("Failed to read footer: {}", e));
             break;
         }
 
         let stored_crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
         let stored_isize = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+        let info = BlockInfo {
+            compressed_offset: block_offset,
+            compressed_size: block_size as u32,
+            uncompressed_size: stored_isize,
+        };
+
+        // ISIZE is untrusted, so check it before sizing the output by it
+        if stored_isize as usize > BGZF_MAX_BLOCK_SIZE {
+            result.isize_valid = false;
+            let message = format!(
+                "ISIZE {} exceeds the {} byte BGZF maximum",
+                stored_isize, BGZF_MAX_BLOCK_SIZE
+            );
+            fail(&mut result, BlockErrorKind::Isize, message);
+            result.block_count += 1;
+            if !on_block(&info) {
+                break;
+            }
+            continue;
+        }
 
         // Decompress data using libdeflate
-        let mut decompressor = libdeflater::Decompressor::new();
         let mut decompressed = vec![0u8; stored_isize as usize];
         match decompressor.deflate_decompress(&compressed_data, &mut decompressed) {
             Ok(actual_size) => {
@@ -308,11 +535,12 @@ pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
             }
             Err(e) => {
                 result.is_valid_bgzf = false;
-                if result.first_error.is_none() {
-                    result.first_error_block = Some(result.block_count);
-                    result.first_error = Some(format!("Decompression failed: {:?}", e));
-                }
+                let message = format!("Decompression failed: {:?}", e);
+                fail(&mut result, BlockErrorKind::Inflate, message);
                 result.block_count += 1;
+                if !on_block(&info) {
+                    break;
+                }
                 continue;
             }
         }
@@ -320,59 +548,122 @@ pub fn verify_bgzf<R: Read>(reader: &mut R) -> Result<BgzfVerification> {
         // Verify ISIZE
         if decompressed.len() as u32 != stored_isize {
             result.isize_valid = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some(format!(
-                    "ISIZE mismatch: stored {} but decompressed {} bytes",
-                    stored_isize,
-                    decompressed.len()
-                ));
-            }
+            let message = format!(
+                "ISIZE mismatch: stored {} but decompressed {} bytes",
+                stored_isize,
+                decompressed.len()
+            );
+            fail(&mut result, BlockErrorKind::Isize, message);
         }
 
         // Compute and verify CRC32
         let computed_crc = crc32fast::hash(&decompressed);
         if computed_crc != stored_crc {
             result.crc_valid = false;
-            if result.first_error.is_none() {
-                result.first_error_block = Some(result.block_count);
-                result.first_error = Some(format!(
-                    "CRC32 mismatch: stored {:08x} but computed {:08x}",
-                    stored_crc, computed_crc
-                ));
-            }
+            let message = format!(
+                "CRC32 mismatch: stored {:08x} but computed {:08x}",
+                stored_crc, computed_crc
+            );
+            fail(&mut result, BlockErrorKind::Crc, message);
         }
 
         result.uncompressed_size += decompressed.len() as u64;
         result.block_count += 1;
 
+        let keep_going = on_block(&info);
+
         // Check for EOF block
-        if stored_isize == 0 && block_size == 28 {
+        if stored_isize == 0 && block_size == BGZF_EOF_SIZE {
+            result.has_eof_block = true;
+            break;
+        }
+        if !keep_going {
             break;
         }
     }
 
+    if let Some(ref error) = result.first_error {
+        debug!(
+            "BGZF verification failed at block {:?} (offset {:?}): {}",
+            result.first_error_block, result.first_error_offset, error
+        );
+    }
+    Ok(result)
+}
+
+/// Deep verification that also copies every byte read to `writer`.
+///
+/// Behaves like [`verify_bgzf`], but forwards the input to `writer` as it is
+/// consumed so a single pass both checks and copies the stream. Any bytes that
+/// follow the point where verification stops (e.g. after the EOF block or the
+/// first structural error) are copied as well, so `writer` always receives the
+/// complete input.
+///
+/// Errors writing to `writer` are returned as [`Error::Io`] rather than being
+/// reported as verification failures.
+pub fn verify_bgzf_tee<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<BgzfVerification> {
+    let mut tee = TeeReader { reader, writer, write_error: None };
+    let result = verify_bgzf(&mut tee);
+    if let Some(e) = tee.write_error.take() {
+        return Err(Error::Io(e));
+    }
+    let result = result?;
+
+    std::io::copy(&mut tee, &mut std::io::sink())?;
+    if let Some(e) = tee.write_error.take() {
+        return Err(Error::Io(e));
+    }
+    tee.writer.flush()?;
+
     Ok(result)
 }
 
+/// Reader adapter that writes everything it reads to a secondary writer.
+pub(super) struct TeeReader<'a, R, W> {
+    pub reader: &'a mut R,
+    pub writer: &'a mut W,
+    /// First error from `writer`, kept so it is not mistaken for a read failure
+    pub write_error: Option<std::io::Error>,
+}
+
+impl<R: Read, W: Write> Read for TeeReader<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.write_error.is_some() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "tee writer failed"));
+        }
+        let n = self.reader.read(buf)?;
+        if let Err(e) = self.writer.write_all(&buf[..n]) {
+            self.write_error = Some(e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "tee writer failed"));
+        }
+        Ok(n)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Cursor;
 
-    // Valid BGZF EOF block (28 bytes)
-    const BGZF_EOF: [u8; 28] = [
-        0x1f, 0x8b, 0x08, 0x04, // gzip magic, method, flags (FEXTRA)
-        0x00, 0x00, 0x00, 0x00, // mtime
-        0x00, 0xff, // xfl, os
-        0x06, 0x00, // xlen = 6
-        0x42, 0x43, // subfield ID "BC"
-        0x02, 0x00, // subfield length = 2
-        0x1b, 0x00, // BSIZE = 27 (28 - 1)
-        0x03, 0x00, // empty deflate block
-        0x00, 0x00, 0x00, 0x00, // CRC32 = 0
-        0x00, 0x00, 0x00, 0x00, // ISIZE = 0
-    ];
+    #[test]
+    fn test_is_eof_block() {
+        assert!(is_eof_block(&BGZF_EOF));
+
+        // Blocks of the same length with any other content are not the terminator
+        let mut data_block = BGZF_EOF;
+        data_block[24..28].copy_from_slice(&1u32.to_le_bytes()); // ISIZE = 1
+        assert!(!is_eof_block(&data_block));
+
+        let mut unix_os = BGZF_EOF;
+        unix_os[9] = 0x03;
+        assert!(!is_eof_block(&unix_os));
+
+        assert!(!is_eof_block(&BGZF_EOF[..27]));
+        assert!(!is_eof_block(&[]));
+    }
 
     #[test]
     fn test_is_bgzf_with_eof_block() {
@@ -406,6 +697,100 @@ mod tests {
         assert!(!is_bgzf(&mut cursor).unwrap());
     }
 
+    /// A BGZF block holding "hi" whose extra field has a dummy subfield before `BC`
+    fn block_with_leading_subfield() -> Vec<u8> {
+        let deflate = [0x01, 0x02, 0x00, 0xfd, 0xff, b'h', b'i']; // final stored block
+        let xlen = 7 + 6;
+        let block_size = 12 + xlen + deflate.len() + 8;
+
+        let mut block = vec![0x1f, 0x8b, 0x08, 0x04, 0, 0, 0, 0, 0, 0xff];
+        block.extend_from_slice(&(xlen as u16).to_le_bytes());
+        block.extend_from_slice(&[b'X', b'Y', 3, 0, 1, 2, 3]);
+        block.extend_from_slice(&[b'B', b'C', 2, 0]);
+        block.extend_from_slice(&(block_size as u16 - 1).to_le_bytes());
+        block.extend_from_slice(&deflate);
+        block.extend_from_slice(&crc32fast::hash(b"hi").to_le_bytes());
+        block.extend_from_slice(&2u32.to_le_bytes());
+        assert_eq!(block.len(), block_size);
+        block
+    }
+
+    #[test]
+    fn test_bc_subfield_after_other_subfield() {
+        let mut data = block_with_leading_subfield();
+        data.extend_from_slice(&BGZF_EOF);
+
+        assert!(is_bgzf(&mut Cursor::new(&data)).unwrap());
+
+        let validation = validate_bgzf_streaming(&mut Cursor::new(&data)).unwrap();
+        assert!(validation.is_valid_bgzf);
+        assert_eq!(validation.block_count, Some(2));
+        assert_eq!(validation.total_uncompressed_size, Some(2));
+
+        let verification = verify_bgzf(&mut Cursor::new(&data)).unwrap();
+        assert!(verification.is_valid_bgzf && verification.crc_valid, "{:?}", verification);
+        assert_eq!(verification.compressed_size, data.len() as u64);
+
+        let sizes: Vec<_> = crate::bgzf::iter_blocks(Cursor::new(&data))
+            .map(|b| b.unwrap().compressed_size as usize)
+            .collect();
+        assert_eq!(sizes, [data.len() - BGZF_EOF_SIZE, BGZF_EOF_SIZE]);
+    }
+
+    #[test]
+    fn test_extra_field_without_bc_is_not_bgzf() {
+        let mut data = block_with_leading_subfield();
+        data[20] = b'Z'; // Rename BC
+        assert!(!is_bgzf(&mut Cursor::new(&data)).unwrap());
+
+        // A subfield claiming more bytes than XLEN holds is malformed
+        let mut data = block_with_leading_subfield();
+        data[14] = 40;
+        assert!(!is_bgzf(&mut Cursor::new(&data)).unwrap());
+    }
+
+    #[test]
+    fn test_header_checks_on_slices() {
+        assert!(is_bgzf_bytes(&BGZF_EOF));
+        assert!(validate_bgzf_header_bytes(&BGZF_EOF[..MIN_HEADER_SIZE]));
+
+        let data = block_with_leading_subfield();
+        assert!(is_bgzf_bytes(&data));
+        // The whole extra field must be present
+        assert!(!validate_bgzf_header_bytes(&data[..24]));
+        assert!(validate_bgzf_header_bytes(&data[..25]));
+
+        assert!(!is_bgzf_bytes(&[]));
+        assert!(!is_bgzf_bytes(&BGZF_EOF[..MIN_HEADER_SIZE - 1]));
+        assert!(!is_bgzf_bytes(&[0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0, 0xff]));
+    }
+
+    #[test]
+    fn test_validate_detailed_lists_blocks() {
+        let block = block_with_leading_subfield();
+        let mut data = [&block[..], &block[..], &BGZF_EOF[..]].concat();
+
+        let validation = validate_bgzf_detailed(&mut Cursor::new(&data)).unwrap();
+        assert!(validation.is_valid_bgzf);
+        let size = block.len() as u32;
+        let info = |offset, compressed_size, uncompressed_size| BlockInfo {
+            compressed_offset: offset,
+            compressed_size,
+            uncompressed_size,
+        };
+        assert_eq!(
+            validation.blocks.unwrap(),
+            [info(0, size, 2), info(size as u64, size, 2), info(2 * size as u64, 28, 0)]
+        );
+        assert!(validate_bgzf_strict(&mut Cursor::new(&data)).unwrap().blocks.is_none());
+
+        // A bad second block: only the first is listed, so the bad one starts after it
+        data[block.len()] = 0;
+        let validation = validate_bgzf_detailed(&mut Cursor::new(&data)).unwrap();
+        assert!(!validation.is_valid_bgzf);
+        assert_eq!(validation.blocks.unwrap(), [info(0, size, 2)]);
+    }
+
     #[test]
     fn test_validate_strict_eof_only() {
         let mut cursor = Cursor::new(&BGZF_EOF);
@@ -414,6 +799,26 @@ mod tests {
         assert!(result.is_valid_bgzf);
         assert_eq!(result.block_count, Some(1));
         assert_eq!(result.total_uncompressed_size, Some(0));
+        assert!(result.has_eof_block);
+    }
+
+    #[test]
+    fn test_validate_strict_reports_missing_eof() {
+        // Truncated before the EOF block: the blocks present are still valid
+        let block = block_with_leading_subfield();
+        let result = validate_bgzf_strict(&mut Cursor::new(&block)).unwrap();
+        assert!(result.is_valid_bgzf && !result.has_eof_block);
+
+        // An empty 28-byte block that differs from the canonical marker ends
+        // validation but is not the EOF block
+        let mut unix_os = BGZF_EOF;
+        unix_os[9] = 0x03;
+        let data = [&block[..], &unix_os[..]].concat();
+        let result = validate_bgzf_streaming(&mut Cursor::new(&data)).unwrap();
+        assert!(result.is_valid_bgzf && !result.has_eof_block);
+
+        let data = [&block[..], &BGZF_EOF[..]].concat();
+        assert!(validate_bgzf_streaming(&mut Cursor::new(&data)).unwrap().has_eof_block);
     }
 
     #[test]
@@ -10,7 +10,13 @@
 //!   - Compressed offset: u64 (little-endian)
 //!   - Uncompressed offset: u64 (little-endian)
 
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
+
+use super::blocks::iter_blocks;
+use super::constants::BGZF_MAX_BLOCK_SIZE;
+use super::detector::TeeReader;
+use crate::error::{Error, Result};
+use crate::TranscodeStats;
 
 /// An entry in the GZI index mapping compressed to uncompressed offset.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -29,6 +35,9 @@ pub struct GziIndexBuilder {
     entries: Vec<GziEntry>,
     current_compressed_offset: u64,
     current_uncompressed_offset: u64,
+    /// Set by [`read`](Self::read): the last block's size is unknown, so the
+    /// current offsets are its start rather than the end of the data.
+    open_ended: bool,
 }
 
 impl GziIndexBuilder {
@@ -37,6 +46,26 @@ impl GziIndexBuilder {
         Self::default()
     }
 
+    /// Index an existing BGZF stream by reading it sequentially, e.g. from a pipe.
+    ///
+    /// Every block is read in full (header, payload, and footer) to learn BSIZE
+    /// and ISIZE, but nothing is decompressed. Empty blocks, such as the EOF
+    /// block or the EOF blocks left inside concatenated BGZF files, advance the
+    /// compressed offset without an entry, so the entries match those written
+    /// while transcoding and the builder ends at the stream's total sizes.
+    pub fn build_from_stream<R: Read>(reader: R) -> Result<Self> {
+        let mut builder = Self::new();
+        for block in iter_blocks(reader) {
+            let block = block?;
+            if block.uncompressed_size > 0 {
+                builder.add_block(block.compressed_size as u64, block.uncompressed_size as u64);
+            } else {
+                builder.current_compressed_offset += block.compressed_size as u64;
+            }
+        }
+        Ok(builder)
+    }
+
     /// Record the start of a new BGZF block.
     ///
     /// Call this before writing each block to record its position.
@@ -77,20 +106,95 @@ impl GziIndexBuilder {
         &self.entries
     }
 
+    /// Load an existing GZI index, e.g. one written with `--index`.
+    ///
+    /// Unlike [`read_gzi`], the whole index is checked: the declared entry count
+    /// must match the file's size exactly, and offsets must increase from entry
+    /// to entry. Indexes written by `bgzip` leave out the first block's `(0, 0)`
+    /// entry; it is not added here. Since a GZI does not record the size of the
+    /// last block, the builder's current offsets are those of the last entry,
+    /// and [`block_containing`](Self::block_containing) treats the last block
+    /// as holding up to a full BGZF block.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let count_field = bytes.get(..8).ok_or_else(|| {
+            Error::InvalidGzi(format!("{} bytes is too short for the entry count", bytes.len()))
+        })?;
+        let count = u64::from_le_bytes(count_field.try_into().expect("8 bytes"));
+        let expected = count.checked_mul(16).and_then(|n| n.checked_add(8));
+        if expected != Some(bytes.len() as u64) {
+            return Err(Error::InvalidGzi(format!(
+                "declares {} entries but holds {} bytes of entries",
+                count,
+                bytes.len() - 8
+            )));
+        }
+
+        let entries = read_gzi(&bytes[..])?;
+        for (i, pair) in entries.windows(2).enumerate() {
+            if pair[1].compressed_offset <= pair[0].compressed_offset
+                || pair[1].uncompressed_offset < pair[0].uncompressed_offset
+            {
+                return Err(Error::InvalidGzi(format!("entry {} goes backwards", i + 1)));
+            }
+        }
+
+        let last = entries
+            .last()
+            .copied()
+            .unwrap_or(GziEntry { compressed_offset: 0, uncompressed_offset: 0 });
+        Ok(Self {
+            entries,
+            current_compressed_offset: last.compressed_offset,
+            current_uncompressed_offset: last.uncompressed_offset,
+            open_ended: true,
+        })
+    }
+
+    /// Entry of the block holding uncompressed byte `uncompressed_pos`.
+    ///
+    /// Returns `None` at or past [`uncompressed_offset`](Self::uncompressed_offset),
+    /// the end of the indexed data. For an index loaded with [`read`](Self::read)
+    /// that is the start of the last block, whose size the GZI does not record,
+    /// so positions up to [`BGZF_MAX_BLOCK_SIZE`] bytes past it map to that block.
+    /// A leading `(0, 0)` entry is implied, as in indexes written by `bgzip`.
+    pub fn block_containing(&self, uncompressed_pos: u64) -> Option<GziEntry> {
+        let end = if self.open_ended {
+            self.current_uncompressed_offset + BGZF_MAX_BLOCK_SIZE as u64
+        } else {
+            self.current_uncompressed_offset
+        };
+        if uncompressed_pos >= end {
+            return None;
+        }
+        let index = self.entries.partition_point(|e| e.uncompressed_offset <= uncompressed_pos);
+        Some(match index {
+            0 => GziEntry { compressed_offset: 0, uncompressed_offset: 0 },
+            _ => self.entries[index - 1],
+        })
+    }
+
+    /// BGZF virtual offset of uncompressed byte `uncompressed_pos`: the block's
+    /// compressed offset shifted left 16 bits, plus the offset within the block.
+    ///
+    /// Returns `None` where [`block_containing`](Self::block_containing) does.
+    pub fn virtual_offset_for(&self, uncompressed_pos: u64) -> Option<u64> {
+        let block = self.block_containing(uncompressed_pos)?;
+        Some(block.compressed_offset << 16 | (uncompressed_pos - block.uncompressed_offset))
+    }
+
     /// Write the GZI index to a writer.
     ///
     /// Format: number of entries (u64 LE), then pairs of (compressed, uncompressed) offsets.
-    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        // Write number of entries
-        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
-
-        // Write each entry
-        for entry in &self.entries {
-            writer.write_all(&entry.compressed_offset.to_le_bytes())?;
-            writer.write_all(&entry.uncompressed_offset.to_le_bytes())?;
-        }
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_gzi(&self.entries, writer)
+    }
 
-        Ok(())
+    /// Write the GZI index in the layout `bgzip -i` writes, which htslib
+    /// (`samtools`, `tabix`) loads. See [`write_gzi_htslib`].
+    pub fn write_htslib<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_gzi_htslib(&self.entries, writer)
     }
 
     /// Reset the builder for reuse.
@@ -98,7 +202,118 @@ impl GziIndexBuilder {
         self.entries.clear();
         self.current_compressed_offset = 0;
         self.current_uncompressed_offset = 0;
+        self.open_ended = false;
+    }
+}
+
+/// Write index entries (e.g. `TranscodeStats::index_entries`) in GZI format.
+pub fn write_gzi<W: Write>(entries: &[GziEntry], mut writer: W) -> io::Result<()> {
+    // Write number of entries
+    writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+
+    // Write each entry
+    for entry in entries {
+        writer.write_all(&entry.compressed_offset.to_le_bytes())?;
+        writer.write_all(&entry.uncompressed_offset.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Write index entries in GZI format as `bgzip -i` does.
+///
+/// htslib implies the first block's `(0, 0)` entry, so it is left out along with
+/// its share of the count. [`read_gzi`] and [`IndexedReader`](super::IndexedReader)
+/// accept both layouts.
+pub fn write_gzi_htslib<W: Write>(entries: &[GziEntry], writer: W) -> io::Result<()> {
+    let first = GziEntry { compressed_offset: 0, uncompressed_offset: 0 };
+    let entries = entries.strip_prefix(&[first]).unwrap_or(entries);
+    write_gzi(entries, writer)
+}
+
+/// Read index entries in GZI format, as written by [`write_gzi`] or `bgzip -i`.
+///
+/// `bgzip` leaves out the first block's `(0, 0)` entry; it is not added here.
+pub fn read_gzi<R: Read>(mut reader: R) -> Result<Vec<GziEntry>> {
+    let mut field = [0u8; 8];
+    read_field(&mut reader, &mut field)?;
+    let count = u64::from_le_bytes(field);
+
+    // The count comes from the file, so grow as entries actually arrive
+    let mut entries = Vec::with_capacity(count.min(1 << 16) as usize);
+    for _ in 0..count {
+        read_field(&mut reader, &mut field)?;
+        let compressed_offset = u64::from_le_bytes(field);
+        read_field(&mut reader, &mut field)?;
+        let uncompressed_offset = u64::from_le_bytes(field);
+        entries.push(GziEntry { compressed_offset, uncompressed_offset });
+    }
+    Ok(entries)
+}
+
+fn read_field<R: Read>(reader: &mut R, field: &mut [u8; 8]) -> Result<()> {
+    reader.read_exact(field).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+        _ => Error::Io(e),
+    })
+}
+
+/// Build GZI entries from the blocks of an existing BGZF stream, without decompressing.
+///
+/// Has one entry per non-empty block, like the index written while transcoding,
+/// so the EOF marker is not indexed.
+pub fn index_bgzf<R: Read>(reader: R) -> Result<Vec<GziEntry>> {
+    Ok(GziIndexBuilder::build_from_stream(reader)?.entries)
+}
+
+/// Copy a BGZF stream through unchanged, indexing its existing blocks on the way.
+///
+/// This keeps the original block boundaries and is much faster than re-transcoding.
+/// The returned stats have `copied_directly` set and `index_entries` populated.
+pub fn copy_with_index<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<TranscodeStats> {
+    let mut tee = TeeReader { reader, writer, write_error: None };
+    let mut stats = TranscodeStats { copied_directly: true, ..Default::default() };
+    let mut builder = GziIndexBuilder::new();
+
+    let mut blocks = iter_blocks(&mut tee);
+    for block in &mut blocks {
+        let block = match block {
+            Ok(block) => block,
+            Err(e) => return Err(tee_error(&mut tee).unwrap_or(e)),
+        };
+        let size = block.uncompressed_size;
+        if size == 0 {
+            builder.current_compressed_offset += block.compressed_size as u64;
+            continue;
+        }
+        builder.add_block(block.compressed_size as u64, size as u64);
+        stats.uncompressed_bytes += size as u64;
+        if stats.blocks_written == 0 || size < stats.min_block_uncompressed {
+            stats.min_block_uncompressed = size;
+        }
+        stats.max_block_uncompressed = stats.max_block_uncompressed.max(size);
+        stats.blocks_written += 1;
+    }
+
+    // Trailing bytes after the last block are copied as well
+    let copied = blocks.offset() + io::copy(&mut tee, &mut io::sink())?;
+    if let Some(e) = tee_error(&mut tee) {
+        return Err(e);
     }
+    tee.writer.flush()?;
+
+    stats.input_bytes = copied;
+    stats.output_bytes = copied;
+    stats.index_entries = Some(builder.entries);
+    Ok(stats)
+}
+
+/// A write failure on the tee, which takes precedence over the read error it caused
+fn tee_error<R, W>(tee: &mut TeeReader<'_, R, W>) -> Option<Error> {
+    tee.write_error.take().map(Error::Io)
 }
 
 #[cfg(test)]
@@ -127,6 +342,187 @@ mod tests {
         assert_eq!(entries[2].uncompressed_offset, 3000);
     }
 
+    #[test]
+    fn test_gzi_builder_offsets_past_4gib() {
+        // ~4.3 GiB of full-size blocks; offsets must not wrap at u32::MAX
+        let blocks = 65_600u64;
+        let mut builder = GziIndexBuilder::new();
+        for _ in 0..blocks {
+            builder.add_block(20_000, 65_536);
+        }
+
+        assert_eq!(builder.uncompressed_offset(), blocks * 65_536);
+        assert!(builder.uncompressed_offset() > u32::MAX as u64);
+        let last = builder.entries()[builder.len() - 1];
+        assert_eq!(last.uncompressed_offset, (blocks - 1) * 65_536);
+        assert_eq!(last.compressed_offset, (blocks - 1) * 20_000);
+    }
+
+    #[test]
+    fn test_build_from_stream() {
+        use crate::transcoder::single::SingleThreadedTranscoder;
+        use crate::{TranscodeConfig, Transcoder};
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Cursor;
+
+        let data: Vec<u8> =
+            (0..20_000u32).flat_map(|i| format!("record {}\n", i * 31).into_bytes()).collect();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let config = TranscodeConfig { block_size: 8192, build_index: true, ..Default::default() };
+        let mut bgzf = Vec::new();
+        let stats =
+            SingleThreadedTranscoder::new(config).transcode(Cursor::new(gz), &mut bgzf).unwrap();
+        let expected = stats.index_entries.unwrap();
+        assert!(expected.len() > 10);
+
+        // Matches the index built while transcoding, and covers the EOF block
+        let builder = GziIndexBuilder::build_from_stream(Cursor::new(&bgzf)).unwrap();
+        assert_eq!(builder.entries(), &expected[..]);
+        assert_eq!(builder.compressed_offset(), bgzf.len() as u64);
+        assert_eq!(builder.uncompressed_offset(), data.len() as u64);
+
+        // Concatenated files keep going past the inner EOF block
+        let doubled = [&bgzf[..], &bgzf[..]].concat();
+        let builder = GziIndexBuilder::build_from_stream(&doubled[..]).unwrap();
+        assert_eq!(builder.len(), 2 * expected.len());
+        assert_eq!(builder.entries()[expected.len()].compressed_offset, bgzf.len() as u64);
+        assert_eq!(builder.entries()[expected.len()].uncompressed_offset, data.len() as u64);
+
+        // A truncated stream is an error
+        let truncated = &bgzf[..bgzf.len() - 40];
+        assert!(GziIndexBuilder::build_from_stream(truncated).is_err());
+    }
+
+    #[test]
+    fn test_read_gzi_round_trip() {
+        let mut builder = GziIndexBuilder::new();
+        builder.add_block(100, 1000);
+        builder.add_block(200, 2000);
+        builder.add_block(150, 500);
+
+        let mut output = Vec::new();
+        builder.write(&mut output).unwrap();
+        assert_eq!(read_gzi(&output[..]).unwrap(), builder.entries());
+
+        assert!(matches!(read_gzi(&output[..output.len() - 1]), Err(Error::UnexpectedEof)));
+        assert!(read_gzi(&0u64.to_le_bytes()[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gzi_builder_read() {
+        for blocks in [0, 1, 1000] {
+            let mut builder = GziIndexBuilder::new();
+            for i in 0..blocks {
+                builder.add_block(100 + i % 7, 65280);
+            }
+            let mut output = Vec::new();
+            builder.write(&mut output).unwrap();
+            let read = GziIndexBuilder::read(&output[..]).unwrap();
+            assert_eq!(read.entries(), builder.entries(), "{} blocks", blocks);
+        }
+    }
+
+    #[test]
+    fn test_gzi_builder_read_finds_last_block() {
+        let mut builder = GziIndexBuilder::new();
+        builder.add_block(100, 1000);
+        builder.add_block(200, 2000);
+        builder.add_block(150, 500);
+        let mut output = Vec::new();
+        builder.write(&mut output).unwrap();
+        let read = GziIndexBuilder::read(&output[..]).unwrap();
+
+        // The last block starts at 3000; its size is not in the GZI
+        let last = Some(GziEntry { compressed_offset: 300, uncompressed_offset: 3000 });
+        assert_eq!(read.block_containing(2999), builder.block_containing(2999));
+        assert_eq!(read.block_containing(3000), last);
+        assert_eq!(read.block_containing(3499), last);
+        assert_eq!(read.virtual_offset_for(3499), builder.virtual_offset_for(3499));
+        assert_eq!(read.virtual_offset_for(3000 + 65535), Some(300 << 16 | 65535));
+        assert_eq!(read.virtual_offset_for(3000 + 65536), None);
+
+        // An empty index holds at most the implied first block
+        let empty = GziIndexBuilder::read(&0u64.to_le_bytes()[..]).unwrap();
+        assert_eq!(empty.virtual_offset_for(10), Some(10));
+    }
+
+    #[test]
+    fn test_gzi_builder_read_rejects_bad_index() {
+        let mut builder = GziIndexBuilder::new();
+        builder.add_block(100, 1000);
+        builder.add_block(200, 2000);
+        let mut output = Vec::new();
+        builder.write(&mut output).unwrap();
+
+        let invalid =
+            |bytes: &[u8]| matches!(GziIndexBuilder::read(bytes), Err(Error::InvalidGzi(_)));
+        // Truncated, trailing bytes, a missing count
+        assert!(invalid(&output[..output.len() - 1]));
+        assert!(invalid(&[&output[..], &[0]].concat()));
+        assert!(invalid(&output[..4]));
+
+        // A count far larger than the file
+        let mut absurd = output.clone();
+        absurd[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(invalid(&absurd));
+
+        // Offsets going backwards
+        let mut backwards = output.clone();
+        backwards[24..32].copy_from_slice(&0u64.to_le_bytes());
+        assert!(invalid(&backwards));
+    }
+
+    #[test]
+    fn test_virtual_offset_for() {
+        let mut builder = GziIndexBuilder::new();
+        builder.add_block(100, 1000);
+        builder.add_block(200, 2000);
+        builder.add_block(150, 500);
+
+        let entry = |c, u| Some(GziEntry { compressed_offset: c, uncompressed_offset: u });
+        // First byte, the last byte of a block, the first of the next, and the last byte
+        assert_eq!(builder.block_containing(0), entry(0, 0));
+        assert_eq!(builder.block_containing(999), entry(0, 0));
+        assert_eq!(builder.block_containing(1000), entry(100, 1000));
+        assert_eq!(builder.block_containing(3499), entry(300, 3000));
+        assert_eq!(builder.block_containing(3500), None);
+
+        assert_eq!(builder.virtual_offset_for(0), Some(0));
+        assert_eq!(builder.virtual_offset_for(999), Some(999));
+        assert_eq!(builder.virtual_offset_for(1000), Some(100 << 16));
+        assert_eq!(builder.virtual_offset_for(2999), Some(100 << 16 | 1999));
+        assert_eq!(builder.virtual_offset_for(3499), Some(300 << 16 | 499));
+        assert_eq!(builder.virtual_offset_for(3500), None);
+        assert_eq!(GziIndexBuilder::new().virtual_offset_for(0), None);
+    }
+
+    #[test]
+    fn test_gzi_write_htslib() {
+        let mut builder = GziIndexBuilder::new();
+        builder.add_block(100, 1000);
+        builder.add_block(200, 2000);
+        builder.add_block(150, 500);
+
+        // What `bgzip -i` writes for the same three blocks: a count of 2, then
+        // the second and third blocks' offsets
+        let mut expected = Vec::new();
+        for value in [2u64, 100, 1000, 300, 3000] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut output = Vec::new();
+        builder.write_htslib(&mut output).unwrap();
+        assert_eq!(output, expected);
+
+        assert_eq!(read_gzi(&output[..]).unwrap(), &builder.entries()[1..]);
+        let mut output = Vec::new();
+        write_gzi_htslib(&[], &mut output).unwrap();
+        assert_eq!(output, 0u64.to_le_bytes());
+    }
+
     #[test]
     fn test_gzi_write() {
         let mut builder = GziIndexBuilder::new();
@@ -1,12 +1,31 @@
+pub mod blocks;
+pub mod bsize;
 pub mod constants;
+pub mod coords;
 pub mod detector;
 pub mod index;
+pub mod random_access;
+pub mod reader;
+pub mod split;
 pub mod writer;
 
+pub use blocks::{
+    count_blocks, inflate_block, iter_blocks, repair_footers, verify_structure, BgzfBlocks,
+    BlockInfo, StructureReport,
+};
+pub use bsize::{block_size_from_bsize, bsize_from_block_size};
 pub use constants::*;
+pub use coords::{write_coordinate_index, BlockCoordinate};
 pub use detector::{
-    is_bgzf, validate_bgzf_streaming, validate_bgzf_strict, verify_bgzf, BgzfValidation,
-    BgzfVerification,
+    is_bgzf, is_bgzf_bytes, is_eof_block, validate_bgzf_detailed, validate_bgzf_header_bytes,
+    validate_bgzf_streaming, validate_bgzf_streaming_detailed, validate_bgzf_strict, verify_bgzf,
+    verify_bgzf_tee, verify_bgzf_with, verify_bgzf_with_options, BgzfValidation, BgzfVerification,
+    BlockError, BlockErrorKind, VerifyOptions,
+};
+pub use index::{
+    copy_with_index, index_bgzf, read_gzi, write_gzi, write_gzi_htslib, GziEntry, GziIndexBuilder,
 };
-pub use index::{GziEntry, GziIndexBuilder};
-pub use writer::BgzfBlockWriter;
+pub use random_access::{BlockSource, IndexedReader, SeekSource};
+pub use reader::BgzfReader;
+pub use split::split_decompress;
+pub use writer::{write_empty, BgzfBlockWriter};
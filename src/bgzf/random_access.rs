@@ -0,0 +1,282 @@
+//! Random access into BGZF data, driven by a GZI index.
+//!
+//! [`IndexedReader`] fetches whole blocks through a [`BlockSource`] rather than
+//! seeking a reader, so the data can live anywhere that can serve a byte range:
+//! a local file ([`SeekSource`]), memory, or object storage through HTTP range
+//! requests.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use super::blocks::inflate_block_bytes;
+use super::constants::{BGZF_FOOTER_SIZE, MAX_BGZF_BLOCK_SIZE};
+use super::detector::parse_bgzf_header;
+use super::index::GziEntry;
+use crate::error::{Error, Result};
+
+/// Something that can return a byte range of BGZF data.
+///
+/// An HTTP implementation issues a GET with
+/// `Range: bytes={compressed_offset}-{compressed_offset + compressed_size - 1}`.
+pub trait BlockSource {
+    /// Return up to `compressed_size` bytes starting at `compressed_offset`.
+    ///
+    /// Fewer bytes may be returned only when the data ends first.
+    fn read_block(&mut self, compressed_offset: u64, compressed_size: u64) -> Result<Vec<u8>>;
+}
+
+/// [`BlockSource`] over a seekable reader such as a local file
+#[derive(Debug)]
+pub struct SeekSource<R> {
+    inner: R,
+}
+
+impl<R: Read + Seek> SeekSource<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek> BlockSource for SeekSource<R> {
+    fn read_block(&mut self, compressed_offset: u64, compressed_size: u64) -> Result<Vec<u8>> {
+        self.inner.seek(SeekFrom::Start(compressed_offset))?;
+        let mut bytes =
+            Vec::with_capacity(compressed_size.min(MAX_BGZF_BLOCK_SIZE as u64) as usize);
+        (&mut self.inner).take(compressed_size).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Reads uncompressed bytes at any offset of a BGZF file through a [`BlockSource`].
+///
+/// Block `i` of the index spans compressed bytes from its entry to the next
+/// entry (or `compressed_len` for the last one). Only that range is requested,
+/// capped at the maximum block size, and the block's own BSIZE decides how much
+/// of it is inflated, so a trailing EOF block in the range is ignored. The most
+/// recently inflated block is cached, so sequential reads fetch each block once.
+pub struct IndexedReader<S> {
+    source: S,
+    entries: Vec<GziEntry>,
+    compressed_len: u64,
+    /// Index and uncompressed bytes of the last block inflated
+    cached: Option<(usize, Vec<u8>)>,
+    decompressor: libdeflater::Decompressor,
+}
+
+impl<S: BlockSource> IndexedReader<S> {
+    /// Create a reader over `source`, whose total size is `compressed_len` bytes.
+    ///
+    /// `entries` is a GZI index, e.g. from [`read_gzi`](super::read_gzi),
+    /// [`index_bgzf`](super::index_bgzf), or `TranscodeStats::index_entries`.
+    /// Indexes written by `bgzip`, which leave out the first block, are accepted.
+    pub fn new(source: S, mut entries: Vec<GziEntry>, compressed_len: u64) -> Self {
+        if entries.first().map_or(true, |e| e.uncompressed_offset != 0) {
+            entries.insert(0, GziEntry { compressed_offset: 0, uncompressed_offset: 0 });
+        }
+        Self {
+            source,
+            entries,
+            compressed_len,
+            cached: None,
+            decompressor: libdeflater::Decompressor::new(),
+        }
+    }
+
+    /// Index entries, including the first block's
+    pub fn entries(&self) -> &[GziEntry] {
+        &self.entries
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Read uncompressed bytes starting at `offset` into `buf`.
+    ///
+    /// Reads across blocks until `buf` is full, and returns the number of bytes
+    /// read, which is less than `buf.len()` only at the end of the data.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let pos = offset + filled as u64;
+            // Last block starting at or before `pos`; entry 0 always starts at 0
+            let index = self.entries.partition_point(|e| e.uncompressed_offset <= pos) - 1;
+            let start = self.entries[index].uncompressed_offset;
+            let data = self.load(index)?;
+
+            let within = (pos - start) as usize;
+            if within >= data.len() {
+                break;
+            }
+            let n = (data.len() - within).min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&data[within..within + n]);
+            filled += n;
+        }
+        Ok(filled)
+    }
+
+    /// Fetch and inflate block `index`, unless it is already cached
+    fn load(&mut self, index: usize) -> Result<&[u8]> {
+        if self.cached.as_ref().map_or(true, |(cached, _)| *cached != index) {
+            self.cached = None;
+            let data = self.fetch(index)?;
+            self.cached = Some((index, data));
+        }
+        Ok(&self.cached.as_ref().expect("block was just cached").1)
+    }
+
+    fn fetch(&mut self, index: usize) -> Result<Vec<u8>> {
+        let entry = self.entries[index];
+        let next = self.entries.get(index + 1);
+        let end = next.map_or(self.compressed_len, |e| e.compressed_offset);
+        let offset = entry.compressed_offset;
+        let range = end.saturating_sub(offset).min(MAX_BGZF_BLOCK_SIZE as u64);
+
+        let bytes = self.source.read_block(offset, range)?;
+        let header = parse_bgzf_header(&bytes).ok_or(Error::InvalidBgzfHeader { offset })?;
+        if header.block_size < header.header_size + BGZF_FOOTER_SIZE {
+            return Err(Error::InvalidBgzfHeader { offset });
+        }
+        let block = bytes.get(..header.block_size).ok_or(Error::UnexpectedEof)?;
+
+        let footer = &block[block.len() - 4..];
+        let isize = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+        if let Some(next) = next {
+            let expected = next.uncompressed_offset - entry.uncompressed_offset;
+            if expected != isize as u64 {
+                return Err(Error::SizeMismatch { expected: expected as u32, found: isize });
+            }
+        }
+        inflate_block_bytes(&mut self.decompressor, block, offset, isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::{read_gzi, write_gzi};
+    use crate::transcoder::single::SingleThreadedTranscoder;
+    use crate::{TranscodeConfig, Transcoder};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    /// In-memory stand-in for an HTTP range client: no `Read` or `Seek`
+    struct RangeBuffer {
+        data: Vec<u8>,
+        requests: Vec<(u64, u64)>,
+    }
+
+    impl BlockSource for RangeBuffer {
+        fn read_block(&mut self, compressed_offset: u64, compressed_size: u64) -> Result<Vec<u8>> {
+            self.requests.push((compressed_offset, compressed_size));
+            let start = (compressed_offset as usize).min(self.data.len());
+            let end = (start + compressed_size as usize).min(self.data.len());
+            Ok(self.data[start..end].to_vec())
+        }
+    }
+
+    /// Transcode `data` into small blocks, returning the BGZF bytes and their index
+    fn bgzf_with_index(data: &[u8]) -> (Vec<u8>, Vec<GziEntry>) {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let config = TranscodeConfig { block_size: 4096, build_index: true, ..Default::default() };
+        let mut bgzf = Vec::new();
+        let stats =
+            SingleThreadedTranscoder::new(config).transcode(Cursor::new(gz), &mut bgzf).unwrap();
+        (bgzf, stats.index_entries.unwrap())
+    }
+
+    fn sample() -> Vec<u8> {
+        (0..50_000u32)
+            .flat_map(|i| format!("line {} {}\n", i, i * 7919 % 1000).into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_at_without_seek() {
+        let data = sample();
+        let (bgzf, entries) = bgzf_with_index(&data);
+        assert!(entries.len() > 10);
+        let len = bgzf.len() as u64;
+        let mut reader =
+            IndexedReader::new(RangeBuffer { data: bgzf, requests: Vec::new() }, entries, len);
+
+        // Within one block, across several blocks, and at block boundaries
+        for (offset, size) in [(0, 10), (5000, 100), (4000, 20_000), (8192, 4096), (123_456, 1)] {
+            let mut buf = vec![0u8; size];
+            assert_eq!(reader.read_at(offset as u64, &mut buf).unwrap(), size);
+            assert_eq!(buf, &data[offset..offset + size], "offset {}", offset);
+        }
+
+        // Short read at the end, nothing past it
+        let mut buf = vec![0u8; 100];
+        let tail = data.len() - 30;
+        assert_eq!(reader.read_at(tail as u64, &mut buf).unwrap(), 30);
+        assert_eq!(&buf[..30], &data[tail..]);
+        assert_eq!(reader.read_at(data.len() as u64 + 10, &mut buf).unwrap(), 0);
+
+        // Each request covers at most one block's range
+        let source = reader.into_inner();
+        assert!(source.requests.iter().all(|&(_, size)| size <= MAX_BGZF_BLOCK_SIZE as u64));
+    }
+
+    #[test]
+    fn test_read_at_sequential_fetches_each_block_once() {
+        let data = sample();
+        let (bgzf, entries) = bgzf_with_index(&data);
+        let blocks = entries.len();
+        let len = bgzf.len() as u64;
+        let mut reader =
+            IndexedReader::new(RangeBuffer { data: bgzf, requests: Vec::new() }, entries, len);
+
+        let mut out = Vec::new();
+        let mut buf = [0u8; 1000];
+        loop {
+            let n = reader.read_at(out.len() as u64, &mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, data);
+        assert_eq!(reader.into_inner().requests.len(), blocks);
+    }
+
+    #[test]
+    fn test_read_at_bgzip_style_index() {
+        let data = sample();
+        let (bgzf, entries) = bgzf_with_index(&data);
+
+        // bgzip's .gzi leaves out the first block; a round trip through GZI bytes
+        let mut gzi = Vec::new();
+        write_gzi(&entries[1..], &mut gzi).unwrap();
+        let len = bgzf.len() as u64;
+        let mut reader = IndexedReader::new(
+            SeekSource::new(Cursor::new(bgzf)),
+            read_gzi(&gzi[..]).unwrap(),
+            len,
+        );
+        assert_eq!(reader.entries(), &entries[..]);
+
+        let mut buf = vec![0u8; data.len()];
+        assert_eq!(reader.read_at(0, &mut buf).unwrap(), data.len());
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn test_read_at_index_mismatch() {
+        let data = sample();
+        let (bgzf, mut entries) = bgzf_with_index(&data);
+        entries[1].uncompressed_offset += 1;
+        let len = bgzf.len() as u64;
+        let mut reader = IndexedReader::new(SeekSource::new(Cursor::new(bgzf)), entries, len);
+        let mut buf = [0u8; 10];
+        assert!(matches!(reader.read_at(0, &mut buf), Err(Error::SizeMismatch { .. })));
+    }
+}
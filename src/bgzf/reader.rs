@@ -0,0 +1,248 @@
+//! Streaming decompression of BGZF back to plain bytes.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::blocks::{inflate_block_bytes, read_exact, read_header};
+use crate::error::{Error, Result};
+
+/// Reads the uncompressed bytes of a BGZF stream.
+///
+/// Each block is read whole, inflated, and checked against the CRC32 and
+/// ISIZE in its footer before any of it is returned. Empty blocks, including
+/// the EOF block and those left inside concatenated BGZF files, are skipped,
+/// and reading ends at the end of the underlying stream.
+///
+/// Errors surface through [`Read`] as [`io::ErrorKind::InvalidData`] wrapping
+/// the crate's [`Error`] (e.g. [`Error::Crc32Mismatch`]), which
+/// [`io::Error::get_ref`] and `downcast_ref` recover; I/O errors pass through.
+pub struct BgzfReader<R> {
+    inner: R,
+    /// Compressed offset of the next block
+    offset: u64,
+    /// Uncompressed bytes of the current block
+    block: Vec<u8>,
+    /// Read position within `block`
+    pos: usize,
+    /// Raw bytes of the block being read, reused between blocks
+    raw: Vec<u8>,
+    decompressor: libdeflater::Decompressor,
+}
+
+impl<R: Read> BgzfReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            block: Vec::new(),
+            pos: 0,
+            raw: Vec::new(),
+            decompressor: libdeflater::Decompressor::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read and inflate the next block into `block`, returning `false` at the
+    /// end of the stream.
+    fn next_block(&mut self) -> Result<bool> {
+        let Some(block_size) = read_header(&mut self.inner, &mut self.raw, self.offset)? else {
+            return Ok(false);
+        };
+        let header_size = self.raw.len();
+        self.raw.resize(block_size, 0);
+        read_exact(&mut self.inner, &mut self.raw[header_size..])?;
+
+        let footer = &self.raw[block_size - 4..];
+        let isize = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+        self.block = inflate_block_bytes(&mut self.decompressor, &self.raw, self.offset, isize)?;
+        self.pos = 0;
+        self.offset += block_size as u64;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    /// Move to BGZF virtual offset `voffset`: the compressed offset of a block
+    /// in the upper 48 bits and an offset into its uncompressed bytes in the
+    /// lower 16, as from [`GziIndexBuilder::virtual_offset_for`](super::GziIndexBuilder::virtual_offset_for).
+    ///
+    /// The block is read and inflated right away, and reading continues from
+    /// there across the following blocks. An offset within the block past its
+    /// uncompressed size is an [`Error::InvalidVirtualOffset`].
+    pub fn seek_virtual(&mut self, voffset: u64) -> Result<()> {
+        let block_offset = voffset >> 16;
+        let within = (voffset & 0xffff) as usize;
+        self.inner.seek(SeekFrom::Start(block_offset))?;
+        self.offset = block_offset;
+        self.block.clear();
+        self.pos = 0;
+
+        // The end of the stream is only a valid position with no offset into it
+        let found = self.next_block()?;
+        if within > self.block.len() || (!found && within > 0) {
+            return Err(Error::InvalidVirtualOffset(voffset));
+        }
+        self.pos = within;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            match self.next_block() {
+                Ok(true) => {}
+                Ok(false) => return Ok(0),
+                Err(Error::Io(e)) => return Err(e),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+        }
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::{block_size_from_bsize, GziEntry, GziIndexBuilder};
+    use crate::transcoder::single::SingleThreadedTranscoder;
+    use crate::{TranscodeConfig, Transcoder};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    fn bgzf(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let config = TranscodeConfig { block_size: 4096, ..Default::default() };
+        let mut output = Vec::new();
+        SingleThreadedTranscoder::new(config).transcode(Cursor::new(gz), &mut output).unwrap();
+        output
+    }
+
+    fn sample() -> Vec<u8> {
+        (0..20_000u32).flat_map(|i| format!("line {}\n", i * 7919 % 10_000).into_bytes()).collect()
+    }
+
+    #[test]
+    fn test_bgzf_reader_round_trip() {
+        let data = sample();
+        let compressed = bgzf(&data);
+
+        let mut out = Vec::new();
+        io::copy(&mut BgzfReader::new(&compressed[..]), &mut out).unwrap();
+        assert_eq!(out, data);
+
+        // Concatenated files read through the inner EOF block; small reads work too
+        let doubled = [&compressed[..], &compressed[..]].concat();
+        let mut reader = BgzfReader::new(&doubled[..]);
+        let mut out = Vec::new();
+        let mut buf = [0u8; 7];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, [&data[..], &data[..]].concat());
+    }
+
+    #[test]
+    fn test_seek_virtual() {
+        let data = sample();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let config = TranscodeConfig { block_size: 4096, build_index: true, ..Default::default() };
+        let mut compressed = Vec::new();
+        let stats = SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(encoder.finish().unwrap()), &mut compressed)
+            .unwrap();
+        let mut index = GziIndexBuilder::new();
+        let mut offsets = stats.index_entries.unwrap();
+        offsets.push(GziEntry {
+            compressed_offset: compressed.len() as u64,
+            uncompressed_offset: data.len() as u64,
+        });
+        for pair in offsets.windows(2) {
+            index.add_block(
+                pair[1].compressed_offset - pair[0].compressed_offset,
+                pair[1].uncompressed_offset - pair[0].uncompressed_offset,
+            );
+        }
+
+        let mut reader = BgzfReader::new(Cursor::new(&compressed));
+        // Block starts, mid-block, the first byte, and the last byte; each read
+        // continues across the following blocks
+        let starts = offsets[1..4].iter().map(|e| e.uncompressed_offset as usize);
+        for pos in starts.chain([5000, 0, data.len() - 1]) {
+            reader.seek_virtual(index.virtual_offset_for(pos as u64).unwrap()).unwrap();
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(out, &data[pos..], "position {}", pos);
+        }
+
+        // The end of a block is valid (the next read moves on); past it is not
+        let first_len = offsets[1].uncompressed_offset;
+        reader.seek_virtual(first_len).unwrap();
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], data[first_len as usize]);
+        let result = reader.seek_virtual(first_len + 1);
+        assert!(matches!(result, Err(Error::InvalidVirtualOffset(_))));
+    }
+
+    #[test]
+    fn test_bgzf_reader_errors() {
+        let mut compressed = bgzf(&sample());
+
+        // Truncated mid-block
+        let truncated = &compressed[..compressed.len() / 2];
+        let err = io::copy(&mut BgzfReader::new(truncated), &mut io::sink()).unwrap_err();
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<Error>());
+        assert!(matches!(inner, Some(Error::UnexpectedEof)), "{:?}", err);
+
+        // Corrupt CRC32 in the first block's footer
+        let block_size =
+            block_size_from_bsize(u16::from_le_bytes([compressed[16], compressed[17]]));
+        compressed[block_size - 8] ^= 0xff;
+        let err = io::copy(&mut BgzfReader::new(&compressed[..]), &mut io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<Error>());
+        assert!(matches!(inner, Some(Error::Crc32Mismatch { .. })), "{:?}", err);
+    }
+
+    #[test]
+    fn test_bgzf_reader_rejects_huge_isize() {
+        // An ISIZE of 0xFFFFFFFF fails before 4 GiB is allocated for the block
+        let mut compressed = bgzf(&sample());
+        let block_size =
+            block_size_from_bsize(u16::from_le_bytes([compressed[16], compressed[17]]));
+        compressed[block_size - 4..block_size].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let err = io::copy(&mut BgzfReader::new(&compressed[..]), &mut io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<Error>());
+        assert!(
+            matches!(inner, Some(Error::BgzfBlockTooLarge { size, .. }) if *size == u32::MAX as usize),
+            "{:?}",
+            err
+        );
+
+        // Verification reports the block instead
+        let verification = crate::bgzf::verify_bgzf(&mut &compressed[..]).unwrap();
+        assert!(!verification.isize_valid);
+        assert!(verification.crc_valid);
+    }
+}
@@ -0,0 +1,116 @@
+//! Decompressing a BGZF stream into several outputs, one per original gzip member.
+
+use std::io::{self, Read, Write};
+
+use flate2::read::MultiGzDecoder;
+
+use crate::error::{Error, Result};
+
+/// Decompress `reader` into one output per original gzip member.
+///
+/// BGZF does not record where the input's members began, so the caller passes
+/// `member_boundaries`: the uncompressed offsets at which the second, third, ...
+/// members start (e.g. running totals of the members' ISIZE trailers, recorded
+/// before transcoding). A stream transcoded with
+/// `block_per_member` has a block boundary at each of them, but any offsets
+/// work. `make_output(i)` is called once per member, in order, and receives that
+/// member's bytes; the last member takes everything after the last boundary.
+///
+/// Returns the number of bytes written to each output. A boundary past the end
+/// of the data is an [`Error::UnexpectedEof`].
+pub fn split_decompress<R, W, F>(
+    reader: R,
+    member_boundaries: &[u64],
+    mut make_output: F,
+) -> Result<Vec<u64>>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(usize) -> W,
+{
+    if member_boundaries.windows(2).any(|w| w[0] > w[1]) {
+        return Err(Error::InvalidConfig(
+            "member_boundaries must be in ascending order".to_string(),
+        ));
+    }
+
+    let mut decoder = MultiGzDecoder::new(reader);
+    let mut sizes = Vec::with_capacity(member_boundaries.len() + 1);
+    let mut offset = 0;
+    for i in 0..=member_boundaries.len() {
+        let end = member_boundaries.get(i).copied();
+        let mut output = make_output(i);
+        let limit = end.map_or(u64::MAX, |end| end - offset);
+        let copied = io::copy(&mut (&mut decoder).take(limit), &mut output)?;
+        output.flush()?;
+        if end.is_some() && copied < limit {
+            return Err(Error::UnexpectedEof);
+        }
+        offset += copied;
+        sizes.push(copied);
+    }
+    Ok(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::single::SingleThreadedTranscoder;
+    use crate::{TranscodeConfig, Transcoder};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Cursor;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_split_decompress_members() {
+        let members: Vec<Vec<u8>> = (0..3u32)
+            .map(|m| {
+                (0..5_000 * (m + 1)).flat_map(|i| format!("{} {}\n", m, i).into_bytes()).collect()
+            })
+            .collect();
+        let input: Vec<u8> = members.iter().flat_map(|m| gzip(m)).collect();
+
+        let config =
+            TranscodeConfig { block_size: 16384, block_per_member: true, ..Default::default() };
+        let mut bgzf = Vec::new();
+        SingleThreadedTranscoder::new(config).transcode(Cursor::new(input), &mut bgzf).unwrap();
+
+        let first = members[0].len() as u64;
+        let boundaries = [first, first + members[1].len() as u64];
+        let mut outputs = vec![Vec::new(); 3];
+        let mut slots = outputs.iter_mut();
+        let sizes = split_decompress(&bgzf[..], &boundaries, |_| slots.next().unwrap()).unwrap();
+
+        assert_eq!(outputs, members);
+        assert_eq!(sizes, members.iter().map(|m| m.len() as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_split_decompress_bad_boundaries() {
+        let bgzf = {
+            let mut out = Vec::new();
+            SingleThreadedTranscoder::new(TranscodeConfig::default())
+                .transcode(Cursor::new(gzip(b"0123456789")), &mut out)
+                .unwrap();
+            out
+        };
+
+        // Past the end of the data
+        let result = split_decompress(&bgzf[..], &[4, 20], |_| Vec::new());
+        assert!(matches!(result, Err(Error::UnexpectedEof)));
+
+        // Out of order
+        let result = split_decompress(&bgzf[..], &[6, 4], |_| Vec::new());
+        assert!(matches!(result, Err(Error::InvalidConfig(_))));
+
+        // Empty members are allowed
+        let sizes = split_decompress(&bgzf[..], &[0, 10], |_| io::sink()).unwrap();
+        assert_eq!(sizes, [0, 10, 0]);
+    }
+}
@@ -1,32 +1,77 @@
+use super::bsize::bsize_from_block_size;
 use super::constants::*;
-use crate::error::{Error, Result};
+use crate::error::Result;
 use std::io::Write;
 
 /// Writes BGZF blocks with custom deflate data
 pub struct BgzfBlockWriter<W: Write> {
     writer: W,
+    /// Encoded extra subfields written after the mandatory BC subfield
+    extra: Vec<u8>,
+    /// Header fields set in every block header
+    fields: HeaderFields,
+}
+
+/// Gzip header fields of BGZF data blocks that BGZF leaves free.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HeaderFields {
+    /// Set FTEXT
+    pub text: bool,
+    /// MTIME
+    pub mtime: u32,
+    /// OS
+    pub os: u8,
+}
+
+impl Default for HeaderFields {
+    /// No FTEXT, MTIME=0, OS=255 (unknown), as in the BGZF EOF block
+    fn default() -> Self {
+        Self { text: false, mtime: 0, os: 0xff }
+    }
 }
 
 impl<W: Write> BgzfBlockWriter<W> {
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self { writer, extra: Vec::new(), fields: HeaderFields::default() }
+    }
+
+    /// Create a writer that appends `subfields` (SI1, SI2, data) to every block
+    /// header after the BC subfield.
+    pub fn with_extra_subfields(writer: W, subfields: &[(u8, u8, Vec<u8>)]) -> Self {
+        Self { writer, extra: encode_extra_subfields(subfields), fields: HeaderFields::default() }
+    }
+
+    /// Set the FTEXT flag in the headers of blocks written from now on
+    /// (the EOF block is always written as-is).
+    pub fn set_text(&mut self, text: bool) {
+        self.fields.text = text;
+    }
+
+    /// Set all the free header fields of blocks written from now on
+    pub(crate) fn set_header_fields(&mut self, fields: HeaderFields) {
+        self.fields = fields;
+    }
+
+    /// Size of each block header written by this writer, including extra subfields
+    pub fn header_size(&self) -> usize {
+        BGZF_HEADER_SIZE + self.extra.len()
     }
 
     /// Write a BGZF block with pre-encoded deflate data and pre-computed CRC
+    ///
+    /// `isize` stays `u32` as the gzip footer requires; a BGZF block holds at most
+    /// 64 KiB, so unlike a member's ISIZE it never wraps.
     pub fn write_block_with_crc(
         &mut self,
         deflate_data: &[u8],
         crc: u32,
         isize: u32,
     ) -> Result<()> {
-        let block_size = BGZF_HEADER_SIZE + deflate_data.len() + BGZF_FOOTER_SIZE;
-
-        if block_size > MAX_BGZF_BLOCK_SIZE {
-            return Err(Error::BgzfBlockTooLarge { size: block_size, max: MAX_BGZF_BLOCK_SIZE });
-        }
+        let block_size = self.header_size() + deflate_data.len() + BGZF_FOOTER_SIZE;
+        let bsize = bsize_from_block_size(block_size)?;
 
         // Write BGZF header
-        self.write_header(block_size - 1)?; // BSIZE is block_size - 1
+        write_bgzf_header(&mut self.writer, bsize, &self.extra, self.fields)?;
 
         // Write deflate data
         self.writer.write_all(deflate_data)?;
@@ -44,32 +89,6 @@ impl<W: Write> BgzfBlockWriter<W> {
         self.write_block_with_crc(deflate_data, crc, uncompressed.len() as u32)
     }
 
-    /// Write the BGZF header (18 bytes)
-    fn write_header(&mut self, bsize: usize) -> Result<()> {
-        let header = [
-            0x1f,
-            0x8b, // gzip magic
-            0x08, // compression method (DEFLATE)
-            0x04, // flags (FEXTRA)
-            0x00,
-            0x00,
-            0x00,
-            0x00, // mtime
-            0x00, // extra flags
-            0xff, // OS (unknown)
-            0x06,
-            0x00, // xlen = 6
-            0x42,
-            0x43, // subfield ID "BC"
-            0x02,
-            0x00,                        // subfield length = 2
-            (bsize & 0xFF) as u8,        // BSIZE low byte
-            ((bsize >> 8) & 0xFF) as u8, // BSIZE high byte
-        ];
-        self.writer.write_all(&header)?;
-        Ok(())
-    }
-
     /// Write the BGZF EOF marker
     pub fn write_eof(&mut self) -> Result<()> {
         self.writer.write_all(&BGZF_EOF)?;
@@ -93,9 +112,67 @@ impl<W: Write> BgzfBlockWriter<W> {
     }
 }
 
+/// Serialize extra subfields as they appear in the gzip FEXTRA field.
+pub(crate) fn encode_extra_subfields(subfields: &[(u8, u8, Vec<u8>)]) -> Vec<u8> {
+    let mut extra = Vec::with_capacity(subfields.iter().map(|(_, _, d)| 4 + d.len()).sum());
+    for (si1, si2, data) in subfields {
+        extra.push(*si1);
+        extra.push(*si2);
+        extra.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        extra.extend_from_slice(data);
+    }
+    extra
+}
+
+/// Write a BGZF block header: the BC subfield first, then any pre-encoded `extra` subfields.
+pub(crate) fn write_bgzf_header<W: Write>(
+    writer: &mut W,
+    bsize: u16,
+    extra: &[u8],
+    fields: HeaderFields,
+) -> Result<()> {
+    let xlen = 6 + extra.len();
+    let [bsize_lo, bsize_hi] = bsize.to_le_bytes();
+    let [mtime0, mtime1, mtime2, mtime3] = fields.mtime.to_le_bytes();
+    let header = [
+        0x1f,
+        0x8b,                     // gzip magic
+        0x08,                     // compression method (DEFLATE)
+        0x04 | fields.text as u8, // flags (FEXTRA, and FTEXT if requested)
+        mtime0,
+        mtime1,
+        mtime2,
+        mtime3,    // mtime
+        0x00,      // extra flags
+        fields.os, // OS
+        (xlen & 0xFF) as u8,
+        ((xlen >> 8) & 0xFF) as u8, // xlen
+        0x42,
+        0x43, // subfield ID "BC"
+        0x02,
+        0x00,     // subfield length = 2
+        bsize_lo, // BSIZE low byte
+        bsize_hi, // BSIZE high byte
+    ];
+    writer.write_all(&header)?;
+    writer.write_all(extra)?;
+    Ok(())
+}
+
+/// Write a minimal valid BGZF file containing no data: just the EOF block.
+///
+/// Useful when a pipeline must produce a BGZF file even though there is nothing
+/// to compress (e.g. the upstream step produced no records).
+pub fn write_empty<W: Write>(mut writer: W) -> Result<()> {
+    writer.write_all(&BGZF_EOF)?;
+    writer.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bgzf::block_size_from_bsize;
 
     #[test]
     fn test_write_eof() {
@@ -106,6 +183,24 @@ mod tests {
         assert_eq!(output, BGZF_EOF);
     }
 
+    #[test]
+    fn test_write_empty() {
+        use crate::bgzf::{is_bgzf, verify_bgzf};
+        use std::io::Cursor;
+
+        let mut output = Vec::new();
+        write_empty(&mut output).unwrap();
+        assert_eq!(output.len(), BGZF_EOF_SIZE);
+
+        assert!(is_bgzf(&mut Cursor::new(&output)).unwrap());
+        let verification = verify_bgzf(&mut Cursor::new(&output)).unwrap();
+        assert!(verification.is_valid_bgzf && verification.crc_valid);
+        assert!(verification.has_eof_block);
+        // The EOF block is the only block and carries no data
+        assert_eq!(verification.block_count, 1);
+        assert_eq!(verification.uncompressed_size, 0);
+    }
+
     #[test]
     fn test_write_block() {
         let mut output = Vec::new();
@@ -128,7 +223,48 @@ mod tests {
         assert_eq!(output[13], b'C');
 
         // Check total size
-        let bsize = u16::from_le_bytes([output[16], output[17]]) as usize + 1;
-        assert_eq!(output.len(), bsize);
+        let block_size = block_size_from_bsize(u16::from_le_bytes([output[16], output[17]]));
+        assert_eq!(output.len(), block_size);
+    }
+
+    #[test]
+    fn test_write_block_at_size_limit() {
+        let max_deflate = MAX_BGZF_BLOCK_SIZE - BGZF_HEADER_SIZE - BGZF_FOOTER_SIZE;
+        let mut output = Vec::new();
+        let mut writer = BgzfBlockWriter::new(&mut output);
+        writer.write_block_with_crc(&vec![0; max_deflate], 0, 0).unwrap();
+        assert!(writer.write_block_with_crc(&vec![0; max_deflate + 1], 0, 0).is_err());
+
+        // The largest block stores BSIZE 65535 and nothing of the rejected one is written
+        assert_eq!(&output[16..18], &[0xff, 0xff]);
+        assert_eq!(output.len(), MAX_BGZF_BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_write_block_with_extra_subfields() {
+        use crate::bgzf::is_bgzf;
+        use crate::gzip::GzipHeader;
+        use std::io::Cursor;
+
+        let mut output = Vec::new();
+        let subfields = vec![(b'X', b'Y', vec![1, 2, 3])];
+        let mut writer = BgzfBlockWriter::with_extra_subfields(&mut output, &subfields);
+        assert_eq!(writer.header_size(), BGZF_HEADER_SIZE + 7);
+        writer.write_block(&[0x01, 0x00, 0x00, 0xff, 0xff], &[]).unwrap();
+        writer.write_eof().unwrap();
+
+        // XLEN covers both subfields and BSIZE covers the larger header
+        assert_eq!(u16::from_le_bytes([output[10], output[11]]), 13);
+        let bsize = u16::from_le_bytes([output[16], output[17]]);
+        let block_size = block_size_from_bsize(bsize);
+        assert_eq!(block_size, BGZF_HEADER_SIZE + 7 + 5 + BGZF_FOOTER_SIZE);
+        assert_eq!(&output[block_size..], &BGZF_EOF);
+
+        assert!(is_bgzf(&mut Cursor::new(&output)).unwrap());
+        let header = GzipHeader::parse(&mut Cursor::new(&output)).unwrap();
+        assert_eq!(
+            header.subfields(),
+            vec![(b'B', b'C', bsize.to_le_bytes().to_vec()), (b'X', b'Y', vec![1, 2, 3])]
+        );
     }
 }
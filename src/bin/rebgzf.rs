@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -7,10 +7,16 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use clap::{Parser, ValueEnum};
+use rebgzf::bgzf::{
+    copy_with_index, inflate_block, iter_blocks, write_coordinate_index, write_gzi,
+    write_gzi_htslib, BgzfReader, BlockInfo, GziEntry, GziIndexBuilder, BGZF_BLOCK_SIZE,
+    BGZF_EOF_SIZE,
+};
+use rebgzf::testdata;
 use rebgzf::{
-    is_bgzf, validate_bgzf_streaming, validate_bgzf_strict, verify_bgzf, BgzfValidation,
-    BgzfVerification, CompressionLevel, FormatProfile, ParallelDecodeTranscoder,
-    ParallelTranscoder, SingleThreadedTranscoder, TranscodeConfig, Transcoder,
+    degzip, is_bgzf, validate_bgzf_streaming, validate_bgzf_strict, verify_bgzf, verify_bgzf_tee,
+    BgzfValidation, BgzfVerification, CompressionLevel, FormatProfile, ParallelGzipReader,
+    TranscodeConfig,
 };
 
 /// Format argument for CLI (maps to FormatProfile)
@@ -20,15 +26,22 @@ enum FormatArg {
     Default,
     /// FASTQ-optimized (implies level 6+ and record-aligned boundaries)
     Fastq,
+    /// Sorted VCF (header in its own block(s), line-aligned records; with --index,
+    /// also writes a coarse CHROM/POS index next to the GZI)
+    Vcf,
+    /// Line-oriented text (blocks end on line boundaries; see --lines-per-record)
+    Lines,
     /// Auto-detect from file extension
     Auto,
 }
 
 impl FormatArg {
-    fn to_format_profile(self) -> FormatProfile {
+    fn to_format_profile(self, lines_per_record: usize) -> FormatProfile {
         match self {
             Self::Default => FormatProfile::Default,
             Self::Fastq => FormatProfile::Fastq,
+            Self::Vcf => FormatProfile::Vcf,
+            Self::Lines => FormatProfile::Lines { per_record: lines_per_record },
             Self::Auto => FormatProfile::Auto,
         }
     }
@@ -40,11 +53,17 @@ impl FormatArg {
 #[command(version)]
 struct Args {
     /// Input gzip file (use - for stdin)
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        required_unless_present = "self_test",
+        default_value = "-",
+        hide_default_value = true
+    )]
     input: PathBuf,
 
     /// Output BGZF file (use - for stdout)
-    #[arg(short, long, required_unless_present_any = ["check", "verify", "stats"])]
+    #[arg(short, long, required_unless_present_any = ["check", "verify", "stats", "block_range", "region", "decompress", "to_gzip", "self_test"])]
     output: Option<PathBuf>,
 
     /// Number of threads (0 = auto, 1 = single-threaded)
@@ -55,13 +74,26 @@ struct Args {
     #[arg(short = 'l', long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=9))]
     level: u8,
 
+    /// Re-run LZ77 matching on each block for smaller output (levels 7-9; much slower)
+    #[arg(long)]
+    recompress: bool,
+
     /// Input format profile for optimization
     #[arg(long, value_enum, default_value = "default")]
     format: FormatArg,
 
-    /// BGZF block size (default: 65280)
-    #[arg(long, default_value = "65280")]
-    block_size: usize,
+    /// Lines per record for --format lines
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    lines_per_record: u64,
+
+    /// BGZF block size (default: chosen by level, 65280; larger values are reduced to 65280)
+    #[arg(long)]
+    block_size: Option<usize>,
 
     /// Show verbose statistics
     #[arg(short, long)]
@@ -87,32 +119,126 @@ struct Args {
     #[arg(long)]
     verify: bool,
 
+    /// With --verify, copy the input to this path while verifying (use - for stdout)
+    #[arg(long, value_name = "PATH", requires = "verify")]
+    tee: Option<PathBuf>,
+
+    /// With --verify, treat a file without the BGZF EOF block as invalid (like
+    /// samtools quickcheck)
+    #[arg(long, requires = "verify")]
+    require_eof: bool,
+
     /// Show file statistics without transcoding
     #[arg(long)]
     stats: bool,
 
+    /// Decompress only BGZF blocks START..END (zero-indexed, END exclusive) to the output
+    /// (stdout if -o is omitted); requires a file input
+    #[arg(long, value_name = "START:END", value_parser = parse_block_range)]
+    block_range: Option<(u64, u64)>,
+
+    /// Write uncompressed bytes START..END (END exclusive, clamped to the end of the data)
+    /// to the output (stdout if -o is omitted), seeking with the GZI from --index PATH or
+    /// INPUT.gzi, or indexing the input first if neither exists; requires a file input
+    #[arg(long, value_name = "START:END", value_parser = parse_region)]
+    region: Option<(u64, u64)>,
+
+    /// Decompress BGZF or gzip input to the output (stdout if -o is omitted), checking
+    /// every CRC32 (0=success, 2=error or mismatch)
+    #[arg(short = 'd', long)]
+    decompress: bool,
+
+    /// Convert BGZF input into a single-member gzip file at --level (stdout if -o is
+    /// omitted), for tools that mishandle concatenated members
+    #[arg(long, conflicts_with = "decompress")]
+    to_gzip: bool,
+
     /// Force transcoding even if input is already BGZF
     #[arg(long)]
     force: bool,
 
-    /// Show progress during transcoding (throughput display)
+    /// Show progress during transcoding (bar with ETA on a terminal, plain lines otherwise)
     #[arg(short = 'p', long)]
     progress: bool,
 
     /// Write GZI index file (for random access). If no path given, uses output.gzi
     #[arg(long, value_name = "PATH")]
     index: Option<Option<PathBuf>>,
+
+    /// Write the GZI index in bgzip's layout, without the first block's entry,
+    /// so samtools and tabix can load it
+    #[arg(long, requires = "index")]
+    htslib_index: bool,
+
+    /// Round-trip generated data through every transcoder and exit (0=pass, 1=fail)
+    #[arg(long, hide = true)]
+    self_test: bool,
 }
 
-/// Exit codes for --check mode
-const EXIT_IS_BGZF: u8 = 0;
-const EXIT_NOT_BGZF: u8 = 1;
-const EXIT_ERROR: u8 = 2;
+/// Process exit status for every mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RebgzfExit {
+    /// Transcoding or stats completed
+    Success,
+    /// --check: input is BGZF
+    IsBgzf,
+    /// --check: input is not BGZF
+    NotBgzf,
+    /// --verify: structure, CRC32 and ISIZE all valid
+    VerifyValid,
+    /// --verify: at least one block failed verification
+    VerifyInvalid,
+    /// --self-test: at least one pattern failed to round-trip
+    SelfTestFailed,
+    /// Any error (I/O, parse, invalid arguments)
+    Error,
+}
 
-/// Exit codes for --verify mode
-const EXIT_VERIFY_VALID: u8 = 0;
-const EXIT_VERIFY_INVALID: u8 = 1;
-// EXIT_ERROR (2) also used for verify errors
+impl RebgzfExit {
+    /// Exit code reported to the shell (0=success, 1=negative result, 2=error)
+    fn as_code(self) -> u8 {
+        match self {
+            RebgzfExit::Success | RebgzfExit::IsBgzf | RebgzfExit::VerifyValid => 0,
+            RebgzfExit::NotBgzf | RebgzfExit::VerifyInvalid | RebgzfExit::SelfTestFailed => 1,
+            RebgzfExit::Error => 2,
+        }
+    }
+}
+
+/// Result of --check: the validation and the exit status it maps to
+struct CheckOutcome {
+    validation: BgzfValidation,
+    exit: RebgzfExit,
+}
+
+impl CheckOutcome {
+    fn new(validation: BgzfValidation) -> Self {
+        let exit = if validation.is_valid_bgzf { RebgzfExit::IsBgzf } else { RebgzfExit::NotBgzf };
+        Self { validation, exit }
+    }
+}
+
+/// Result of --verify: the verification and the exit status it maps to
+struct VerifyOutcome {
+    verification: BgzfVerification,
+    exit: RebgzfExit,
+}
+
+impl VerifyOutcome {
+    /// With `require_eof`, a missing EOF block also makes the input invalid
+    fn new(verification: BgzfVerification, require_eof: bool) -> Self {
+        let is_valid = verification.is_valid_bgzf
+            && verification.crc_valid
+            && verification.isize_valid
+            && (verification.has_eof_block || !require_eof);
+        let exit = if is_valid { RebgzfExit::VerifyValid } else { RebgzfExit::VerifyInvalid };
+        Self { verification, exit }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.exit == RebgzfExit::VerifyValid
+    }
+}
 
 /// Progress tracking state shared between reader wrapper and progress thread
 struct ProgressState {
@@ -141,6 +267,39 @@ impl<R: Read> Read for ProgressReader<R> {
     }
 }
 
+/// Parse a `START:END` block range
+fn parse_block_range(s: &str) -> Result<(u64, u64), String> {
+    parse_range(s, "block index")
+}
+
+/// Parse a `START:END` range of uncompressed bytes
+fn parse_region(s: &str) -> Result<(u64, u64), String> {
+    parse_range(s, "offset")
+}
+
+/// Parse `START:END` with `START <= END`, naming the values `what` in errors
+fn parse_range(s: &str, what: &str) -> Result<(u64, u64), String> {
+    let (start, end) = s.split_once(':').ok_or_else(|| format!("expected START:END, got '{s}'"))?;
+    let start: u64 = start.parse().map_err(|_| format!("invalid {what} '{start}'"))?;
+    let end: u64 = end.parse().map_err(|_| format!("invalid {what} '{end}'"))?;
+    if start > end {
+        return Err(format!("START ({start}) must not exceed END ({end})"));
+    }
+    Ok((start, end))
+}
+
+/// Validate a requested `--block-size`, snapping over-limit values to the BGZF default.
+///
+/// A block's uncompressed size must leave room for header and footer overhead within
+/// the 64KB BGZF limit, so anything above `BGZF_BLOCK_SIZE` is reduced to it.
+fn resolve_block_size(requested: usize) -> Result<usize, String> {
+    match requested {
+        0 => Err("--block-size must be greater than 0".to_string()),
+        n if n > BGZF_BLOCK_SIZE => Ok(BGZF_BLOCK_SIZE),
+        n => Ok(n),
+    }
+}
+
 /// Format bytes as human-readable string
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -158,74 +317,183 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Spawn progress display thread
-fn spawn_progress_thread(state: Arc<ProgressState>) -> std::thread::JoinHandle<()> {
-    std::thread::spawn(move || {
-        let start = Instant::now();
-        let mut last_bytes = 0u64;
-        let mut last_time = start;
+/// Format a duration in seconds as `H:MM:SS` or `M:SS`
+fn format_eta(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
 
-        while !state.done.load(Ordering::Relaxed) {
-            std::thread::sleep(Duration::from_millis(500));
+/// Rendering state for the progress display
+struct ProgressRenderer {
+    start: Instant,
+    last_bytes: u64,
+    last_time: Instant,
+    /// Exponentially smoothed throughput in bytes/second
+    smoothed_rate: Option<f64>,
+    spinner: usize,
+    /// Whether stderr is a terminal (bar + carriage returns) or a log (plain lines)
+    is_tty: bool,
+}
 
-            let bytes = state.bytes_read.load(Ordering::Relaxed);
-            let now = Instant::now();
-            let elapsed = now.duration_since(start);
-            let delta_bytes = bytes.saturating_sub(last_bytes);
-            let delta_time = now.duration_since(last_time);
-
-            // Calculate throughput
-            let throughput = if delta_time.as_secs_f64() > 0.0 {
-                delta_bytes as f64 / delta_time.as_secs_f64() / 1_000_000.0
-            } else {
-                0.0
-            };
+impl ProgressRenderer {
+    const BAR_WIDTH: usize = 30;
+    const LINE_WIDTH: usize = 79;
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+    /// Weight of the newest throughput sample in the smoothed rate
+    const SMOOTHING: f64 = 0.3;
 
-            // Build progress line
-            let progress_str = if let Some(total) = state.total_size {
-                let pct = (bytes as f64 / total as f64 * 100.0).min(100.0);
+    fn new(is_tty: bool) -> Self {
+        let now = Instant::now();
+        Self { start: now, last_bytes: 0, last_time: now, smoothed_rate: None, spinner: 0, is_tty }
+    }
+
+    /// Interval between updates: frequent on a terminal, sparse when logging
+    fn interval(&self) -> Duration {
+        if self.is_tty {
+            Duration::from_millis(200)
+        } else {
+            Duration::from_secs(5)
+        }
+    }
+
+    /// Fold in a new byte count and return the line to display
+    fn update(&mut self, bytes: u64, total: Option<u64>) -> String {
+        let now = Instant::now();
+        let delta_time = now.duration_since(self.last_time).as_secs_f64();
+        if delta_time > 0.0 {
+            let rate = bytes.saturating_sub(self.last_bytes) as f64 / delta_time;
+            self.smoothed_rate = Some(match self.smoothed_rate {
+                Some(prev) => prev + Self::SMOOTHING * (rate - prev),
+                None => rate,
+            });
+        }
+        self.last_bytes = bytes;
+        self.last_time = now;
+
+        let rate = self.smoothed_rate.unwrap_or(0.0);
+        let elapsed = now.duration_since(self.start).as_secs_f64();
+        let throughput = format!("{:.1} MB/s", rate / 1_000_000.0);
+
+        match total {
+            Some(total) if self.is_tty => {
+                let fraction = if total > 0 { (bytes as f64 / total as f64).min(1.0) } else { 1.0 };
+                let filled = (fraction * Self::BAR_WIDTH as f64) as usize;
+                let bar: String = (0..Self::BAR_WIDTH)
+                    .map(|i| match i.cmp(&filled) {
+                        std::cmp::Ordering::Less => '=',
+                        std::cmp::Ordering::Equal => '>',
+                        std::cmp::Ordering::Greater => ' ',
+                    })
+                    .collect();
+                let eta = if rate > 0.0 {
+                    format_eta(total.saturating_sub(bytes) as f64 / rate)
+                } else {
+                    "--:--".to_string()
+                };
+                format!(
+                    "[{}] {:5.1}% {} / {} {} ETA {}",
+                    bar,
+                    fraction * 100.0,
+                    format_bytes(bytes),
+                    format_bytes(total),
+                    throughput,
+                    eta
+                )
+            }
+            Some(total) => {
+                let pct = if total > 0 {
+                    (bytes as f64 / total as f64 * 100.0).min(100.0)
+                } else {
+                    100.0
+                };
                 format!(
-                    "\r{} / {} ({:.1}%) - {:.1} MB/s - {:.1}s elapsed",
+                    "{} / {} ({:.1}%) - {} - {:.1}s elapsed",
                     format_bytes(bytes),
                     format_bytes(total),
                     pct,
                     throughput,
-                    elapsed.as_secs_f64()
+                    elapsed
                 )
-            } else {
+            }
+            None if self.is_tty => {
+                self.spinner = (self.spinner + 1) % Self::SPINNER.len();
                 format!(
-                    "\r{} - {:.1} MB/s - {:.1}s elapsed",
+                    "{} {} {} {:.1}s elapsed",
+                    Self::SPINNER[self.spinner],
                     format_bytes(bytes),
                     throughput,
-                    elapsed.as_secs_f64()
+                    elapsed
                 )
-            };
+            }
+            None => format!("{} - {} - {:.1}s elapsed", format_bytes(bytes), throughput, elapsed),
+        }
+    }
+
+    fn draw(&self, line: &str) {
+        if self.is_tty {
+            eprint!("\r{:<width$.width$}", line, width = Self::LINE_WIDTH);
+        } else {
+            eprintln!("{}", line);
+        }
+        let _ = io::stderr().flush();
+    }
 
-            eprint!("{:<60}", progress_str);
+    /// Erase the progress line so later output starts on a clean line
+    fn clear(&self) {
+        if self.is_tty {
+            eprint!("\r{:<width$}\r", "", width = Self::LINE_WIDTH);
             let _ = io::stderr().flush();
+        }
+    }
+}
 
-            last_bytes = bytes;
-            last_time = now;
+/// Spawn progress display thread.
+///
+/// Draws a progress bar with ETA when the input size is known (a spinner otherwise) if
+/// stderr is a terminal, and falls back to periodic plain lines when it is not.
+fn spawn_progress_thread(state: Arc<ProgressState>) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut renderer = ProgressRenderer::new(io::stderr().is_terminal());
+        let poll = Duration::from_millis(50);
+        let mut next_draw = Instant::now() + renderer.interval();
+
+        while !state.done.load(Ordering::Relaxed) {
+            std::thread::sleep(poll);
+            if Instant::now() < next_draw {
+                continue;
+            }
+            next_draw += renderer.interval();
+
+            let bytes = state.bytes_read.load(Ordering::Relaxed);
+            let line = renderer.update(bytes, state.total_size);
+            renderer.draw(&line);
         }
 
-        // Clear progress line
-        eprint!("\r{:<60}\r", "");
-        let _ = io::stderr().flush();
+        renderer.clear();
     })
 }
 
 fn main() -> ExitCode {
-    match run() {
-        Ok(code) => ExitCode::from(code),
+    let exit = match run(Args::parse()) {
+        Ok(exit) => exit,
         Err(e) => {
             eprintln!("Error: {}", e);
-            ExitCode::from(EXIT_ERROR)
+            RebgzfExit::Error
         }
-    }
+    };
+    ExitCode::from(exit.as_code())
 }
 
-fn run() -> Result<u8, Box<dyn std::error::Error>> {
-    let args = Args::parse();
+fn run(args: Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    // Handle --self-test mode
+    if args.self_test {
+        return Ok(run_self_test(&args));
+    }
 
     // Handle --check mode
     if args.check {
@@ -242,6 +510,26 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
         return run_stats_mode(&args);
     }
 
+    // Handle --block-range mode
+    if let Some((start, end)) = args.block_range {
+        return run_block_range_mode(&args, start, end);
+    }
+
+    // Handle --region mode
+    if let Some((start, end)) = args.region {
+        return run_region_mode(&args, start, end);
+    }
+
+    // Handle --decompress mode
+    if args.decompress {
+        return run_decompress_mode(&args);
+    }
+
+    // Handle --to-gzip mode
+    if args.to_gzip {
+        return run_to_gzip_mode(&args);
+    }
+
     // Normal transcoding mode - output is required
     let output_path = args.output.as_ref().expect("output required when not in check mode");
 
@@ -250,7 +538,8 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
     let is_stdout = output_path.to_str() == Some("-");
 
     // Resolve format profile (Auto -> detected from extension)
-    let format = args.format.to_format_profile().resolve(Some(&args.input));
+    let format =
+        args.format.to_format_profile(args.lines_per_record as usize).resolve(Some(&args.input));
 
     // Determine effective compression level
     // --format fastq implies at least level 6 for dynamic Huffman
@@ -264,9 +553,9 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
     let index_path: Option<PathBuf> = match &args.index {
         Some(Some(path)) => Some(path.clone()),
         Some(None) => {
-            // --index without path: use output.gzi
+            // --index without path: use output.bgzf.gzi
             if !is_stdout {
-                Some(output_path.with_extension("bgzf.gzi"))
+                Some(rebgzf::index_path(output_path))
             } else {
                 eprintln!("Warning: --index requires an explicit path when output is stdout");
                 None
@@ -275,14 +564,26 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
         None => None,
     };
 
+    let requested_block_size =
+        args.block_size.unwrap_or_else(|| compression_level.default_block_size());
+    let block_size = resolve_block_size(requested_block_size)?;
+    if block_size != requested_block_size && !args.quiet {
+        eprintln!(
+            "Warning: --block-size {} exceeds the BGZF limit; using {}",
+            requested_block_size, block_size
+        );
+    }
+
     let config = TranscodeConfig {
-        block_size: args.block_size,
+        block_size,
         compression_level,
         format,
         num_threads: args.threads,
         strict_bgzf_check: args.strict,
         force_transcode: args.force,
         build_index: index_path.is_some(),
+        collect_parallel_stats: args.verbose,
+        recompress: args.recompress,
         ..Default::default()
     };
 
@@ -312,13 +613,25 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
             file.seek(SeekFrom::Start(0))?;
 
             let start = std::time::Instant::now();
-            let bytes_copied = if is_stdout {
-                let mut stdout = io::stdout().lock();
-                io::copy(&mut file, &mut stdout)?
+            let mut output: Box<dyn Write> = if is_stdout {
+                Box::new(io::stdout().lock())
             } else {
-                let mut output = BufWriter::new(File::create(output_path)?);
-                io::copy(&mut file, &mut output)?
+                Box::new(BufWriter::new(File::create(output_path)?))
             };
+            let bytes_copied = match &index_path {
+                // Index the existing blocks while copying instead of re-transcoding
+                Some(path) => {
+                    let stats = copy_with_index(&mut file, &mut output)?;
+                    let entries = stats.index_entries.unwrap_or_default();
+                    write_index_file(path, &entries, args.htslib_index)?;
+                    if args.verbose {
+                        eprintln!("Index written: {} ({} entries)", path.display(), entries.len());
+                    }
+                    stats.output_bytes
+                }
+                None => io::copy(&mut file, &mut output)?,
+            };
+            output.flush()?;
             let elapsed = start.elapsed();
 
             if args.verbose {
@@ -331,7 +644,7 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
                 );
             }
 
-            return Ok(0);
+            return Ok(RebgzfExit::Success);
         }
 
         // Not BGZF, need to reopen for transcoding
@@ -342,8 +655,8 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
     let total_size =
         if !is_stdin { std::fs::metadata(&args.input).ok().map(|m| m.len()) } else { None };
 
-    // Set up progress tracking if enabled
-    let progress_state = if args.progress {
+    // Set up progress tracking if enabled (never alongside --quiet or --json output)
+    let progress_state = if args.progress && !args.quiet && !args.json {
         Some(Arc::new(ProgressState {
             bytes_read: AtomicU64::new(0),
             total_size,
@@ -398,21 +711,15 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
                 }
             }
         }
-        if config.num_threads == 1 {
-            let mut transcoder = SingleThreadedTranscoder::new(config);
-            transcoder.transcode_slice(&mmap, output)?
-        } else {
-            // Parallel DEFLATE decode + BGZF encode
-            let mut transcoder = ParallelDecodeTranscoder::new(config);
-            transcoder.transcode_mmap(&mmap, output)?
-        }
-    } else if config.num_threads == 1 {
-        let mut transcoder = SingleThreadedTranscoder::new(config);
-        transcoder.transcode(input.expect("input must be set for streaming path"), output)?
+        // Parallel DEFLATE decode + BGZF encode
+        rebgzf::transcode_bytes(&mmap, output, config)?
     } else {
         // Fallback: parallel encoding only (for stdin/progress mode)
-        let mut transcoder = ParallelTranscoder::new(config);
-        transcoder.transcode(input.expect("input must be set for streaming path"), output)?
+        rebgzf::transcode_stream(
+            input.expect("input must be set for streaming path"),
+            output,
+            config,
+        )?
     };
 
     let elapsed = start.elapsed();
@@ -427,20 +734,30 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
 
     // Write index file if requested
     if let (Some(path), Some(entries)) = (&index_path, &stats.index_entries) {
-        let mut index_file = BufWriter::new(File::create(path)?);
-        // Write number of entries (u64 LE)
-        index_file.write_all(&(entries.len() as u64).to_le_bytes())?;
-        // Write each entry (compressed_offset, uncompressed_offset as u64 LE pairs)
-        for entry in entries {
-            index_file.write_all(&entry.compressed_offset.to_le_bytes())?;
-            index_file.write_all(&entry.uncompressed_offset.to_le_bytes())?;
-        }
-        index_file.flush()?;
+        write_index_file(path, entries, args.htslib_index)?;
 
         if args.verbose {
             eprintln!("Index written: {} ({} entries)", path.display(), entries.len());
         }
     }
+    if let (Some(path), Some(coordinates)) = (&index_path, &stats.coordinate_index) {
+        let path = rebgzf::coordinate_index_path(path);
+        let mut coords_file = BufWriter::new(File::create(&path)?);
+        write_coordinate_index(coordinates, &mut coords_file)?;
+        coords_file.flush()?;
+
+        if args.verbose {
+            eprintln!(
+                "Coordinate index written: {} ({} entries)",
+                path.display(),
+                coordinates.len()
+            );
+        }
+    }
+
+    if stats.input_was_stored && !args.quiet {
+        eprintln!("Warning: input appears uncompressed (stored blocks)");
+    }
 
     if !args.quiet && (args.verbose || args.progress) {
         eprintln!("Transcoding complete:");
@@ -448,6 +765,28 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
         eprintln!("  Output bytes:     {}", stats.output_bytes);
         eprintln!("  BGZF blocks:      {}", stats.blocks_written);
         eprintln!("  Boundary refs:    {}", stats.boundary_refs_resolved);
+        if args.verbose {
+            eprintln!("  Resolved bytes:   {}", stats.resolved_bytes);
+            eprintln!("  Uncompressed:     {}", stats.uncompressed_bytes);
+            eprintln!("  Gzip members:     {}", stats.members_processed);
+        }
+        if args.recompress {
+            eprintln!("  Recompression:    {} bytes saved", stats.recompressed_bytes_saved);
+        }
+        if args.verbose && stats.blocks_written > 0 {
+            eprintln!(
+                "  Block sizes:      {}-{} bytes uncompressed",
+                stats.min_block_uncompressed, stats.max_block_uncompressed
+            );
+        }
+        if let (true, Some(parallel)) = (args.verbose, &stats.parallel) {
+            for (i, (blocks, idle)) in
+                parallel.worker_blocks.iter().zip(&parallel.worker_idle).enumerate()
+            {
+                eprintln!("  Worker {:<3}       {} blocks, {:.2?} idle", i, blocks, idle);
+            }
+            eprintln!("  Peak buffered:    {} blocks", parallel.peak_buffered_blocks);
+        }
         eprintln!("  Time:             {:.2?}", elapsed);
         eprintln!(
             "  Throughput:       {:.1} MB/s",
@@ -455,36 +794,133 @@ fn run() -> Result<u8, Box<dyn std::error::Error>> {
         );
     }
 
-    Ok(0)
+    Ok(RebgzfExit::Success)
 }
 
-fn run_check_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
-    let is_stdin = args.input.to_str() == Some("-");
+/// Header-only validation: checks the first block without walking the file
+fn quick_validation<R: Read>(reader: &mut R) -> rebgzf::Result<BgzfValidation> {
+    Ok(BgzfValidation {
+        is_valid_bgzf: is_bgzf(reader)?,
+        block_count: None,
+        total_uncompressed_size: None,
+        has_eof_block: false,
+        blocks: None,
+    })
+}
 
-    let validation = if is_stdin {
-        let mut stdin = io::stdin().lock();
-        if args.strict {
-            // Use streaming validation for stdin (no seek required)
-            validate_bgzf_streaming(&mut stdin)?
-        } else {
-            BgzfValidation {
-                is_valid_bgzf: is_bgzf(&mut stdin)?,
-                block_count: None,
-                total_uncompressed_size: None,
-            }
-        }
+/// --check on a non-seekable stream (strict mode reads every block)
+fn check_stream<R: Read>(reader: &mut R, strict: bool) -> rebgzf::Result<CheckOutcome> {
+    let validation =
+        if strict { validate_bgzf_streaming(reader)? } else { quick_validation(reader)? };
+    Ok(CheckOutcome::new(validation))
+}
+
+/// --check on a seekable file (strict mode seeks over block payloads)
+fn check_file<R: Read + Seek>(reader: &mut R, strict: bool) -> rebgzf::Result<CheckOutcome> {
+    let validation = if strict { validate_bgzf_strict(reader)? } else { quick_validation(reader)? };
+    Ok(CheckOutcome::new(validation))
+}
+
+/// --verify on any stream, optionally copying the input to `tee`
+fn verify_stream<R: Read>(
+    reader: &mut R,
+    tee: Option<&mut dyn Write>,
+    require_eof: bool,
+) -> rebgzf::Result<VerifyOutcome> {
+    let verification = match tee {
+        Some(mut writer) => verify_bgzf_tee(reader, &mut writer)?,
+        None => verify_bgzf(reader)?,
+    };
+    Ok(VerifyOutcome::new(verification, require_eof))
+}
+
+/// Write a GZI index to `path`, in bgzip's layout with `htslib`
+fn write_index_file(path: &PathBuf, entries: &[GziEntry], htslib: bool) -> io::Result<()> {
+    let mut index_file = BufWriter::new(File::create(path)?);
+    if htslib {
+        write_gzi_htslib(entries, &mut index_file)?;
     } else {
-        let mut file = BufReader::new(File::open(&args.input)?);
+        write_gzi(entries, &mut index_file)?;
+    }
+    index_file.flush()
+}
 
-        if args.strict {
-            validate_bgzf_strict(&mut file)?
-        } else {
-            BgzfValidation {
-                is_valid_bgzf: is_bgzf(&mut file)?,
-                block_count: None,
-                total_uncompressed_size: None,
+/// Transcode one --self-test pattern at `level` with `num_threads` and check the result
+fn self_test_pattern(data: &[u8], level: u8, num_threads: usize) -> Result<(), String> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).map_err(|e| e.to_string())?;
+    let gzip_data = encoder.finish().map_err(|e| e.to_string())?;
+
+    let config = TranscodeConfig {
+        compression_level: CompressionLevel::from_level(level),
+        num_threads,
+        ..Default::default()
+    };
+    let mut bgzf = Vec::new();
+    let stats = rebgzf::transcode_stream(&gzip_data[..], &mut bgzf, config)
+        .map_err(|e| format!("transcode failed: {}", e))?;
+
+    let verification = verify_bgzf(&mut &bgzf[..]).map_err(|e| e.to_string())?;
+    let outcome = VerifyOutcome::new(verification, true);
+    if !outcome.is_valid() {
+        let error = outcome.verification.first_error.unwrap_or_else(|| "no EOF block".into());
+        return Err(format!("verification failed: {}", error));
+    }
+    let mut decoded = Vec::with_capacity(data.len());
+    flate2::read::MultiGzDecoder::new(&bgzf[..])
+        .read_to_end(&mut decoded)
+        .map_err(|e| e.to_string())?;
+    if decoded != data || stats.output_bytes != bgzf.len() as u64 {
+        return Err("decompressed output differs from the input".to_string());
+    }
+    Ok(())
+}
+
+/// Round-trip the shared test patterns through each transcoder, reporting each result
+fn run_self_test(args: &Args) -> RebgzfExit {
+    let patterns = [
+        ("random", testdata::generate_random_data(200_000, 12345)),
+        ("repetitive", testdata::generate_repetitive_data(200_000)),
+        ("mixed", testdata::generate_mixed_data(200_000)),
+        ("fastq", testdata::generate_fastq_data(2000, 150)),
+        ("all-bytes", testdata::generate_all_bytes(100)),
+    ];
+
+    let mut failures = 0;
+    for (name, data) in &patterns {
+        // Fixed and dynamic Huffman, serial and parallel
+        for (level, num_threads) in [(1, 1), (6, 1), (1, 4), (6, 4)] {
+            let result = self_test_pattern(data, level, num_threads);
+            if result.is_err() {
+                failures += 1;
+            }
+            if !args.quiet {
+                let label = format!("{} (level {}, {} threads)", name, level, num_threads);
+                match result {
+                    Ok(()) => eprintln!("PASS {}", label),
+                    Err(e) => eprintln!("FAIL {}: {}", label, e),
+                }
             }
         }
+    }
+
+    if failures == 0 {
+        RebgzfExit::Success
+    } else {
+        if !args.quiet {
+            eprintln!("Self-test: {} check(s) failed", failures);
+        }
+        RebgzfExit::SelfTestFailed
+    }
+}
+
+fn run_check_mode(args: &Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    let is_stdin = args.input.to_str() == Some("-");
+
+    let CheckOutcome { validation, exit } = if is_stdin {
+        check_stream(&mut io::stdin().lock(), args.strict)?
+    } else {
+        check_file(&mut BufReader::new(File::open(&args.input)?), args.strict)?
     };
 
     // Output results
@@ -509,22 +945,18 @@ fn run_check_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
         }
     }
 
-    if validation.is_valid_bgzf {
-        Ok(EXIT_IS_BGZF)
-    } else {
-        Ok(EXIT_NOT_BGZF)
-    }
+    Ok(exit)
 }
 
-fn run_verify_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
+fn run_verify_mode(args: &Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
     let is_stdin = args.input.to_str() == Some("-");
 
     // Get file size for progress (if not stdin)
     let total_size =
         if !is_stdin { std::fs::metadata(&args.input).ok().map(|m| m.len()) } else { None };
 
-    // Set up progress tracking if enabled
-    let progress_state = if args.progress {
+    // Set up progress tracking if enabled (never alongside --quiet or --json output)
+    let progress_state = if args.progress && !args.quiet && !args.json {
         Some(Arc::new(ProgressState {
             bytes_read: AtomicU64::new(0),
             total_size,
@@ -540,20 +972,26 @@ fn run_verify_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
 
     let start = Instant::now();
 
-    let verification: BgzfVerification = if is_stdin {
-        let stdin = io::stdin().lock();
-        if let Some(ref state) = progress_state {
-            verify_bgzf(&mut ProgressReader::new(stdin, Arc::clone(state)))?
-        } else {
-            verify_bgzf(&mut io::stdin().lock())?
-        }
+    let input: Box<dyn Read> = if is_stdin {
+        Box::new(io::stdin().lock())
     } else {
-        let file = BufReader::new(File::open(&args.input)?);
-        if let Some(ref state) = progress_state {
-            verify_bgzf(&mut ProgressReader::new(file, Arc::clone(state)))?
-        } else {
-            verify_bgzf(&mut BufReader::new(File::open(&args.input)?))?
+        Box::new(BufReader::new(File::open(&args.input)?))
+    };
+    let mut input: Box<dyn Read> = match progress_state {
+        Some(ref state) => Box::new(ProgressReader::new(input, Arc::clone(state))),
+        None => input,
+    };
+
+    let outcome = match args.tee {
+        Some(ref path) => {
+            let mut tee_output: Box<dyn Write> = if path.to_str() == Some("-") {
+                Box::new(BufWriter::new(io::stdout().lock()))
+            } else {
+                Box::new(BufWriter::new(File::create(path)?))
+            };
+            verify_stream(&mut input, Some(&mut tee_output), args.require_eof)?
         }
+        None => verify_stream(&mut input, None, args.require_eof)?,
     };
 
     let elapsed = start.elapsed();
@@ -566,21 +1004,23 @@ fn run_verify_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
         let _ = handle.join();
     }
 
-    // Determine overall validity
-    let is_valid = verification.is_valid_bgzf && verification.crc_valid && verification.isize_valid;
+    let is_valid = outcome.is_valid();
+    let verification = &outcome.verification;
 
     // Output results
     if args.json {
         println!(
-            "{{\"valid\":{},\"is_valid_bgzf\":{},\"crc_valid\":{},\"isize_valid\":{},\"block_count\":{},\"compressed_size\":{},\"uncompressed_size\":{},\"first_error_block\":{},\"first_error\":{}}}",
+            "{{\"valid\":{},\"is_valid_bgzf\":{},\"crc_valid\":{},\"isize_valid\":{},\"has_eof_block\":{},\"block_count\":{},\"compressed_size\":{},\"uncompressed_size\":{},\"first_error_block\":{},\"first_error_offset\":{},\"first_error\":{}}}",
             is_valid,
             verification.is_valid_bgzf,
             verification.crc_valid,
             verification.isize_valid,
+            verification.has_eof_block,
             verification.block_count,
             verification.compressed_size,
             verification.uncompressed_size,
             verification.first_error_block.map(|b| b.to_string()).unwrap_or_else(|| "null".to_string()),
+            verification.first_error_offset.map(|o| o.to_string()).unwrap_or_else(|| "null".to_string()),
             verification.first_error.as_ref().map(|e| format!("\"{}\"", e.replace('\"', "\\\""))).unwrap_or_else(|| "null".to_string())
         );
     } else if !args.quiet {
@@ -588,16 +1028,21 @@ fn run_verify_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
         eprintln!("BGZF structure: {}", if verification.is_valid_bgzf { "ok" } else { "invalid" });
         eprintln!("CRC32 checksums: {}", if verification.crc_valid { "ok" } else { "MISMATCH" });
         eprintln!("ISIZE values: {}", if verification.isize_valid { "ok" } else { "MISMATCH" });
+        eprintln!("EOF block: {}", if verification.has_eof_block { "ok" } else { "MISSING" });
         eprintln!("Blocks: {}", verification.block_count);
         eprintln!("Compressed size: {} bytes", verification.compressed_size);
         eprintln!("Uncompressed size: {} bytes", verification.uncompressed_size);
 
         if let Some(err) = &verification.first_error {
-            if let Some(block) = verification.first_error_block {
-                eprintln!("First error at block {}: {}", block, err);
+            if let (Some(block), Some(offset)) =
+                (verification.first_error_block, verification.first_error_offset)
+            {
+                eprintln!("First error at block {} (byte offset {}): {}", block, offset, err);
             } else {
                 eprintln!("Error: {}", err);
             }
+        } else if !verification.has_eof_block {
+            eprintln!("Warning: no BGZF EOF block; the file may be truncated");
         }
 
         if args.verbose || args.progress {
@@ -611,14 +1056,10 @@ fn run_verify_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
         }
     }
 
-    if is_valid {
-        Ok(EXIT_VERIFY_VALID)
-    } else {
-        Ok(EXIT_VERIFY_INVALID)
-    }
+    Ok(outcome.exit)
 }
 
-fn run_stats_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
+fn run_stats_mode(args: &Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
     let is_stdin = args.input.to_str() == Some("-");
 
     // Get file size
@@ -695,7 +1136,8 @@ fn run_stats_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
 
                     if let Some(blocks) = validation.block_count {
                         if blocks > 1 {
-                            let avg_compressed = (size - 28) as f64 / (blocks - 1) as f64;
+                            let avg_compressed =
+                                (size - BGZF_EOF_SIZE as u64) as f64 / (blocks - 1) as f64;
                             let avg_uncompressed = uncompressed as f64 / (blocks - 1) as f64;
                             eprintln!("Avg compressed block: {:.0} bytes", avg_compressed);
                             eprintln!("Avg uncompressed block: {:.0} bytes", avg_uncompressed);
@@ -703,11 +1145,500 @@ fn run_stats_mode(args: &Args) -> Result<u8, Box<dyn std::error::Error>> {
                     }
                 }
             }
+
+            if args.verbose {
+                print_block_size_histogram(&args.input)?;
+            }
         } else if !is_bgzf_file && !is_stdin {
             // For plain gzip, try to decompress and get size
             eprintln!("Note: For detailed gzip statistics, use --verify mode");
         }
     }
 
-    Ok(0)
+    Ok(RebgzfExit::Success)
+}
+
+fn run_decompress_mode(args: &Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    let is_stdin = args.input.to_str() == Some("-");
+    let total_size =
+        if !is_stdin { std::fs::metadata(&args.input).ok().map(|m| m.len()) } else { None };
+    let progress_state = if args.progress && !args.quiet {
+        Some(Arc::new(ProgressState {
+            bytes_read: AtomicU64::new(0),
+            total_size,
+            done: AtomicBool::new(false),
+        }))
+    } else {
+        None
+    };
+    let progress_handle =
+        progress_state.as_ref().map(|state| spawn_progress_thread(Arc::clone(state)));
+
+    // BGZF is also valid gzip, so detection only picks the faster block-wise
+    // path; a short first read from stdin just falls back to the gzip decoder
+    let mut input: Box<dyn Read + Send> = if is_stdin {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(&args.input)?))
+    };
+    let mut peeked = [0u8; 18];
+    let peeked_len = read_up_to(&mut input, &mut peeked)?;
+    let input_is_bgzf = is_bgzf(&mut &peeked[..peeked_len])?;
+    let input: Box<dyn Read + Send> =
+        Box::new(io::Cursor::new(peeked[..peeked_len].to_vec()).chain(input));
+    let input: Box<dyn Read + Send> = match progress_state {
+        Some(ref state) => Box::new(ProgressReader::new(input, Arc::clone(state))),
+        None => input,
+    };
+
+    // Plain gzip files go through the parallel reader unless progress needs the
+    // compressed bytes to flow through a reader
+    let mut decoder: Box<dyn Read> = if input_is_bgzf {
+        Box::new(BgzfReader::new(input))
+    } else if is_stdin || progress_state.is_some() {
+        Box::new(ParallelGzipReader::from_reader(input, args.threads)?)
+    } else {
+        Box::new(ParallelGzipReader::from_file(&args.input, args.threads)?)
+    };
+
+    let mut output: Box<dyn Write> = match args.output {
+        Some(ref path) if path.to_str() != Some("-") => {
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let copied = io::copy(&mut decoder, &mut output);
+
+    if let Some(ref state) = progress_state {
+        state.done.store(true, Ordering::Relaxed);
+    }
+    if let Some(handle) = progress_handle {
+        let _ = handle.join();
+    }
+
+    let uncompressed_bytes = copied?;
+    output.flush()?;
+    if args.verbose {
+        eprintln!("Decompressed {} bytes", uncompressed_bytes);
+    }
+
+    Ok(RebgzfExit::Success)
+}
+
+fn run_to_gzip_mode(args: &Args) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    let input: Box<dyn Read> = if args.input.to_str() == Some("-") {
+        Box::new(BufReader::new(io::stdin().lock()))
+    } else {
+        Box::new(BufReader::new(File::open(&args.input)?))
+    };
+    let output: Box<dyn Write> = match args.output {
+        Some(ref path) if path.to_str() != Some("-") => {
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let written = degzip(input, output, CompressionLevel::from_level(args.level))?;
+    if args.verbose {
+        eprintln!("Wrote {} bytes of gzip", written);
+    }
+    Ok(RebgzfExit::Success)
+}
+
+/// Fill as much of `buf` as `reader` has, returning the number of bytes read
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+fn run_region_mode(
+    args: &Args,
+    start: u64,
+    end: u64,
+) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    if args.input.to_str() == Some("-") {
+        return Err("--region requires a seekable input file, not stdin".into());
+    }
+
+    let mut input_gzi = args.input.clone().into_os_string();
+    input_gzi.push(".gzi");
+    let index = match args.index {
+        Some(Some(ref path)) => GziIndexBuilder::read(File::open(path)?)?,
+        _ => match File::open(&input_gzi) {
+            Ok(file) => GziIndexBuilder::read(file)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                GziIndexBuilder::build_from_stream(BufReader::new(File::open(&args.input)?))?
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    // Seek to the start of the block and skip to `start`, rather than seeking
+    // to its virtual offset, so a start past the end of the data gives an
+    // empty region; the skip and the copy both stop early at the end of the data
+    let mut output: Box<dyn Write> = match args.output {
+        Some(ref path) if path.to_str() != Some("-") => {
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let written = match index.block_containing(start) {
+        Some(block) => {
+            let mut reader = BgzfReader::new(BufReader::new(File::open(&args.input)?));
+            reader.seek_virtual(block.compressed_offset << 16)?;
+            io::copy(&mut (&mut reader).take(start - block.uncompressed_offset), &mut io::sink())?;
+            io::copy(&mut reader.take(end - start), &mut output)?
+        }
+        None => 0,
+    };
+    output.flush()?;
+
+    if !args.quiet {
+        eprintln!("Region {}..{}: {} bytes uncompressed", start, start + written, written);
+    }
+
+    Ok(RebgzfExit::Success)
+}
+
+fn run_block_range_mode(
+    args: &Args,
+    start: u64,
+    end: u64,
+) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+    if args.input.to_str() == Some("-") {
+        return Err("--block-range requires a seekable input file, not stdin".into());
+    }
+
+    let mut file = BufReader::new(File::open(&args.input)?);
+    let wanted = (end - start) as usize;
+    let blocks = iter_blocks(&mut file)
+        .skip(start as usize)
+        .take(wanted)
+        .collect::<rebgzf::Result<Vec<BlockInfo>>>()?;
+    if blocks.len() < wanted {
+        return Err(format!(
+            "block range {start}:{end} extends past the last block ({})",
+            start as usize + blocks.len()
+        )
+        .into());
+    }
+
+    let mut output: Box<dyn Write> = match args.output {
+        Some(ref path) if path.to_str() != Some("-") => {
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut uncompressed_bytes = 0u64;
+    for block in &blocks {
+        let data = inflate_block(&mut file, block)?;
+        output.write_all(&data)?;
+        uncompressed_bytes += data.len() as u64;
+    }
+    output.flush()?;
+
+    if !args.quiet {
+        eprintln!("Blocks {}..{}: {} bytes uncompressed", start, end, uncompressed_bytes);
+    }
+
+    Ok(RebgzfExit::Success)
+}
+
+/// Print a histogram of uncompressed data block sizes in 8 KiB buckets.
+fn print_block_size_histogram(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    const BUCKET_SIZE: u32 = 8192;
+    const BUCKETS: usize = 8;
+    const BAR_WIDTH: u64 = 40;
+
+    let mut counts = [0u64; BUCKETS];
+    let mut eof_blocks = 0u64;
+    for block in iter_blocks(BufReader::new(File::open(path)?)) {
+        let block = block?;
+        if block.uncompressed_size == 0 {
+            eof_blocks += 1;
+            continue;
+        }
+        // Sizes are 1..=65536, so (size - 1) / 8K always lands in 0..8
+        let bucket = ((block.uncompressed_size - 1) / BUCKET_SIZE) as usize;
+        counts[bucket.min(BUCKETS - 1)] += 1;
+    }
+
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+    eprintln!("Block size distribution (uncompressed):");
+    for (i, &count) in counts.iter().enumerate() {
+        let lo = i as u32 * BUCKET_SIZE / 1024;
+        let hi = (i as u32 + 1) * BUCKET_SIZE / 1024;
+        let bar = "#".repeat(((count * BAR_WIDTH + max_count - 1) / max_count) as usize);
+        eprintln!("  {:>2}-{:>2} KiB: {:>8} {}", lo, hi, count, bar);
+    }
+    if eof_blocks > 0 {
+        eprintln!("  empty/EOF: {:>8}", eof_blocks);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rebgzf::bgzf::block_size_from_bsize;
+    use rebgzf::{SingleThreadedTranscoder, Transcoder};
+    use std::io::Cursor;
+
+    fn gzip_bytes(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn bgzf_bytes(data: &[u8]) -> Vec<u8> {
+        let mut transcoder = SingleThreadedTranscoder::new(TranscodeConfig::default());
+        let mut output = Vec::new();
+        transcoder.transcode(Cursor::new(gzip_bytes(data)), &mut output).unwrap();
+        output
+    }
+
+    fn sample() -> Vec<u8> {
+        b"@read\nACGT\n+\nIIII\n".repeat(500)
+    }
+
+    /// Uncompressed size of each BGZF block, from its ISIZE footer field
+    fn block_isizes(bgzf: &[u8]) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        let mut pos = 0;
+        while pos < bgzf.len() {
+            let end =
+                pos + block_size_from_bsize(u16::from_le_bytes([bgzf[pos + 16], bgzf[pos + 17]]));
+            sizes.push(u32::from_le_bytes(bgzf[end - 4..end].try_into().unwrap()) as usize);
+            pos = end;
+        }
+        sizes
+    }
+
+    fn run_args(args: &[&str]) -> Result<RebgzfExit, Box<dyn std::error::Error>> {
+        run(Args::try_parse_from(std::iter::once("rebgzf").chain(args.iter().copied())).unwrap())
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(RebgzfExit::Success.as_code(), 0);
+        assert_eq!(RebgzfExit::IsBgzf.as_code(), 0);
+        assert_eq!(RebgzfExit::VerifyValid.as_code(), 0);
+        assert_eq!(RebgzfExit::NotBgzf.as_code(), 1);
+        assert_eq!(RebgzfExit::VerifyInvalid.as_code(), 1);
+        assert_eq!(RebgzfExit::Error.as_code(), 2);
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(run_args(&["--self-test", "-q"]).unwrap(), RebgzfExit::Success);
+    }
+
+    #[test]
+    fn test_parse_block_range() {
+        assert_eq!(parse_block_range("1:4"), Ok((1, 4)));
+        assert_eq!(parse_block_range("0:0"), Ok((0, 0)));
+        assert!(parse_block_range("4:1").is_err());
+        assert!(parse_block_range("4").is_err());
+        assert!(parse_block_range("a:2").is_err());
+        assert_eq!(parse_region("10:70000"), Ok((10, 70000)));
+        assert!(parse_region("5:1").is_err());
+    }
+
+    #[test]
+    fn test_resolve_block_size() {
+        assert_eq!(resolve_block_size(65280), Ok(65280));
+        assert_eq!(resolve_block_size(16384), Ok(16384));
+        assert_eq!(resolve_block_size(1), Ok(1));
+        assert_eq!(resolve_block_size(65281), Ok(BGZF_BLOCK_SIZE));
+        assert_eq!(resolve_block_size(70000), Ok(BGZF_BLOCK_SIZE));
+        assert!(resolve_block_size(0).is_err());
+    }
+
+    #[test]
+    fn test_check_decision() {
+        let bgzf = bgzf_bytes(&sample());
+        let gzip = gzip_bytes(&sample());
+
+        for strict in [false, true] {
+            let outcome = check_stream(&mut Cursor::new(&bgzf), strict).unwrap();
+            assert_eq!(outcome.exit, RebgzfExit::IsBgzf);
+            let outcome = check_file(&mut Cursor::new(&bgzf), strict).unwrap();
+            assert_eq!(outcome.exit, RebgzfExit::IsBgzf);
+
+            let outcome = check_stream(&mut Cursor::new(&gzip), strict).unwrap();
+            assert_eq!(outcome.exit, RebgzfExit::NotBgzf);
+            let outcome = check_file(&mut Cursor::new(&gzip), strict).unwrap();
+            assert_eq!(outcome.exit, RebgzfExit::NotBgzf);
+        }
+
+        let strict = check_file(&mut Cursor::new(&bgzf), true).unwrap();
+        assert_eq!(strict.validation.block_count, Some(2));
+    }
+
+    #[test]
+    fn test_verify_decision() {
+        let mut bgzf = bgzf_bytes(&sample());
+        let outcome = verify_stream(&mut Cursor::new(&bgzf), None, false).unwrap();
+        assert_eq!(outcome.exit, RebgzfExit::VerifyValid);
+
+        // Corrupt the first block's CRC32 (footer starts 8 bytes before its end)
+        let first_block_end = block_size_from_bsize(u16::from_le_bytes([bgzf[16], bgzf[17]]));
+        bgzf[first_block_end - 8] ^= 0xFF;
+        let mut copy = Vec::new();
+        let outcome = verify_stream(&mut Cursor::new(&bgzf), Some(&mut copy), false).unwrap();
+        assert_eq!(outcome.exit, RebgzfExit::VerifyInvalid);
+        assert!(!outcome.verification.crc_valid);
+        assert_eq!(copy, bgzf);
+    }
+
+    #[test]
+    fn test_run_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let gz_path = dir.path().join("input.gz");
+        let bgzf_path = dir.path().join("output.bgzf");
+        std::fs::write(&gz_path, gzip_bytes(&sample())).unwrap();
+        let gz = gz_path.to_str().unwrap();
+        let bgzf = bgzf_path.to_str().unwrap();
+
+        assert_eq!(run_args(&["-q", "--check", "-i", gz]).unwrap(), RebgzfExit::NotBgzf);
+        assert_eq!(run_args(&["-q", "-i", gz, "-o", bgzf]).unwrap(), RebgzfExit::Success);
+        assert_eq!(run_args(&["-q", "--check", "-i", bgzf]).unwrap(), RebgzfExit::IsBgzf);
+        assert_eq!(run_args(&["-q", "--verify", "-i", bgzf]).unwrap(), RebgzfExit::VerifyValid);
+
+        // Without its EOF block the data still verifies, unless the EOF is required
+        let no_eof_path = dir.path().join("no_eof.bgzf");
+        let full = std::fs::read(&bgzf_path).unwrap();
+        std::fs::write(&no_eof_path, &full[..full.len() - 28]).unwrap();
+        let no_eof = no_eof_path.to_str().unwrap();
+        assert_eq!(run_args(&["-q", "--verify", "-i", no_eof]).unwrap(), RebgzfExit::VerifyValid);
+        assert_eq!(
+            run_args(&["-q", "--verify", "--require-eof", "-i", no_eof]).unwrap(),
+            RebgzfExit::VerifyInvalid
+        );
+        assert_eq!(
+            run_args(&["-q", "--verify", "--require-eof", "-i", bgzf]).unwrap(),
+            RebgzfExit::VerifyValid
+        );
+        assert_eq!(run_args(&["-q", "--stats", "-i", bgzf]).unwrap(), RebgzfExit::Success);
+
+        // Over-limit block sizes are snapped rather than failing deep in encoding
+        let snapped = dir.path().join("snapped.bgzf");
+        let snapped_str = snapped.to_str().unwrap();
+        assert_eq!(
+            run_args(&["-q", "--block-size", "70000", "-i", gz, "-o", snapped_str]).unwrap(),
+            RebgzfExit::Success
+        );
+        assert_eq!(std::fs::read(&snapped).unwrap(), std::fs::read(&bgzf_path).unwrap());
+        assert!(run_args(&["-q", "--block-size", "0", "-i", gz, "-o", snapped_str]).is_err());
+        assert_eq!(
+            run_args(&["-q", "-l", "9", "--recompress", "-i", gz, "-o", snapped_str]).unwrap(),
+            RebgzfExit::Success
+        );
+        assert!(run_args(&["-q", "--recompress", "-i", gz, "-o", snapped_str]).is_err());
+
+        // --format lines ends each block after a whole record of N lines
+        let lines = ["-q", "--format", "lines", "--lines-per-record", "2", "--block-size", "1000"];
+        let mut args = lines.to_vec();
+        args.extend(["-i", gz, "-o", snapped_str]);
+        assert_eq!(run_args(&args).unwrap(), RebgzfExit::Success);
+        let mut offset = 0;
+        for isize in block_isizes(&std::fs::read(&snapped).unwrap()) {
+            offset += isize;
+            assert_eq!(sample()[..offset].iter().filter(|&&b| b == b'\n').count() % 2, 0);
+        }
+        assert_eq!(offset, sample().len());
+        let zero = ["--format", "lines", "--lines-per-record", "0", "-i", gz, "-o", snapped_str];
+        assert!(Args::try_parse_from(std::iter::once("rebgzf").chain(zero)).is_err());
+
+        let range_path = dir.path().join("range.out");
+        let range = range_path.to_str().unwrap();
+        assert_eq!(
+            run_args(&["-q", "--block-range", "0:1", "-i", bgzf, "-o", range]).unwrap(),
+            RebgzfExit::Success
+        );
+        assert_eq!(std::fs::read(&range_path).unwrap(), sample());
+        assert!(run_args(&["-q", "--block-range", "0:5", "-i", bgzf, "-o", range]).is_err());
+
+        // bgzip's index layout drops the first block's (0, 0) entry
+        let gzi = dir.path().join("output.gzi");
+        let htslib_gzi = dir.path().join("htslib.gzi");
+        for (path, extra) in [(&gzi, None), (&htslib_gzi, Some("--htslib-index"))] {
+            let index = format!("--index={}", path.display());
+            let mut args = vec!["-q", "--block-size", "1024", "-i", gz, "-o", snapped_str, &index];
+            args.extend(extra);
+            assert_eq!(run_args(&args).unwrap(), RebgzfExit::Success);
+        }
+        let entries = rebgzf::bgzf::read_gzi(File::open(&gzi).unwrap()).unwrap();
+        assert!(entries.len() > 2);
+        let htslib_entries = rebgzf::bgzf::read_gzi(File::open(&htslib_gzi).unwrap()).unwrap();
+        assert_eq!(htslib_entries, &entries[1..]);
+
+        // -d expands both BGZF and plain gzip, and fails on a corrupt CRC32
+        let plain_path = dir.path().join("plain.txt");
+        let plain = plain_path.to_str().unwrap();
+        for input in [bgzf, gz] {
+            for progress in [None, Some("-p")] {
+                let mut args = vec!["-q", "-d", "-i", input, "-o", plain];
+                args.extend(progress);
+                assert_eq!(run_args(&args).unwrap(), RebgzfExit::Success);
+                assert_eq!(std::fs::read(&plain_path).unwrap(), sample());
+            }
+        }
+        let mut corrupt = std::fs::read(&bgzf_path).unwrap();
+        let footer = corrupt.len() - BGZF_EOF_SIZE - 8;
+        corrupt[footer] ^= 0xff;
+        let corrupt_path = dir.path().join("corrupt.bgzf");
+        std::fs::write(&corrupt_path, corrupt).unwrap();
+        let corrupt = corrupt_path.to_str().unwrap();
+        assert!(run_args(&["-q", "-d", "-i", corrupt, "-o", plain]).is_err());
+
+        // --to-gzip writes one member holding all the data
+        let single_path = dir.path().join("single.gz");
+        let single = single_path.to_str().unwrap();
+        assert_eq!(
+            run_args(&["-q", "--to-gzip", "-l", "6", "-i", bgzf, "-o", single]).unwrap(),
+            RebgzfExit::Success
+        );
+        let gz_bytes = std::fs::read(&single_path).unwrap();
+        let mut decoder = flate2::read::GzDecoder::new(&gz_bytes[..]);
+        let mut decoded = Vec::new();
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, sample());
+        assert!(decoder.into_inner().is_empty());
+        assert!(run_args(&["-q", "--to-gzip", "-i", corrupt, "-o", single]).is_err());
+
+        // --region seeks with the index, or builds one when none is found
+        let region_path = dir.path().join("region.out");
+        let region = region_path.to_str().unwrap();
+        let data = sample();
+        let len = data.len() as u64;
+        let cases = [(1000, 7000), (1, 2), (len - 10, len + 100), (len + 5, len + 10), (0, 0)];
+        for (start, end) in cases {
+            let range = format!("{}:{}", start, end);
+            let expected = &data[(start.min(len) as usize)..(end.min(len) as usize)];
+            for gzi_path in [&gzi, &htslib_gzi] {
+                let index = format!("--index={}", gzi_path.display());
+                let args = ["-q", "--region", &range, "-i", snapped_str, "-o", region, &index];
+                assert_eq!(run_args(&args).unwrap(), RebgzfExit::Success);
+                assert_eq!(std::fs::read(&region_path).unwrap(), expected, "{}", range);
+            }
+            let args = ["-q", "--region", &range, "-i", snapped_str, "-o", region];
+            assert_eq!(run_args(&args).unwrap(), RebgzfExit::Success);
+            assert_eq!(std::fs::read(&region_path).unwrap(), expected, "{}", range);
+        }
+        // INPUT.gzi is picked up when present; a corrupt one is an error, not rebuilt
+        std::fs::write(format!("{}.gzi", snapped_str), b"bad").unwrap();
+        assert!(run_args(&["-q", "--region", "0:1", "-i", snapped_str, "-o", region]).is_err());
+        assert!(run_args(&["-q", "--region", "0:1", "-i", "-"]).is_err());
+
+        let missing = dir.path().join("missing.gz");
+        assert!(run_args(&["-q", "--check", "-i", missing.to_str().unwrap()]).is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use crate::bits::traits::BitRead;
 use crate::error::{Error, Result};
-use std::io::Read;
+use std::io::{self, Read, Seek, SeekFrom};
 
 /// Bit-level reader for DEFLATE streams backed by a `Read` source.
 ///
@@ -173,22 +173,89 @@ impl<R: Read> BitReader<R> {
         Ok(())
     }
 
-    /// Get position in bytes (approximate, for error reporting)
+    /// Skip `n` bits, which need not end on a byte boundary
+    pub fn skip_bits(&mut self, n: u64) -> Result<()> {
+        if n <= self.bits_available as u64 {
+            self.drop_buffered(n as u8);
+            return Ok(());
+        }
+        // The buffer only ever holds whole bytes past the current one, so once it
+        // is empty the reader is byte-aligned
+        let rest = n - self.bits_available as u64;
+        self.drop_buffered(self.bits_available);
+        self.skip_bytes(rest / 8)?;
+        self.read_bits((rest % 8) as u8).map(drop)
+    }
+
+    /// Skip `n` bytes (aligns to byte boundary first), draining the bit buffer
+    /// and then reading past the rest in bulk
+    pub fn skip_bytes(&mut self, n: u64) -> Result<()> {
+        self.align_to_byte();
+        let buffered = (self.bits_available / 8) as u64;
+        if n <= buffered {
+            self.drop_buffered((n * 8) as u8);
+            return Ok(());
+        }
+        self.drop_buffered(self.bits_available);
+
+        let rest = n - buffered;
+        let skipped = io::copy(&mut (&mut self.reader).take(rest), &mut io::sink())?;
+        self.bytes_read += skipped;
+        if skipped < rest {
+            return Err(Error::UnexpectedEof);
+        }
+        Ok(())
+    }
+
+    /// Drop `n` buffered bits (up to all 64)
+    fn drop_buffered(&mut self, n: u8) {
+        self.buffer = self.buffer.checked_shr(n as u32).unwrap_or(0);
+        self.bits_available -= n;
+    }
+
+    /// Bytes pulled from the underlying reader, including buffered look-ahead
     pub fn bytes_read(&self) -> u64 {
         self.bytes_read
     }
 
+    /// Bytes actually consumed by the decoder (excludes whole buffered bytes)
+    pub fn bytes_consumed(&self) -> u64 {
+        self.bytes_read - (self.bits_available / 8) as u64
+    }
+
+    /// Bits actually consumed by the decoder
+    pub fn bits_consumed(&self) -> u64 {
+        self.bytes_read * 8 - self.bits_available as u64
+    }
+
     /// Check if we have bits available without reading more
     pub fn bits_available(&self) -> u8 {
         self.bits_available
     }
 
+    /// Get the inner reader mutably
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
     /// Get the inner reader (consumes self)
     pub fn into_inner(self) -> R {
         self.reader
     }
 }
 
+impl<R: Read + Seek> BitReader<R> {
+    /// Seek the inner reader to byte `pos`, dropping any buffered bits; bytes
+    /// read are counted from there
+    pub fn seek_to(&mut self, pos: u64) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(pos))?;
+        self.buffer = 0;
+        self.bits_available = 0;
+        self.bytes_read = 0;
+        Ok(())
+    }
+}
+
 impl<R: Read> BitRead for BitReader<R> {
     #[inline]
     fn fill_buffer(&mut self, n: u8) -> Result<()> {
@@ -214,9 +281,25 @@ impl<R: Read> BitRead for BitReader<R> {
         self.align_to_byte()
     }
 
+    fn skip_bits(&mut self, n: u64) -> Result<()> {
+        self.skip_bits(n)
+    }
+
+    fn skip_bytes(&mut self, n: u64) -> Result<()> {
+        self.skip_bytes(n)
+    }
+
     fn bytes_read(&self) -> u64 {
         self.bytes_read
     }
+
+    fn bytes_consumed(&self) -> u64 {
+        self.bytes_consumed()
+    }
+
+    fn bits_consumed(&self) -> u64 {
+        self.bits_consumed()
+    }
 }
 
 #[cfg(test)]
@@ -286,4 +369,49 @@ mod tests {
         // Read 12 bits across byte boundary
         assert_eq!(reader.read_bits(12).unwrap(), 0x0FF);
     }
+
+    #[test]
+    fn test_bytes_consumed_excludes_lookahead() {
+        let data = vec![0u8; 32];
+        let mut reader = BitReader::new(data.as_slice());
+
+        reader.read_bits(3).unwrap();
+        assert!(reader.bytes_read() > 1, "expected bulk look-ahead");
+        assert_eq!(reader.bytes_consumed(), 1);
+
+        reader.align_to_byte();
+        reader.read_u16_le().unwrap();
+        assert_eq!(reader.bytes_consumed(), 3);
+    }
+
+    #[test]
+    fn test_skip_bits_and_bytes() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut reader = BitReader::new(data.as_slice());
+
+        // Within the buffer
+        reader.read_bits(3).unwrap();
+        reader.skip_bits(13).unwrap();
+        assert_eq!(reader.bytes_consumed(), 2);
+        assert_eq!(reader.read_byte().unwrap(), 2);
+
+        // Past the buffer, from the underlying reader in bulk
+        reader.skip_bytes(100).unwrap();
+        assert_eq!(reader.bytes_read(), 103);
+        assert_eq!(reader.bytes_consumed(), 103);
+        assert_eq!(reader.read_byte().unwrap(), 103);
+
+        // Unaligned bits past the buffer
+        reader.read_bits(4).unwrap();
+        reader.skip_bits(4 + 100 * 8 + 4).unwrap();
+        assert_eq!(reader.bits_consumed(), 205 * 8 + 4);
+        assert_eq!(reader.read_bits(4).unwrap(), 205 >> 4);
+
+        // skip_bytes aligns first
+        reader.skip_bytes(10).unwrap();
+        assert_eq!(reader.read_byte().unwrap(), 216);
+
+        assert!(matches!(reader.skip_bytes(40), Err(Error::UnexpectedEof)));
+        assert_eq!(reader.bytes_read(), 256);
+    }
 }
@@ -196,9 +196,37 @@ impl<'a> BitRead for SliceBitReader<'a> {
         }
     }
 
+    fn skip_bits(&mut self, n: u64) -> Result<()> {
+        let (byte, bit) = self.bit_position();
+        let target = byte as u64 * 8 + bit as u64 + n;
+        if target > self.data.len() as u64 * 8 {
+            return Err(Error::UnexpectedEof);
+        }
+        self.set_bit_position((target / 8) as usize, (target % 8) as u8);
+        Ok(())
+    }
+
+    fn skip_bytes(&mut self, n: u64) -> Result<()> {
+        self.align_to_byte();
+        let pos = self.bytes_consumed() + n;
+        if pos > self.data.len() as u64 {
+            return Err(Error::UnexpectedEof);
+        }
+        self.set_position(pos as usize);
+        Ok(())
+    }
+
     fn bytes_read(&self) -> u64 {
         self.pos as u64
     }
+
+    fn bytes_consumed(&self) -> u64 {
+        (self.pos - (self.bits_available / 8) as usize) as u64
+    }
+
+    fn bits_consumed(&self) -> u64 {
+        self.pos as u64 * 8 - self.bits_available as u64
+    }
 }
 
 #[cfg(test)]
@@ -274,6 +302,33 @@ mod tests {
         assert_eq!(reader.read_u32_le().unwrap(), 0x12345678);
     }
 
+    #[test]
+    fn test_skip_bits_and_bytes() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut reader = SliceBitReader::new(&data);
+
+        reader.read_bits(3).unwrap();
+        reader.skip_bits(13).unwrap();
+        assert_eq!(reader.bytes_consumed(), 2);
+        assert_eq!(reader.read_byte().unwrap(), 2);
+
+        reader.skip_bytes(100).unwrap();
+        assert_eq!(reader.bytes_consumed(), 103);
+        assert_eq!(reader.read_byte().unwrap(), 103);
+
+        reader.read_bits(4).unwrap();
+        reader.skip_bits(4 + 100 * 8 + 4).unwrap();
+        assert_eq!(reader.bits_consumed(), 205 * 8 + 4);
+        assert_eq!(reader.read_bits(4).unwrap(), 205 >> 4);
+
+        // skip_bytes aligns first
+        reader.skip_bytes(10).unwrap();
+        assert_eq!(reader.read_byte().unwrap(), 216);
+
+        assert!(matches!(reader.skip_bytes(40), Err(Error::UnexpectedEof)));
+        assert!(matches!(reader.skip_bits(40 * 8), Err(Error::UnexpectedEof)));
+    }
+
     #[test]
     fn test_past_position_partial_byte() {
         // After refilling 7 bytes and consuming 3 bits, we're partway through byte 0.
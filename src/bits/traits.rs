@@ -64,6 +64,21 @@ pub trait BitRead {
         Ok(())
     }
 
-    /// Get approximate position in bytes (for error reporting).
+    /// Skip `n` bits, which need not end on a byte boundary.
+    fn skip_bits(&mut self, n: u64) -> Result<()>;
+
+    /// Skip `n` bytes (aligns to byte boundary first), without decoding them
+    /// bit by bit.
+    fn skip_bytes(&mut self, n: u64) -> Result<()>;
+
+    /// Bytes pulled from the underlying input so far, including look-ahead
+    /// still sitting in the bit buffer.
     fn bytes_read(&self) -> u64;
+
+    /// Bytes the decoder has actually consumed: `bytes_read` minus whole bytes
+    /// still buffered. A partially consumed byte counts as consumed.
+    fn bytes_consumed(&self) -> u64;
+
+    /// Bits the decoder has actually consumed, counted from the reader's start.
+    fn bits_consumed(&self) -> u64;
 }
@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 /// Bit-level writer for DEFLATE output
 ///
 /// Writes bits LSB-first to match DEFLATE format.
@@ -125,6 +127,17 @@ impl BitWriter {
         &self.output
     }
 
+    /// Write all complete bytes to `writer`, keeping any partial byte buffered.
+    ///
+    /// Lets a long DEFLATE stream be written incrementally. Returns the number of bytes written.
+    pub fn drain_to<W: Write>(&mut self, writer: &mut W) -> io::Result<usize> {
+        self.flush_bytes();
+        writer.write_all(&self.output)?;
+        let written = self.output.len();
+        self.output.clear();
+        Ok(written)
+    }
+
     /// Clear the writer for reuse
     pub fn clear(&mut self) {
         self.output.clear();
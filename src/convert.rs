@@ -0,0 +1,145 @@
+//! File-to-file conversion with the same choices the `rebgzf` binary makes.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::bgzf::{
+    copy_with_index, is_bgzf, validate_bgzf_strict, write_coordinate_index, write_gzi,
+};
+use crate::error::Result;
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFile;
+use crate::transcoder::parallel_decode::ParallelDecodeTranscoder;
+use crate::transcoder::single::SingleThreadedTranscoder;
+use crate::{
+    CompressionLevel, FormatProfile, ParallelTranscoder, TranscodeConfig, TranscodeStats,
+    Transcoder,
+};
+
+/// Convert the gzip file at `input` to a BGZF file at `output`.
+///
+/// This is the library equivalent of running the binary on two paths:
+/// - Unless `force_transcode` is set, input that is already BGZF (checked with
+///   [`validate_bgzf_strict`] when `strict_bgzf_check` is set, otherwise
+///   [`is_bgzf`]) is copied unchanged and the stats have `copied_directly` set.
+/// - `FormatProfile::Auto` is resolved from the input path, and the FASTQ profile
+///   raises the compression level to at least 6 for dynamic Huffman tables.
+/// - One effective thread uses [`SingleThreadedTranscoder`]; more use
+///   [`ParallelDecodeTranscoder`](crate::ParallelDecodeTranscoder). Both read the
+///   input memory-mapped with the default `mmap` feature, and stream it without.
+/// - With `build_index`, a GZI index is written to [`index_path`]`(output)`, and
+///   for VCF input a coordinate index to [`coordinate_index_path`] of that.
+pub fn convert(input: &Path, output: &Path, mut config: TranscodeConfig) -> Result<TranscodeStats> {
+    if !config.force_transcode {
+        let mut file = BufReader::new(File::open(input)?);
+        let is_valid_bgzf = if config.strict_bgzf_check {
+            validate_bgzf_strict(&mut file)?.is_valid_bgzf
+        } else {
+            is_bgzf(&mut file)?
+        };
+
+        if is_valid_bgzf {
+            file.seek(SeekFrom::Start(0))?;
+            let mut writer = BufWriter::new(File::create(output)?);
+            let stats = if config.build_index {
+                copy_with_index(&mut file, &mut writer)?
+            } else {
+                let copied = io::copy(&mut file, &mut writer)?;
+                TranscodeStats {
+                    input_bytes: copied,
+                    output_bytes: copied,
+                    copied_directly: true,
+                    ..Default::default()
+                }
+            };
+            writer.flush()?;
+            write_index(output, &stats)?;
+            return Ok(stats);
+        }
+    }
+
+    config.format = config.format.resolve(Some(input));
+    if config.format == FormatProfile::Fastq && config.compression_level.level() < 6 {
+        config.compression_level = CompressionLevel::Level6;
+    }
+
+    let stats = transcode_file(input, output, config)?;
+    write_index(output, &stats)?;
+    Ok(stats)
+}
+
+#[cfg(feature = "mmap")]
+fn transcode_file(input: &Path, output: &Path, config: TranscodeConfig) -> Result<TranscodeStats> {
+    let mmap = MappedFile::open(input)?;
+    #[cfg(unix)]
+    {
+        // Only a hint; the read pattern is sequential either way
+        let _ = mmap.advise_sequential();
+    }
+    // The transcoders buffer their own output
+    transcode_bytes(&mmap, File::create(output)?, config)
+}
+
+/// Without the `mmap` feature, the input is streamed instead
+#[cfg(not(feature = "mmap"))]
+fn transcode_file(input: &Path, output: &Path, config: TranscodeConfig) -> Result<TranscodeStats> {
+    transcode_stream(File::open(input)?, File::create(output)?, config)
+}
+
+/// Transcode gzip `data` held in memory, e.g. a memory-mapped file, as
+/// [`convert`] does: with [`SingleThreadedTranscoder`] for one effective
+/// thread, and [`ParallelDecodeTranscoder`] for more.
+pub fn transcode_bytes<W: Write>(
+    data: &[u8],
+    output: W,
+    config: TranscodeConfig,
+) -> Result<TranscodeStats> {
+    if config.effective_threads() == 1 {
+        SingleThreadedTranscoder::new(config).transcode_slice(data, output)
+    } else {
+        ParallelDecodeTranscoder::new(config).transcode_mmap(data, output)
+    }
+}
+
+/// Transcode a gzip stream that cannot be memory-mapped, e.g. stdin, as
+/// [`convert`] does without the `mmap` feature: with [`SingleThreadedTranscoder`]
+/// for one effective thread, and [`ParallelTranscoder`] for more.
+pub fn transcode_stream<R: Read, W: Write>(
+    input: R,
+    output: W,
+    config: TranscodeConfig,
+) -> Result<TranscodeStats> {
+    if config.effective_threads() == 1 {
+        SingleThreadedTranscoder::new(config).transcode(input, output)
+    } else {
+        ParallelTranscoder::new(config).transcode(input, output)
+    }
+}
+
+/// Where [`convert`] and the binary's bare `--index` write the GZI index for `output`
+pub fn index_path(output: &Path) -> PathBuf {
+    output.with_extension("bgzf.gzi")
+}
+
+/// Where [`convert`] and the binary write the VCF coordinate index that goes
+/// with the GZI index at `index`
+pub fn coordinate_index_path(index: &Path) -> PathBuf {
+    index.with_extension("coords")
+}
+
+/// Write the indexes in `stats`, if any were built
+fn write_index(output: &Path, stats: &TranscodeStats) -> Result<()> {
+    if let Some(entries) = &stats.index_entries {
+        let mut index_file = BufWriter::new(File::create(index_path(output))?);
+        write_gzi(entries, &mut index_file)?;
+        index_file.flush()?;
+    }
+    if let Some(coordinates) = &stats.coordinate_index {
+        let path = coordinate_index_path(&index_path(output));
+        let mut coords_file = BufWriter::new(File::create(path)?);
+        write_coordinate_index(coordinates, &mut coords_file)?;
+        coords_file.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! LZ77 token statistics for compression analysis.
+
+use std::io::Read;
+
+use super::parser::DeflateParser;
+use super::tokens::LZ77Token;
+use crate::error::Result;
+use crate::gzip::GzipHeader;
+use crate::huffman::encoder::FrequencyCounter;
+
+/// Aggregate LZ77 token mix of a gzip file, as produced by [`analyze`].
+///
+/// Histograms are indexed by DEFLATE code: `length_codes[i]` counts length
+/// code `257 + i` and `distance_codes[i]` counts distance code `i` (RFC 1951
+/// section 3.2.5).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenStats {
+    /// Gzip members parsed
+    pub members: u64,
+    /// DEFLATE blocks parsed, across all members
+    pub blocks: u64,
+    /// Literal tokens
+    pub literals: u64,
+    /// Copy (length, distance) tokens
+    pub copies: u64,
+    /// Uncompressed bytes produced by copies
+    pub copied_bytes: u64,
+    /// Copies per length code (257-285)
+    pub length_codes: [u64; 29],
+    /// Copies per distance code (0-29)
+    pub distance_codes: [u64; 30],
+}
+
+impl TokenStats {
+    /// Total uncompressed bytes the tokens expand to
+    pub fn uncompressed_size(&self) -> u64 {
+        self.literals + self.copied_bytes
+    }
+
+    /// Fraction of uncompressed bytes produced by copies (0.0 for empty input)
+    pub fn copy_ratio(&self) -> f64 {
+        match self.uncompressed_size() {
+            0 => 0.0,
+            total => self.copied_bytes as f64 / total as f64,
+        }
+    }
+
+    /// Mean copy length in bytes (0.0 if there are no copies)
+    pub fn mean_copy_length(&self) -> f64 {
+        match self.copies {
+            0 => 0.0,
+            copies => self.copied_bytes as f64 / copies as f64,
+        }
+    }
+
+    /// Add one block's code frequencies
+    fn add_block(&mut self, freq: &FrequencyCounter) {
+        self.literals += freq.literal_freq[..256].iter().map(|&n| n as u64).sum::<u64>();
+        for (total, &n) in self.length_codes.iter_mut().zip(&freq.literal_freq[257..]) {
+            *total += n as u64;
+        }
+        for (total, &n) in self.distance_codes.iter_mut().zip(&freq.distance_freq) {
+            *total += n as u64;
+        }
+    }
+}
+
+/// Parse every member of the gzip file in `gzip_reader` into LZ77 tokens and
+/// aggregate their statistics, without decompressing or re-encoding.
+///
+/// Per-block counts come from a [`FrequencyCounter`], so the histograms match
+/// what the dynamic Huffman encoder would see block by block.
+pub fn analyze<R: Read>(mut gzip_reader: R) -> Result<TokenStats> {
+    let (_header, header_len) = GzipHeader::parse_with_len(&mut gzip_reader)?;
+    let mut parser = DeflateParser::with_input_offset(gzip_reader, header_len);
+    let mut stats = TokenStats::default();
+
+    loop {
+        stats.members += 1;
+        while let Some(block) = parser.parse_block()? {
+            let mut freq = FrequencyCounter::new();
+            freq.count_tokens(&block.tokens);
+            stats.add_block(&freq);
+            stats.blocks += 1;
+
+            for token in &block.tokens {
+                if let LZ77Token::Copy { length, .. } = token {
+                    stats.copies += 1;
+                    stats.copied_bytes += *length as u64;
+                }
+            }
+        }
+        if !parser.read_trailer_and_check_next()? {
+            break;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(6));
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_analyze_repetitive_vs_random() {
+        let repetitive = b"ACGTACGTTTGACCA\n".repeat(20_000);
+        let stats = analyze(&gzip(&repetitive)[..]).unwrap();
+        assert_eq!(stats.members, 1);
+        assert_eq!(stats.uncompressed_size(), repetitive.len() as u64);
+        assert_eq!(stats.length_codes.iter().sum::<u64>(), stats.copies);
+        assert_eq!(stats.distance_codes.iter().sum::<u64>(), stats.copies);
+        assert!(stats.copy_ratio() > 0.99, "copy ratio {}", stats.copy_ratio());
+        // Long runs: most copies are the maximum length (code 285)
+        assert!(stats.length_codes[28] * 2 > stats.copies);
+
+        let mut state = 1u32;
+        let random: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        let stats = analyze(&gzip(&random)[..]).unwrap();
+        assert_eq!(stats.uncompressed_size(), random.len() as u64);
+        assert!(stats.copy_ratio() < 0.01, "copy ratio {}", stats.copy_ratio());
+        assert!(stats.literals > stats.copies * 100);
+    }
+
+    #[test]
+    fn test_analyze_concatenated_members() {
+        let mut gz = gzip(b"hello hello hello hello");
+        gz.extend(gzip(b"world"));
+        let stats = analyze(&gz[..]).unwrap();
+        assert_eq!(stats.members, 2);
+        assert_eq!(stats.uncompressed_size(), 28);
+        assert!(stats.copies >= 1);
+    }
+}
@@ -0,0 +1,100 @@
+//! Whole-stream DEFLATE decoding built on [`DeflateParser`].
+
+use std::io::{Read, Write};
+
+use super::parser::DeflateParser;
+use super::tokens::LZ77Token;
+use crate::error::Result;
+use crate::transcoder::SlidingWindow;
+
+/// Decompress a raw DEFLATE stream (no gzip header or trailer) into memory.
+///
+/// Stored, fixed, and dynamic blocks are all handled; decoding stops after the
+/// final block, leaving anything that follows it unread. Convenient for tests
+/// and whole-member decodes; libdeflate is much faster for bulk work.
+pub fn inflate<R: Read>(reader: R) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    inflate_to(reader, &mut output)?;
+    Ok(output)
+}
+
+/// Decompress a raw DEFLATE stream into `writer` one block at a time, returning
+/// the number of bytes written.
+pub fn inflate_to<R: Read, W: Write>(reader: R, mut writer: W) -> Result<u64> {
+    let mut parser = DeflateParser::new(reader);
+    let mut window = SlidingWindow::new();
+    let mut block_output = Vec::new();
+    let mut written = 0u64;
+    while let Some(block) = parser.parse_block()? {
+        block_output.clear();
+        expand_tokens(&block.tokens, &mut window, &mut block_output)?;
+        writer.write_all(&block_output)?;
+        written += block_output.len() as u64;
+    }
+    Ok(written)
+}
+
+/// Append the bytes `tokens` expand to onto `output`, resolving copies against
+/// `window` and pushing everything produced into it.
+pub(crate) fn expand_tokens(
+    tokens: &[LZ77Token],
+    window: &mut SlidingWindow,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    for &token in tokens {
+        match token {
+            LZ77Token::Literal(byte) => {
+                output.push(byte);
+                window.push_byte(byte);
+            }
+            LZ77Token::Copy { length, distance } => {
+                let start = output.len();
+                window.copy_to_vec(distance, length, output)?;
+                window.push_bytes(&output[start..]);
+            }
+            LZ77Token::EndOfBlock => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    fn deflate(data: &[u8], level: u32) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(level));
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_inflate_block_types() {
+        let data: Vec<u8> =
+            (0..30_000u32).flat_map(|i| format!("row {} {}\n", i % 97, i).into_bytes()).collect();
+        // Level 0 writes stored blocks; a short input gets fixed Huffman, a long one dynamic
+        for (input, level) in [(&data[..], 0), (&b"hello hello hello"[..], 6), (&data[..], 6)] {
+            assert_eq!(inflate(&deflate(input, level)[..]).unwrap(), input);
+        }
+
+        // Decoding stops at the final block, ignoring trailing bytes
+        let mut stream = deflate(&data, 9);
+        stream.extend_from_slice(b"trailing");
+        let mut output = Vec::new();
+        assert_eq!(inflate_to(&stream[..], &mut output).unwrap(), data.len() as u64);
+        assert_eq!(output, data);
+
+        assert!(inflate(&[][..]).is_err());
+    }
+
+    #[test]
+    fn test_inflate_rejects_distance_past_start() {
+        // Fixed block: a length-3 copy at distance 1 with nothing written yet
+        // (BFINAL=1, BTYPE=01, length code 257, distance code 0, end of block)
+        let stream = [0x03, 0x02, 0x00];
+        assert!(matches!(inflate(&stream[..]), Err(Error::InvalidBackReference { .. })));
+    }
+}
@@ -0,0 +1,188 @@
+use super::tokens::LZ77Token;
+
+/// Maximum LZ77 back-reference distance (the DEFLATE window size)
+const WINDOW_SIZE: usize = 32768;
+
+/// Shortest and longest matches DEFLATE can encode
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Number of hash buckets for 3-byte prefixes
+const HASH_BITS: u32 = 15;
+const HASH_SIZE: usize = 1 << HASH_BITS;
+
+/// Sentinel for an empty hash bucket or chain link
+const NONE: u32 = u32::MAX;
+
+/// Greedy hash-chain LZ77 match finder for raw bytes.
+///
+/// Input is fed in chunks; the last 32KB of each chunk is kept so matches can
+/// reach back across chunk boundaries, as they may within one DEFLATE stream.
+pub struct Matcher {
+    /// Previous input still within the window
+    history: Vec<u8>,
+    /// Maximum chain entries examined per position
+    max_chain: usize,
+    /// Most recent position for each hash bucket
+    head: Vec<u32>,
+    /// Previous position with the same hash, indexed by position
+    prev: Vec<u32>,
+}
+
+impl Matcher {
+    /// Create a matcher that examines up to `max_chain` candidates per position
+    pub fn new(max_chain: usize) -> Self {
+        Self {
+            history: Vec::new(),
+            max_chain: max_chain.max(1),
+            head: vec![NONE; HASH_SIZE],
+            prev: Vec::new(),
+        }
+    }
+
+    /// Forget earlier input, so the next chunk is matched on its own
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// Tokenize the next chunk of input.
+    ///
+    /// Copies may refer to bytes from earlier chunks.
+    pub fn tokenize(&mut self, data: &[u8]) -> Vec<LZ77Token> {
+        let history_len = self.history.len();
+        let mut buf = std::mem::take(&mut self.history);
+        buf.extend_from_slice(data);
+
+        self.head.fill(NONE);
+        self.prev.clear();
+        self.prev.resize(buf.len(), NONE);
+        for pos in 0..history_len {
+            self.insert(&buf, pos);
+        }
+
+        let mut tokens = Vec::with_capacity(data.len() / 2);
+        let mut pos = history_len;
+        while pos < buf.len() {
+            let (length, distance) = self.longest_match(&buf, pos);
+            if length >= MIN_MATCH {
+                tokens.push(LZ77Token::Copy { length: length as u16, distance: distance as u16 });
+                for p in pos..pos + length {
+                    self.insert(&buf, p);
+                }
+                pos += length;
+            } else {
+                tokens.push(LZ77Token::Literal(buf[pos]));
+                self.insert(&buf, pos);
+                pos += 1;
+            }
+        }
+
+        let keep = buf.len().min(WINDOW_SIZE);
+        buf.drain(..buf.len() - keep);
+        self.history = buf;
+        tokens
+    }
+
+    /// Link `pos` into its hash chain (positions without 3 bytes left are skipped)
+    #[inline]
+    fn insert(&mut self, buf: &[u8], pos: usize) {
+        if pos + MIN_MATCH > buf.len() {
+            return;
+        }
+        let hash = hash3(&buf[pos..]);
+        self.prev[pos] = self.head[hash];
+        self.head[hash] = pos as u32;
+    }
+
+    /// Find the longest earlier match for `pos` within the window: (length, distance)
+    fn longest_match(&self, buf: &[u8], pos: usize) -> (usize, usize) {
+        if pos + MIN_MATCH > buf.len() {
+            return (0, 0);
+        }
+        let max_len = (buf.len() - pos).min(MAX_MATCH);
+        let mut best = (0, 0);
+        let mut candidate = self.head[hash3(&buf[pos..])];
+
+        for _ in 0..self.max_chain {
+            if candidate == NONE {
+                break;
+            }
+            let start = candidate as usize;
+            let distance = pos - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+
+            let length = buf[start..start + max_len]
+                .iter()
+                .zip(&buf[pos..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, distance);
+                if length == max_len {
+                    break;
+                }
+            }
+            candidate = self.prev[start];
+        }
+        best
+    }
+}
+
+#[inline]
+fn hash3(bytes: &[u8]) -> usize {
+    let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+    (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand tokens back to bytes, continuing from `output`
+    fn expand(tokens: &[LZ77Token], output: &mut Vec<u8>) {
+        for token in tokens {
+            match *token {
+                LZ77Token::Literal(byte) => output.push(byte),
+                LZ77Token::Copy { length, distance } => {
+                    let start = output.len() - distance as usize;
+                    for i in 0..length as usize {
+                        output.push(output[start + i]);
+                    }
+                }
+                LZ77Token::EndOfBlock => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokenize_roundtrip_across_chunks() {
+        let text = b"the quick brown fox jumps over the lazy dog; ".repeat(2000);
+        let mut matcher = Matcher::new(16);
+        let mut output = Vec::new();
+        let mut copies = 0;
+        for chunk in text.chunks(10_000) {
+            let tokens = matcher.tokenize(chunk);
+            copies += tokens.iter().filter(|t| matches!(t, LZ77Token::Copy { .. })).count();
+            for token in &tokens {
+                if let LZ77Token::Copy { length, distance } = *token {
+                    assert!((3..=258).contains(&length) && distance as usize <= WINDOW_SIZE);
+                }
+            }
+            expand(&tokens, &mut output);
+        }
+        assert_eq!(output, text);
+        assert!(copies > 0 && copies < text.len() / 100, "{} copies", copies);
+    }
+
+    #[test]
+    fn test_tokenize_short_input() {
+        let mut matcher = Matcher::new(4);
+        assert!(matcher.tokenize(b"").is_empty());
+        assert_eq!(
+            matcher.tokenize(b"ab"),
+            vec![LZ77Token::Literal(b'a'), LZ77Token::Literal(b'b')]
+        );
+    }
+}
@@ -1,6 +1,14 @@
+pub mod analyze;
+pub mod inflate;
+pub mod matcher;
 pub mod parser;
+pub mod resume;
 pub mod tables;
 pub mod tokens;
 
+pub use analyze::{analyze, TokenStats};
+pub use inflate::{inflate, inflate_to};
+pub use matcher::Matcher;
 pub use parser::DeflateParser;
+pub use resume::{resume_at, ResumedDecoder};
 pub use tokens::{LZ77Block, LZ77Token};
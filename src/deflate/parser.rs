@@ -2,8 +2,9 @@ use super::tables::{CODE_LENGTH_ORDER, DISTANCE_TABLE, LENGTH_TABLE};
 use super::tokens::{CodeLengths, LZ77Block, LZ77Token};
 use crate::bits::{BitRead, BitReader, SliceBitReader};
 use crate::error::{Error, Result};
+use crate::gzip::header::check_header_crc;
 use crate::huffman::HuffmanDecoder;
-use std::io::Read;
+use std::io::{Read, Seek};
 
 /// Parses DEFLATE blocks and extracts LZ77 stream.
 ///
@@ -13,12 +14,68 @@ pub struct DeflateParser<B: BitRead> {
     bits: B,
     /// Whether we've seen the final block
     finished: bool,
+    /// Zero-based index of the gzip member currently being parsed
+    member: u64,
+    /// Input bytes that precede the bit reader's first byte
+    input_offset: u64,
+    /// CRC32 and ISIZE from the most recently read gzip trailer
+    trailer: Option<(u32, u32)>,
+    /// Input offset just past the most recently read gzip trailer
+    member_end: u64,
+    /// Check the FHCRC field of each following member's header
+    verify_header_crc: bool,
 }
 
 impl<R: Read> DeflateParser<BitReader<R>> {
     /// Create a parser from a `Read` source (stdin, network, etc.)
     pub fn new(reader: R) -> Self {
-        Self { bits: BitReader::new(reader), finished: false }
+        Self::with_input_offset(reader, 0)
+    }
+
+    /// Create a parser whose reader starts `input_offset` bytes into the input
+    /// (e.g. just after a gzip header that was already read), so positions it
+    /// reports are relative to the start of the input.
+    pub fn with_input_offset(reader: R, input_offset: u64) -> Self {
+        Self {
+            bits: BitReader::new(reader),
+            finished: false,
+            member: 0,
+            input_offset,
+            trailer: None,
+            member_end: 0,
+            verify_header_crc: false,
+        }
+    }
+}
+
+impl<R: Read + Seek> DeflateParser<BitReader<R>> {
+    /// Create a parser positioned `bit_offset` bits into `reader`, which must be
+    /// the start of a DEFLATE block (e.g. one recorded with [`bit_position`]).
+    ///
+    /// Tokens are parsed as usual; copies may reach into output from before the
+    /// offset, which the caller must supply to resolve them.
+    ///
+    /// [`bit_position`]: DeflateParser::bit_position
+    pub fn at_bit_offset(reader: R, bit_offset: u64) -> Result<Self> {
+        let mut parser = Self::new(reader);
+        parser.rewind(bit_offset)?;
+        Ok(parser)
+    }
+
+    /// Move back to `bit_offset`, a block boundary from [`bit_position`],
+    /// dropping everything read since (e.g. to retry a block whose input had
+    /// not all arrived).
+    ///
+    /// [`bit_position`]: DeflateParser::bit_position
+    pub fn rewind(&mut self, bit_offset: u64) -> Result<()> {
+        let start = bit_offset / 8;
+        self.bits.seek_to(start)?;
+        self.input_offset = start;
+        let skip = (bit_offset % 8) as u8;
+        if skip > 0 {
+            self.bits.read_bits(skip)?;
+        }
+        Ok(())
     }
 }
 
@@ -28,7 +85,15 @@ impl<'a> DeflateParser<SliceBitReader<'a>> {
     pub fn from_slice(data: &'a [u8], offset: usize) -> Self {
         let mut bits = SliceBitReader::new(data);
         bits.set_position(offset);
-        Self { bits, finished: false }
+        Self {
+            bits,
+            finished: false,
+            member: 0,
+            input_offset: 0,
+            trailer: None,
+            member_end: 0,
+            verify_header_crc: false,
+        }
     }
 }
 
@@ -289,9 +354,48 @@ impl<B: BitRead> DeflateParser<B> {
         Ok(tokens)
     }
 
-    /// Get bytes read so far
+    /// Bytes pulled from the input so far, including the bit reader's look-ahead
     pub fn bytes_read(&self) -> u64 {
-        self.bits.bytes_read()
+        self.input_offset + self.bits.bytes_read()
+    }
+
+    /// Bytes of input actually consumed (the position of the next unread byte)
+    pub fn bytes_consumed(&self) -> u64 {
+        self.input_offset + self.bits.bytes_consumed()
+    }
+
+    /// Bit position of the next unread bit, relative to the start of the input.
+    ///
+    /// Taken between blocks, this is a block boundary that [`at_bit_offset`]
+    /// can resume from.
+    ///
+    /// [`at_bit_offset`]: DeflateParser::at_bit_offset
+    pub fn bit_position(&self) -> u64 {
+        self.input_offset * 8 + self.bits.bits_consumed()
+    }
+
+    /// Zero-based index of the gzip member currently being parsed
+    pub fn member_index(&self) -> u64 {
+        self.member
+    }
+
+    /// CRC32 and ISIZE from the trailer last read by
+    /// [`read_trailer_and_check_next`](Self::read_trailer_and_check_next)
+    pub fn last_trailer(&self) -> Option<(u32, u32)> {
+        self.trailer
+    }
+
+    /// Input offset where the member whose trailer was last read by
+    /// [`read_trailer_and_check_next`](Self::read_trailer_and_check_next) ends
+    pub fn member_end(&self) -> u64 {
+        self.member_end
+    }
+
+    /// Check the FHCRC field of each gzip header read by
+    /// [`read_trailer_and_check_next`](Self::read_trailer_and_check_next), when
+    /// present, failing with [`Error::GzipHeaderCrcMismatch`] (default: false).
+    pub fn set_verify_header_crc(&mut self, verify: bool) {
+        self.verify_header_crc = verify;
     }
 
     /// Check if we've finished parsing
@@ -299,6 +403,11 @@ impl<B: BitRead> DeflateParser<B> {
         self.finished
     }
 
+    /// Get the underlying bit reader mutably
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.bits
+    }
+
     /// Get the underlying bit reader (for reading trailer)
     pub fn into_inner(self) -> B {
         self.bits
@@ -315,11 +424,14 @@ impl<B: BitRead> DeflateParser<B> {
         // Align to byte boundary (discard any remaining bits)
         self.bits.align_to_byte();
 
-        // Read CRC32 and ISIZE (we don't validate them, just skip)
-        let _crc32 = self.bits.read_u32_le()?;
-        let _isize = self.bits.read_u32_le()?;
+        // Read CRC32 and ISIZE; checking them is up to the caller
+        let crc32 = self.bits.read_u32_le()?;
+        let isize = self.bits.read_u32_le()?;
+        self.trailer = Some((crc32, isize));
 
         // Try to read the next gzip magic bytes
+        let member_offset = self.bytes_consumed();
+        self.member_end = member_offset;
         match self.bits.read_bits(8) {
             Ok(b1) => {
                 match self.bits.read_bits(8) {
@@ -330,16 +442,23 @@ impl<B: BitRead> DeflateParser<B> {
                             // Read compression method
                             let method = self.bits.read_bits(8)? as u8;
                             if method != 8 {
-                                return Err(Error::UnsupportedCompressionMethod(method));
+                                return Err(Error::UnsupportedCompressionMethod {
+                                    method,
+                                    member: self.member + 1,
+                                    offset: member_offset,
+                                });
                             }
 
                             // Read flags
                             let flags = self.bits.read_bits(8)? as u8;
 
+                            // Header bytes so far, kept only to check FHCRC
+                            let mut header = self
+                                .verify_header_crc
+                                .then(|| vec![b1 as u8, b2 as u8, method, flags]);
+
                             // Skip mtime (4 bytes), xfl (1), os (1)
-                            let _mtime = self.bits.read_u32_le()?;
-                            let _xfl = self.bits.read_bits(8)?;
-                            let _os = self.bits.read_bits(8)?;
+                            self.skip_header_bytes(6, &mut header)?;
 
                             // Handle optional fields based on flags
                             const FEXTRA: u8 = 1 << 2;
@@ -349,35 +468,30 @@ impl<B: BitRead> DeflateParser<B> {
 
                             if flags & FEXTRA != 0 {
                                 let xlen = self.bits.read_u16_le()?;
-                                for _ in 0..xlen {
-                                    self.bits.read_bits(8)?;
+                                if let Some(header) = &mut header {
+                                    header.extend_from_slice(&xlen.to_le_bytes());
                                 }
+                                self.skip_header_bytes(xlen as usize, &mut header)?;
                             }
 
                             if flags & FNAME != 0 {
-                                // Read null-terminated string
-                                loop {
-                                    if self.bits.read_bits(8)? == 0 {
-                                        break;
-                                    }
-                                }
+                                self.skip_header_string(&mut header)?;
                             }
 
                             if flags & FCOMMENT != 0 {
-                                // Read null-terminated string
-                                loop {
-                                    if self.bits.read_bits(8)? == 0 {
-                                        break;
-                                    }
-                                }
+                                self.skip_header_string(&mut header)?;
                             }
 
                             if flags & FHCRC != 0 {
-                                let _hcrc = self.bits.read_u16_le()?;
+                                let header_crc = self.bits.read_u16_le()?;
+                                if let Some(header) = &header {
+                                    check_header_crc(header, header_crc)?;
+                                }
                             }
 
                             // Reset finished flag for next member
                             self.finished = false;
+                            self.member += 1;
                             Ok(true)
                         } else {
                             // Not a gzip header - probably garbage or wrong format
@@ -392,6 +506,31 @@ impl<B: BitRead> DeflateParser<B> {
             Err(e) => Err(e),
         }
     }
+
+    /// Skip `n` gzip header bytes, appending them to `header` if it is kept.
+    fn skip_header_bytes(&mut self, n: usize, header: &mut Option<Vec<u8>>) -> Result<()> {
+        match header {
+            Some(header) => {
+                let start = header.len();
+                header.resize(start + n, 0);
+                self.bits.read_bytes(&mut header[start..])
+            }
+            None => self.bits.skip_bytes(n as u64),
+        }
+    }
+
+    /// Skip a null-terminated gzip header string, appending it to `header` if it is kept.
+    fn skip_header_string(&mut self, header: &mut Option<Vec<u8>>) -> Result<()> {
+        loop {
+            let byte = self.bits.read_bits(8)? as u8;
+            if let Some(header) = header {
+                header.push(byte);
+            }
+            if byte == 0 {
+                return Ok(());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -466,4 +605,56 @@ mod tests {
         assert!(!lit_decoder.is_empty());
         let _ = dist_decoder;
     }
+
+    #[test]
+    fn test_next_member_header_fields_are_skipped() {
+        use crate::gzip::GzipHeader;
+        use std::io::Write;
+
+        let member = |data: &[u8]| {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            let mut bytes = encoder.finish().unwrap();
+            bytes.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes
+        };
+        let header = GzipHeader {
+            compression_method: 8,
+            flags: 0,
+            mtime: 0,
+            extra_flags: 0,
+            os: 255,
+            extra: Some(vec![b'x'; 300]),
+            filename: Some("second.txt".into()),
+            comment: Some("a comment".into()),
+            header_crc: Some(0),
+        };
+        let mut data = member(b"first member");
+        header.write(&mut data).unwrap();
+        data.extend(member(b"second member"));
+
+        fn parse_members<B: BitRead>(parser: &mut DeflateParser<B>) -> Vec<(u32, u32)> {
+            let mut trailers = Vec::new();
+            loop {
+                while parser.parse_block().unwrap().is_some() {}
+                let more = parser.read_trailer_and_check_next().unwrap();
+                trailers.push(parser.last_trailer().unwrap());
+                if !more {
+                    return trailers;
+                }
+            }
+        }
+        let expected =
+            vec![(crc32fast::hash(b"first member"), 12), (crc32fast::hash(b"second member"), 13)];
+
+        let mut parser = DeflateParser::new(Cursor::new(&data));
+        assert_eq!(parse_members(&mut parser), expected);
+        assert_eq!(parser.bytes_consumed(), data.len() as u64);
+
+        let mut parser = DeflateParser::from_slice(&data, 0);
+        assert_eq!(parse_members(&mut parser), expected);
+        assert_eq!(parser.bytes_consumed(), data.len() as u64);
+    }
 }
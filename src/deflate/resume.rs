@@ -0,0 +1,108 @@
+//! Resuming DEFLATE decoding mid-stream from a primed window.
+
+use std::io::{Read, Seek};
+
+use super::inflate::expand_tokens;
+use super::parser::DeflateParser;
+use crate::bits::BitReader;
+use crate::error::Result;
+use crate::transcoder::SlidingWindow;
+
+/// Decoder for the tail of a DEFLATE stream, started at a block boundary.
+///
+/// Created by [`resume_at`]. The window holds the (up to 32KB of) output that
+/// precedes the boundary, so copies reaching back past it resolve exactly as
+/// in a serial decode. Two halves of one gzip member can therefore be decoded
+/// independently once the first half's last 32KB is known.
+pub struct ResumedDecoder<R: Read> {
+    parser: DeflateParser<BitReader<R>>,
+    window: SlidingWindow,
+}
+
+/// Resume decoding `reader` at `bit_offset`, the start of a DEFLATE block,
+/// with `window` primed with the output that precedes it.
+pub fn resume_at<R: Read + Seek>(
+    reader: R,
+    bit_offset: u64,
+    window: SlidingWindow,
+) -> Result<ResumedDecoder<R>> {
+    Ok(ResumedDecoder { parser: DeflateParser::at_bit_offset(reader, bit_offset)?, window })
+}
+
+impl<R: Read> ResumedDecoder<R> {
+    /// Decode the next block, returning its bytes, or `None` after the final block.
+    pub fn decode_block(&mut self) -> Result<Option<Vec<u8>>> {
+        let Some(block) = self.parser.parse_block()? else {
+            return Ok(None);
+        };
+
+        let mut output = Vec::with_capacity(block.tokens.len() * 2);
+        expand_tokens(&block.tokens, &mut self.window, &mut output)?;
+        Ok(Some(output))
+    }
+
+    /// Decode every remaining block through the end of the DEFLATE stream.
+    pub fn decode_to_end(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        while let Some(block) = self.decode_block()? {
+            output.extend_from_slice(&block);
+        }
+        Ok(output)
+    }
+
+    /// Bit position just past the last decoded block
+    pub fn bit_position(&self) -> u64 {
+        self.parser.bit_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_resume_second_half_with_primed_window() {
+        // Records drawn from a small vocabulary: many blocks, full of long-distance copies
+        let mut data = Vec::new();
+        let mut state = 7u32;
+        for _ in 0..40_000 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let (id, base) = (state >> 8 & 0xFFFF, (state >> 24) as usize % 4);
+            writeln!(data, "read{} {} quality IIHHG", id, &"ACGTTGCA"[base..base + 4]).unwrap();
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(6));
+        encoder.write_all(&data).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        // Serial pass: record each block's start bit and how much output precedes it
+        let header_len = 10;
+        let mut parser = DeflateParser::with_input_offset(&gz[header_len..], header_len as u64);
+        let mut boundaries = Vec::new();
+        let mut produced = 0usize;
+        loop {
+            let bit = parser.bit_position();
+            let Some(block) = parser.parse_block().unwrap() else { break };
+            boundaries.push((bit, produced));
+            produced += block.tokens.iter().map(|t| t.uncompressed_size()).sum::<usize>();
+        }
+        assert_eq!(produced, data.len());
+        assert!(boundaries.len() >= 4, "need several blocks, got {}", boundaries.len());
+
+        // Decode the second half on its own, primed with the 32KB before the split
+        let (split_bit, split_out) = boundaries[boundaries.len() / 2];
+        let mut window = SlidingWindow::new();
+        window.push_bytes(&data[split_out.saturating_sub(32768)..split_out]);
+        let mut decoder = resume_at(Cursor::new(&gz), split_bit, window).unwrap();
+        assert_eq!(decoder.decode_to_end().unwrap(), &data[split_out..]);
+
+        // Without the window, copies into the first half cannot be resolved
+        let mut unprimed = resume_at(Cursor::new(&gz), split_bit, SlidingWindow::new()).unwrap();
+        assert!(unprimed.decode_to_end().is_err());
+
+        // The decoder stops within the last byte before the trailer
+        assert_eq!(((decoder.bit_position() + 7) / 8) as usize, gz.len() - 8);
+    }
+}
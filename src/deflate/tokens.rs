@@ -46,7 +46,25 @@ impl LZ77Block {
     }
 
     /// Total uncompressed size of this block
-    pub fn uncompressed_size(&self) -> usize {
-        self.tokens.iter().map(|t| t.uncompressed_size()).sum()
+    ///
+    /// DEFLATE blocks have no size limit, so this is `u64` rather than `usize`.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.tokens.iter().map(|t| t.uncompressed_size() as u64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_uncompressed_size_past_4gib() {
+        // A single DEFLATE block can decode to more than u32::MAX bytes; the sum
+        // must neither wrap nor overflow where usize is 32 bits
+        let count = (u32::MAX as usize) / 258 + 1;
+        let block =
+            LZ77Block::new(vec![LZ77Token::Copy { length: 258, distance: 1 }; count], true, 1);
+        assert_eq!(block.uncompressed_size(), count as u64 * 258);
+        assert!(block.uncompressed_size() > u32::MAX as u64);
     }
 }
@@ -10,8 +10,16 @@ pub enum Error {
     #[error("Invalid gzip magic bytes: expected 0x1f8b, got 0x{0:04x}")]
     InvalidGzipMagic(u16),
 
-    #[error("Unsupported compression method: {0} (only DEFLATE/8 supported)")]
-    UnsupportedCompressionMethod(u8),
+    #[error(
+        "Unsupported compression method {method} in gzip member {member} at byte offset {offset} (only DEFLATE/8 supported)"
+    )]
+    UnsupportedCompressionMethod {
+        method: u8,
+        /// Zero-based index of the gzip member whose header is bad
+        member: u64,
+        /// Approximate byte offset of that member's header in the input
+        offset: u64,
+    },
 
     #[error("Gzip header CRC mismatch: expected 0x{expected:04x}, got 0x{found:04x}")]
     GzipHeaderCrcMismatch { expected: u16, found: u16 },
@@ -51,6 +59,22 @@ pub enum Error {
     #[error("Compressed data exceeds BGZF block limit")]
     CompressedDataTooLarge,
 
+    #[error("Invalid BGZF block header at byte offset {offset}")]
+    InvalidBgzfHeader { offset: u64 },
+
+    #[error("Failed to inflate BGZF block at byte offset {offset}")]
+    BlockInflateFailed { offset: u64 },
+
+    #[error("Invalid GZI index: {0}")]
+    InvalidGzi(String),
+
+    #[error("Virtual offset 0x{0:x} points past the end of its block")]
+    InvalidVirtualOffset(u64),
+
+    // Configuration errors
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
     // Checksum errors
     #[error("CRC32 mismatch: expected 0x{expected:08x}, got 0x{found:08x}")]
     Crc32Mismatch { expected: u32, found: u32 },
@@ -0,0 +1,127 @@
+//! Standalone gzip compression of raw bytes.
+
+use std::io::{Read, Write};
+
+use crate::bits::BitWriter;
+use crate::deflate::Matcher;
+use crate::error::Result;
+use crate::huffman::HuffmanEncoder;
+use crate::CompressionLevel;
+
+/// Raw input compressed per DEFLATE block
+const CHUNK_SIZE: usize = 128 * 1024;
+
+/// Gzip header: magic, CM=8 (DEFLATE), no flags, MTIME=0, XFL=0, OS=255 (unknown)
+const GZIP_HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+/// Compress `raw_input` into a single-member gzip file written to `writer`.
+///
+/// Matches are found with a greedy hash-chain search whose effort grows with
+/// `level`, and each 128KB of input becomes one non-final DEFLATE block using
+/// the level's fixed or dynamic Huffman codes. Input is streamed, so memory use
+/// does not grow with its size. Returns the number of bytes written.
+pub fn encode<R: Read, W: Write>(
+    mut raw_input: R,
+    mut writer: W,
+    level: CompressionLevel,
+) -> Result<u64> {
+    let mut matcher = Matcher::new(max_chain(level));
+    let mut encoder = HuffmanEncoder::new(level.use_fixed_huffman());
+    let mut bits = BitWriter::with_capacity(CHUNK_SIZE);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut input_size: u32 = 0;
+
+    writer.write_all(&GZIP_HEADER)?;
+    let mut written = GZIP_HEADER.len() as u64;
+
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = read_full(&mut raw_input, &mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&chunk[..n]);
+        input_size = input_size.wrapping_add(n as u32);
+
+        let tokens = matcher.tokenize(&chunk[..n]);
+        encoder.encode_to(&mut bits, &tokens, false)?;
+        written += bits.drain_to(&mut writer)? as u64;
+    }
+
+    // An empty fixed block ends the stream
+    encoder.set_use_fixed(true);
+    encoder.encode_to(&mut bits, &[], true)?;
+    let tail = bits.finish();
+    writer.write_all(&tail)?;
+
+    // Trailer: CRC32 + ISIZE (input size mod 2^32)
+    writer.write_all(&hasher.finalize().to_le_bytes())?;
+    writer.write_all(&input_size.to_le_bytes())?;
+    writer.flush()?;
+
+    Ok(written + tail.len() as u64 + 8)
+}
+
+/// Hash-chain candidates examined per position at each level
+fn max_chain(level: CompressionLevel) -> usize {
+    match level.level() {
+        1..=3 => 4,
+        4..=6 => 32,
+        _ => 128,
+    }
+}
+
+/// Read until `buf` is full or the input ends
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Cursor;
+
+    fn roundtrip(data: &[u8], level: CompressionLevel) -> Vec<u8> {
+        let mut gz = Vec::new();
+        let written = encode(Cursor::new(data), &mut gz, level).unwrap();
+        assert_eq!(written, gz.len() as u64);
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gz[..]).read_to_end(&mut decoded).expect("flate2 should decode output");
+        assert_eq!(decoded, data, "round trip failed at {:?}", level);
+        gz
+    }
+
+    #[test]
+    fn test_encode_roundtrip_levels() {
+        let mut data = b"ACGTTGCA".repeat(20_000);
+        let mut state = 1u32;
+        data.extend((0..100_000).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        }));
+
+        for level in [1, 4, 6, 9].map(CompressionLevel::from_level) {
+            let gz = roundtrip(&data, level);
+            assert!(gz.len() < data.len() * 2 / 3, "{:?}: {} bytes", level, gz.len());
+        }
+    }
+
+    #[test]
+    fn test_encode_empty_and_tiny() {
+        for data in [&b""[..], b"a", b"abcabcabc"] {
+            roundtrip(data, CompressionLevel::Level1);
+            roundtrip(data, CompressionLevel::Level6);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::error::{Error, Result};
-use std::io::Read;
+use std::io::{Read, Write};
 
 /// Gzip header flags (RFC 1952)
 const FTEXT: u8 = 1 << 0;
@@ -37,7 +37,11 @@ impl GzipHeader {
         // Compression method (must be 8 for DEFLATE)
         let compression_method = buf[2];
         if compression_method != 8 {
-            return Err(Error::UnsupportedCompressionMethod(compression_method));
+            return Err(Error::UnsupportedCompressionMethod {
+                method: compression_method,
+                member: 0,
+                offset: 0,
+            });
         }
 
         let flags = buf[3];
@@ -85,6 +89,89 @@ impl GzipHeader {
         })
     }
 
+    /// Parse a gzip header and also return its length in bytes
+    pub fn parse_with_len<R: Read>(reader: &mut R) -> Result<(Self, u64)> {
+        let mut counted = reader.take(u64::MAX);
+        let header = Self::parse(&mut counted)?;
+        Ok((header, u64::MAX - counted.limit()))
+    }
+
+    /// Parse a gzip header and return its length, as
+    /// [`parse_with_len`](Self::parse_with_len) does, and with `verify_crc` also
+    /// check its FHCRC field (when present) against the header bytes before it,
+    /// returning [`Error::GzipHeaderCrcMismatch`] if they disagree.
+    pub fn parse_with_len_checked<R: Read>(
+        reader: &mut R,
+        verify_crc: bool,
+    ) -> Result<(Self, u64)> {
+        if !verify_crc {
+            return Self::parse_with_len(reader);
+        }
+        let mut recorder = Recorder { reader, bytes: Vec::new() };
+        let header = Self::parse(&mut recorder)?;
+        let bytes = recorder.bytes;
+        if let Some(expected) = header.header_crc {
+            check_header_crc(&bytes[..bytes.len() - 2], expected)?;
+        }
+        Ok((header, bytes.len() as u64))
+    }
+
+    /// Write the header: the fixed fields, then FEXTRA, FNAME, FCOMMENT, and
+    /// FHCRC for each optional field that is present.
+    ///
+    /// The FEXTRA, FNAME, FCOMMENT, and FHCRC flags are set from which fields
+    /// are `Some` rather than taken from `flags`, whose other bits are kept;
+    /// FHCRC is recomputed over the bytes written, ignoring `header_crc`'s value.
+    /// Names and comments are written in Latin-1, as RFC 1952 specifies, so a
+    /// parsed Latin-1 string keeps its bytes; one with characters past U+00FF,
+    /// which Latin-1 cannot hold, is written as UTF-8. An extra field over 65535
+    /// bytes or a string containing NUL is an [`Error::InvalidConfig`].
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        let optional_flags = FHCRC | FEXTRA | FNAME | FCOMMENT;
+        let mut flags = self.flags & !optional_flags;
+        for (present, flag) in [
+            (self.extra.is_some(), FEXTRA),
+            (self.filename.is_some(), FNAME),
+            (self.comment.is_some(), FCOMMENT),
+            (self.header_crc.is_some(), FHCRC),
+        ] {
+            if present {
+                flags |= flag;
+            }
+        }
+
+        let mut bytes = vec![0x1f, 0x8b, self.compression_method, flags];
+        bytes.extend_from_slice(&self.mtime.to_le_bytes());
+        bytes.extend_from_slice(&[self.extra_flags, self.os]);
+        if let Some(extra) = &self.extra {
+            let xlen = u16::try_from(extra.len()).map_err(|_| {
+                Error::InvalidConfig(format!("gzip extra field too long: {} bytes", extra.len()))
+            })?;
+            bytes.extend_from_slice(&xlen.to_le_bytes());
+            bytes.extend_from_slice(extra);
+        }
+        for text in [&self.filename, &self.comment].into_iter().flatten() {
+            if text.contains('\0') {
+                return Err(Error::InvalidConfig(format!(
+                    "gzip header string contains NUL: {:?}",
+                    text
+                )));
+            }
+            match text.chars().map(|c| u8::try_from(c).ok()).collect::<Option<Vec<u8>>>() {
+                Some(latin1) => bytes.extend_from_slice(&latin1),
+                None => bytes.extend_from_slice(text.as_bytes()),
+            }
+            bytes.push(0);
+        }
+        if self.header_crc.is_some() {
+            // CRC16 is the low half of the CRC32 of the preceding header bytes
+            let crc16 = crc32fast::hash(&bytes) as u16;
+            bytes.extend_from_slice(&crc16.to_le_bytes());
+        }
+        w.write_all(&bytes)?;
+        Ok(())
+    }
+
     /// Check if the FTEXT flag is set
     pub fn is_text(&self) -> bool {
         self.flags & FTEXT != 0
@@ -109,6 +196,29 @@ impl GzipHeader {
     pub fn has_header_crc(&self) -> bool {
         self.flags & FHCRC != 0
     }
+
+    /// Split the FEXTRA field into its subfields as (SI1, SI2, data).
+    ///
+    /// Parsing stops at the first subfield whose length runs past the end of
+    /// the extra field; returns an empty list when there is no extra field.
+    pub fn subfields(&self) -> Vec<(u8, u8, Vec<u8>)> {
+        let mut subfields = Vec::new();
+        let Some(extra) = self.extra.as_deref() else {
+            return subfields;
+        };
+
+        let mut pos = 0;
+        while pos + 4 <= extra.len() {
+            let len = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+            let start = pos + 4;
+            if start + len > extra.len() {
+                break;
+            }
+            subfields.push((extra[pos], extra[pos + 1], extra[start..start + len].to_vec()));
+            pos = start + len;
+        }
+        subfields
+    }
 }
 
 /// Gzip trailer (8 bytes at end of file)
@@ -131,6 +241,30 @@ impl GzipTrailer {
     }
 }
 
+/// Check a header's FHCRC value against the header bytes that precede it: the
+/// low 16 bits of their CRC32
+pub(crate) fn check_header_crc(bytes: &[u8], expected: u16) -> Result<()> {
+    let found = crc32fast::hash(bytes) as u16;
+    if found != expected {
+        return Err(Error::GzipHeaderCrcMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// Reader that keeps a copy of every byte read through it
+struct Recorder<'a, R> {
+    reader: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> Read for Recorder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
 /// Read a null-terminated string from a reader
 fn read_null_terminated_string<R: Read>(reader: &mut R) -> Result<String> {
     let mut bytes = Vec::new();
@@ -195,6 +329,112 @@ mod tests {
         assert_eq!(header.filename.as_deref(), Some("test.txt"));
     }
 
+    #[test]
+    fn test_parse_with_len_counts_raw_bytes() {
+        // Latin-1 filename: the decoded String is longer than the raw bytes
+        let mut data = vec![0x1f, 0x8b, 0x08, 0x08, 0, 0, 0, 0, 0, 0x03];
+        data.extend_from_slice(&[b'c', 0xe9, 0x00]);
+        data.extend_from_slice(b"deflate data follows");
+
+        let (header, len) = GzipHeader::parse_with_len(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(header.filename.as_deref(), Some("c\u{e9}"));
+        assert_eq!(len, 13);
+    }
+
+    #[test]
+    fn test_parse_checks_header_crc() {
+        let mut data = vec![0x1f, 0x8b, 0x08, FNAME | FHCRC, 0, 0, 0, 0, 0, 0x03];
+        data.extend_from_slice(b"a.txt\0");
+        let crc16 = crc32fast::hash(&data) as u16;
+        data.extend_from_slice(&crc16.to_le_bytes());
+        data.extend_from_slice(b"deflate data follows");
+
+        let (header, len) = GzipHeader::parse_with_len_checked(&mut &data[..], true).unwrap();
+        assert_eq!(header.header_crc, Some(crc16));
+        assert_eq!(len, 18);
+
+        data[16] ^= 0x01;
+        let result = GzipHeader::parse_with_len_checked(&mut &data[..], true);
+        assert!(matches!(
+            result,
+            Err(Error::GzipHeaderCrcMismatch { expected, found }) if expected == crc16 ^ 1 && found == crc16
+        ));
+        // Unchecked unless asked
+        assert!(GzipHeader::parse_with_len_checked(&mut &data[..], false).is_ok());
+    }
+
+    #[test]
+    fn test_write_round_trips_optional_fields() {
+        let fixed = |flags: u8| vec![0x1f, 0x8b, 0x08, flags, 0x78, 0x56, 0x34, 0x12, 0x02, 0x03];
+        let with_crc = |mut bytes: Vec<u8>| {
+            let crc16 = crc32fast::hash(&bytes) as u16;
+            bytes.extend_from_slice(&crc16.to_le_bytes());
+            bytes
+        };
+        let mut extra = fixed(FEXTRA);
+        extra.extend_from_slice(&[6, 0, b'B', b'C', 2, 0, 0x1b, 0]);
+        let mut name = fixed(FNAME | FTEXT);
+        name.extend_from_slice(b"reads.fastq\0");
+        let mut comment = fixed(FCOMMENT);
+        comment.extend_from_slice(b"made by a test\0");
+        let mut all = fixed(FEXTRA | FNAME | FCOMMENT | FHCRC);
+        all.extend_from_slice(&[4, 0, b'X', b'Y', 0, 0]);
+        all.extend_from_slice(b"a.txt\0note\0");
+
+        for bytes in [fixed(0), extra, name, comment, with_crc(fixed(FHCRC)), with_crc(all)] {
+            let header = GzipHeader::parse(&mut Cursor::new(&bytes)).unwrap();
+            let mut written = Vec::new();
+            header.write(&mut written).unwrap();
+            assert_eq!(written, bytes);
+
+            let reparsed = GzipHeader::parse(&mut Cursor::new(&written)).unwrap();
+            assert_eq!(reparsed.flags, header.flags);
+            assert_eq!(reparsed.filename, header.filename);
+            assert_eq!(reparsed.header_crc, header.header_crc);
+        }
+    }
+
+    #[test]
+    fn test_write_keeps_latin1_strings() {
+        let mut bytes = vec![0x1f, 0x8b, 0x08, FNAME | FCOMMENT, 0, 0, 0, 0, 0, 0x03];
+        bytes.extend_from_slice(&[b'c', 0xe9, 0x00, b'n', 0xf6, b't', 0x00]);
+        let header = GzipHeader::parse(&mut Cursor::new(&bytes)).unwrap();
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        // Characters Latin-1 cannot hold are written as UTF-8, which parse reads first
+        let header = GzipHeader { filename: Some("\u{65e5}.txt".to_string()), ..header };
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        let reparsed = GzipHeader::parse(&mut Cursor::new(&written)).unwrap();
+        assert_eq!(reparsed.filename, header.filename);
+    }
+
+    #[test]
+    fn test_write_sets_flags_from_fields() {
+        let header = GzipHeader {
+            compression_method: 8,
+            flags: FNAME | FTEXT,
+            mtime: 0,
+            extra_flags: 0,
+            os: 255,
+            extra: None,
+            filename: None,
+            comment: Some("hi".to_string()),
+            header_crc: Some(0),
+        };
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        let reparsed = GzipHeader::parse(&mut Cursor::new(&written)).unwrap();
+        assert_eq!(reparsed.flags, FTEXT | FCOMMENT | FHCRC);
+        assert_eq!(reparsed.comment.as_deref(), Some("hi"));
+        assert_eq!(reparsed.header_crc, Some(crc32fast::hash(&written[..13]) as u16));
+
+        let bad = GzipHeader { filename: Some("a\0b".to_string()), ..header };
+        assert!(matches!(bad.write(&mut Vec::new()), Err(Error::InvalidConfig(_))));
+    }
+
     #[test]
     fn test_invalid_magic() {
         let data = vec![0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
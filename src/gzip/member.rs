@@ -0,0 +1,139 @@
+//! Member-level access to a (possibly concatenated) gzip stream.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use super::header::GzipHeader;
+use crate::deflate::DeflateParser;
+use crate::error::{Error, Result};
+
+/// Iterator over the members of a concatenated gzip stream.
+///
+/// Yields each member's parsed header and its compressed byte range (header
+/// through trailer). The DEFLATE payload is walked with the parser to find the
+/// trailer but is not decompressed to bytes. Iteration stops cleanly at the end
+/// of the stream, or after the first error.
+pub struct GzipMemberIter<R: Read + Seek> {
+    reader: R,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read + Seek> GzipMemberIter<R> {
+    /// Iterate over members starting at the reader's current position.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let offset = reader.stream_position()?;
+        Ok(Self { reader, offset, done: false })
+    }
+
+    fn read_member(&mut self) -> Result<Option<(GzipHeader, Range<u64>)>> {
+        let start = self.offset;
+        self.reader.seek(SeekFrom::Start(start))?;
+
+        // Clean end of stream: no bytes left after the previous trailer
+        let mut first = [0u8; 1];
+        if self.reader.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+        self.reader.seek(SeekFrom::Start(start))?;
+
+        let header = GzipHeader::parse(&mut self.reader)?;
+        let deflate_start = self.reader.stream_position()?;
+
+        let mut parser = DeflateParser::new(&mut self.reader);
+        while parser.parse_block()?.is_some() {}
+
+        // The bit reader reads ahead; only whole bytes it has consumed belong to DEFLATE
+        let mut bits = parser.into_inner();
+        bits.align_to_byte();
+        let trailer_start = deflate_start + bits.bytes_consumed();
+
+        self.reader.seek(SeekFrom::Start(trailer_start))?;
+        let mut trailer = [0u8; 8];
+        self.reader.read_exact(&mut trailer).map_err(|_| Error::UnexpectedEof)?;
+
+        let end = trailer_start + trailer.len() as u64;
+        self.offset = end;
+        Ok(Some((header, start..end)))
+    }
+}
+
+impl<R: Read + Seek> Iterator for GzipMemberIter<R> {
+    type Item = Result<(GzipHeader, Range<u64>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_member() {
+            Ok(Some(member)) => Some(Ok(member)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, GzBuilder};
+    use std::io::{Cursor, Write};
+
+    fn member(name: &str, mtime: u32, data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            GzBuilder::new().filename(name).mtime(mtime).write(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_iter_three_members() {
+        let members = [
+            member("a.txt", 1, b"first member"),
+            member("b.txt", 2, &b"second member ".repeat(1000)),
+            member("c.txt", 3, b""),
+        ];
+        let stream = members.concat();
+
+        let parsed: Vec<_> =
+            GzipMemberIter::new(Cursor::new(&stream)).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(parsed.len(), 3);
+
+        let names: Vec<_> = parsed.iter().map(|(h, _)| h.filename.clone().unwrap()).collect();
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(parsed.iter().map(|(h, _)| h.mtime).collect::<Vec<_>>(), [1, 2, 3]);
+
+        // Ranges are contiguous, non-overlapping, and match each member exactly
+        let mut expected_start = 0;
+        for ((_, range), bytes) in parsed.iter().zip(&members) {
+            assert_eq!(range.start, expected_start);
+            assert_eq!(range.end - range.start, bytes.len() as u64);
+            expected_start = range.end;
+        }
+        assert_eq!(expected_start, stream.len() as u64);
+    }
+
+    #[test]
+    fn test_iter_truncated_member() {
+        let mut stream = member("a.txt", 0, b"complete");
+        let second = member("b.txt", 0, b"truncated");
+        stream.extend_from_slice(&second[..second.len() - 4]);
+
+        let mut iter = GzipMemberIter::new(Cursor::new(&stream)).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(matches!(iter.next(), Some(Err(Error::UnexpectedEof))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_empty_stream() {
+        let mut iter = GzipMemberIter::new(Cursor::new(Vec::new())).unwrap();
+        assert!(iter.next().is_none());
+    }
+}
@@ -1,3 +1,7 @@
+pub mod encode;
 pub mod header;
+pub mod member;
 
+pub use encode::encode;
 pub use header::GzipHeader;
+pub use member::GzipMemberIter;
@@ -1,7 +1,8 @@
 use crate::bits::BitWriter;
 use crate::deflate::tables::{encode_distance, encode_length, CODE_LENGTH_ORDER};
 use crate::deflate::tokens::LZ77Token;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use std::ops::Range;
 
 /// Maximum code length for literal/length and distance alphabets (RFC 1951)
 const MAX_CODE_LENGTH: u8 = 15;
@@ -9,7 +10,23 @@ const MAX_CODE_LENGTH: u8 = 15;
 /// Maximum code length for the code length alphabet
 const MAX_CL_CODE_LENGTH: u8 = 7;
 
+/// Granularity (in tokens) at which sub-block splitting considers a new DEFLATE block
+const SUBBLOCK_CHUNK_TOKENS: usize = 4096;
+
+/// Maximum payload of one stored DEFLATE block (LEN is 16 bits)
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// One self-contained DEFLATE stream: (DEFLATE bytes, CRC32, uncompressed size)
+pub type FittedBlock = (Vec<u8>, u32, u32);
+
 /// Frequency counter for dynamic Huffman code generation
+///
+/// Counts are `u32` because a counter only ever covers the tokens of one
+/// DEFLATE block, which is bounded: every token expands to at least one byte,
+/// so a BGZF block (at most 64KB uncompressed) or a `gzip::encode` chunk holds
+/// far fewer than `u32::MAX` tokens. Totals spanning a whole member or file
+/// must be kept as `u64` instead (see `TranscodeStats`); do not aggregate these
+/// counters across blocks. Code-length construction sums them as `u64`.
 #[derive(Clone, Debug)]
 pub struct FrequencyCounter {
     /// Frequencies for literal (0-255), EOB (256), and length codes (257-285)
@@ -77,6 +94,16 @@ impl FrequencyCounter {
         // Always need at least 1 distance code
         (last + 1).max(1)
     }
+
+    /// Add another counter's frequencies to this one
+    pub fn merge(&mut self, other: &FrequencyCounter) {
+        for (a, b) in self.literal_freq.iter_mut().zip(other.literal_freq.iter()) {
+            *a += b;
+        }
+        for (a, b) in self.distance_freq.iter_mut().zip(other.distance_freq.iter()) {
+            *a += b;
+        }
+    }
 }
 
 impl Default for FrequencyCounter {
@@ -86,275 +113,336 @@ impl Default for FrequencyCounter {
 }
 
 /// Compute optimal Huffman code lengths for given frequencies with a maximum length limit.
-/// Uses a simplified package-merge algorithm.
+/// Uses the package-merge algorithm, which finds the lengths minimizing the coded size
+/// among all codes no longer than `max_bits`.
 ///
-/// Returns a vector of code lengths (0 for unused symbols).
+/// Returns a vector of code lengths (0 for unused symbols). With two or more used
+/// symbols the code is complete: `sum(2^-len) == 1`. There must be at most
+/// `2^max_bits` of them.
 pub fn compute_code_lengths(frequencies: &[u32], max_bits: u8) -> Vec<u8> {
     let n = frequencies.len();
-    if n == 0 {
-        return vec![];
-    }
+    let mut lengths = vec![0u8; n];
 
-    // Collect symbols with non-zero frequency
-    let symbols: Vec<(usize, u32)> =
+    // Symbols with non-zero frequency, least frequent first
+    let mut symbols: Vec<(usize, u32)> =
         frequencies.iter().enumerate().filter(|(_, &f)| f > 0).map(|(i, &f)| (i, f)).collect();
+    symbols.sort_by_key(|&(sym, freq)| (freq, sym));
 
-    if symbols.is_empty() {
-        return vec![0; n];
-    }
-
-    // Special case: only one symbol
+    // Special case: one symbol still needs a code
     if symbols.len() == 1 {
-        let mut lengths = vec![0u8; n];
         lengths[symbols[0].0] = 1;
         return lengths;
     }
 
-    // Special case: two symbols
-    if symbols.len() == 2 {
-        let mut lengths = vec![0u8; n];
-        lengths[symbols[0].0] = 1;
-        lengths[symbols[1].0] = 1;
-        return lengths;
+    let weights: Vec<u64> = symbols.iter().map(|&(_, freq)| freq as u64).collect();
+    for (&(sym, _), len) in symbols.iter().zip(package_merge(&weights, max_bits)) {
+        lengths[sym] = len;
     }
-
-    // Build Huffman tree using a priority queue approach
-    // Then limit lengths if needed
-    let mut lengths = build_huffman_lengths(&symbols, n);
-
-    // Limit code lengths to max_bits
-    limit_code_lengths(&mut lengths, &symbols, max_bits);
-
     lengths
 }
 
-/// Build initial Huffman code lengths (may exceed max_bits)
-fn build_huffman_lengths(symbols: &[(usize, u32)], n: usize) -> Vec<u8> {
-    use std::cmp::Reverse;
-    use std::collections::BinaryHeap;
-
-    #[derive(Clone)]
-    struct Node {
-        freq: u64,
-        symbols: Vec<usize>, // Leaf symbols in this subtree
-        depth: u8,
-    }
-
-    impl PartialEq for Node {
-        fn eq(&self, other: &Self) -> bool {
-            self.freq == other.freq
-        }
+/// Length-limited code lengths for `weights`, sorted ascending, by package-merge.
+///
+/// Each of `max_bits` rounds pairs up the previous round's items into packages and
+/// merges them with the leaves; the cheapest `2n - 2` items of the last round select
+/// the code. A leaf's length is the number of rounds in which it was selected, and
+/// since items merge in weight order the selected leaves are always a prefix.
+fn package_merge(weights: &[u64], max_bits: u8) -> Vec<u8> {
+    let n = weights.len();
+    let mut lengths = vec![0u8; n];
+    if n < 2 {
+        return lengths;
     }
-    impl Eq for Node {}
-    impl PartialOrd for Node {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            Some(self.cmp(other))
+    assert!(n <= 1 << max_bits, "{} symbols cannot be coded in {} bits", n, max_bits);
+
+    // For each round, whether each item of its merged list is a leaf
+    let mut rounds: Vec<Vec<bool>> = Vec::with_capacity(max_bits as usize);
+    let mut previous: Vec<u64> = Vec::new();
+    for _ in 0..max_bits {
+        let packages: Vec<u64> = previous.chunks_exact(2).map(|pair| pair[0] + pair[1]).collect();
+        let mut merged = Vec::with_capacity(n + packages.len());
+        let mut is_leaf = Vec::with_capacity(n + packages.len());
+        let (mut leaf, mut package) = (0, 0);
+        while leaf < n || package < packages.len() {
+            // Leaves first on ties, so the choice is deterministic
+            if package == packages.len() || (leaf < n && weights[leaf] <= packages[package]) {
+                merged.push(weights[leaf]);
+                is_leaf.push(true);
+                leaf += 1;
+            } else {
+                merged.push(packages[package]);
+                is_leaf.push(false);
+                package += 1;
+            }
         }
+        rounds.push(is_leaf);
+        previous = merged;
     }
-    impl Ord for Node {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.freq.cmp(&other.freq)
+
+    // Walk back from the last round; each selected package selects two items before it
+    let mut selected = 2 * n - 2;
+    for is_leaf in rounds.iter().rev() {
+        let leaves = is_leaf[..selected].iter().filter(|&&leaf| leaf).count();
+        for len in &mut lengths[..leaves] {
+            *len += 1;
         }
+        selected = 2 * (selected - leaves);
     }
+    lengths
+}
 
-    let mut heap: BinaryHeap<Reverse<Node>> = symbols
-        .iter()
-        .map(|&(sym, freq)| Reverse(Node { freq: freq as u64, symbols: vec![sym], depth: 0 }))
-        .collect();
-
-    // Build tree by combining lowest frequency nodes
-    while heap.len() > 1 {
-        let Reverse(left) = heap.pop().unwrap();
-        let Reverse(right) = heap.pop().unwrap();
+/// Huffman encoder for DEFLATE output
+pub struct HuffmanEncoder {
+    use_fixed: bool,
+    /// Split dynamic output into several DEFLATE blocks where token statistics shift
+    split_subblocks: bool,
+    /// Fixed literal/length codes (precomputed)
+    fixed_lit_codes: Vec<(u32, u8)>,
+    /// Fixed distance codes (precomputed)
+    fixed_dist_codes: Vec<(u32, u8)>,
+}
 
-        let mut combined_symbols = left.symbols;
-        combined_symbols.extend(right.symbols);
+impl HuffmanEncoder {
+    pub fn new(use_fixed: bool) -> Self {
+        let fixed_lit_codes = build_fixed_literal_codes();
+        let fixed_dist_codes = build_fixed_distance_codes();
 
-        heap.push(Reverse(Node {
-            freq: left.freq + right.freq,
-            symbols: combined_symbols,
-            depth: left.depth.max(right.depth) + 1,
-        }));
+        Self { use_fixed, split_subblocks: false, fixed_lit_codes, fixed_dist_codes }
     }
 
-    // Extract depths by traversing from root
-    let mut lengths = vec![0u8; n];
-
-    if heap.pop().is_some() {
-        // BFS to compute depths
-        compute_depths_bfs(symbols, &mut lengths);
+    /// Switch between fixed and dynamic Huffman encoding
+    pub fn set_use_fixed(&mut self, use_fixed: bool) {
+        self.use_fixed = use_fixed;
     }
 
-    lengths
-}
-
-/// Compute code lengths using BFS on a reconstructed tree
-fn compute_depths_bfs(symbols: &[(usize, u32)], lengths: &mut [u8]) {
-    use std::cmp::Reverse;
-    use std::collections::BinaryHeap;
-
-    // Rebuild tree properly tracking depths
-    #[derive(Clone)]
-    enum TreeNode {
-        Leaf(usize), // symbol index
-        Internal(Box<TreeNode>, Box<TreeNode>),
+    /// Allow dynamic output to be split into several DEFLATE blocks.
+    ///
+    /// Each sub-block gets Huffman tables fitted to its own region; only the last
+    /// one carries BFINAL. The split is kept only when it is smaller than a single
+    /// block, so enabling this never grows the output. Has no effect with fixed codes.
+    pub fn set_split_subblocks(&mut self, split_subblocks: bool) {
+        self.split_subblocks = split_subblocks;
     }
 
-    #[derive(Clone)]
-    struct HeapNode {
-        freq: u64,
-        node: TreeNode,
-    }
+    /// Encode LZ77 tokens to DEFLATE format.
+    ///
+    /// Emits whichever block type is smallest for the tokens. With dynamic codes
+    /// enabled, fixed codes are used instead when the code table header would
+    /// cost more than it saves, as for tiny blocks. In either mode, data that
+    /// Huffman coding would expand is written as stored blocks; that needs the
+    /// tokens to be self-contained, and otherwise the Huffman encoding is kept.
+    pub fn encode(&mut self, tokens: &[LZ77Token], is_final: bool) -> Result<Vec<u8>> {
+        let encoded = if self.use_fixed {
+            self.encode_single(tokens, is_final, true)?
+        } else {
+            self.encode_huffman(tokens, is_final)?
+        };
 
-    impl PartialEq for HeapNode {
-        fn eq(&self, other: &Self) -> bool {
-            self.freq == other.freq
-        }
-    }
-    impl Eq for HeapNode {}
-    impl PartialOrd for HeapNode {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            Some(self.cmp(other))
+        let uncompressed: usize = tokens.iter().map(|t| t.uncompressed_size()).sum();
+        if stored_len(uncompressed) < encoded.len() {
+            if let Ok(data) = inflate_tokens(tokens) {
+                return Ok(encode_stored(&data, is_final));
+            }
         }
+        Ok(encoded)
     }
-    impl Ord for HeapNode {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.freq.cmp(&other.freq)
+
+    /// Encode with fixed or dynamic codes, whichever is estimated smaller,
+    /// splitting into dynamic sub-blocks when enabled and smaller still
+    fn encode_huffman(&self, tokens: &[LZ77Token], is_final: bool) -> Result<Vec<u8>> {
+        let mut freq = FrequencyCounter::new();
+        freq.count_tokens(tokens);
+        let fixed = self.estimate_fixed_bits(&freq) <= self.estimate_dynamic_bits(&freq);
+        let single = self.encode_single(tokens, is_final, fixed)?;
+
+        if self.split_subblocks {
+            let ranges = self.plan_subblocks(tokens);
+            if ranges.len() > 1 {
+                let split = self.encode_subblocks(tokens, &ranges, is_final)?;
+                return Ok(if split.len() < single.len() { split } else { single });
+            }
         }
+        Ok(single)
     }
 
-    let mut heap: BinaryHeap<Reverse<HeapNode>> = symbols
-        .iter()
-        .map(|&(sym, freq)| Reverse(HeapNode { freq: freq as u64, node: TreeNode::Leaf(sym) }))
-        .collect();
-
-    while heap.len() > 1 {
-        let Reverse(left) = heap.pop().unwrap();
-        let Reverse(right) = heap.pop().unwrap();
-
-        heap.push(Reverse(HeapNode {
-            freq: left.freq + right.freq,
-            node: TreeNode::Internal(Box::new(left.node), Box::new(right.node)),
-        }));
+    /// Encode tokens into one or more final DEFLATE streams of at most `max_compressed` bytes.
+    ///
+    /// Tries dynamic codes, then fixed codes, either of which falls back to stored
+    /// blocks when those are smaller. If none fits, the
+    /// tokens are split in half and each half is encoded the same way, so the result
+    /// may hold several streams, each destined for its own BGZF block. The tokens must
+    /// be self-contained: no Copy may reach before the first token.
+    pub fn encode_fitting(
+        &mut self,
+        tokens: &[LZ77Token],
+        max_compressed: usize,
+    ) -> Result<Vec<FittedBlock>> {
+        let data = inflate_tokens(tokens)?;
+        let mut blocks = Vec::new();
+        self.encode_fitting_into(tokens, &data, max_compressed, &mut blocks)?;
+        Ok(blocks)
     }
 
-    // Traverse tree to get depths
-    fn traverse(node: &TreeNode, depth: u8, lengths: &mut [u8]) {
-        match node {
-            TreeNode::Leaf(sym) => {
-                lengths[*sym] = depth.max(1); // Minimum code length is 1
-            }
-            TreeNode::Internal(left, right) => {
-                traverse(left, depth + 1, lengths);
-                traverse(right, depth + 1, lengths);
+    fn encode_fitting_into(
+        &mut self,
+        tokens: &[LZ77Token],
+        data: &[u8],
+        max_compressed: usize,
+        blocks: &mut Vec<FittedBlock>,
+    ) -> Result<()> {
+        let use_fixed = self.use_fixed;
+        for fixed in [false, true] {
+            self.use_fixed = fixed;
+            let encoded = self.encode(tokens, true);
+            self.use_fixed = use_fixed;
+
+            let encoded = encoded?;
+            if encoded.len() <= max_compressed {
+                blocks.push((encoded, crc32fast::hash(data), data.len() as u32));
+                return Ok(());
             }
         }
-    }
 
-    if let Some(Reverse(root)) = heap.pop() {
-        // Start at depth 0, so direct children of root are at depth 1
-        traverse(&root.node, 0, lengths);
-    }
-}
+        if tokens.len() < 2 {
+            let size = stored_len(data.len());
+            return Err(Error::BgzfBlockTooLarge { size, max: max_compressed });
+        }
 
-/// Limit code lengths to max_bits using the algorithm from RFC 1951
-fn limit_code_lengths(lengths: &mut [u8], symbols: &[(usize, u32)], max_bits: u8) {
-    // Check if any lengths exceed max_bits
-    let max_len = lengths.iter().copied().max().unwrap_or(0);
-    if max_len <= max_bits {
-        return;
+        // Split in half; copies in the second half that reach into the first become literals
+        let mid = tokens.len() / 2;
+        let split_at = tokens[..mid].iter().map(|t| t.uncompressed_size()).sum();
+        let (head, tail) = data.split_at(split_at);
+        self.encode_fitting_into(&tokens[..mid], head, max_compressed, blocks)?;
+        let rebased = rebase_tokens(&tokens[mid..], tail);
+        self.encode_fitting_into(&rebased, tail, max_compressed, blocks)
     }
 
-    // Count codes at each length
-    let mut bl_count = vec![0u32; max_len as usize + 1];
-    for &(sym, _) in symbols {
-        let len = lengths[sym];
-        if len > 0 {
-            bl_count[len as usize] += 1;
-        }
+    /// Encode all tokens as one DEFLATE block with fixed or dynamic codes
+    fn encode_single(&self, tokens: &[LZ77Token], is_final: bool, fixed: bool) -> Result<Vec<u8>> {
+        let mut writer = BitWriter::with_capacity(tokens.len() * 2);
+        self.write_block(&mut writer, tokens, is_final, fixed)?;
+        Ok(writer.finish())
     }
 
-    // Move codes from lengths > max_bits down to max_bits
-    // This requires redistributing to maintain Kraft inequality
-    let mut overflow = 0u32;
-    for bits in ((max_bits as usize + 1)..=max_len as usize).rev() {
-        overflow += bl_count[bits];
-        bl_count[bits] = 0;
+    /// Append tokens as one DEFLATE block to `writer`, without byte alignment.
+    ///
+    /// Consecutive calls build a single multi-block DEFLATE stream; only the last
+    /// block should be final. Sub-block splitting does not apply.
+    pub fn encode_to(
+        &self,
+        writer: &mut BitWriter,
+        tokens: &[LZ77Token],
+        is_final: bool,
+    ) -> Result<()> {
+        self.write_block(writer, tokens, is_final, self.use_fixed)
     }
 
-    // Redistribute overflow by moving codes to longer lengths
-    bl_count[max_bits as usize] += overflow;
-
-    // Now we need to shorten some codes to make room
-    // Use a greedy approach: for each overflow bit at max_bits,
-    // we need to split a shorter code
-    while overflow > 0 {
-        // Find the shortest length with codes that can be split
-        for bits in (1..max_bits as usize).rev() {
-            if bl_count[bits] > 0 {
-                // Split this code: remove one code at 'bits', add two at 'bits+1'
-                bl_count[bits] -= 1;
-                bl_count[bits + 1] += 2;
-                bl_count[max_bits as usize] -= 1;
-                overflow -= 1;
-                break;
-            }
+    fn write_block(
+        &self,
+        writer: &mut BitWriter,
+        tokens: &[LZ77Token],
+        is_final: bool,
+        fixed: bool,
+    ) -> Result<()> {
+        // Write block header
+        writer.write_bit(is_final); // BFINAL
+        if fixed {
+            writer.write_bits(1, 2); // BTYPE = 01 (fixed Huffman)
+            self.encode_fixed(writer, tokens)
+        } else {
+            writer.write_bits(2, 2); // BTYPE = 10 (dynamic Huffman)
+            self.encode_dynamic(writer, tokens)
         }
-        // Safety check to prevent infinite loop
-        if bl_count[1..(max_bits as usize)].iter().all(|&c| c == 0) {
-            break;
+    }
+
+    /// Encode each range of tokens as its own dynamic block; only the last may be final
+    fn encode_subblocks(
+        &self,
+        tokens: &[LZ77Token],
+        ranges: &[Range<usize>],
+        is_final: bool,
+    ) -> Result<Vec<u8>> {
+        let mut writer = BitWriter::with_capacity(tokens.len() * 2);
+        for (i, range) in ranges.iter().enumerate() {
+            writer.write_bit(is_final && i + 1 == ranges.len()); // BFINAL
+            writer.write_bits(2, 2); // BTYPE = 10 (dynamic Huffman)
+            self.encode_dynamic(&mut writer, &tokens[range.clone()])?;
         }
+        Ok(writer.finish())
     }
 
-    // Reassign lengths based on new distribution
-    // Sort symbols by frequency (descending) to assign shorter codes to more frequent
-    let mut sorted_syms: Vec<(usize, u32)> = symbols.to_vec();
-    sorted_syms.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    /// Choose token ranges for DEFLATE sub-blocks within one BGZF block.
+    ///
+    /// Tokens are considered in chunks of `SUBBLOCK_CHUNK_TOKENS`; a chunk starts a new
+    /// sub-block when coding it with its own tables is estimated to be cheaper than
+    /// extending the current sub-block's tables to cover it.
+    fn plan_subblocks(&self, tokens: &[LZ77Token]) -> Vec<Range<usize>> {
+        if tokens.len() < 2 * SUBBLOCK_CHUNK_TOKENS {
+            return std::iter::once(0..tokens.len()).collect();
+        }
 
-    // Assign lengths starting from shortest
-    let mut sym_idx = 0;
-    for (bits, &count) in bl_count.iter().enumerate().skip(1).take(max_bits as usize) {
-        for _ in 0..count {
-            if sym_idx < sorted_syms.len() {
-                lengths[sorted_syms[sym_idx].0] = bits as u8;
-                sym_idx += 1;
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        let mut current = FrequencyCounter::new();
+        current.count_tokens(&tokens[..SUBBLOCK_CHUNK_TOKENS]);
+
+        for chunk_start in (SUBBLOCK_CHUNK_TOKENS..tokens.len()).step_by(SUBBLOCK_CHUNK_TOKENS) {
+            let chunk_end = (chunk_start + SUBBLOCK_CHUNK_TOKENS).min(tokens.len());
+            let mut chunk = FrequencyCounter::new();
+            chunk.count_tokens(&tokens[chunk_start..chunk_end]);
+
+            let mut merged = current.clone();
+            merged.merge(&chunk);
+            let separate =
+                self.estimate_dynamic_bits(&current) + self.estimate_dynamic_bits(&chunk);
+            if separate < self.estimate_dynamic_bits(&merged) {
+                ranges.push(start..chunk_start);
+                start = chunk_start;
+                current = chunk;
+            } else {
+                current = merged;
             }
         }
+        ranges.push(start..tokens.len());
+        ranges
     }
-}
 
-/// Huffman encoder for DEFLATE output
-pub struct HuffmanEncoder {
-    use_fixed: bool,
-    /// Fixed literal/length codes (precomputed)
-    fixed_lit_codes: Vec<(u32, u8)>,
-    /// Fixed distance codes (precomputed)
-    fixed_dist_codes: Vec<(u32, u8)>,
-}
-
-impl HuffmanEncoder {
-    pub fn new(use_fixed: bool) -> Self {
-        let fixed_lit_codes = build_fixed_literal_codes();
-        let fixed_dist_codes = build_fixed_distance_codes();
-
-        Self { use_fixed, fixed_lit_codes, fixed_dist_codes }
+    /// Size in bits of a fixed block with these frequencies, counted the same way
+    /// as [`Self::estimate_dynamic_bits`].
+    fn estimate_fixed_bits(&self, freq: &FrequencyCounter) -> u64 {
+        let lit_bits: u64 = self
+            .fixed_lit_codes
+            .iter()
+            .zip(freq.literal_freq.iter())
+            .map(|(&(_, l), &f)| l as u64 * f as u64)
+            .sum();
+        let dist_bits: u64 = freq.distance_freq.iter().map(|&f| 5 * f as u64).sum();
+        3 + lit_bits + dist_bits
     }
 
-    /// Encode LZ77 tokens to DEFLATE format
-    pub fn encode(&mut self, tokens: &[LZ77Token], is_final: bool) -> Result<Vec<u8>> {
-        let mut writer = BitWriter::with_capacity(tokens.len() * 2);
-
-        // Write block header
-        writer.write_bit(is_final); // BFINAL
-        if self.use_fixed {
-            writer.write_bits(1, 2); // BTYPE = 01 (fixed Huffman)
-            self.encode_fixed(&mut writer, tokens)?;
-        } else {
-            writer.write_bits(2, 2); // BTYPE = 10 (dynamic Huffman)
-            self.encode_dynamic(&mut writer, tokens)?;
-        }
-
-        Ok(writer.finish())
+    /// Estimate the size in bits of a dynamic block with these frequencies.
+    ///
+    /// Counts the code table header and Huffman-coded symbols; extra bits for lengths
+    /// and distances are left out since they are the same however the tokens are split.
+    fn estimate_dynamic_bits(&self, freq: &FrequencyCounter) -> u64 {
+        let (lit_lengths, dist_lengths) = dynamic_code_lengths(freq);
+
+        let mut header = BitWriter::new();
+        // Writing to an in-memory BitWriter cannot fail
+        let _ = self.write_dynamic_header(&mut header, &lit_lengths, &dist_lengths);
+        let header_bits = 3 + header.finish().len() as u64 * 8;
+
+        let lit_bits: u64 = lit_lengths
+            .iter()
+            .zip(freq.literal_freq.iter())
+            .map(|(&l, &f)| l as u64 * f as u64)
+            .sum();
+        let dist_bits: u64 = dist_lengths
+            .iter()
+            .zip(freq.distance_freq.iter())
+            .map(|(&l, &f)| l as u64 * f as u64)
+            .sum();
+        header_bits + lit_bits + dist_bits
     }
 
     /// Access fixed literal codes (for fused resolve+encode paths).
@@ -415,27 +503,7 @@ impl HuffmanEncoder {
         freq.count_tokens(tokens);
 
         // Compute optimal code lengths
-        let num_lit = freq.num_literal_codes();
-        let num_dist = freq.num_distance_codes();
-
-        let mut lit_lengths = compute_code_lengths(&freq.literal_freq[..num_lit], MAX_CODE_LENGTH);
-        let mut dist_lengths =
-            compute_code_lengths(&freq.distance_freq[..num_dist], MAX_CODE_LENGTH);
-
-        // Ensure EOB (symbol 256) has a valid code - it's always needed
-        if lit_lengths.len() > 256 && lit_lengths[256] == 0 {
-            lit_lengths[256] = 1;
-        }
-
-        // DEFLATE requires at least one distance code even if not used
-        // If all distance lengths are 0, set the first one to 1
-        if dist_lengths.iter().all(|&l| l == 0) {
-            if dist_lengths.is_empty() {
-                dist_lengths = vec![1];
-            } else {
-                dist_lengths[0] = 1;
-            }
-        }
+        let (lit_lengths, dist_lengths) = dynamic_code_lengths(&freq);
 
         // Build codes from lengths
         let lit_codes = build_codes_from_lengths(&lit_lengths);
@@ -561,6 +629,102 @@ impl HuffmanEncoder {
     }
 }
 
+/// Compute literal/length and distance code lengths for a dynamic block.
+fn dynamic_code_lengths(freq: &FrequencyCounter) -> (Vec<u8>, Vec<u8>) {
+    let num_lit = freq.num_literal_codes();
+    let num_dist = freq.num_distance_codes();
+
+    let mut lit_lengths = compute_code_lengths(&freq.literal_freq[..num_lit], MAX_CODE_LENGTH);
+    let mut dist_lengths = compute_code_lengths(&freq.distance_freq[..num_dist], MAX_CODE_LENGTH);
+
+    // Ensure EOB (symbol 256) has a valid code - it's always needed
+    if lit_lengths.len() > 256 && lit_lengths[256] == 0 {
+        lit_lengths[256] = 1;
+    }
+
+    // DEFLATE requires at least one distance code even if not used
+    // If all distance lengths are 0, set the first one to 1
+    if dist_lengths.iter().all(|&l| l == 0) {
+        if dist_lengths.is_empty() {
+            dist_lengths = vec![1];
+        } else {
+            dist_lengths[0] = 1;
+        }
+    }
+
+    (lit_lengths, dist_lengths)
+}
+
+/// Decode self-contained tokens to bytes.
+fn inflate_tokens(tokens: &[LZ77Token]) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(tokens.len() * 2);
+    for token in tokens {
+        match *token {
+            LZ77Token::Literal(byte) => data.push(byte),
+            LZ77Token::Copy { length, distance } => {
+                if distance == 0 || distance as usize > data.len() {
+                    return Err(Error::InvalidBackReference { distance, available: data.len() });
+                }
+                let src_start = data.len() - distance as usize;
+                for i in 0..length as usize {
+                    data.push(data[src_start + i]);
+                }
+            }
+            LZ77Token::EndOfBlock => {}
+        }
+    }
+    Ok(data)
+}
+
+/// Replace copies that reach before the start of `tokens` with the literals they produce.
+///
+/// `data` is the decoded output of `tokens`.
+fn rebase_tokens(tokens: &[LZ77Token], data: &[u8]) -> Vec<LZ77Token> {
+    let mut rebased = Vec::with_capacity(tokens.len());
+    let mut position = 0;
+    for token in tokens {
+        match *token {
+            LZ77Token::Copy { length, distance } if distance as usize > position => {
+                let bytes = &data[position..position + length as usize];
+                rebased.extend(bytes.iter().map(|&b| LZ77Token::Literal(b)));
+            }
+            LZ77Token::EndOfBlock => continue,
+            other => rebased.push(other),
+        }
+        position += token.uncompressed_size();
+    }
+    rebased
+}
+
+/// Size of `len` bytes written as stored DEFLATE blocks
+pub(crate) fn stored_len(len: usize) -> usize {
+    let blocks = ((len + MAX_STORED_BLOCK - 1) / MAX_STORED_BLOCK).max(1);
+    len + blocks * 5
+}
+
+/// Write bytes as stored DEFLATE blocks; the last one is final if `is_final`
+pub(crate) fn encode_stored(data: &[u8], is_final: bool) -> Vec<u8> {
+    let mut writer = BitWriter::with_capacity(stored_len(data.len()));
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        // An empty stream still needs one block
+        writer.write_bit(is_final);
+        writer.write_bits(0, 2);
+        writer.align_to_byte();
+        writer.write_u16_le(0);
+        writer.write_u16_le(0xFFFF);
+    }
+    while let Some(chunk) = chunks.next() {
+        writer.write_bit(is_final && chunks.peek().is_none()); // BFINAL
+        writer.write_bits(0, 2); // BTYPE = 00 (stored)
+        writer.align_to_byte();
+        writer.write_u16_le(chunk.len() as u16);
+        writer.write_u16_le(!(chunk.len() as u16));
+        writer.write_bytes(chunk);
+    }
+    writer.finish()
+}
+
 /// RLE encode code lengths using symbols 16, 17, 18
 fn rle_encode_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
     let mut result = Vec::new();
@@ -665,6 +829,7 @@ fn build_codes_from_lengths(lengths: &[u8]) -> Vec<(u32, u8)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bgzf::BGZF_MAX_BLOCK_SIZE;
 
     #[test]
     fn test_build_fixed_literal_codes() {
@@ -699,14 +864,10 @@ mod tests {
 
     #[test]
     fn test_encode_dynamic() {
+        // Enough skewed literals for dynamic tables to pay for their header
+        let tokens: Vec<LZ77Token> =
+            b"Hello".iter().cycle().take(500).map(|&b| LZ77Token::Literal(b)).collect();
         let mut encoder = HuffmanEncoder::new(false); // Use dynamic
-        let tokens = vec![
-            LZ77Token::Literal(b'H'),
-            LZ77Token::Literal(b'e'),
-            LZ77Token::Literal(b'l'),
-            LZ77Token::Literal(b'l'),
-            LZ77Token::Literal(b'o'),
-        ];
         let data = encoder.encode(&tokens, true).unwrap();
         assert!(!data.is_empty());
         // Dynamic block type should be in the header (bits 1-2 = 10)
@@ -735,6 +896,26 @@ mod tests {
         assert_eq!(freq.distance_freq[0], 1);
     }
 
+    #[test]
+    fn test_frequency_counts_bounded_by_block() {
+        // The most any one symbol can be counted: a full BGZF block of one literal
+        let tokens = vec![LZ77Token::Literal(b'A'); BGZF_MAX_BLOCK_SIZE];
+        let mut freq = FrequencyCounter::new();
+        freq.count_tokens(&tokens);
+        assert_eq!(freq.literal_freq[b'A' as usize], BGZF_MAX_BLOCK_SIZE as u32);
+
+        // Even merging two such blocks (sub-block planning merges within one block)
+        // leaves the u32 counts orders of magnitude from overflow
+        let mut merged = freq.clone();
+        merged.merge(&freq);
+        assert_eq!(merged.literal_freq[b'A' as usize], 2 * BGZF_MAX_BLOCK_SIZE as u32);
+        assert!(merged.literal_freq.iter().map(|&f| f as u64).sum::<u64>() < u32::MAX as u64);
+
+        // And the skewed counts still produce a usable code
+        let lengths = compute_code_lengths(&merged.literal_freq, 15);
+        assert!(lengths[b'A' as usize] > 0 && lengths[256] > 0);
+    }
+
     #[test]
     fn test_compute_code_lengths() {
         // Simple case: 4 symbols with equal frequency
@@ -760,6 +941,72 @@ mod tests {
         assert!(lengths[0] <= lengths[3]);
     }
 
+    #[test]
+    fn test_compute_code_lengths_limited_is_complete() {
+        // Fibonacci-like frequencies force an unlimited tree far deeper than 15
+        let mut freqs = vec![1u32, 1];
+        while freqs.len() < 30 {
+            let next = freqs[freqs.len() - 1] + freqs[freqs.len() - 2];
+            freqs.push(next);
+        }
+        let lengths = compute_code_lengths(&freqs, MAX_CODE_LENGTH);
+
+        assert!(lengths.iter().all(|&l| (1..=MAX_CODE_LENGTH).contains(&l)));
+        let kraft: u64 = lengths.iter().map(|&l| 1u64 << (MAX_CODE_LENGTH - l)).sum();
+        assert_eq!(kraft, 1 << MAX_CODE_LENGTH, "code must be complete, not over-subscribed");
+    }
+
+    #[test]
+    fn test_compute_code_lengths_random_is_complete() {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for round in 0..500 {
+            let n = 2 + next() as usize % 285;
+            // Mix uniform, exponentially skewed, and sparse distributions
+            let freqs: Vec<u32> = (0..n)
+                .map(|_| match round % 3 {
+                    0 => next() as u32 % 1000,
+                    1 => 1 << (next() % 31),
+                    _ => (next() % 8 == 0) as u32 * (1 + next() as u32 % 100_000),
+                })
+                .collect();
+            let used = freqs.iter().filter(|&&f| f > 0).count();
+            let lengths = compute_code_lengths(&freqs, MAX_CODE_LENGTH);
+
+            assert_eq!(lengths.len(), n);
+            for (&freq, &len) in freqs.iter().zip(&lengths) {
+                assert_eq!(freq > 0, len > 0, "round {}", round);
+                assert!(len <= MAX_CODE_LENGTH);
+            }
+            if used >= 2 {
+                let kraft: u64 = lengths
+                    .iter()
+                    .filter(|&&l| l > 0)
+                    .map(|&l| 1u64 << (MAX_CODE_LENGTH - l))
+                    .sum();
+                assert_eq!(kraft, 1 << MAX_CODE_LENGTH, "round {}", round);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_code_lengths_optimal() {
+        // Unlimited Huffman lengths for these frequencies are 1, 2, 3, 4, 4
+        let freqs = [16u32, 8, 4, 2, 2];
+        assert_eq!(compute_code_lengths(&freqs, 15), [1, 2, 3, 4, 4]);
+
+        // Under a 3-bit limit, 1,3,3,3,3 (64 bits) beats 2,2,2,3,3 (68 bits)
+        assert_eq!(compute_code_lengths(&freqs, 3), [1, 3, 3, 3, 3]);
+
+        // Every symbol at the limit fills the code exactly
+        assert_eq!(compute_code_lengths(&[1u32; 8], 3), [3; 8]);
+    }
+
     #[test]
     fn test_rle_encode_zeros() {
         // Test RLE encoding of zeros
@@ -817,4 +1064,170 @@ mod tests {
             .expect("flate2 should inflate dynamic Huffman output");
         assert_eq!(inflated, input);
     }
+
+    /// Sub-block splitting inflates correctly and beats one table for shifting statistics.
+    #[test]
+    fn test_encode_roundtrip_subblocks() {
+        use std::io::Read;
+        // Two regions with disjoint alphabets: DNA, then digits
+        let mut input: Vec<u8> = (0..20_000).map(|i| b"ACGT"[(i * 7 + i / 3) % 4]).collect();
+        input.extend((0..20_000).map(|i| b"0123456789"[(i * 3 + i / 5) % 10]));
+        let tokens: Vec<LZ77Token> = input.iter().map(|&b| LZ77Token::Literal(b)).collect();
+
+        let mut encoder = HuffmanEncoder::new(false);
+        let single = encoder.encode(&tokens, true).unwrap();
+        encoder.set_split_subblocks(true);
+        assert!(encoder.plan_subblocks(&tokens).len() > 1);
+        let split = encoder.encode(&tokens, true).unwrap();
+        assert!(split.len() < single.len(), "split {} vs single {}", split.len(), single.len());
+
+        let mut inflated = Vec::new();
+        flate2::read::DeflateDecoder::new(&split[..])
+            .read_to_end(&mut inflated)
+            .expect("flate2 should inflate multi-subblock output");
+        assert_eq!(inflated, input);
+
+        // Splitting never makes the output larger
+        let uniform: Vec<LZ77Token> = tokens[..20_000].to_vec();
+        encoder.set_split_subblocks(false);
+        let unsplit = encoder.encode(&uniform, true).unwrap();
+        encoder.set_split_subblocks(true);
+        assert!(encoder.encode(&uniform, true).unwrap().len() <= unsplit.len());
+    }
+
+    /// Random self-contained token stream: literals and copies within the history so far
+    fn random_tokens(seed: u64, count: usize) -> Vec<LZ77Token> {
+        let mut state = seed;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut tokens = Vec::with_capacity(count);
+        let mut position = 0usize;
+        for _ in 0..count {
+            let r = next();
+            if position >= 3 && r % 4 == 0 {
+                let distance = 1 + (next() as usize % position.min(32768));
+                let length = 3 + (next() % 256) as u16;
+                tokens.push(LZ77Token::Copy { length, distance: distance as u16 });
+                position += length as usize;
+            } else {
+                tokens.push(LZ77Token::Literal((r >> 8) as u8));
+                position += 1;
+            }
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_encode_fitting_respects_limit() {
+        use std::io::Read;
+        for seed in 1..=8u64 {
+            let tokens = random_tokens(seed, 2_000 + seed as usize * 500);
+            let expected = inflate_tokens(&tokens).unwrap();
+
+            for max_compressed in [65535 - 26, 2_048] {
+                for use_fixed in [true, false] {
+                    let mut encoder = HuffmanEncoder::new(use_fixed);
+                    let blocks = encoder.encode_fitting(&tokens, max_compressed).unwrap();
+
+                    let mut inflated = Vec::new();
+                    for (data, crc, size) in &blocks {
+                        assert!(data.len() <= max_compressed && data.len() <= 65535);
+                        let mut block = Vec::new();
+                        flate2::read::DeflateDecoder::new(&data[..])
+                            .read_to_end(&mut block)
+                            .expect("each fitted block is a complete DEFLATE stream");
+                        assert_eq!(block.len(), *size as usize);
+                        assert_eq!(crc32fast::hash(&block), *crc);
+                        inflated.extend(block);
+                    }
+                    assert_eq!(inflated, expected, "seed {} max {}", seed, max_compressed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_fitting_stored_fallback() {
+        // Incompressible literals: Huffman coding expands them, stored blocks do not
+        let tokens = random_tokens(7, 200_000)
+            .into_iter()
+            .filter(|t| matches!(t, LZ77Token::Literal(_)))
+            .take(65_000)
+            .collect::<Vec<_>>();
+        let mut encoder = HuffmanEncoder::new(true);
+        assert!(encoder.encode(&tokens, true).unwrap().len() > 65_000);
+
+        // Tight enough that dynamic codes (table overhead included) do not fit either
+        let blocks = encoder.encode_fitting(&tokens, stored_len(65_000)).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].0.len(), stored_len(65_000));
+    }
+
+    /// Uniformly random bytes
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_picks_smallest_block_type() {
+        // BTYPE from the first byte: 0 = stored, 1 = fixed, 2 = dynamic
+        let btype = |data: &[u8]| (data[0] >> 1) & 0x03;
+
+        // Tiny blocks: a dynamic header costs more than fixed codes save
+        let tiny: Vec<LZ77Token> = b"Hello".iter().map(|&b| LZ77Token::Literal(b)).collect();
+        let data = HuffmanEncoder::new(false).encode(&tiny, true).unwrap();
+        assert_eq!(btype(&data), 1);
+        assert_eq!(crate::deflate::inflate(&data[..]).unwrap(), b"Hello");
+
+        // Non-final stored blocks leave BFINAL clear
+        let literals: Vec<LZ77Token> =
+            random_bytes(1_000).iter().map(|&b| LZ77Token::Literal(b)).collect();
+        let data = HuffmanEncoder::new(true).encode(&literals, false).unwrap();
+        assert_eq!(data[0] & 0x07, 0x00);
+    }
+
+    #[test]
+    fn test_encode_random_never_expands() {
+        // Literals over all 256 byte values code to about 8 bits each under any
+        // table, so without stored blocks they expand
+        let bytes = random_bytes(65_000);
+        for len in [1, 10, 100, 1_000, 10_000, 65_000] {
+            let tokens: Vec<LZ77Token> =
+                bytes[..len].iter().map(|&b| LZ77Token::Literal(b)).collect();
+            for use_fixed in [true, false] {
+                let mut encoder = HuffmanEncoder::new(use_fixed);
+                encoder.set_split_subblocks(!use_fixed);
+                let data = encoder.encode(&tokens, true).unwrap();
+                assert!(data.len() <= len + 5, "{} bytes from {} literals", data.len(), len);
+                assert_eq!(crate::deflate::inflate(&data[..]).unwrap(), &bytes[..len]);
+            }
+        }
+
+        // Copies that are not self-contained cannot be stored; Huffman output is kept
+        let tokens = [LZ77Token::Copy { length: 258, distance: 1 }];
+        let data = HuffmanEncoder::new(true).encode(&tokens, true).unwrap();
+        assert_eq!(data[0] & 0x07, 0x03);
+    }
+
+    #[test]
+    fn test_encode_stored_empty() {
+        use std::io::Read;
+        let data = encode_stored(&[], true);
+        assert_eq!(data.len(), stored_len(0));
+        let mut inflated = Vec::new();
+        flate2::read::DeflateDecoder::new(&data[..]).read_to_end(&mut inflated).unwrap();
+        assert!(inflated.is_empty());
+    }
 }
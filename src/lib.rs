@@ -1,28 +1,45 @@
 pub mod bgzf;
 pub mod bits;
+mod convert;
 pub mod deflate;
 pub mod error;
 pub mod gzip;
 pub mod huffman;
+mod logging;
+#[cfg(feature = "mmap")]
 pub mod mmap;
 pub mod reader;
+#[doc(hidden)]
+pub mod testdata;
+mod timing;
 pub mod transcoder;
 
 pub use bgzf::{
-    is_bgzf, validate_bgzf_streaming, validate_bgzf_strict, verify_bgzf, BgzfValidation,
-    BgzfVerification, GziEntry, GziIndexBuilder,
+    is_bgzf, is_bgzf_bytes, is_eof_block, validate_bgzf_detailed, validate_bgzf_header_bytes,
+    validate_bgzf_streaming, validate_bgzf_streaming_detailed, validate_bgzf_strict, verify_bgzf,
+    verify_bgzf_tee, verify_bgzf_with, verify_bgzf_with_options, verify_structure, BgzfReader,
+    BgzfValidation, BgzfVerification, BlockCoordinate, BlockError, BlockErrorKind, BlockSource,
+    GziEntry, GziIndexBuilder, IndexedReader, StructureReport, VerifyOptions,
 };
+pub use convert::{convert, coordinate_index_path, index_path, transcode_bytes, transcode_stream};
 pub use deflate::tokens::LZ77Token;
 pub use error::{Error, Result};
+#[cfg(feature = "mmap")]
 pub use mmap::MappedFile;
 pub use reader::{decode_member_batch, scan_gzip_members, ParallelGzipReader};
+#[cfg(feature = "timing")]
+pub use timing::{take_phase_times, PhaseTimes};
 pub use transcoder::{
-    parallel::ParallelTranscoder, parallel_decode::ParallelDecodeTranscoder,
-    single::SingleThreadedTranscoder,
+    context::TranscodeContext, degzip::degzip, parallel::ParallelTranscoder,
+    parallel_decode::ParallelDecodeTranscoder, shard::shard_transcode, shard::ShardStats,
+    single::BlockIterator, single::SingleThreadedTranscoder, streaming::StreamingTranscoder,
 };
 
-use std::io::{Read, Write};
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Compression level for encoding (1-9)
 ///
@@ -74,6 +91,68 @@ impl CompressionLevel {
     pub fn use_smart_boundaries(&self) -> bool {
         matches!(self, Self::Level7 | Self::Level8 | Self::Level9)
     }
+
+    /// Whether this level may split a BGZF block into several DEFLATE blocks (levels 7-9)
+    pub fn use_subblock_splitting(&self) -> bool {
+        matches!(self, Self::Level7 | Self::Level8 | Self::Level9)
+    }
+
+    /// Hash-chain depth for [`TranscodeConfig::recompress`], or `None` below level 7,
+    /// where recompression is not available
+    pub fn recompress_chain(&self) -> Option<usize> {
+        match self {
+            Self::Level7 => Some(32),
+            Self::Level8 => Some(128),
+            Self::Level9 => Some(1024),
+            _ => None,
+        }
+    }
+
+    /// Block size used when the caller does not choose one.
+    ///
+    /// Every level uses the largest BGZF block, 65280 bytes. Smaller blocks would
+    /// give finer random access, but back-references that cross a block boundary
+    /// are re-emitted as literals, so smaller blocks compress worse at any level.
+    pub fn default_block_size(&self) -> usize {
+        bgzf::BGZF_BLOCK_SIZE
+    }
+
+    /// Suggest a re-encoding level for an existing BGZF stream.
+    ///
+    /// Samples the compression ratio of the first few data blocks: data that
+    /// barely compressed gets the fastest level, highly compressible data gets
+    /// the strongest. Returns the default level if the stream has no data blocks.
+    /// The reader is left where it started.
+    pub fn suggest_from_bgzf<R: Read + Seek>(mut reader: R) -> Result<CompressionLevel> {
+        const SAMPLE_BLOCKS: usize = 8;
+
+        let start = reader.stream_position()?;
+        let mut compressed = 0u64;
+        let mut uncompressed = 0u64;
+        for block in bgzf::iter_blocks(&mut reader)
+            .filter(|b| !matches!(b, Ok(info) if info.uncompressed_size == 0))
+            .take(SAMPLE_BLOCKS)
+        {
+            let block = block?;
+            compressed += block.compressed_size as u64;
+            uncompressed += block.uncompressed_size as u64;
+        }
+        reader.seek(SeekFrom::Start(start))?;
+
+        if compressed == 0 {
+            return Ok(Self::default());
+        }
+        let ratio = uncompressed as f64 / compressed as f64;
+        Ok(if ratio < 1.1 {
+            Self::Level1
+        } else if ratio < 2.0 {
+            Self::Level4
+        } else if ratio < 3.0 {
+            Self::Level6
+        } else {
+            Self::Level9
+        })
+    }
 }
 
 /// Format profile for input-aware optimization
@@ -84,6 +163,16 @@ pub enum FormatProfile {
     Default,
     /// FASTQ-optimized (dynamic Huffman, record-aligned boundaries)
     Fastq,
+    /// Sorted VCF: blocks end on line boundaries, with the header in blocks of
+    /// its own (see [`transcoder::VcfSplitter`]); with `build_index`, also
+    /// builds a coarse coordinate index (see [`bgzf::coords`])
+    Vcf,
+    /// Line-oriented text (VCF, BED, TSV, ...): blocks end after every
+    /// `per_record`-th newline where possible (see [`transcoder::LineSplitter`])
+    Lines { per_record: usize },
+    /// Fixed-width binary records: blocks end on multiples of `record_size`
+    /// bytes where possible (see [`transcoder::FixedRecordSplitter`])
+    FixedRecords { record_size: usize },
     /// Auto-detect from file extension
     Auto,
 }
@@ -96,6 +185,8 @@ impl FormatProfile {
 
         if name.ends_with(".fastq.gz") || name.ends_with(".fq.gz") {
             Self::Fastq
+        } else if name.ends_with(".vcf.gz") {
+            Self::Vcf
         } else {
             Self::Default
         }
@@ -110,10 +201,48 @@ impl FormatProfile {
     }
 }
 
+/// Progress after one BGZF data block is written, passed to [`TranscodeConfig::on_block`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockProgress {
+    /// Position of the block in the output, counting from 0
+    pub block_id: u64,
+    /// Size of the block in the output, header and footer included
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+    /// Uncompressed bytes written so far, this block included
+    pub cumulative_in: u64,
+    /// BGZF bytes written so far, this block included
+    pub cumulative_out: u64,
+}
+
+/// Callback receiving each [`BlockProgress`]; clones share the one callback
+#[derive(Clone)]
+pub struct BlockCallback(Arc<dyn Fn(BlockProgress) + Send + Sync>);
+
+impl BlockCallback {
+    pub fn new(callback: impl Fn(BlockProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, progress: BlockProgress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for BlockCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockCallback(Fn(BlockProgress))")
+    }
+}
+
+/// Runs a task on a caller-provided executor, for [`ParallelTranscoder::with_spawner`]
+pub type Spawner = Arc<dyn Fn(Box<dyn FnOnce() + Send>) + Send + Sync>;
+
 /// Configuration for transcoding
 #[derive(Clone, Debug)]
 pub struct TranscodeConfig {
-    /// Target uncompressed block size (default: 65280, max for BGZF)
+    /// Target uncompressed block size
+    /// (default: [`CompressionLevel::default_block_size`], 65280, the max for BGZF)
     pub block_size: usize,
     /// Compression level (1-9)
     pub compression_level: CompressionLevel,
@@ -121,25 +250,240 @@ pub struct TranscodeConfig {
     pub format: FormatProfile,
     /// Number of threads for parallel encoding (0 = auto, 1 = single-threaded)
     pub num_threads: usize,
+    /// BGZF blocks handed to a parallel encoding worker at a time (default: 1).
+    ///
+    /// Batching cuts channel traffic when blocks are small (e.g. a 4KB `block_size`),
+    /// at the cost of coarser load balancing across workers.
+    pub blocks_per_job: usize,
+    /// Most encoding jobs a parallel transcoder dispatches past the oldest one
+    /// not yet written (default: 0, which allows 16 per thread).
+    ///
+    /// Jobs that finish early are held in memory until the jobs before them
+    /// are written, so this bounds that buffer when one job is slow; once
+    /// reached, parsing waits for the output to catch up.
+    /// [`ParallelStats::peak_buffered_blocks`] shows how much was needed.
+    pub max_jobs_ahead: usize,
     /// Buffer size for I/O operations
     pub buffer_size: usize,
+    /// Write outputs smaller than this many bytes with a single `write` call
+    /// (default: 0, off).
+    ///
+    /// The whole output, EOF block included, is buffered until it reaches this
+    /// size; larger outputs then stream in `buffer_size` chunks as usual. Meant
+    /// for shared filesystems such as NFS or Lustre, where each write is costly.
+    pub single_write_below: usize,
     /// Use thorough BGZF validation (validates all blocks vs just first)
     pub strict_bgzf_check: bool,
     /// Skip BGZF detection entirely (always transcode)
     pub force_transcode: bool,
     /// Build GZI index during transcoding
     pub build_index: bool,
+    /// Fraction a block may grow past `block_size` while waiting for a good split
+    /// point with smart boundaries (0.0-0.5, default: 0.1)
+    pub boundary_overshoot: f64,
+    /// Smallest acceptable final block (at most half of `block_size`, default: none).
+    ///
+    /// When set, the last full block is held back so a smaller trailing block can be
+    /// merged into it (or the two rebalanced into halves), at the cost of the final
+    /// blocks not matching `block_size` or record boundaries exactly.
+    pub min_block_size: Option<usize>,
+    /// Extra gzip subfields (SI1, SI2, data) written in every block header after the
+    /// mandatory BC subfield (default: none). Each block grows by `4 + data.len()` bytes
+    /// per subfield.
+    pub extra_subfields: Vec<(u8, u8, Vec<u8>)>,
+    /// Set FTEXT in every data block header when the input's first gzip header
+    /// has it (default: false), so text formats keep the hint. The EOF block is
+    /// unchanged.
+    pub preserve_ftext: bool,
+    /// Copy MTIME and OS from the input's first gzip header into every data
+    /// block header (default: false, which writes MTIME=0 and OS=255 as
+    /// bgzip does), so reproducible pipelines keep the source timestamp. The
+    /// EOF block is unchanged.
+    pub preserve_mtime: bool,
+    /// End the output with the BGZF EOF block (default: true). Turn off to
+    /// produce parts that are concatenated, then finished with a single
+    /// [`bgzf::BGZF_EOF`]:
+    ///
+    /// ```
+    /// use rebgzf::{bgzf::BGZF_EOF, SingleThreadedTranscoder, TranscodeConfig, Transcoder};
+    /// # use std::io::Write;
+    /// # let gzip = |data: &[u8]| {
+    /// #     let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+    /// #     encoder.write_all(data).unwrap();
+    /// #     encoder.finish().unwrap()
+    /// # };
+    /// let config = TranscodeConfig { write_eof: false, ..Default::default() };
+    /// let mut combined = Vec::new();
+    /// for part in [gzip(b"first\n"), gzip(b"second\n")] {
+    ///     SingleThreadedTranscoder::new(config.clone()).transcode(&part[..], &mut combined)?;
+    /// }
+    /// combined.extend_from_slice(&BGZF_EOF);
+    /// # Ok::<(), rebgzf::Error>(())
+    /// ```
+    pub write_eof: bool,
+    /// Check each input gzip member's CRC32 and ISIZE trailer against the bytes
+    /// it decodes to (default: false), failing with [`Error::Crc32Mismatch`] or
+    /// [`Error::SizeMismatch`] instead of writing BGZF from corrupt input. Member
+    /// headers with an FHCRC field are checked too, failing with
+    /// [`Error::GzipHeaderCrcMismatch`].
+    ///
+    /// Every byte already passes through the boundary resolver, so this costs
+    /// little beyond a CRC combine per block.
+    pub verify_input_crc: bool,
+    /// With `verify_input_crc`, log a member whose CRC32 or ISIZE trailer
+    /// disagrees with its bytes and carry on, setting
+    /// [`TranscodeStats::input_trailer_mismatch`], instead of failing (default:
+    /// false). The output is correct either way, since it is recomputed; this
+    /// lets pipelines accept input from compressors known to write bad trailers
+    /// while still flagging it.
+    pub tolerate_bad_input_trailer: bool,
+    /// Re-run LZ77 over each block's bytes instead of only re-coding the input's
+    /// tokens (default: false; levels 7-9 only).
+    ///
+    /// Input written by a fast compressor has short or few matches, which token
+    /// reuse carries over. With this set, each block also gets a fresh hash-chain
+    /// match search, as deep as the level allows, and the smaller encoding is kept;
+    /// [`TranscodeStats::recompressed_bytes_saved`] reports the gain. Much slower.
+    pub recompress: bool,
+    /// End a BGZF block at every input gzip member boundary (default: false).
+    ///
+    /// Members larger than `block_size` are still split, but no block spans two
+    /// members, so each member stays independently addressable. Cannot be combined
+    /// with `min_block_size`, which would merge blocks across members.
+    pub block_per_member: bool,
+    /// Record per-worker utilization in [`TranscodeStats::parallel`] (default: false).
+    ///
+    /// Only the parallel encoding paths report it; off by default to skip the timing calls.
+    pub collect_parallel_stats: bool,
+    /// Record each input member's trailer in [`TranscodeStats::members`]
+    /// (default: false).
+    ///
+    /// Off by default, since the list grows by one entry per member and inputs
+    /// can hold millions of them; [`TranscodeStats::members_processed`] is
+    /// counted either way.
+    pub collect_member_stats: bool,
+    /// Called as each data block is written, in output order on every path
+    /// (default: none), for rendering progress without wrapping the reader.
+    ///
+    /// The parallel transcoders call it from the thread writing the output, so
+    /// a slow callback slows the writer; the EOF block is not reported, nor are
+    /// BGZF inputs copied through unchanged.
+    pub on_block: Option<BlockCallback>,
 }
 
 impl TranscodeConfig {
+    /// Start a [`TranscodeConfigBuilder`] from the default configuration.
+    pub fn builder() -> TranscodeConfigBuilder {
+        TranscodeConfigBuilder::default()
+    }
+
     /// Whether to use fixed Huffman tables based on compression level
     pub fn use_fixed_huffman(&self) -> bool {
         self.compression_level.use_fixed_huffman()
     }
 
+    /// Whether to build a [`bgzf::coords`] index along with the GZI index
+    pub fn build_coordinate_index(&self) -> bool {
+        self.build_index && self.format == FormatProfile::Vcf
+    }
+
+    /// Hash-chain depth to recompress with, if `recompress` is set and the level allows it
+    pub fn recompress_chain(&self) -> Option<usize> {
+        self.recompress.then(|| self.compression_level.recompress_chain()).flatten()
+    }
+
     /// Whether to use smart boundary splitting based on level and format
     pub fn use_smart_boundaries(&self) -> bool {
-        self.compression_level.use_smart_boundaries() || self.format == FormatProfile::Fastq
+        self.compression_level.use_smart_boundaries()
+            || matches!(
+                self.format,
+                FormatProfile::Fastq
+                    | FormatProfile::Vcf
+                    | FormatProfile::Lines { .. }
+                    | FormatProfile::FixedRecords { .. }
+            )
+    }
+
+    /// Largest uncompressed block size allowed before a split is forced.
+    ///
+    /// With smart boundaries this is `block_size` plus the `boundary_overshoot`
+    /// allowance, capped at [`bgzf::BGZF_MAX_BLOCK_SIZE`]; otherwise it is
+    /// `block_size` itself.
+    pub fn max_block_size(&self) -> usize {
+        if self.use_smart_boundaries() {
+            let overshoot = (self.block_size as f64 * (1.0 + self.boundary_overshoot)) as usize;
+            overshoot.min(bgzf::BGZF_MAX_BLOCK_SIZE).max(self.block_size)
+        } else {
+            self.block_size
+        }
+    }
+
+    /// Check that configuration values are within supported ranges.
+    ///
+    /// A `block_size` over [`bgzf::BGZF_MAX_BLOCK_SIZE`] is an
+    /// [`Error::BgzfBlockTooLarge`]: BGZF readers reject blocks that inflate to
+    /// more than 64 KiB.
+    pub fn validate(&self) -> Result<()> {
+        if self.block_size == 0 {
+            return Err(Error::InvalidConfig("block_size must be at least 1".to_string()));
+        }
+        if self.block_size > bgzf::BGZF_MAX_BLOCK_SIZE {
+            return Err(Error::BgzfBlockTooLarge {
+                size: self.block_size,
+                max: bgzf::BGZF_MAX_BLOCK_SIZE,
+            });
+        }
+        if !(0.0..=0.5).contains(&self.boundary_overshoot) {
+            return Err(Error::InvalidConfig(format!(
+                "boundary_overshoot must be between 0.0 and 0.5, got {}",
+                self.boundary_overshoot
+            )));
+        }
+        if self.recompress && self.compression_level.recompress_chain().is_none() {
+            return Err(Error::InvalidConfig(format!(
+                "recompress requires compression level 7-9, got {}",
+                self.compression_level.level()
+            )));
+        }
+        if self.format == (FormatProfile::Lines { per_record: 0 }) {
+            return Err(Error::InvalidConfig("lines per record must be at least 1".to_string()));
+        }
+        if self.format == (FormatProfile::FixedRecords { record_size: 0 }) {
+            return Err(Error::InvalidConfig("record size must be at least 1".to_string()));
+        }
+        if self.blocks_per_job == 0 {
+            return Err(Error::InvalidConfig("blocks_per_job must be at least 1".to_string()));
+        }
+        if let Some(min_block_size) = self.min_block_size {
+            if min_block_size > self.block_size / 2 {
+                return Err(Error::InvalidConfig(format!(
+                    "min_block_size must be at most half of block_size ({}), got {}",
+                    self.block_size / 2,
+                    min_block_size
+                )));
+            }
+            if self.block_per_member {
+                return Err(Error::InvalidConfig(
+                    "min_block_size cannot be combined with block_per_member".to_string(),
+                ));
+            }
+        }
+        let mut xlen = 6;
+        for (si1, si2, data) in &self.extra_subfields {
+            if (*si1, *si2) == (b'B', b'C') {
+                return Err(Error::InvalidConfig(
+                    "extra_subfields must not contain the BC subfield".to_string(),
+                ));
+            }
+            xlen += 4 + data.len();
+        }
+        if xlen > u16::MAX as usize {
+            return Err(Error::InvalidConfig(format!(
+                "extra_subfields do not fit in the gzip extra field ({xlen} > {} bytes)",
+                u16::MAX
+            )));
+        }
+        Ok(())
     }
 
     /// Number of encoding threads to use, resolving 0 to auto-detect.
@@ -149,38 +493,233 @@ impl TranscodeConfig {
             n => n.clamp(1, 32),
         }
     }
+
+    /// Cap on encoding jobs in flight for `num_threads` workers, resolving
+    /// `max_jobs_ahead` 0 to 16 per thread.
+    pub fn effective_max_jobs_ahead(&self, num_threads: usize) -> usize {
+        match self.max_jobs_ahead {
+            0 => num_threads * 16,
+            n => n,
+        }
+    }
+}
+
+/// Chainable construction of a [`TranscodeConfig`], checked by [`build`](Self::build).
+///
+/// Fields without a setter keep their defaults; set them on the built config.
+///
+/// ```
+/// use rebgzf::{CompressionLevel, FormatProfile, TranscodeConfig};
+///
+/// let config = TranscodeConfig::builder()
+///     .compression_level(CompressionLevel::Level6)
+///     .format(FormatProfile::Fastq)
+///     .threads(4)
+///     .index(true)
+///     .build()?;
+/// assert!(config.build_index);
+///
+/// assert!(TranscodeConfig::builder().block_size(100_000).build().is_err());
+/// # Ok::<(), rebgzf::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TranscodeConfigBuilder {
+    config: TranscodeConfig,
+    /// Explicit block size; otherwise the compression level's default
+    block_size: Option<usize>,
+}
+
+impl TranscodeConfigBuilder {
+    /// Target uncompressed bytes per block (1 to [`bgzf::BGZF_MAX_BLOCK_SIZE`])
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = Some(block_size);
+        self
+    }
+
+    pub fn compression_level(mut self, level: CompressionLevel) -> Self {
+        self.config.compression_level = level;
+        self
+    }
+
+    /// Number of encoding threads (0 = auto-detect)
+    pub fn threads(mut self, num_threads: usize) -> Self {
+        self.config.num_threads = num_threads;
+        self
+    }
+
+    pub fn format(mut self, format: FormatProfile) -> Self {
+        self.config.format = format;
+        self
+    }
+
+    /// Build a GZI index during transcoding
+    pub fn index(mut self, build_index: bool) -> Self {
+        self.config.build_index = build_index;
+        self
+    }
+
+    /// Finish the configuration, rejecting anything [`TranscodeConfig::validate`]
+    /// rejects, such as a block size of 0 or over [`bgzf::BGZF_MAX_BLOCK_SIZE`].
+    pub fn build(self) -> Result<TranscodeConfig> {
+        let mut config = self.config;
+        config.block_size =
+            self.block_size.unwrap_or_else(|| config.compression_level.default_block_size());
+        config.validate()?;
+        Ok(config)
+    }
 }
 
 impl Default for TranscodeConfig {
     fn default() -> Self {
+        let compression_level = CompressionLevel::Level1;
         Self {
-            block_size: 65280,
-            compression_level: CompressionLevel::Level1,
+            block_size: compression_level.default_block_size(),
+            compression_level,
             format: FormatProfile::Default,
             num_threads: 0,
+            blocks_per_job: 1,
+            max_jobs_ahead: 0,
             buffer_size: 128 * 1024,
+            single_write_below: 0,
             strict_bgzf_check: false,
             force_transcode: false,
             build_index: false,
+            boundary_overshoot: 0.1,
+            min_block_size: None,
+            extra_subfields: Vec::new(),
+            preserve_ftext: false,
+            preserve_mtime: false,
+            write_eof: true,
+            verify_input_crc: false,
+            tolerate_bad_input_trailer: false,
+            recompress: false,
+            block_per_member: false,
+            collect_parallel_stats: false,
+            collect_member_stats: false,
+            on_block: None,
         }
     }
 }
 
 /// Statistics from a transcoding operation
+///
+/// Stream-level totals are `u64` so inputs over 4 GiB are counted correctly on
+/// 32-bit targets; only per-block sizes fit in `u32`.
 #[derive(Clone, Debug, Default)]
 pub struct TranscodeStats {
     pub input_bytes: u64,
     pub output_bytes: u64,
+    /// Bytes the input decoded to, counted as blocks are parsed rather than taken
+    /// from the members' ISIZE trailers, so members over 4 GiB do not wrap (0 when
+    /// the input was copied directly without an index)
+    pub uncompressed_bytes: u64,
     pub blocks_written: u64,
     pub boundary_refs_resolved: u64,
+    /// Bytes re-emitted as literals because their back-reference crossed a block boundary
+    pub resolved_bytes: u64,
+    /// Output bytes saved by [`TranscodeConfig::recompress`] over re-coding the input's
+    /// tokens (0 when off)
+    pub recompressed_bytes_saved: u64,
+    /// Smallest uncompressed size of any data block (excludes the EOF block; 0 if none)
+    pub min_block_uncompressed: u32,
+    /// Largest uncompressed size of any data block (excludes the EOF block)
+    pub max_block_uncompressed: u32,
     /// Input was already valid BGZF and was copied directly
     pub copied_directly: bool,
+    /// Every DEFLATE block in the input was stored (type 0), so the input was
+    /// not actually compressed (e.g. `pigz -0` output)
+    pub input_was_stored: bool,
     /// GZI index entries (populated when build_index is true)
     pub index_entries: Option<Vec<GziEntry>>,
+    /// First (CHROM, POS) of each block (populated when build_index is true and
+    /// the format is [`FormatProfile::Vcf`])
+    pub coordinate_index: Option<Vec<BlockCoordinate>>,
+    /// Encoding worker utilization (populated when collect_parallel_stats is true and
+    /// the parallel path ran)
+    pub parallel: Option<ParallelStats>,
+    /// Each input gzip member's trailer and decoded size, in input order
+    /// (populated when collect_member_stats is true and the input was not copied
+    /// directly)
+    pub members: Vec<MemberInfo>,
+    /// Number of gzip members transcoded
+    pub members_processed: u64,
+    /// A member's bytes disagreed with its trailer, which
+    /// [`TranscodeConfig::tolerate_bad_input_trailer`] let through
+    pub input_trailer_mismatch: bool,
+}
+
+impl TranscodeStats {
+    /// Count a gzip member that ended with trailer `crc32` and `isize`, also
+    /// recording it in `members` if `collect`.
+    pub(crate) fn add_member(
+        &mut self,
+        collect: bool,
+        crc32: u32,
+        isize: u32,
+        uncompressed_bytes: u64,
+    ) {
+        if collect {
+            self.members.push(MemberInfo { crc32, isize, uncompressed_bytes });
+        }
+        self.members_processed += 1;
+    }
+}
+
+/// One input gzip member, as reported in [`TranscodeStats::members`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemberInfo {
+    /// CRC32 from the member's trailer
+    pub crc32: u32,
+    /// ISIZE from the member's trailer: the uncompressed size modulo 2^32
+    pub isize: u32,
+    /// Bytes the member actually decoded to
+    pub uncompressed_bytes: u64,
+}
+
+/// Per-worker utilization from the parallel encoder.
+///
+/// High idle times mean workers were starved waiting on the single parsing
+/// thread, so more threads will not help; low idle times mean the workers
+/// were saturated.
+#[derive(Clone, Debug, Default)]
+pub struct ParallelStats {
+    /// Blocks encoded by each worker thread
+    pub worker_blocks: Vec<u64>,
+    /// Time each worker thread spent waiting for a job
+    pub worker_idle: Vec<Duration>,
+    /// Most encoded blocks held in memory at once, waiting for an earlier,
+    /// slower job to be written; bounded by [`TranscodeConfig::max_jobs_ahead`]
+    pub peak_buffered_blocks: usize,
+}
+
+impl ParallelStats {
+    /// Total idle time summed over all workers
+    pub fn total_idle(&self) -> Duration {
+        self.worker_idle.iter().sum()
+    }
 }
 
 /// Trait for the complete transcoding operation
 pub trait Transcoder {
     /// Transcode from gzip input to BGZF output
+    ///
+    /// On error, the output is best-effort: it holds the complete blocks written
+    /// so far and never the BGZF EOF block, so `verify_bgzf` reports
+    /// `has_eof_block == false`. A failing output writer may still be left with
+    /// part of a block.
     fn transcode<R: Read, W: Write>(&mut self, input: R, output: W) -> Result<TranscodeStats>;
+
+    /// Transcode reusing the buffers in `context`, amortizing allocation across many inputs.
+    ///
+    /// Output is identical to [`transcode`](Transcoder::transcode). Implementations
+    /// that cannot make use of the context fall back to `transcode`.
+    fn transcode_with_context<R: Read, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        context: &mut TranscodeContext,
+    ) -> Result<TranscodeStats> {
+        let _ = context;
+        self.transcode(input, output)
+    }
 }
@@ -0,0 +1,35 @@
+//! Internal logging macros.
+//!
+//! With the `log` feature these forward to the `log` crate, so records show up
+//! under targets like `rebgzf::transcoder::single`. Without it they expand to
+//! dead code: the arguments are still type-checked but nothing is evaluated.
+
+macro_rules! log_at {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::$level!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::logging::log_at!(error, $($arg)+) };
+}
+
+// Not `warn`, which would clash with the built-in attribute
+macro_rules! warning {
+    ($($arg:tt)+) => { $crate::logging::log_at!(warn, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::logging::log_at!(debug, $($arg)+) };
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::logging::log_at!(trace, $($arg)+) };
+}
+
+pub(crate) use {debug, error, log_at, trace, warning};
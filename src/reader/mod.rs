@@ -19,8 +19,10 @@ pub use replacer::replace_markers;
 pub use speculative::{decode_with_libdeflate, speculative_decode};
 pub use window_map::WindowMap;
 
+#[cfg(feature = "mmap")]
 use crate::mmap::MappedFile;
 
+#[cfg(feature = "mmap")]
 use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, BufReader, Read};
@@ -43,6 +45,7 @@ pub struct ParallelGzipReader {
 
 enum ReaderInner {
     /// Multi-member gzip: decompress batches of members on demand.
+    #[cfg(feature = "mmap")]
     MultiMember {
         /// Memory-mapped file data (kept alive for the reader's lifetime).
         mapped: MappedFile,
@@ -60,6 +63,7 @@ enum ReaderInner {
         pending_chunks: VecDeque<ChunkData>,
     },
     /// Single-member gzip: all chunks pre-decoded via speculative parallel decode.
+    #[cfg(feature = "mmap")]
     SingleMember {
         /// Mapped file kept alive for safety.
         _mapped: MappedFile,
@@ -81,8 +85,22 @@ impl ParallelGzipReader {
     /// # Arguments
     /// * `path` - path to the gzip file
     /// * `threads` - number of worker threads (0 = auto-detect)
+    ///
+    /// Without the `mmap` feature, always decompresses by streaming.
     pub fn from_file<P: AsRef<Path>>(path: P, threads: usize) -> io::Result<Self> {
         let file = File::open(path.as_ref())?;
+        #[cfg(feature = "mmap")]
+        {
+            Self::from_mapped_file(file, threads)
+        }
+        #[cfg(not(feature = "mmap"))]
+        {
+            Self::from_reader(file, threads)
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    fn from_mapped_file(file: File, threads: usize) -> io::Result<Self> {
         let mapped = match MappedFile::try_from_file(file) {
             Ok(m) => m,
             Err((_e, file)) => {
@@ -156,6 +174,7 @@ impl Read for ParallelGzipReader {
         }
 
         match &mut self.inner {
+            #[cfg(feature = "mmap")]
             ReaderInner::MultiMember {
                 mapped,
                 members,
@@ -202,6 +221,7 @@ impl Read for ParallelGzipReader {
                     pending_chunks.extend(new_chunks);
                 }
             }
+            #[cfg(feature = "mmap")]
             ReaderInner::SingleMember { fetcher, .. } => {
                 loop {
                     match fetcher.next_chunk() {
@@ -0,0 +1,97 @@
+//! Deterministic data patterns for round-trip checks.
+//!
+//! Shared by the integration tests and the CLI's `--self-test`, so both
+//! exercise the transcoder on the same inputs. Public only for them, so it is
+//! hidden from the docs and not part of the stable API.
+
+use std::io::{self, Write};
+
+/// Generate random data using a simple PRNG
+pub fn generate_random_data(size: usize, seed: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size);
+    let mut state = seed;
+    for _ in 0..size {
+        // Simple xorshift PRNG
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.push((state & 0xFF) as u8);
+    }
+    data
+}
+
+/// Generate highly repetitive data (good compression)
+pub fn generate_repetitive_data(size: usize) -> Vec<u8> {
+    let pattern = b"AAAAAAAAAAAAAAAA";
+    pattern.iter().cycle().take(size).copied().collect()
+}
+
+/// Generate data with mixed patterns (moderate compression)
+pub fn generate_mixed_data(size: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size);
+    let patterns = [
+        b"ACGTACGTACGTACGT".as_slice(),
+        b"NNNNNNNNNNNNNNNN".as_slice(),
+        b"ATATATATATATATAT".as_slice(),
+    ];
+
+    let mut pattern_idx = 0;
+    while data.len() < size {
+        let pattern = patterns[pattern_idx % patterns.len()];
+        let remaining = size - data.len();
+        let chunk_size = remaining.min(pattern.len());
+        data.extend_from_slice(&pattern[..chunk_size]);
+        pattern_idx += 1;
+    }
+    data
+}
+
+/// Generate FASTQ-formatted data
+pub fn generate_fastq_data(num_reads: usize, read_length: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    let bases = [b'A', b'C', b'G', b'T'];
+
+    for i in 0..num_reads {
+        // Header
+        writeln!(data, "@read_{}", i).expect("writing to a Vec cannot fail");
+
+        // Sequence - use deterministic pattern based on read number
+        for j in 0..read_length {
+            data.push(bases[(i + j) % 4]);
+        }
+        data.push(b'\n');
+
+        // Plus line
+        data.extend_from_slice(b"+\n");
+
+        // Quality scores
+        data.resize(data.len() + read_length, b'I'); // High quality
+        data.push(b'\n');
+    }
+    data
+}
+
+/// Generate every byte value in order, repeated `repeats` times
+pub fn generate_all_bytes(repeats: usize) -> Vec<u8> {
+    (0..repeats).flat_map(|_| 0..=255u8).collect()
+}
+
+/// Writer that keeps everything written and counts calls to `write`, as each
+/// one is a round trip on NFS or Lustre
+#[derive(Default)]
+pub struct CountingWriter {
+    pub data: Vec<u8>,
+    pub writes: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-phase timing hooks for the benchmark harness.
+//!
+//! With the `timing` feature, the single-threaded transcoder adds the time it
+//! spends in each phase to per-thread totals that [`take_phase_times`] reads and
+//! resets. Without it [`timed`] just calls its closure, so normal builds pay
+//! nothing.
+
+#[cfg(feature = "timing")]
+use std::cell::Cell;
+#[cfg(feature = "timing")]
+use std::time::{Duration, Instant};
+
+/// A transcoding phase measured by [`timed`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Phase {
+    /// `DeflateParser::parse_block`
+    Parse,
+    /// `BoundaryResolver::resolve_block`
+    Resolve,
+    /// `HuffmanEncoder::encode`, and the fused resolve-and-encode used for fixed Huffman
+    Encode,
+}
+
+/// Time spent in each phase on this thread since the last [`take_phase_times`].
+///
+/// Whatever the phases leave out of a transcode's wall time is block
+/// splitting, BGZF framing, CRC bookkeeping, and I/O.
+#[cfg(feature = "timing")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimes {
+    pub parse: Duration,
+    pub resolve: Duration,
+    pub encode: Duration,
+}
+
+#[cfg(feature = "timing")]
+thread_local! {
+    static TIMES: Cell<PhaseTimes> = Cell::new(PhaseTimes::default());
+}
+
+/// Run `f`, charging its time to `phase` when the `timing` feature is on
+#[inline(always)]
+pub(crate) fn timed<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "timing")]
+    {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        TIMES.with(|times| {
+            let mut totals = times.get();
+            match phase {
+                Phase::Parse => totals.parse += elapsed,
+                Phase::Resolve => totals.resolve += elapsed,
+                Phase::Encode => totals.encode += elapsed,
+            }
+            times.set(totals);
+        });
+        result
+    }
+    #[cfg(not(feature = "timing"))]
+    {
+        let _ = phase;
+        f()
+    }
+}
+
+/// Return this thread's phase totals and reset them to zero.
+///
+/// Only the single-threaded transcoder is instrumented; call this on the
+/// thread that ran it.
+#[cfg(feature = "timing")]
+pub fn take_phase_times() -> PhaseTimes {
+    TIMES.with(Cell::take)
+}
@@ -1,10 +1,15 @@
+use std::collections::VecDeque;
+
 use crate::bits::BitWriter;
 use crate::deflate::tables::{encode_distance, encode_length};
 use crate::deflate::tokens::LZ77Token;
+use crate::error::{Error, Result};
 use crate::huffman::HuffmanEncoder;
+use crate::logging::warning;
+use crate::transcoder::window::DEFAULT_WINDOW_SIZE;
 
-/// Maximum LZ77 back-reference distance (32KB).
-const MAX_DISTANCE: usize = 32768;
+/// Maximum LZ77 back-reference distance (the DEFLATE window size).
+const MAX_DISTANCE: usize = DEFAULT_WINDOW_SIZE;
 
 /// Resolves LZ77 back-references that cross BGZF block boundaries.
 ///
@@ -28,6 +33,103 @@ pub struct BoundaryResolver {
     /// Statistics
     refs_resolved: u64,
     refs_preserved: u64,
+    /// Bytes emitted as literals because their Copy crossed a block boundary
+    resolved_bytes: u64,
+    /// Checks resolved bytes against gzip member trailers, when enabled
+    member_check: Option<MemberCheck>,
+    /// Record a member that disagrees with its trailer instead of failing
+    tolerate_mismatch: bool,
+}
+
+/// Running CRC32 and size of the gzip member being resolved, compared with
+/// the member's trailer once its last byte has been resolved.
+///
+/// Blocks are resolved some time after their tokens are parsed, so a trailer
+/// is usually known before all of its member's bytes have been seen; trailers
+/// wait in `pending` until resolution reaches their end position.
+#[derive(Default)]
+struct MemberCheck {
+    /// Members whose last byte has not been resolved yet: (end position, CRC32, ISIZE)
+    pending: VecDeque<(u64, u32, u32)>,
+    /// CRC32 of the current member's bytes resolved so far
+    hasher: crc32fast::Hasher,
+    /// Position of the current member's first byte
+    member_start: u64,
+    /// Bytes resolved so far
+    position: u64,
+    /// Record a member that disagrees with its trailer instead of failing
+    tolerate: bool,
+    /// Whether any member disagreed with its trailer
+    mismatch: bool,
+}
+
+impl MemberCheck {
+    /// Fold in a resolved block whose bytes have CRC32 `crc`
+    fn add_block(&mut self, bytes: &[u8], crc: u32) -> Result<()> {
+        let len = bytes.len() as u64;
+        let next_end = self.pending.front().map(|&(end, _, _)| end);
+        if next_end.map_or(true, |end| end >= self.position + len) {
+            // The whole block belongs to the current member: combine rather than rehash
+            self.hasher.combine(&crc32fast::Hasher::new_with_initial_len(crc, len));
+            self.position += len;
+            return self.check_ended();
+        }
+
+        let mut rest = bytes;
+        while let Some(&(end, _, _)) = self.pending.front() {
+            let n = ((end - self.position) as usize).min(rest.len());
+            self.hasher.update(&rest[..n]);
+            self.position += n as u64;
+            rest = &rest[n..];
+            if self.position < end {
+                return Ok(());
+            }
+            self.check_ended()?;
+        }
+        self.hasher.update(rest);
+        self.position += rest.len() as u64;
+        Ok(())
+    }
+
+    /// Record the trailer of the member ending at uncompressed position `end`
+    fn end_member(&mut self, end: u64, crc: u32, isize: u32) -> Result<()> {
+        // Bytes past a member's end can only be resolved after its trailer is read
+        if end < self.position {
+            return Err(Error::Internal(format!(
+                "member ending at {} reported after resolving to {}",
+                end, self.position
+            )));
+        }
+        self.pending.push_back((end, crc, isize));
+        self.check_ended()
+    }
+
+    /// Compare every member that ends at the current position with its trailer
+    fn check_ended(&mut self) -> Result<()> {
+        while let Some(&(end, expected_crc, expected_size)) = self.pending.front() {
+            if end != self.position {
+                return Ok(());
+            }
+            self.pending.pop_front();
+            let found = std::mem::take(&mut self.hasher).finalize();
+            // ISIZE is the member's size modulo 2^32
+            let size = (end - self.member_start) as u32;
+            self.member_start = end;
+            let error = if found != expected_crc {
+                Error::Crc32Mismatch { expected: expected_crc, found }
+            } else if size != expected_size {
+                Error::SizeMismatch { expected: expected_size, found: size }
+            } else {
+                continue;
+            };
+            if !self.tolerate {
+                return Err(error);
+            }
+            warning!("gzip member ending at uncompressed byte {}: {}", end, error);
+            self.mismatch = true;
+        }
+        Ok(())
+    }
 }
 
 impl BoundaryResolver {
@@ -40,6 +142,46 @@ impl BoundaryResolver {
             position: 0,
             refs_resolved: 0,
             refs_preserved: 0,
+            resolved_bytes: 0,
+            member_check: None,
+            tolerate_mismatch: false,
+        }
+    }
+
+    /// Check each gzip member's resolved bytes against its trailer, as reported
+    /// through [`end_member`](Self::end_member).
+    pub fn set_check_members(&mut self, check: bool) {
+        let tolerate = self.tolerate_mismatch;
+        self.member_check = check.then(|| MemberCheck { tolerate, ..Default::default() });
+    }
+
+    /// Have the member check record a member whose bytes disagree with its
+    /// trailer, reported by [`trailer_mismatch`](Self::trailer_mismatch), instead
+    /// of failing.
+    pub fn set_tolerate_mismatch(&mut self, tolerate: bool) {
+        self.tolerate_mismatch = tolerate;
+        if let Some(ref mut check) = self.member_check {
+            check.tolerate = tolerate;
+        }
+    }
+
+    /// Whether a checked member disagreed with its trailer, when tolerated.
+    pub fn trailer_mismatch(&self) -> bool {
+        self.member_check.as_ref().is_some_and(|check| check.mismatch)
+    }
+
+    /// Record that the gzip member whose trailer holds `crc` and `isize` ends at
+    /// uncompressed position `end`, i.e. after `end` bytes of parsed tokens.
+    ///
+    /// Once the resolver has resolved up to `end`, the member's CRC32 and size are
+    /// compared with the trailer, returning [`Error::Crc32Mismatch`] or
+    /// [`Error::SizeMismatch`] from this call or from the block resolution that
+    /// reaches `end`. Does nothing unless enabled with
+    /// [`set_check_members`](Self::set_check_members).
+    pub fn end_member(&mut self, end: u64, crc: u32, isize: u32) -> Result<()> {
+        match self.member_check {
+            Some(ref mut check) => check.end_member(end, crc, isize),
+            None => Ok(()),
         }
     }
 
@@ -50,6 +192,19 @@ impl BoundaryResolver {
         self.current_len += 1;
     }
 
+    /// Reject a back-reference that reaches before the start of the decoded stream.
+    ///
+    /// Only corrupt input (or a parser bug) produces one; without this check the
+    /// lookup would underflow the decode buffer.
+    #[inline]
+    fn check_distance(&self, distance: u16) -> Result<()> {
+        let available = self.decode_buf.len();
+        if distance == 0 || distance as usize > available {
+            return Err(Error::InvalidBackReference { distance, available });
+        }
+        Ok(())
+    }
+
     /// Copy `length` bytes from `distance` bytes back in the decode buffer.
     /// Handles both non-RLE (distance >= length) and RLE (distance < length) cases.
     #[inline]
@@ -92,12 +247,14 @@ impl BoundaryResolver {
     /// `block_start`: position where this BGZF block starts
     /// `tokens`: LZ77 tokens to process
     ///
-    /// Returns: (tokens with cross-boundary references resolved, CRC32, uncompressed size)
+    /// Returns: (tokens with cross-boundary references resolved, CRC32, uncompressed size),
+    /// or `InvalidBackReference` if a Copy reaches past the available history.
     pub fn resolve_block(
         &mut self,
         _block_start: u64,
         tokens: &[LZ77Token],
-    ) -> (Vec<LZ77Token>, u32, u32) {
+    ) -> Result<(Vec<LZ77Token>, u32, u32)> {
+        self.rotate_tail();
         let mut output = Vec::with_capacity(tokens.len());
 
         for token in tokens {
@@ -109,6 +266,7 @@ impl BoundaryResolver {
                 }
 
                 LZ77Token::Copy { length, distance } => {
+                    self.check_distance(*distance)?;
                     let dist = *distance as usize;
                     let len = *length as usize;
 
@@ -121,6 +279,7 @@ impl BoundaryResolver {
                             output.push(LZ77Token::Literal(byte));
                         }
                         self.refs_resolved += 1;
+                        self.resolved_bytes += len as u64;
                     } else {
                         // Within-block: preserve Copy, append decoded bytes
                         self.copy_from_back(*distance, *length);
@@ -135,18 +294,25 @@ impl BoundaryResolver {
             }
         }
 
-        let (crc, uncompressed_size) = self.finish_block();
-        (output, crc, uncompressed_size)
+        let (crc, uncompressed_size) = self.finish_block()?;
+        Ok((output, crc, uncompressed_size))
     }
 
-    /// Finalize the current block: compute CRC over decoded bytes and rotate tail.
+    /// Finalize the current block: compute CRC over decoded bytes.
+    /// The tail is rotated when the next block starts, so `last_block` stays valid until then.
     /// Returns (CRC32, uncompressed_size).
-    fn finish_block(&mut self) -> (u32, u32) {
-        let block_bytes = &self.decode_buf[self.tail_len..self.tail_len + self.current_len];
-        let crc = crc32fast::hash(block_bytes);
-        let uncompressed_size = self.current_len as u32;
-        self.rotate_tail();
-        (crc, uncompressed_size)
+    fn finish_block(&mut self) -> Result<(u32, u32)> {
+        let block = &self.decode_buf[self.tail_len..self.tail_len + self.current_len];
+        let crc = crc32fast::hash(block);
+        if let Some(ref mut check) = self.member_check {
+            check.add_block(block, crc)?;
+        }
+        Ok((crc, self.current_len as u32))
+    }
+
+    /// Decoded bytes of the most recently resolved block
+    pub fn last_block(&self) -> &[u8] {
+        &self.decode_buf[self.tail_len..self.tail_len + self.current_len]
     }
 
     /// Fused resolve + encode for fixed Huffman (single-threaded path).
@@ -161,7 +327,8 @@ impl BoundaryResolver {
         _block_start: u64,
         tokens: &[LZ77Token],
         encoder: &HuffmanEncoder,
-    ) -> (Vec<u8>, u32, u32) {
+    ) -> Result<(Vec<u8>, u32, u32)> {
+        self.rotate_tail();
         let mut writer = BitWriter::with_capacity(tokens.len() * 2);
         writer.write_bit(true); // BFINAL
         writer.write_bits(1, 2); // BTYPE = 01 (fixed Huffman)
@@ -179,6 +346,7 @@ impl BoundaryResolver {
                 }
 
                 LZ77Token::Copy { length, distance } => {
+                    self.check_distance(*distance)?;
                     let dist = *distance as usize;
                     let len = *length as usize;
 
@@ -192,6 +360,7 @@ impl BoundaryResolver {
                             writer.write_bits(code, code_len);
                         }
                         self.refs_resolved += 1;
+                        self.resolved_bytes += len as u64;
                     } else {
                         // Within-block: encode as Copy
                         self.copy_from_back(*distance, *length);
@@ -225,8 +394,8 @@ impl BoundaryResolver {
         writer.write_bits(code, len);
 
         let deflate_data = writer.finish();
-        let (crc, uncompressed_size) = self.finish_block();
-        (deflate_data, crc, uncompressed_size)
+        let (crc, uncompressed_size) = self.finish_block()?;
+        Ok((deflate_data, crc, uncompressed_size))
     }
 
     /// Get the current position in uncompressed stream
@@ -239,6 +408,11 @@ impl BoundaryResolver {
         (self.refs_resolved, self.refs_preserved)
     }
 
+    /// Total bytes expanded to literals by cross-boundary resolution
+    pub fn resolved_bytes(&self) -> u64 {
+        self.resolved_bytes
+    }
+
     /// Reset the resolver
     pub fn reset(&mut self) {
         self.decode_buf.clear();
@@ -247,6 +421,10 @@ impl BoundaryResolver {
         self.position = 0;
         self.refs_resolved = 0;
         self.refs_preserved = 0;
+        self.resolved_bytes = 0;
+        if self.member_check.is_some() {
+            self.set_check_members(true);
+        }
     }
 }
 
@@ -265,7 +443,7 @@ mod tests {
         let mut resolver = BoundaryResolver::new();
 
         let tokens = vec![LZ77Token::Literal(b'H'), LZ77Token::Literal(b'i')];
-        let (resolved, crc, size) = resolver.resolve_block(0, &tokens);
+        let (resolved, crc, size) = resolver.resolve_block(0, &tokens).unwrap();
 
         assert_eq!(resolved.len(), 2);
         assert_eq!(resolved[0], LZ77Token::Literal(b'H'));
@@ -285,7 +463,7 @@ mod tests {
             LZ77Token::Literal(b'B'),
             LZ77Token::Copy { length: 2, distance: 2 }, // Copy "AB"
         ];
-        let (resolved, crc, size) = resolver.resolve_block(0, &tokens);
+        let (resolved, crc, size) = resolver.resolve_block(0, &tokens).unwrap();
 
         // Copy should be preserved since it references within block
         assert_eq!(resolved.len(), 3);
@@ -309,7 +487,7 @@ mod tests {
             LZ77Token::Literal(b'C'),
             LZ77Token::Literal(b'D'),
         ];
-        let (_, crc1, size1) = resolver.resolve_block(0, &tokens1);
+        let (_, crc1, size1) = resolver.resolve_block(0, &tokens1).unwrap();
         assert_eq!(resolver.position(), 4);
         assert_eq!(size1, 4);
         assert_eq!(crc1, crc32fast::hash(b"ABCD"));
@@ -320,7 +498,7 @@ mod tests {
             LZ77Token::Literal(b'E'),
             LZ77Token::Copy { length: 2, distance: 5 }, // refs "AB" in block 1
         ];
-        let (resolved, crc2, size2) = resolver.resolve_block(4, &tokens2);
+        let (resolved, crc2, size2) = resolver.resolve_block(4, &tokens2).unwrap();
 
         // Copy should be resolved to literals since it references previous block
         assert_eq!(resolved.len(), 3);
@@ -333,6 +511,7 @@ mod tests {
         let (refs_resolved, refs_preserved) = resolver.stats();
         assert_eq!(refs_resolved, 1);
         assert_eq!(refs_preserved, 0);
+        assert_eq!(resolver.resolved_bytes(), 2);
     }
 
     #[test]
@@ -346,7 +525,7 @@ mod tests {
             LZ77Token::Literal(b'C'),
             LZ77Token::Literal(b'D'),
         ];
-        let _ = resolver.resolve_block(0, &tokens1);
+        let _ = resolver.resolve_block(0, &tokens1).unwrap();
 
         // Second block: "E" + copy from block 1 + copy within block 2
         let tokens2 = vec![
@@ -354,7 +533,7 @@ mod tests {
             LZ77Token::Copy { length: 2, distance: 5 }, // refs block 1 -> resolve
             LZ77Token::Copy { length: 2, distance: 1 }, // refs within block 2 -> preserve
         ];
-        let (resolved, crc, size) = resolver.resolve_block(4, &tokens2);
+        let (resolved, crc, size) = resolver.resolve_block(4, &tokens2).unwrap();
 
         // Should have: E, A, B, Copy(2,1)
         assert_eq!(resolved.len(), 4);
@@ -375,7 +554,7 @@ mod tests {
         for i in 0..40000u32 {
             tokens1.push(LZ77Token::Literal((i & 0xFF) as u8));
         }
-        let (_, _, size1) = resolver.resolve_block(0, &tokens1);
+        let (_, _, size1) = resolver.resolve_block(0, &tokens1).unwrap();
         assert_eq!(size1, 40000);
 
         // Second block: starts at position 40000.
@@ -388,7 +567,7 @@ mod tests {
             LZ77Token::Copy { length: 5, distance: 100 }, // cross-boundary
             LZ77Token::Copy { length: 6, distance: 3 },   // within-block RLE
         ];
-        let (resolved, crc, size2) = resolver.resolve_block(40000, &tokens2);
+        let (resolved, crc, size2) = resolver.resolve_block(40000, &tokens2).unwrap();
 
         assert_eq!(size2, 3 + 5 + 6);
         // First 3: literals
@@ -442,18 +621,115 @@ mod tests {
 
         // Two-pass path
         let mut resolver_2pass = BoundaryResolver::new();
-        resolver_2pass.resolve_block(0, &tokens1);
-        let (resolved, crc_2pass, size_2pass) = resolver_2pass.resolve_block(1000, &tokens2);
+        resolver_2pass.resolve_block(0, &tokens1).unwrap();
+        let (resolved, crc_2pass, size_2pass) =
+            resolver_2pass.resolve_block(1000, &tokens2).unwrap();
         let deflate_2pass = encoder.encode(&resolved, true).unwrap();
 
         // Fused path
         let mut resolver_fused = BoundaryResolver::new();
-        resolver_fused.resolve_block(0, &tokens1);
+        resolver_fused.resolve_block(0, &tokens1).unwrap();
         let (deflate_fused, crc_fused, size_fused) =
-            resolver_fused.resolve_and_encode_fixed(1000, &tokens2, &encoder);
+            resolver_fused.resolve_and_encode_fixed(1000, &tokens2, &encoder).unwrap();
 
         assert_eq!(deflate_fused, deflate_2pass, "DEFLATE output must match");
         assert_eq!(crc_fused, crc_2pass, "CRC must match");
         assert_eq!(size_fused, size_2pass, "Uncompressed size must match");
     }
+
+    #[test]
+    fn test_copy_past_stream_start_is_error() {
+        let mut resolver = BoundaryResolver::new();
+        let tokens = vec![LZ77Token::Literal(b'A'), LZ77Token::Copy { length: 3, distance: 2 }];
+
+        assert!(matches!(
+            resolver.resolve_block(0, &tokens),
+            Err(Error::InvalidBackReference { distance: 2, available: 1 })
+        ));
+
+        let encoder = HuffmanEncoder::new(false);
+        let mut resolver = BoundaryResolver::new();
+        assert!(resolver.resolve_and_encode_fixed(0, &tokens, &encoder).is_err());
+    }
+
+    #[test]
+    fn test_member_check() {
+        let literals =
+            |bytes: &[u8]| bytes.iter().map(|&b| LZ77Token::Literal(b)).collect::<Vec<_>>();
+        let trailer = |bytes: &[u8]| (crc32fast::hash(bytes), bytes.len() as u32);
+
+        // Members "ABC", "" and "DEFGH" across blocks "AB", "CDE" and "FGH"; the
+        // first two trailers are known before their members are resolved, the last after
+        let mut resolver = BoundaryResolver::new();
+        resolver.set_check_members(true);
+        let (crc, isize) = trailer(b"ABC");
+        resolver.end_member(3, crc, isize).unwrap();
+        let (crc, isize) = trailer(b"");
+        resolver.end_member(3, crc, isize).unwrap();
+        resolver.resolve_block(0, &literals(b"AB")).unwrap();
+        resolver.resolve_block(2, &literals(b"CDE")).unwrap();
+        resolver.resolve_block(5, &literals(b"FGH")).unwrap();
+        let (crc, isize) = trailer(b"DEFGH");
+        resolver.end_member(8, crc, isize).unwrap();
+
+        // A trailer that disagrees fails once its member is resolved
+        resolver.reset();
+        resolver.end_member(4, crc32fast::hash(b"ABCD"), 5).unwrap();
+        let result = resolver.resolve_block(0, &literals(b"ABCDEF"));
+        assert!(matches!(result, Err(Error::SizeMismatch { expected: 5, found: 4 })));
+
+        resolver.reset();
+        resolver.resolve_block(0, &literals(b"ABCD")).unwrap();
+        let result = resolver.end_member(4, crc32fast::hash(b"ABCE"), 4);
+        assert!(matches!(result, Err(Error::Crc32Mismatch { .. })));
+        assert!(!resolver.trailer_mismatch());
+
+        // Tolerated, a mismatch is recorded and the following members still checked
+        resolver.reset();
+        resolver.set_tolerate_mismatch(true);
+        resolver.end_member(4, crc32fast::hash(b"ABCE"), 4).unwrap();
+        let (crc, isize) = trailer(b"EF");
+        resolver.end_member(6, crc, isize).unwrap();
+        resolver.resolve_block(0, &literals(b"ABCDEF")).unwrap();
+        assert!(resolver.trailer_mismatch());
+
+        // Off unless enabled
+        let mut resolver = BoundaryResolver::new();
+        resolver.resolve_block(0, &literals(b"ABCD")).unwrap();
+        resolver.end_member(4, 0, 0).unwrap();
+    }
+
+    #[test]
+    fn test_member_check_past_4gib() {
+        let literals =
+            |bytes: &[u8]| bytes.iter().map(|&b| LZ77Token::Literal(b)).collect::<Vec<_>>();
+
+        // A member that has already resolved 4 GiB (with CRC32 `prefix_crc`) ends
+        // with "ABC"; its ISIZE is the size modulo 2^32
+        let prefix_len = 1u64 << 32;
+        let prefix_crc = 0x1234_5678;
+        let mut resolver = BoundaryResolver::new();
+        resolver.member_check = Some(MemberCheck {
+            hasher: crc32fast::Hasher::new_with_initial_len(prefix_crc, prefix_len),
+            position: prefix_len,
+            ..Default::default()
+        });
+        let mut expected = crc32fast::Hasher::new_with_initial_len(prefix_crc, prefix_len);
+        expected.update(b"ABC");
+        let crc = expected.finalize();
+
+        resolver.end_member(prefix_len + 3, crc, 3).unwrap();
+        resolver.resolve_block(0, &literals(b"ABC")).unwrap();
+
+        // A trailer whose ISIZE disagrees modulo 2^32 still fails
+        let mut resolver = BoundaryResolver::new();
+        resolver.member_check = Some(MemberCheck {
+            hasher: crc32fast::Hasher::new_with_initial_len(prefix_crc, prefix_len),
+            position: prefix_len,
+            ..Default::default()
+        });
+        resolver.resolve_block(0, &literals(b"ABC")).unwrap();
+        let result = resolver.end_member(prefix_len + 3, crc, 0);
+        assert!(matches!(result, Err(Error::SizeMismatch { expected: 0, found: 3 })));
+    }
 }
@@ -0,0 +1,39 @@
+use super::boundary::BoundaryResolver;
+use crate::deflate::LZ77Token;
+use crate::huffman::HuffmanEncoder;
+
+/// Reusable transcoding buffers for converting many inputs in sequence.
+///
+/// Holds the boundary resolver's decode buffer, the Huffman encoder, and the
+/// pending-token accumulator so that repeated calls to
+/// [`Transcoder::transcode_with_context`](crate::Transcoder::transcode_with_context)
+/// don't reallocate them. State is reset at the start of each transcode, so a
+/// context can be reused after an error.
+pub struct TranscodeContext {
+    pub(crate) resolver: BoundaryResolver,
+    pub(crate) encoder: HuffmanEncoder,
+    pub(crate) pending_tokens: Vec<LZ77Token>,
+}
+
+impl TranscodeContext {
+    pub fn new() -> Self {
+        Self {
+            resolver: BoundaryResolver::new(),
+            encoder: HuffmanEncoder::new(true),
+            // Larger initial capacity to reduce reallocs while accumulating a block
+            pending_tokens: Vec::with_capacity(32768),
+        }
+    }
+
+    /// Clear per-stream state while keeping allocated capacity
+    pub fn reset(&mut self) {
+        self.resolver.reset();
+        self.pending_tokens.clear();
+    }
+}
+
+impl Default for TranscodeContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,73 @@
+//! BGZF → gzip: re-deflating a BGZF file into a single standard gzip member.
+//!
+//! Some tools mishandle concatenated gzip members. [`degzip`] inflates every
+//! BGZF block with [`BgzfReader`] and compresses the concatenated bytes as one
+//! DEFLATE stream under one header and trailer.
+
+use std::io::{Read, Write};
+
+use crate::bgzf::BgzfReader;
+use crate::error::{Error, Result};
+use crate::gzip::encode;
+use crate::CompressionLevel;
+
+/// Convert the BGZF stream `input` into a single-member gzip file written to
+/// `output`, compressing at `level`; returns the number of bytes written.
+///
+/// Each block's CRC32 and ISIZE are checked as it is inflated, and the output
+/// trailer holds the CRC32 and size of all the data. Input that is not BGZF,
+/// or a corrupt block, fails with the error [`BgzfReader`] reports (e.g.
+/// [`Error::InvalidBgzfHeader`] or [`Error::Crc32Mismatch`]).
+pub fn degzip<R: Read, W: Write>(input: R, output: W, level: CompressionLevel) -> Result<u64> {
+    encode(BgzfReader::new(input), output, level).map_err(|err| match err {
+        // BgzfReader reports its errors through io::Error; hand them back as they were
+        Error::Io(e) if e.get_ref().is_some_and(|inner| inner.is::<Error>()) => {
+            *e.into_inner().and_then(|inner| inner.downcast::<Error>().ok()).expect("checked above")
+        }
+        err => err,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bgzf::block_size_from_bsize;
+    use crate::transcoder::SingleThreadedTranscoder;
+    use crate::{TranscodeConfig, Transcoder};
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_degzip_writes_one_member() {
+        let data: Vec<u8> =
+            (0..50_000u32).flat_map(|i| format!("row {}\n", i * 31 % 7_000).into_bytes()).collect();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let config = TranscodeConfig { block_size: 8192, ..Default::default() };
+        let mut bgzf = Vec::new();
+        SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(encoder.finish().unwrap()), &mut bgzf)
+            .unwrap();
+
+        for level in [1, 6] {
+            let mut gz = Vec::new();
+            let written = degzip(&bgzf[..], &mut gz, CompressionLevel::from_level(level)).unwrap();
+            assert_eq!(written, gz.len() as u64);
+
+            // GzDecoder stops after the first member, so it must hold everything
+            let mut decoder = GzDecoder::new(&gz[..]);
+            let mut decoded = Vec::new();
+            decoder.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+            assert!(decoder.into_inner().is_empty());
+        }
+
+        // Corrupt CRC32 in the first block's footer
+        let block_size = block_size_from_bsize(u16::from_le_bytes([bgzf[16], bgzf[17]]));
+        bgzf[block_size - 8] ^= 0xff;
+        let result = degzip(&bgzf[..], &mut Vec::new(), CompressionLevel::default());
+        assert!(matches!(result, Err(Error::Crc32Mismatch { .. })), "{:?}", result);
+    }
+}
@@ -3,210 +3,582 @@
 //! Contains the encoding job/result types, worker thread function, and
 //! output ordering helpers used by both `parallel.rs` and `parallel_decode.rs`.
 
+use std::any::Any;
 use std::collections::BTreeMap;
 use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::{bounded, Receiver, Sender, TrySendError};
+use crossbeam::thread::ScopedJoinHandle;
 
-use crate::bgzf::GziEntry;
+use super::output::BlockOutput;
+use super::recompress::Recompressor;
+use crate::bgzf::writer::{encode_extra_subfields, write_bgzf_header, HeaderFields};
+use crate::bgzf::{
+    bsize_from_block_size, GziEntry, BGZF_EOF, BGZF_EOF_SIZE, BGZF_FOOTER_SIZE, BGZF_HEADER_SIZE,
+    MAX_BGZF_BLOCK_SIZE,
+};
 use crate::deflate::LZ77Token;
 use crate::error::{Error, Result};
 use crate::huffman::HuffmanEncoder;
+use crate::logging::{debug, error, trace};
+use crate::{BlockCallback, BlockProgress, ParallelStats, Spawner, TranscodeConfig};
 
-/// A job for a worker thread to encode a resolved BGZF block.
-pub(super) struct EncodingJob {
-    pub block_id: u64,
+/// One resolved BGZF block's tokens, ready to encode.
+pub(super) struct ResolvedBlock {
     pub tokens: Vec<LZ77Token>,
     pub uncompressed_size: u32,
     pub crc: u32,
 }
 
-/// Result from a worker: an encoded BGZF block ready to write.
+/// A job for a worker thread: a run of consecutive resolved BGZF blocks.
+pub(super) struct EncodingJob {
+    pub job_id: u64,
+    pub blocks: Vec<ResolvedBlock>,
+}
+
+/// Result from a worker: an encoded job ready to write.
 pub(super) struct EncodedBlock {
-    pub block_id: u64,
-    pub data: Vec<u8>,
-    pub uncompressed_size: u32,
+    pub job_id: u64,
+    /// Complete BGZF blocks with their uncompressed sizes, in stream order; one
+    /// or more per block in the job (more when a block did not fit the BGZF limit)
+    pub blocks: Vec<(Vec<u8>, u32)>,
 }
 
-/// Encode a single BGZF block from resolved tokens.
-fn encode_block(encoder: &mut HuffmanEncoder, job: EncodingJob) -> Result<EncodedBlock> {
-    let crc = job.crc;
-    let usize_val = job.uncompressed_size;
+/// Groups resolved blocks into jobs of `blocks_per_job` blocks each.
+pub(super) struct JobBatcher {
+    blocks_per_job: usize,
+    next_job_id: u64,
+    pending: Vec<ResolvedBlock>,
+}
 
-    // Encode to DEFLATE
-    let deflate_data = encoder.encode(&job.tokens, true)?;
+impl JobBatcher {
+    pub fn new(blocks_per_job: usize) -> Self {
+        Self { blocks_per_job, next_job_id: 0, pending: Vec::with_capacity(blocks_per_job) }
+    }
 
-    // Build complete BGZF block
-    let block_size = 18 + deflate_data.len() + 8; // header + deflate + footer
-    let bsize = block_size - 1;
+    /// Add a block, returning the job once the batch is full.
+    pub fn push(
+        &mut self,
+        tokens: Vec<LZ77Token>,
+        uncompressed_size: u32,
+        crc: u32,
+    ) -> Option<EncodingJob> {
+        self.pending.push(ResolvedBlock { tokens, uncompressed_size, crc });
+        if self.pending.len() >= self.blocks_per_job {
+            self.flush()
+        } else {
+            None
+        }
+    }
 
-    let mut data = Vec::with_capacity(block_size);
+    /// Take the partly filled batch as a job, if it holds any blocks.
+    pub fn flush(&mut self) -> Option<EncodingJob> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let blocks = std::mem::replace(&mut self.pending, Vec::with_capacity(self.blocks_per_job));
+        let job = EncodingJob { job_id: self.next_job_id, blocks };
+        self.next_job_id += 1;
+        Some(job)
+    }
+
+    /// Number of jobs handed out so far
+    pub fn jobs(&self) -> u64 {
+        self.next_job_id
+    }
+}
 
-    // Header
-    data.extend_from_slice(&[
-        0x1f,
-        0x8b, // gzip magic
-        0x08, // compression method (DEFLATE)
-        0x04, // flags (FEXTRA)
-        0x00,
-        0x00,
-        0x00,
-        0x00, // mtime
-        0x00, // extra flags
-        0xff, // OS (unknown)
-        0x06,
-        0x00, // xlen = 6
-        0x42,
-        0x43, // subfield ID "BC"
-        0x02,
-        0x00, // subfield length = 2
-        (bsize & 0xFF) as u8,
-        ((bsize >> 8) & 0xFF) as u8,
-    ]);
+/// Encode a job's blocks into BGZF blocks from resolved tokens, or from fresh
+/// matches when `recompressor` is given, adding any bytes that saves to `saved`.
+///
+/// Output that would overflow the 64KB BGZF limit is re-encoded with
+/// [`HuffmanEncoder::encode_fitting`], which may produce several blocks.
+fn encode_job(
+    encoder: &mut HuffmanEncoder,
+    recompressor: Option<&mut Recompressor>,
+    extra: &[u8],
+    header: HeaderFields,
+    job: EncodingJob,
+    saved: &mut u64,
+) -> Result<EncodedBlock> {
+    let max_deflate = MAX_BGZF_BLOCK_SIZE - BGZF_HEADER_SIZE - extra.len() - BGZF_FOOTER_SIZE;
+
+    let mut recompressor = recompressor;
+    let mut blocks = Vec::with_capacity(job.blocks.len());
+    for mut block in job.blocks {
+        // Encode to DEFLATE
+        let deflate_data = match recompressor {
+            Some(ref mut recompressor) => {
+                let recompressed = recompressor.encode(encoder, block.tokens)?;
+                *saved += recompressed.saved;
+                block.tokens = recompressed.tokens;
+                recompressed.deflate_data
+            }
+            None => encoder.encode(&block.tokens, true)?,
+        };
+
+        if deflate_data.len() <= max_deflate {
+            blocks.push(bgzf_block(
+                extra,
+                header,
+                &deflate_data,
+                block.crc,
+                block.uncompressed_size,
+            )?);
+        } else {
+            debug!(
+                "block in job {} encodes to {} bytes, over the BGZF limit; re-encoding to fit",
+                job.job_id,
+                deflate_data.len()
+            );
+            for (data, crc, size) in encoder.encode_fitting(&block.tokens, max_deflate)? {
+                blocks.push(bgzf_block(extra, header, &data, crc, size)?);
+            }
+        }
+    }
+
+    Ok(EncodedBlock { job_id: job.job_id, blocks })
+}
+
+/// Build one complete BGZF block around DEFLATE data.
+fn bgzf_block(
+    extra: &[u8],
+    header: HeaderFields,
+    deflate_data: &[u8],
+    crc: u32,
+    uncompressed_size: u32,
+) -> Result<(Vec<u8>, u32)> {
+    let block_size = BGZF_HEADER_SIZE + extra.len() + deflate_data.len() + BGZF_FOOTER_SIZE;
+    let bsize = bsize_from_block_size(block_size)?;
+
+    let mut data = Vec::with_capacity(block_size);
+    write_bgzf_header(&mut data, bsize, extra, header)?;
 
     // Deflate data
-    data.extend_from_slice(&deflate_data);
+    data.extend_from_slice(deflate_data);
 
     // Footer: CRC32 + ISIZE
     data.extend_from_slice(&crc.to_le_bytes());
-    data.extend_from_slice(&usize_val.to_le_bytes());
+    data.extend_from_slice(&uncompressed_size.to_le_bytes());
+
+    Ok((data, uncompressed_size))
+}
 
-    Ok(EncodedBlock { block_id: job.block_id, data, uncompressed_size: usize_val })
+/// Utilization of one encoding worker, recorded when stats collection is on.
+#[derive(Default)]
+pub(super) struct WorkerStats {
+    pub blocks: u64,
+    pub idle: Duration,
+    /// Bytes saved by recompression; counted whether or not stats collection is on
+    pub recompressed_bytes_saved: u64,
 }
 
 /// Worker thread: receives encoding jobs and sends back encoded BGZF blocks.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn encoding_worker(
     job_rx: Receiver<EncodingJob>,
     result_tx: Sender<Result<EncodedBlock>>,
     use_fixed_huffman: bool,
-) {
+    split_subblocks: bool,
+    recompress_chain: Option<usize>,
+    extra_subfields: &[(u8, u8, Vec<u8>)],
+    header: HeaderFields,
+    collect_stats: bool,
+) -> WorkerStats {
     let mut encoder = HuffmanEncoder::new(use_fixed_huffman);
-    while let Ok(job) = job_rx.recv() {
-        let result = encode_block(&mut encoder, job);
+    encoder.set_split_subblocks(split_subblocks);
+    let mut recompressor = recompress_chain.map(Recompressor::new);
+    let extra = encode_extra_subfields(extra_subfields);
+    let mut stats = WorkerStats::default();
+    loop {
+        let wait_start = collect_stats.then(Instant::now);
+        let Ok(job) = job_rx.recv() else {
+            break;
+        };
+        if let Some(start) = wait_start {
+            stats.idle += start.elapsed();
+        }
+
+        let result = encode_job(
+            &mut encoder,
+            recompressor.as_mut(),
+            &extra,
+            header,
+            job,
+            &mut stats.recompressed_bytes_saved,
+        );
+        if let Ok(ref block) = result {
+            stats.blocks += block.blocks.len() as u64;
+        }
         if result_tx.send(result).is_err() {
             break;
         }
     }
+    stats
 }
 
-/// Send a job to workers, draining results if the channel is full (prevents deadlock).
-#[allow(clippy::too_many_arguments)]
-pub(super) fn send_job_and_drain<W: Write>(
-    job_tx: &Sender<EncodingJob>,
-    result_rx: &Receiver<Result<EncodedBlock>>,
-    job: EncodingJob,
-    writer: &mut W,
-    pending_blocks: &mut BTreeMap<u64, EncodedBlock>,
-    next_write_id: &mut u64,
-    blocks_written: &mut u64,
-    output_bytes: &mut u64,
+/// Sends an error on the result channel when dropped by a panicking worker.
+///
+/// The job a worker panics on never gets a result, so without this the writer
+/// would wait for it forever: the other workers keep the channel open.
+pub(super) struct PanicReporter(pub Sender<Result<EncodedBlock>>);
+
+impl Drop for PanicReporter {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let _ = self.0.send(Err(Error::Internal("encoding worker panicked".into())));
+        }
+    }
+}
+
+/// An encoding worker, running on a scoped thread or on a caller's executor.
+pub(super) enum WorkerHandle<'scope> {
+    Scoped(ScopedJoinHandle<'scope, WorkerStats>),
+    /// Receives the worker's stats, or its panic, when it ends
+    Spawned(Receiver<thread::Result<WorkerStats>>),
+}
+
+impl WorkerHandle<'_> {
+    /// Run `worker` on `spawner`, catching a panic so that joining reports it.
+    pub fn spawn(
+        spawner: &Spawner,
+        worker: impl FnOnce() -> WorkerStats + Send + 'static,
+    ) -> WorkerHandle<'static> {
+        let (done_tx, done_rx) = bounded(1);
+        spawner(Box::new(move || {
+            let _ = done_tx.send(panic::catch_unwind(AssertUnwindSafe(worker)));
+        }));
+        WorkerHandle::Spawned(done_rx)
+    }
+
+    fn join(self) -> thread::Result<WorkerStats> {
+        match self {
+            WorkerHandle::Scoped(handle) => handle.join(),
+            // A task dropped without running ends like a panic
+            WorkerHandle::Spawned(done_rx) => done_rx
+                .recv()
+                .unwrap_or_else(|_| Err(Box::new("worker task was dropped without running"))),
+        }
+    }
+}
+
+/// Join the encoding workers, turning a worker panic into an error.
+///
+/// Returns the utilization stats (if collected) and the bytes saved by recompression.
+pub(super) fn join_workers(
+    handles: Vec<WorkerHandle<'_>>,
+    collect_stats: bool,
+) -> Result<(Option<ParallelStats>, u64)> {
+    let mut parallel = ParallelStats::default();
+    let mut recompressed_bytes_saved = 0;
+    for handle in handles {
+        let stats = handle.join().map_err(|e| {
+            let message = panic_message(e.as_ref());
+            error!("encoding worker panicked: {}", message);
+            Error::Internal(format!("Worker thread panicked: {}", message))
+        })?;
+        parallel.worker_blocks.push(stats.blocks);
+        parallel.worker_idle.push(stats.idle);
+        recompressed_bytes_saved += stats.recompressed_bytes_saved;
+    }
+    Ok((collect_stats.then_some(parallel), recompressed_bytes_saved))
+}
+
+/// Extract a readable message from a `crossbeam::scope` panic payload.
+///
+/// The scope reports child panics as a `Vec` of payloads; each payload is usually
+/// a `&str` or `String` from `panic!`.
+pub(super) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    fn single(payload: &(dyn Any + Send)) -> String {
+        if let Some(s) = payload.downcast_ref::<&str>() {
+            (*s).to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic payload".to_string()
+        }
+    }
+
+    match payload.downcast_ref::<Vec<Box<dyn Any + Send>>>() {
+        Some(panics) => panics.iter().map(|p| single(p.as_ref())).collect::<Vec<_>>().join("; "),
+        None => single(payload),
+    }
+}
+
+/// Totals from an [`OrderedWriter`] once every job has been written.
+pub(super) struct OrderedOutput {
+    pub blocks_written: u64,
+    pub output_bytes: u64,
+    pub index_entries: Vec<GziEntry>,
+    /// Most encoded BGZF blocks held back at once, waiting for an earlier job
+    pub peak_buffered_blocks: usize,
+}
+
+/// Writes encoded jobs in job order, holding back any that finish early.
+///
+/// At most `max_jobs_ahead` jobs are dispatched past the oldest one not yet
+/// written; [`send`](Self::send) waits on results before going further, so a
+/// slow job cannot make the held-back blocks grow without bound.
+pub(super) struct OrderedWriter<W: Write> {
+    writer: BlockOutput<W>,
+    /// Jobs that finished before an earlier one, keyed by job id
+    pending: BTreeMap<u64, EncodedBlock>,
+    pending_blocks: usize,
+    peak_buffered_blocks: usize,
+    next_write_id: u64,
+    max_jobs_ahead: u64,
     build_index: bool,
-    index_entries: &mut Vec<GziEntry>,
-    current_compressed_offset: &mut u64,
-    current_uncompressed_offset: &mut u64,
-) -> Result<()> {
-    let mut job_to_send = Some(job);
-    while let Some(j) = job_to_send.take() {
-        match job_tx.try_send(j) {
-            Ok(()) => {}
-            Err(crossbeam::channel::TrySendError::Full(returned)) => {
-                job_to_send = Some(returned);
-                match result_rx.recv() {
-                    Ok(result) => {
-                        let block = result?;
-                        buffer_and_write_block(
-                            writer,
-                            block,
-                            pending_blocks,
-                            next_write_id,
-                            blocks_written,
-                            output_bytes,
-                            build_index,
-                            index_entries,
-                            current_compressed_offset,
-                            current_uncompressed_offset,
-                        )?;
-                    }
-                    Err(_) => return Err(Error::Internal("Result channel disconnected".into())),
+    index_entries: Vec<GziEntry>,
+    blocks_written: u64,
+    output_bytes: u64,
+    uncompressed_offset: u64,
+    on_block: Option<BlockCallback>,
+}
+
+impl<W: Write> OrderedWriter<W> {
+    pub fn new(writer: BlockOutput<W>, config: &TranscodeConfig, num_threads: usize) -> Self {
+        Self {
+            writer,
+            pending: BTreeMap::new(),
+            pending_blocks: 0,
+            peak_buffered_blocks: 0,
+            next_write_id: 0,
+            max_jobs_ahead: config.effective_max_jobs_ahead(num_threads) as u64,
+            build_index: config.build_index,
+            index_entries: Vec::new(),
+            blocks_written: 0,
+            output_bytes: 0,
+            uncompressed_offset: 0,
+            on_block: config.on_block.clone(),
+        }
+    }
+
+    /// Send a job to workers, writing results while the job channel is full or
+    /// the job is too far ahead of the output (prevents deadlock and bounds memory).
+    ///
+    /// The job is moved into the channel exactly once: `try_send` hands it back
+    /// when the channel is full, so a busy pipeline never copies its tokens.
+    pub fn send(
+        &mut self,
+        job_tx: &Sender<EncodingJob>,
+        result_rx: &Receiver<Result<EncodedBlock>>,
+        job: EncodingJob,
+    ) -> Result<()> {
+        while job.job_id >= self.next_write_id + self.max_jobs_ahead {
+            self.receive(result_rx)?;
+        }
+
+        let mut job_to_send = Some(job);
+        while let Some(j) = job_to_send.take() {
+            match job_tx.try_send(j) {
+                Ok(()) => {}
+                Err(TrySendError::Full(returned)) => {
+                    job_to_send = Some(returned);
+                    self.receive(result_rx)?;
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    return Err(Error::Internal("Workers disconnected".into()));
                 }
             }
-            Err(crossbeam::channel::TrySendError::Disconnected(_)) => {
-                return Err(Error::Internal("Workers disconnected".into()));
+        }
+        Ok(())
+    }
+
+    /// Wait for one encoded job and buffer or write it.
+    fn receive(&mut self, result_rx: &Receiver<Result<EncodedBlock>>) -> Result<()> {
+        match result_rx.recv() {
+            Ok(result) => self.accept(result?),
+            Err(_) => Err(Error::Internal("Result channel disconnected".into())),
+        }
+    }
+
+    /// Buffer an out-of-order job, writing consecutive jobs when possible.
+    fn accept(&mut self, block: EncodedBlock) -> Result<()> {
+        if block.job_id != self.next_write_id {
+            self.pending_blocks += block.blocks.len();
+            self.peak_buffered_blocks = self.peak_buffered_blocks.max(self.pending_blocks);
+            self.pending.insert(block.job_id, block);
+            return Ok(());
+        }
+
+        self.write_job(&block)?;
+        while let Some(buffered) = self.pending.remove(&self.next_write_id) {
+            self.pending_blocks -= buffered.blocks.len();
+            self.write_job(&buffered)?;
+        }
+        Ok(())
+    }
+
+    /// Write one encoded job's BGZF blocks to output and update tracking.
+    fn write_job(&mut self, block: &EncodedBlock) -> Result<()> {
+        for (data, uncompressed_size) in &block.blocks {
+            self.write_block(data, *uncompressed_size)?;
+        }
+        self.next_write_id += 1;
+        Ok(())
+    }
+
+    /// Write a complete BGZF block, with its uncompressed size, after those
+    /// written so far, for callers that order their output themselves.
+    pub fn write_block(&mut self, data: &[u8], uncompressed_size: u32) -> Result<()> {
+        if self.build_index {
+            self.index_entries.push(GziEntry {
+                compressed_offset: self.output_bytes,
+                uncompressed_offset: self.uncompressed_offset,
+            });
+        }
+        trace!(
+            "BGZF block at offset {}: {} bytes compressed, {} uncompressed",
+            self.output_bytes,
+            data.len(),
+            uncompressed_size
+        );
+        self.writer.write_all(data).map_err(Error::Io)?;
+        self.writer.end_block().map_err(Error::Io)?;
+        self.output_bytes += data.len() as u64;
+        self.uncompressed_offset += uncompressed_size as u64;
+        self.blocks_written += 1;
+        if let Some(ref on_block) = self.on_block {
+            on_block.call(BlockProgress {
+                block_id: self.blocks_written - 1,
+                compressed_size: data.len() as u32,
+                uncompressed_size,
+                cumulative_in: self.uncompressed_offset,
+                cumulative_out: self.output_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Write the remaining `jobs` results and, if `write_eof`, the EOF block,
+    /// then flush the output.
+    ///
+    /// Call after dropping the job sender, so the result channel closes once
+    /// the workers are done. If it closes before every job's result arrives,
+    /// the output is left truncated, without the EOF block, and this fails.
+    pub fn finish(
+        mut self,
+        result_rx: &Receiver<Result<EncodedBlock>>,
+        jobs: u64,
+        write_eof: bool,
+    ) -> Result<OrderedOutput> {
+        while self.next_write_id < jobs {
+            match result_rx.recv() {
+                Ok(result) => self.accept(result?)?,
+                Err(_) => {
+                    return Err(Error::Internal(format!(
+                        "Workers stopped after {} of {} jobs",
+                        self.next_write_id, jobs
+                    )));
+                }
             }
         }
+        self.close(write_eof)
+    }
+
+    /// Write the EOF block if `write_eof`, then flush the output.
+    pub fn close(mut self, write_eof: bool) -> Result<OrderedOutput> {
+        if write_eof {
+            self.writer.write_all(&BGZF_EOF).map_err(Error::Io)?;
+            self.output_bytes += BGZF_EOF_SIZE as u64;
+        }
+        self.writer.finish().map_err(Error::Io)?;
+
+        Ok(OrderedOutput {
+            blocks_written: self.blocks_written,
+            output_bytes: self.output_bytes,
+            index_entries: self.index_entries,
+            peak_buffered_blocks: self.peak_buffered_blocks,
+        })
     }
-    Ok(())
 }
 
-/// Buffer an out-of-order block, writing consecutive blocks when possible.
-#[allow(clippy::too_many_arguments)]
-pub(super) fn buffer_and_write_block<W: Write>(
-    writer: &mut W,
-    block: EncodedBlock,
-    pending: &mut BTreeMap<u64, EncodedBlock>,
-    next_write_id: &mut u64,
-    blocks_written: &mut u64,
-    output_bytes: &mut u64,
-    build_index: bool,
-    index_entries: &mut Vec<GziEntry>,
-    current_compressed_offset: &mut u64,
-    current_uncompressed_offset: &mut u64,
-) -> Result<()> {
-    if block.block_id == *next_write_id {
-        write_single_block(
-            writer,
-            &block.data,
-            block.uncompressed_size,
-            output_bytes,
-            build_index,
-            index_entries,
-            current_compressed_offset,
-            current_uncompressed_offset,
-        )?;
-        *blocks_written += 1;
-        *next_write_id += 1;
-
-        while let Some(buffered) = pending.remove(next_write_id) {
-            write_single_block(
-                writer,
-                &buffered.data,
-                buffered.uncompressed_size,
-                output_bytes,
-                build_index,
-                index_entries,
-                current_compressed_offset,
-                current_uncompressed_offset,
-            )?;
-            *blocks_written += 1;
-            *next_write_id += 1;
-        }
-    } else {
-        pending.insert(block.block_id, block);
-    }
-    Ok(())
-}
-
-/// Write one BGZF block to output and update tracking.
-#[allow(clippy::too_many_arguments)]
-pub(super) fn write_single_block<W: Write>(
-    writer: &mut W,
-    data: &[u8],
-    uncompressed_size: u32,
-    output_bytes: &mut u64,
-    build_index: bool,
-    index_entries: &mut Vec<GziEntry>,
-    current_compressed_offset: &mut u64,
-    current_uncompressed_offset: &mut u64,
-) -> Result<()> {
-    if build_index {
-        index_entries.push(GziEntry {
-            compressed_offset: *current_compressed_offset,
-            uncompressed_offset: *current_uncompressed_offset,
-        });
-    }
-    *output_bytes += data.len() as u64;
-    writer.write_all(data).map_err(Error::Io)?;
-    *current_compressed_offset += data.len() as u64;
-    *current_uncompressed_offset += uncompressed_size as u64;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::bounded;
+
+    fn job(job_id: u64) -> EncodingJob {
+        EncodingJob { job_id, blocks: Vec::new() }
+    }
+
+    /// Stand-in worker that holds job 0 until it has received `release_after`
+    /// jobs, returning every later job immediately.
+    fn delay_first_job(
+        job_rx: Receiver<EncodingJob>,
+        result_tx: Sender<Result<EncodedBlock>>,
+        release_after: u64,
+    ) {
+        let encoded = |id: u64| Ok(EncodedBlock { job_id: id, blocks: vec![(vec![id as u8], 1)] });
+        let mut held = None;
+        let mut received = 0;
+        for job in job_rx {
+            received += 1;
+            if job.job_id == 0 {
+                held = Some(job);
+            } else if result_tx.send(encoded(job.job_id)).is_err() {
+                return;
+            }
+            if received == release_after {
+                if let Some(job) = held.take() {
+                    let _ = result_tx.send(encoded(job.job_id));
+                }
+            }
+        }
+    }
+
+    /// Dispatch `jobs` jobs to a worker holding job 0 until it has received
+    /// `release_after` of them
+    fn run_delayed(
+        max_jobs_ahead: usize,
+        jobs: u64,
+        release_after: u64,
+    ) -> (Vec<u8>, OrderedOutput) {
+        let config = TranscodeConfig { max_jobs_ahead, write_eof: false, ..Default::default() };
+        let (job_tx, job_rx) = bounded(2);
+        let (result_tx, result_rx) = bounded(2);
+        let worker = std::thread::spawn(move || delay_first_job(job_rx, result_tx, release_after));
+
+        let mut output = Vec::new();
+        let mut ordered =
+            OrderedWriter::new(BlockOutput::with_capacity(1024, &mut output), &config, 1);
+        for id in 0..jobs {
+            ordered.send(&job_tx, &result_rx, job(id)).unwrap();
+        }
+        drop(job_tx);
+        let written = ordered.finish(&result_rx, jobs, false).unwrap();
+        worker.join().unwrap();
+        (output, written)
+    }
+
+    #[test]
+    fn test_slow_first_job_buffering_is_bounded() {
+        // Job 0 is held until the dispatcher has sent all it may: jobs 0-3
+        let (output, written) = run_delayed(4, 50, 4);
+        assert_eq!(output, (0..50).collect::<Vec<u8>>());
+        assert_eq!(written.blocks_written, 50);
+        assert_eq!(written.peak_buffered_blocks, 3);
+
+        // A generous cap lets every later job pile up behind the slow one
+        let (output, written) = run_delayed(1000, 50, 50);
+        assert_eq!(output, (0..50).collect::<Vec<u8>>());
+        assert_eq!(written.peak_buffered_blocks, 49);
+    }
+
+    #[test]
+    fn test_finish_without_every_result_writes_no_eof() {
+        let config = TranscodeConfig::default();
+        let (result_tx, result_rx) = bounded(2);
+        result_tx.send(Ok(EncodedBlock { job_id: 0, blocks: vec![(vec![0], 1)] })).unwrap();
+        drop(result_tx);
+
+        let mut output = Vec::new();
+        let ordered = OrderedWriter::new(BlockOutput::with_capacity(1024, &mut output), &config, 1);
+        let result = ordered.finish(&result_rx, 2, true);
+        assert!(matches!(result, Err(Error::Internal(_))));
+        assert_eq!(output, [0]);
+    }
 }
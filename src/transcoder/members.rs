@@ -0,0 +1,393 @@
+//! Member-parallel transcoding of concatenated gzip input held in memory.
+//!
+//! Each gzip member is an independent DEFLATE stream, so members can be parsed
+//! and re-encoded on separate threads. Consecutive members are grouped into
+//! work units that end where every path ends a block (see [`MEMBER_UNIT_BYTES`]),
+//! so the output does not depend on the thread count. Member boundaries come
+//! from [`scan_gzip_members`], whose candidates are only plausible: one that
+//! falls inside a member makes that unit fail to transcode, and it is then
+//! merged with the following unit and retried, as [`decode_member_batch`] does.
+//!
+//! [`decode_member_batch`]: crate::reader::decode_member_batch
+
+use std::collections::VecDeque;
+use std::io::Write;
+
+use crossbeam::channel::{bounded, Receiver, Sender, TrySendError};
+
+use super::context::TranscodeContext;
+use super::encoding::{panic_message, OrderedWriter};
+use super::output::{BlockOutput, BlockQueue};
+use super::single::{block_header_fields, parse_gzip_header_size, BlockProducer};
+use crate::bgzf::writer::HeaderFields;
+use crate::bgzf::BgzfBlockWriter;
+use crate::deflate::DeflateParser;
+use crate::error::{Error, Result};
+use crate::gzip::GzipHeader;
+use crate::logging::debug;
+use crate::reader::scan_gzip_members;
+use crate::{TranscodeConfig, TranscodeStats};
+
+/// Spacing, in compressed input bytes, of the points where work units end.
+///
+/// Every path ends a BGZF block at the first member boundary past each
+/// multiple of this offset, so concatenated members can be transcoded a unit
+/// per thread and still give the blocks a single thread would. Whether a
+/// member ends a unit depends only on where it starts and ends, so a unit
+/// merged after a false boundary lines up again with the next one.
+pub(super) const MEMBER_UNIT_BYTES: u64 = 1 << 20;
+
+/// Most work units merged to recover from false member boundaries before the
+/// first one's error is reported
+const MAX_MERGED_PIECES: usize = 8;
+
+/// Blocks a worker may produce ahead of the writer within one work unit
+const UNIT_BLOCKS_AHEAD: usize = 16;
+
+/// What a worker sends for its work unit: each block as it is produced, with
+/// its uncompressed size, then the unit's stats or error.
+enum UnitEvent {
+    Block(Vec<u8>, u32),
+    End(Result<TranscodeStats>),
+}
+
+/// Blocks of the current work unit written so far, so that a retry of the
+/// unit can skip them after checking it produced the same ones.
+#[derive(Default)]
+struct Written {
+    blocks: u64,
+    crc: crc32fast::Hasher,
+}
+
+impl Written {
+    fn add(&mut self, block: &[u8]) {
+        self.blocks += 1;
+        self.crc.update(block);
+    }
+
+    fn matches(&self, other: &Written) -> bool {
+        self.blocks == other.blocks && self.crc.clone().finalize() == other.crc.clone().finalize()
+    }
+}
+
+/// Whether the gzip member at input offsets `start..end` ends a work unit,
+/// being the first to end past a multiple of [`MEMBER_UNIT_BYTES`].
+pub(super) fn ends_unit(start: u64, end: u64) -> bool {
+    end / MEMBER_UNIT_BYTES > start / MEMBER_UNIT_BYTES
+}
+
+/// Byte ranges of the gzip members in `data`, or a single range when there is
+/// only one (or none could be found).
+pub(super) fn member_ranges(data: &[u8]) -> Vec<(usize, usize)> {
+    let members = scan_gzip_members(data);
+    if members.len() > 1 {
+        members
+    } else {
+        vec![(0, data.len())]
+    }
+}
+
+/// Group consecutive member ranges into work units, each ending with a
+/// member that [`ends_unit`] or with the last member.
+fn work_units(members: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut units = Vec::new();
+    let mut unit_start = members.first().map_or(0, |&(start, _)| start);
+    for (i, &(start, end)) in members.iter().enumerate() {
+        if i + 1 == members.len() || ends_unit(start as u64, end as u64) {
+            units.push((unit_start, end));
+            unit_start = end;
+        }
+    }
+    units
+}
+
+/// Transcode the gzip members of `data` at `members` on `num_threads` threads,
+/// writing their blocks to `output` in input order.
+///
+/// Members are grouped into work units as described in [`MEMBER_UNIT_BYTES`].
+/// Each unit's blocks are written as they are produced; at most
+/// `max_jobs_ahead` units (2 per thread by default) are transcoded ahead of the
+/// one being written, each holding at most a few blocks until it is reached.
+pub(super) fn transcode_members<W: Write>(
+    config: &TranscodeConfig,
+    data: &[u8],
+    members: &[(usize, usize)],
+    num_threads: usize,
+    output: W,
+) -> Result<TranscodeStats> {
+    let units = work_units(members);
+    let units = &units[..];
+    debug!("{} gzip members in {} work units", members.len(), units.len());
+    let max_ahead = match config.max_jobs_ahead {
+        0 => 2 * num_threads,
+        n => n,
+    };
+    let (work_tx, work_rx) = bounded::<(usize, Sender<UnitEvent>)>(max_ahead);
+
+    // Blocks carry the first member's header fields, as on the other paths
+    let header = block_header_fields(config, &GzipHeader::parse(&mut &data[..])?);
+    let writer = BlockOutput::with_capacity(config.buffer_size, output)
+        .hold_below(config.single_write_below);
+    let mut ordered = OrderedWriter::new(writer, config, num_threads);
+
+    // The dispatch loop owns the work sender and the units' receivers, so an
+    // early error drops them and releases workers blocked on either
+    let result = crossbeam::scope(move |scope| {
+        for _ in 0..num_threads {
+            let work_rx = work_rx.clone();
+            scope.spawn(move |_| {
+                let mut context = TranscodeContext::new();
+                for (index, event_tx) in work_rx {
+                    let result =
+                        transcode_unit(config, header, data, units[index], &mut context, |b, n| {
+                            event_tx
+                                .send(UnitEvent::Block(b, n))
+                                .map_err(|_| Error::Internal("Work unit dropped".into()))
+                        });
+                    // Units merged into an earlier one are no longer received
+                    let _ = event_tx.send(UnitEvent::End(result));
+                }
+            });
+        }
+        drop(work_rx);
+
+        let mut totals = TranscodeStats::default();
+        let mut unit_events: VecDeque<Receiver<UnitEvent>> = VecDeque::new();
+        let mut written = Written::default();
+        let mut next_dispatch = 0;
+        let mut next = 0;
+        while next < units.len() {
+            while next_dispatch < units.len() && next_dispatch < next + max_ahead {
+                let (event_tx, event_rx) = bounded(UNIT_BLOCKS_AHEAD);
+                match work_tx.try_send((next_dispatch, event_tx)) {
+                    Ok(()) => {
+                        unit_events.push_back(event_rx);
+                        next_dispatch += 1;
+                    }
+                    Err(TrySendError::Full(_)) => break,
+                    Err(TrySendError::Disconnected(_)) => {
+                        return Err(Error::Internal("Workers disconnected".into()));
+                    }
+                }
+            }
+
+            let event = unit_events[0]
+                .recv()
+                .map_err(|_| Error::Internal("Work unit channel disconnected".into()))?;
+            let (stats, merged) = match event {
+                UnitEvent::Block(block, uncompressed_size) => {
+                    ordered.write_block(&block, uncompressed_size)?;
+                    written.add(&block);
+                    continue;
+                }
+                UnitEvent::End(Ok(stats)) => (stats, 1),
+                UnitEvent::End(Err(e)) => retry_merged(
+                    config,
+                    header,
+                    data,
+                    &units[next..],
+                    e,
+                    &mut written,
+                    &mut ordered,
+                )?,
+            };
+            add_member_stats(&mut totals, &stats, written.blocks);
+            written = Written::default();
+            unit_events.drain(..merged);
+            next += merged;
+        }
+        drop(work_tx);
+
+        let written = ordered.close(config.write_eof)?;
+        totals.input_bytes = data.len() as u64;
+        totals.output_bytes = written.output_bytes;
+        totals.blocks_written = written.blocks_written;
+        totals.index_entries = config.build_index.then_some(written.index_entries);
+        Ok(totals)
+    });
+
+    result.map_err(|e| {
+        Error::Internal(format!("Worker thread panicked: {}", panic_message(e.as_ref())))
+    })?
+}
+
+/// Transcode the first of `units` together with as many following ones as it
+/// takes for the combined range to parse, after the first alone failed with
+/// `error`, writing its blocks past those already `written` to `ordered`.
+///
+/// Returns the stats and how many units they cover.
+fn retry_merged<W: Write>(
+    config: &TranscodeConfig,
+    header: HeaderFields,
+    data: &[u8],
+    units: &[(usize, usize)],
+    error: Error,
+    written: &mut Written,
+    ordered: &mut OrderedWriter<W>,
+) -> Result<(TranscodeStats, usize)> {
+    let start = units[0].0;
+    let mut context = TranscodeContext::new();
+    for (i, &(_, end)) in units.iter().enumerate().take(MAX_MERGED_PIECES).skip(1) {
+        debug!("gzip member candidate at byte {} failed ({}); merging with the next", start, error);
+        // Blocks up to where a failed attempt stopped come out the same, since
+        // where each one ends depends only on the input before it
+        let mut replayed = Written::default();
+        let mut write_error = None;
+        let result = transcode_unit(config, header, data, (start, end), &mut context, |b, n| {
+            if replayed.blocks < written.blocks {
+                replayed.add(&b);
+                return Ok(());
+            }
+            if !replayed.matches(written) {
+                return Err(Error::Internal("Retried work unit wrote other blocks".into()));
+            }
+            if let Err(e) = ordered.write_block(&b, n) {
+                write_error = Some(e);
+                return Err(Error::Internal("Output failed".into()));
+            }
+            written.add(&b);
+            replayed.add(&b);
+            Ok(())
+        });
+        if let Some(e) = write_error {
+            return Err(e);
+        }
+        let diverged = match result {
+            Ok(stats) if replayed.matches(written) => return Ok((stats, i + 1)),
+            Ok(_) => true,
+            Err(_) => replayed.blocks == written.blocks && !replayed.matches(written),
+        };
+        if diverged {
+            return Err(Error::Internal("Retried work unit wrote other blocks".into()));
+        }
+    }
+    Err(error)
+}
+
+/// Transcode the gzip members at `start..end` of `data` into BGZF blocks
+/// without an EOF block, as that piece of a transcode of all of `data`,
+/// passing each block and its uncompressed size to `on_block` as soon as it
+/// is produced.
+fn transcode_unit(
+    config: &TranscodeConfig,
+    header: HeaderFields,
+    data: &[u8],
+    (start, end): (usize, usize),
+    context: &mut TranscodeContext,
+    mut on_block: impl FnMut(Vec<u8>, u32) -> Result<()>,
+) -> Result<TranscodeStats> {
+    let piece = &data[start..end];
+    let header_size = parse_gzip_header_size(piece)?;
+    GzipHeader::parse_with_len_checked(&mut &piece[..header_size], config.verify_input_crc)?;
+
+    // The ordered writer indexes and reports the blocks as it writes them
+    let member_config =
+        TranscodeConfig { build_index: false, write_eof: false, on_block: None, ..config.clone() };
+    let parser = DeflateParser::from_slice(piece, header_size);
+    let mut producer = BlockProducer::new(&member_config, parser, context);
+    producer.set_piece(start as u64, end == data.len());
+    let mut writer =
+        BgzfBlockWriter::with_extra_subfields(BlockQueue::default(), &config.extra_subfields);
+    writer.set_header_fields(header);
+
+    loop {
+        let more = producer.step(&mut writer)?;
+        // Read each block's ISIZE from its footer
+        while let Some(block) = writer.get_mut().pop() {
+            let isize = u32::from_le_bytes(block[block.len() - 4..].try_into().unwrap());
+            on_block(block, isize)?;
+        }
+        if !more {
+            break;
+        }
+    }
+
+    let stats = producer.stats().cloned().unwrap_or_default();
+    if stats.input_bytes != piece.len() as u64 {
+        return Err(Error::Internal(format!(
+            "gzip member ended after {} of {} bytes",
+            stats.input_bytes,
+            piece.len()
+        )));
+    }
+    Ok(stats)
+}
+
+/// Fold the stats of a work unit that wrote `blocks` blocks into the running totals.
+fn add_member_stats(totals: &mut TranscodeStats, stats: &TranscodeStats, blocks: u64) {
+    if blocks > 0 {
+        let first = totals.blocks_written == 0;
+        totals.min_block_uncompressed = if first {
+            stats.min_block_uncompressed
+        } else {
+            totals.min_block_uncompressed.min(stats.min_block_uncompressed)
+        };
+        totals.max_block_uncompressed =
+            totals.max_block_uncompressed.max(stats.max_block_uncompressed);
+        totals.input_was_stored = stats.input_was_stored && (first || totals.input_was_stored);
+        // Only counts members so far; replaced by the writer's total at the end
+        totals.blocks_written += blocks;
+    }
+    totals.uncompressed_bytes += stats.uncompressed_bytes;
+    totals.members.extend_from_slice(&stats.members);
+    totals.members_processed += stats.members_processed;
+    totals.boundary_refs_resolved += stats.boundary_refs_resolved;
+    totals.resolved_bytes += stats.resolved_bytes;
+    totals.recompressed_bytes_saved += stats.recompressed_bytes_saved;
+    totals.input_trailer_mismatch |= stats.input_trailer_mismatch;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testdata::generate_random_data;
+    use flate2::read::MultiGzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Read;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_false_member_boundary_is_merged() {
+        let first = generate_random_data(3 * MEMBER_UNIT_BYTES as usize, 1);
+        let second = b"second member\n".to_vec();
+        let first_gz = gzip(&first);
+        let data = [first_gz.clone(), gzip(&second)].concat();
+
+        // A candidate in the middle of the first member, where it would end a
+        // work unit, cannot parse on its own
+        let mid = first_gz.len() / 2;
+        assert_eq!(work_units(&[(0, mid), (mid, data.len())]), [(0, mid), (mid, data.len())]);
+        let members = [(0, mid), (mid, first_gz.len()), (first_gz.len(), data.len())];
+        let config = TranscodeConfig::default();
+        let mut output = Vec::new();
+        let stats = transcode_members(&config, &data, &members, 2, &mut output).unwrap();
+
+        let mut decoded = Vec::new();
+        MultiGzDecoder::new(&output[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, [first, second].concat());
+        assert_eq!(stats.input_bytes, data.len() as u64);
+        assert_eq!(stats.output_bytes, output.len() as u64);
+
+        // Same blocks as without the false candidate
+        let mut expected = Vec::new();
+        let members = [(0, first_gz.len()), (first_gz.len(), data.len())];
+        transcode_members(&config, &data, &members, 2, &mut expected).unwrap();
+        assert!(output == expected);
+    }
+
+    #[test]
+    fn test_work_units_end_past_each_multiple() {
+        let unit = MEMBER_UNIT_BYTES as usize;
+        let members =
+            [(0, 10), (10, unit), (unit, unit + 5), (unit + 5, 3 * unit), (3 * unit, 3 * unit + 1)];
+        assert_eq!(work_units(&members), [(0, unit), (unit, 3 * unit), (3 * unit, 3 * unit + 1)]);
+        assert!(!ends_unit(unit as u64, 2 * unit as u64 - 1));
+        assert!(ends_unit(unit as u64 - 1, unit as u64));
+    }
+}
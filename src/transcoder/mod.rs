@@ -1,15 +1,29 @@
 pub mod block_scanner;
 pub mod boundary;
+pub mod context;
+pub mod degzip;
 mod encoding;
+mod members;
+mod output;
 pub mod parallel;
 pub mod parallel_decode;
+mod recompress;
+pub mod shard;
 pub mod single;
 pub mod splitter;
+pub mod streaming;
 pub mod window;
 
 pub use boundary::BoundaryResolver;
+pub use context::TranscodeContext;
+pub use degzip::degzip;
 pub use parallel::ParallelTranscoder;
 pub use parallel_decode::ParallelDecodeTranscoder;
-pub use single::SingleThreadedTranscoder;
-pub use splitter::{BlockSplitter, DefaultSplitter, FastqByteSplitter, FastqSplitter};
+pub use shard::{shard_transcode, ShardStats};
+pub use single::{BlockIterator, SingleThreadedTranscoder};
+pub use splitter::{
+    BlockSplitter, DefaultSplitter, FastqByteSplitter, FastqSplitter, FixedRecordSplitter,
+    LineSplitter, VcfSplitter,
+};
+pub use streaming::StreamingTranscoder;
 pub use window::SlidingWindow;
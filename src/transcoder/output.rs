@@ -0,0 +1,222 @@
+//! Buffered transcoder output that never leaves a partial block behind.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+/// Buffered writer that hands the underlying writer whole BGZF blocks only.
+///
+/// Transcoders call [`end_block`](BlockOutput::end_block) after each block and
+/// [`finish`](BlockOutput::finish) after the EOF block. If the transcode fails
+/// first, dropping the output writes out the complete blocks buffered so far
+/// and discards the incomplete one, so the result ends on a block boundary
+/// without an EOF block and `verify_bgzf` reports `has_eof_block == false`.
+///
+/// With [`hold_below`](BlockOutput::hold_below), nothing reaches the
+/// underlying writer until the output reaches that size, so an output smaller
+/// than it (EOF block included) is written with a single call.
+///
+/// This is best-effort: a write error on the underlying writer can still
+/// leave part of a block there.
+pub(crate) struct BlockOutput<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    /// Length of the buffer prefix that holds complete blocks
+    committed: usize,
+    capacity: usize,
+    /// Output size below which nothing is written until `finish`
+    hold_below: usize,
+    /// Whether anything has been written to `inner`
+    streaming: bool,
+    finished: bool,
+}
+
+impl<W: Write> BlockOutput<W> {
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            committed: 0,
+            capacity,
+            hold_below: 0,
+            streaming: false,
+            finished: false,
+        }
+    }
+
+    /// Buffer the whole output while it is smaller than `size` bytes.
+    pub fn hold_below(mut self, size: usize) -> Self {
+        self.hold_below = size;
+        self
+    }
+
+    /// Mark everything written so far as complete blocks, writing them through
+    /// once the buffer is full.
+    pub fn end_block(&mut self) -> io::Result<()> {
+        self.committed = self.buf.len();
+        if self.committed >= self.capacity && !self.holding() {
+            self.write_committed()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the output is still being held back for a single write
+    fn holding(&self) -> bool {
+        !self.streaming && self.buf.len() < self.hold_below
+    }
+
+    /// Write out all buffered output (ending with the EOF block) and flush.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.committed = self.buf.len();
+        self.write_committed()?;
+        self.inner.flush()?;
+        self.finished = true;
+        Ok(())
+    }
+
+    fn write_committed(&mut self) -> io::Result<()> {
+        let committed = std::mem::take(&mut self.committed);
+        self.streaming |= committed > 0;
+        let result = self.inner.write_all(&self.buf[..committed]);
+        self.buf.drain(..committed);
+        result
+    }
+}
+
+impl<W: Write> Write for BlockOutput<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    /// Flushes complete blocks only; an incomplete block stays buffered, as
+    /// does everything while the output is held for a single write.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.holding() {
+            self.write_committed()?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> BlockSink for BlockOutput<W> {
+    fn end_block(&mut self) -> io::Result<()> {
+        BlockOutput::end_block(self)
+    }
+}
+
+impl<W: Write> Drop for BlockOutput<W> {
+    fn drop(&mut self) {
+        if !self.finished {
+            // Errors cannot be reported from drop; the transcode already failed
+            let _ = self.write_committed();
+            let _ = self.inner.flush();
+        }
+    }
+}
+
+/// Writer of transcoded blocks that is told where each block ends.
+pub(crate) trait BlockSink: Write {
+    /// Mark everything written since the previous call as one complete block.
+    fn end_block(&mut self) -> io::Result<()>;
+}
+
+impl<S: BlockSink + ?Sized> BlockSink for &mut S {
+    fn end_block(&mut self) -> io::Result<()> {
+        (**self).end_block()
+    }
+}
+
+/// Collects each block as its own buffer, until it is popped.
+#[derive(Default)]
+pub(crate) struct BlockQueue {
+    blocks: VecDeque<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl BlockQueue {
+    /// The oldest complete block, if any.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.blocks.pop_front()
+    }
+}
+
+impl Write for BlockQueue {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.current.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl BlockSink for BlockQueue {
+    fn end_block(&mut self) -> io::Result<()> {
+        if !self.current.is_empty() {
+            self.blocks.push_back(std::mem::take(&mut self.current));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testdata::CountingWriter;
+
+    #[test]
+    fn test_drop_discards_incomplete_block() {
+        let mut sink = Vec::new();
+        {
+            let mut output = BlockOutput::with_capacity(1024, &mut sink);
+            output.write_all(b"block one").unwrap();
+            output.end_block().unwrap();
+            output.write_all(b"half a blo").unwrap();
+        }
+        assert_eq!(sink, b"block one");
+
+        let mut sink = Vec::new();
+        {
+            let mut output = BlockOutput::with_capacity(4, &mut sink);
+            output.write_all(b"first").unwrap();
+            output.end_block().unwrap();
+            output.write_all(b"eof").unwrap();
+            output.finish().unwrap();
+        }
+        assert_eq!(sink, b"firsteof");
+    }
+
+    #[test]
+    fn test_hold_below() {
+        let write_blocks = |output: &mut BlockOutput<&mut CountingWriter>| {
+            for _ in 0..10 {
+                output.write_all(&[7u8; 100]).unwrap();
+                output.end_block().unwrap();
+                output.flush().unwrap();
+            }
+            output.write_all(b"eof").unwrap();
+            output.finish().unwrap();
+        };
+
+        // Below the threshold: one write, EOF included
+        let mut sink = CountingWriter::default();
+        write_blocks(&mut BlockOutput::with_capacity(64, &mut sink).hold_below(2000));
+        assert_eq!((sink.writes, sink.data.len()), (1, 1003));
+
+        // Past it, the output streams again
+        let mut sink = CountingWriter::default();
+        write_blocks(&mut BlockOutput::with_capacity(64, &mut sink).hold_below(500));
+        assert_eq!((sink.writes, sink.data.len()), (7, 1003));
+
+        // Dropping a held output still writes its complete blocks
+        let mut sink = CountingWriter::default();
+        {
+            let mut output = BlockOutput::with_capacity(64, &mut sink).hold_below(2000);
+            output.write_all(b"block").unwrap();
+            output.end_block().unwrap();
+            output.write_all(b"half").unwrap();
+        }
+        assert_eq!(sink.data, b"block");
+    }
+}
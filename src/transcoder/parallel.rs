@@ -5,42 +5,151 @@
 //! - Worker pool: Encode tokens to BGZF blocks in parallel
 //! - Main thread: Receive encoded blocks in order, write to output
 
-use std::collections::BTreeMap;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, Read, Write};
+#[cfg(feature = "mmap")]
+use std::path::Path;
 
 use crossbeam::channel::{bounded, Receiver, Sender};
 
 use super::boundary::BoundaryResolver;
+use super::context::TranscodeContext;
 use super::encoding::{
-    buffer_and_write_block, encoding_worker, send_job_and_drain, write_single_block, EncodedBlock,
-    EncodingJob,
+    encoding_worker, join_workers, panic_message, EncodedBlock, EncodingJob, JobBatcher,
+    OrderedWriter, PanicReporter, WorkerHandle,
 };
-use super::splitter::{BlockSplitter, DefaultSplitter, FastqSplitter};
-use crate::bgzf::{GziEntry, BGZF_EOF};
+use super::members::{ends_unit, member_ranges, transcode_members};
+use super::output::BlockOutput;
+use super::single::{block_header_fields, SingleThreadedTranscoder};
+use super::splitter::{splitter_for, TailMerger};
+use crate::bgzf::coords::CoordinateTracker;
+use crate::bgzf::write_gzi;
 use crate::deflate::{DeflateParser, LZ77Token};
 use crate::error::{Error, Result};
 use crate::gzip::GzipHeader;
-use crate::{FormatProfile, TranscodeConfig, TranscodeStats, Transcoder};
+use crate::logging::debug;
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFile;
+use crate::{MemberInfo, ParallelStats, Spawner, TranscodeConfig, TranscodeStats, Transcoder};
 
 /// Parallel transcoder implementation
 pub struct ParallelTranscoder {
     config: TranscodeConfig,
+    /// Executor for the encoding workers, instead of scoped threads
+    spawner: Option<Spawner>,
+    /// Test-only hook: this many workers panic with this message on their first
+    /// job; the other workers wait for them to take it, so every one panics
+    #[cfg(test)]
+    worker_panic: Option<(&'static str, usize)>,
 }
 
 impl ParallelTranscoder {
     pub fn new(config: TranscodeConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            spawner: None,
+            #[cfg(test)]
+            worker_panic: None,
+        }
+    }
+
+    /// Run the encoding workers as tasks on a caller-provided executor instead
+    /// of threads of their own, so an application that already manages a pool
+    /// keeps control of its thread count. For a rayon `ThreadPool` held in an
+    /// `Arc`, pass `Arc::new(move |task| pool.spawn(task))`.
+    ///
+    /// `num_threads` still sets how many workers are spawned; each runs until
+    /// the transcode ends, and parsing and writing stay on the calling thread,
+    /// so output order is unchanged. This holds for
+    /// [`transcode_slice`](Self::transcode_slice) too, which then parses
+    /// concatenated members on the calling thread instead of transcoding them
+    /// on threads of its own, with the same output. At least one worker must get to run while
+    /// the transcode waits for it: calling from a thread of a pool with no
+    /// other thread free deadlocks.
+    pub fn with_spawner(mut self, spawner: Spawner) -> Self {
+        self.spawner = Some(spawner);
+        self
+    }
+
+    /// Transcode to `output` and write the matching GZI index to `index_writer`.
+    ///
+    /// Builds the index regardless of `config.build_index`; the returned stats
+    /// carry the same entries that were written.
+    pub fn transcode_with_index<R: Read, W: Write, I: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        index_writer: I,
+    ) -> Result<TranscodeStats> {
+        let build_index = std::mem::replace(&mut self.config.build_index, true);
+        let result = self.transcode(input, output);
+        self.config.build_index = build_index;
+
+        let stats = result?;
+        write_gzi(stats.index_entries.as_deref().unwrap_or_default(), index_writer)?;
+        Ok(stats)
+    }
+
+    /// Transcode gzip data held in memory, such as a memory-mapped file.
+    ///
+    /// Concatenated gzip members (as from `cat a.gz b.gz` or `pigz`) are
+    /// independent, so runs of them are parsed and re-encoded on separate
+    /// worker threads, giving the same blocks as one thread would. Single-member
+    /// input is parsed on the calling thread and encoded in parallel, as by
+    /// [`transcode`](Transcoder::transcode), and so is all input when a
+    /// [spawner](Self::with_spawner) is set, since its tasks cannot borrow `data`.
+    pub fn transcode_slice<W: Write>(&mut self, data: &[u8], output: W) -> Result<TranscodeStats> {
+        self.config.validate()?;
+        let num_threads = self.config.effective_threads();
+        if num_threads == 1 {
+            return SingleThreadedTranscoder::new(self.config.clone())
+                .transcode_slice(data, output);
+        }
+
+        // Coordinates are tracked across the whole stream, which members do not
+        // see; a spawner's tasks cannot borrow `data`, so its workers only encode
+        let members = member_ranges(data);
+        if members.len() > 1 && !self.config.build_coordinate_index() && self.spawner.is_none() {
+            debug!("{} gzip members: transcoding them in parallel", members.len());
+            return transcode_members(&self.config, data, &members, num_threads, output);
+        }
+        self.transcode(data, output)
+    }
+
+    /// Memory-map the gzip file at `path` and transcode it with
+    /// [`transcode_slice`](Self::transcode_slice).
+    ///
+    /// The file must not be modified while it is mapped (see [`MappedFile`]).
+    #[cfg(feature = "mmap")]
+    pub fn transcode_mmap<W: Write>(&mut self, path: &Path, output: W) -> Result<TranscodeStats> {
+        let mapped = MappedFile::open(path)?;
+        #[cfg(unix)]
+        {
+            // Only a hint; the read pattern is sequential either way
+            let _ = mapped.advise_sequential();
+        }
+        self.transcode_slice(&mapped, output)
     }
 }
 
 impl Transcoder for ParallelTranscoder {
     fn transcode<R: Read, W: Write>(&mut self, input: R, output: W) -> Result<TranscodeStats> {
+        self.transcode_with_context(input, output, &mut TranscodeContext::new())
+    }
+
+    /// Reuses `context` only on the single-threaded path; worker threads own their encoders.
+    fn transcode_with_context<R: Read, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        context: &mut TranscodeContext,
+    ) -> Result<TranscodeStats> {
+        self.config.validate()?;
         let num_threads = self.config.effective_threads();
 
         // For single thread, delegate to single-threaded implementation for efficiency
         if num_threads == 1 {
-            let mut single = super::single::SingleThreadedTranscoder::new(self.config.clone());
-            return single.transcode(input, output);
+            let mut single = SingleThreadedTranscoder::new(self.config.clone());
+            return single.transcode_with_context(input, output, context);
         }
 
         self.transcode_parallel(input, output, num_threads)
@@ -54,6 +163,12 @@ impl ParallelTranscoder {
         mut output: W,
         num_threads: usize,
     ) -> Result<TranscodeStats> {
+        // Parse the gzip header first, since workers copy fields from it
+        let mut reader = BufReader::with_capacity(self.config.buffer_size, input);
+        let (gzip_header, header_len) =
+            GzipHeader::parse_with_len_checked(&mut reader, self.config.verify_input_crc)?;
+        let header_fields = block_header_fields(&self.config, &gzip_header);
+
         // Channel capacity - enough to keep workers busy without excessive memory
         let channel_capacity = num_threads * 4;
 
@@ -65,242 +180,335 @@ impl ParallelTranscoder {
 
         // Shared config for workers
         let use_fixed_huffman = self.config.use_fixed_huffman();
+        let split_subblocks = self.config.compression_level.use_subblock_splitting();
+        let recompress_chain = self.config.recompress_chain();
+        let collect_stats = self.config.collect_parallel_stats;
 
         // Use crossbeam's scoped threads to avoid 'static lifetime requirements
         let result = crossbeam::scope(|scope| {
-            // Spawn worker threads
+            // Spawn worker threads, or tasks on the caller's executor
+            let mut handles = Vec::with_capacity(num_threads);
+            // Test-only: disconnects once every panicking worker has taken its job
+            #[cfg(test)]
+            let (taken_tx, taken_rx) = bounded::<()>(0);
             for _ in 0..num_threads {
                 let job_rx = job_rx.clone();
                 let result_tx = result_tx.clone();
-
-                scope.spawn(move |_| {
-                    encoding_worker(job_rx, result_tx, use_fixed_huffman);
+                let extra_subfields = self.config.extra_subfields.clone();
+                #[cfg(test)]
+                let worker_panic =
+                    self.worker_panic.map(|(message, workers)| (message, handles.len() < workers));
+                #[cfg(test)]
+                let (taken_tx, taken_rx) = (taken_tx.clone(), taken_rx.clone());
+
+                let worker = move || {
+                    let _reporter = PanicReporter(result_tx.clone());
+                    #[cfg(test)]
+                    match worker_panic {
+                        Some((message, true)) if job_rx.recv().is_ok() => panic!("{}", message),
+                        Some((_, false)) => {
+                            drop(taken_tx);
+                            let _ = taken_rx.recv();
+                        }
+                        _ => {}
+                    }
+                    encoding_worker(
+                        job_rx,
+                        result_tx,
+                        use_fixed_huffman,
+                        split_subblocks,
+                        recompress_chain,
+                        &extra_subfields,
+                        header_fields,
+                        collect_stats,
+                    )
+                };
+                handles.push(match &self.spawner {
+                    Some(spawner) => WorkerHandle::spawn(spawner, worker),
+                    None => WorkerHandle::Scoped(scope.spawn(move |_| worker())),
                 });
             }
 
             // Drop our copies of the channels that workers use
             drop(job_rx);
             drop(result_tx);
+            #[cfg(test)]
+            drop(taken_tx);
 
             // Parse and send jobs on main thread, interleaved with receiving results
-            self.parse_dispatch_and_write(input, &mut output, job_tx, result_rx)
+            let result = self.parse_dispatch_and_write(
+                reader,
+                header_len,
+                &mut output,
+                num_threads,
+                job_tx,
+                result_rx,
+            );
+
+            // A worker panic explains any error the main thread saw, so report it first
+            let (parallel, recompressed_bytes_saved) = join_workers(handles, collect_stats)?;
+            let mut stats = result?;
+            let peak_buffered_blocks = stats.parallel.take().map_or(0, |p| p.peak_buffered_blocks);
+            stats.parallel = parallel.map(|p| ParallelStats { peak_buffered_blocks, ..p });
+            stats.recompressed_bytes_saved = recompressed_bytes_saved;
+            Ok(stats)
         });
 
         // Unwrap scope result
-        result.map_err(|_| Error::Internal("Thread panicked".to_string()))?
+        result.map_err(|e| {
+            Error::Internal(format!("Worker thread panicked: {}", panic_message(e.as_ref())))
+        })?
     }
 
     fn parse_dispatch_and_write<R: Read, W: Write>(
         &self,
-        input: R,
+        mut reader: BufReader<R>,
+        header_len: u64,
         output: &mut W,
+        num_threads: usize,
         job_tx: Sender<EncodingJob>,
         result_rx: Receiver<Result<EncodedBlock>>,
     ) -> Result<TranscodeStats> {
-        let mut reader = BufReader::with_capacity(self.config.buffer_size, input);
-        let mut writer = BufWriter::with_capacity(self.config.buffer_size, output);
-
-        // Parse gzip header
-        let _gzip_header = GzipHeader::parse(&mut reader)?;
+        let writer = BlockOutput::with_capacity(self.config.buffer_size, output)
+            .hold_below(self.config.single_write_below);
+        let mut ordered = OrderedWriter::new(writer, &self.config, num_threads);
 
         // Initialize components
-        let mut parser = DeflateParser::new(&mut reader);
+        let mut parser = DeflateParser::with_input_offset(&mut reader, header_len);
+        parser.set_verify_header_crc(self.config.verify_input_crc);
         let mut resolver = BoundaryResolver::new();
+        resolver.set_tolerate_mismatch(self.config.tolerate_bad_input_trailer);
+        resolver.set_check_members(self.config.verify_input_crc);
 
         // Create splitter based on config
         let use_smart = self.config.use_smart_boundaries();
-        let mut splitter: Box<dyn BlockSplitter> =
-            if use_smart && self.config.format == FormatProfile::Fastq {
-                Box::new(FastqSplitter::new())
-            } else {
-                Box::new(DefaultSplitter)
-            };
+        let mut splitter = splitter_for(&self.config);
+        // A token split where a record ends inside it, so a block can end there
+        let mut pieces: Vec<LZ77Token> = Vec::new();
 
         // Maximum block size with overshoot allowance for smart boundaries
-        let max_block_size = if use_smart {
-            (self.config.block_size as f64 * 1.1) as usize
-        } else {
-            self.config.block_size
-        };
+        let max_block_size = self.config.max_block_size();
+
+        // Optional look-ahead that holds the last full block to absorb a small trailing block
+        let mut tail_merger =
+            self.config.min_block_size.map(|min| TailMerger::new(min, max_block_size));
 
         // Accumulator for current BGZF block
         let mut pending_tokens: Vec<LZ77Token> = Vec::with_capacity(8192);
         let mut pending_uncompressed_size: usize = 0;
         let mut block_start_position: u64 = 0;
-        let mut next_block_id: u64 = 0;
+        let mut batcher = JobBatcher::new(self.config.blocks_per_job);
+        // Uncompressed bytes parsed so far, where each member's trailer applies
+        let mut parsed_size: u64 = 0;
+        let mut member_start: u64 = 0;
+        let mut members = Vec::new();
+        let mut members_processed: u64 = 0;
+        // Input offset where the last member read ended
+        let mut last_member_end: u64 = 0;
 
         // Stats
-        let mut blocks_written: u64 = 0;
-        let mut output_bytes: u64 = 0;
-
-        // Index tracking (compressed and uncompressed offsets)
-        let build_index = self.config.build_index;
-        let mut index_entries: Vec<GziEntry> = Vec::new();
-        let mut current_compressed_offset: u64 = 0;
-        let mut current_uncompressed_offset: u64 = 0;
+        let mut min_block_uncompressed = u32::MAX;
+        let mut max_block_uncompressed: u32 = 0;
+        let mut deflate_blocks: u64 = 0;
+        let mut stored_blocks: u64 = 0;
 
-        // Buffer for out-of-order blocks
-        let mut pending_blocks: BTreeMap<u64, EncodedBlock> = BTreeMap::new();
-        let mut next_write_id: u64 = 0;
+        let mut coords = self.config.build_coordinate_index().then(CoordinateTracker::new);
 
         // Main parsing loop - handles multiple gzip members
         loop {
             // Process all DEFLATE blocks in current gzip member
             while let Some(deflate_block) = parser.parse_block()? {
+                deflate_blocks += 1;
+                stored_blocks += (deflate_block.block_type == 0) as u64;
                 // Take ownership of tokens to avoid cloning
                 for token in deflate_block.tokens {
                     if matches!(token, LZ77Token::EndOfBlock) {
                         continue;
                     }
 
-                    let token_size = token.uncompressed_size();
-
-                    // Update splitter with this token
-                    splitter.process_token(&token);
-
-                    // Determine if we should emit a block
-                    let should_emit = if use_smart {
-                        let near_target =
-                            pending_uncompressed_size + token_size >= self.config.block_size;
-                        let at_good_split = splitter.is_good_split_point();
-                        let exceeds_max = pending_uncompressed_size + token_size > max_block_size;
-
-                        !pending_tokens.is_empty()
-                            && ((near_target && at_good_split) || exceeds_max)
+                    // Let the splitter cut the token where a block may need to end inside it
+                    let reaches_target = pending_uncompressed_size + token.uncompressed_size()
+                        >= self.config.block_size;
+                    if use_smart {
+                        splitter.split_token(token, reaches_target, &mut pieces);
                     } else {
-                        pending_uncompressed_size + token_size > self.config.block_size
-                            && !pending_tokens.is_empty()
-                    };
-
-                    if should_emit {
-                        let (resolved, crc, uncompressed_size) =
-                            resolver.resolve_block(block_start_position, &pending_tokens);
-
-                        let job = EncodingJob {
-                            block_id: next_block_id,
-                            tokens: resolved,
-                            uncompressed_size,
-                            crc,
+                        pieces.push(token);
+                    }
+                    for token in pieces.drain(..) {
+                        let token_size = token.uncompressed_size();
+
+                        // Determine if we should emit a block
+                        let should_emit = if use_smart {
+                            let near_target =
+                                pending_uncompressed_size + token_size >= self.config.block_size;
+                            let at_good_split = splitter.is_good_split_point();
+                            let exceeds_max =
+                                pending_uncompressed_size + token_size > max_block_size;
+
+                            !pending_tokens.is_empty()
+                                && (splitter.must_split()
+                                    || (near_target && at_good_split)
+                                    || exceeds_max)
+                        } else {
+                            pending_uncompressed_size + token_size > self.config.block_size
+                                && !pending_tokens.is_empty()
                         };
-                        next_block_id += 1;
-
-                        // Send job, draining results as needed to prevent deadlock
-                        send_job_and_drain(
-                            &job_tx,
-                            &result_rx,
-                            job,
-                            &mut writer,
-                            &mut pending_blocks,
-                            &mut next_write_id,
-                            &mut blocks_written,
-                            &mut output_bytes,
-                            build_index,
-                            &mut index_entries,
-                            &mut current_compressed_offset,
-                            &mut current_uncompressed_offset,
-                        )?;
-
-                        block_start_position = resolver.position();
-                        pending_tokens.clear();
-                        pending_uncompressed_size = 0;
-                        splitter.reset();
+
+                        if should_emit {
+                            // With a tail merger, dispatch the previously held block instead
+                            if let Some(ref mut merger) = tail_merger {
+                                merger.hold(&mut pending_tokens);
+                            }
+                            if !pending_tokens.is_empty() {
+                                let (resolved, crc, uncompressed_size) = resolver
+                                    .resolve_block(block_start_position, &pending_tokens)?;
+                                if let Some(ref mut coords) = coords {
+                                    coords.add_block(resolver.last_block());
+                                }
+                                min_block_uncompressed =
+                                    min_block_uncompressed.min(uncompressed_size);
+                                max_block_uncompressed =
+                                    max_block_uncompressed.max(uncompressed_size);
+
+                                if let Some(job) = batcher.push(resolved, uncompressed_size, crc) {
+                                    // Send job, draining results as needed to prevent deadlock
+                                    ordered.send(&job_tx, &result_rx, job)?;
+                                }
+
+                                block_start_position = resolver.position();
+                            }
+                            pending_tokens.clear();
+                            pending_uncompressed_size = 0;
+                            splitter.reset();
+                        }
+
+                        // Update splitter after the split decision so it describes the pending block
+                        splitter.process_token(&token);
+                        // No clone needed - we own the token
+                        pending_tokens.push(token);
+                        pending_uncompressed_size += token_size;
+                        parsed_size += token_size as u64;
                     }
+                }
+            }
 
-                    // No clone needed - we own the token
-                    pending_tokens.push(token);
-                    pending_uncompressed_size += token_size;
+            // End the block at the member boundary so no block spans two members
+            if self.config.block_per_member && !pending_tokens.is_empty() {
+                let (resolved, crc, uncompressed_size) =
+                    resolver.resolve_block(block_start_position, &pending_tokens)?;
+                if let Some(ref mut coords) = coords {
+                    coords.add_block(resolver.last_block());
                 }
+                min_block_uncompressed = min_block_uncompressed.min(uncompressed_size);
+                max_block_uncompressed = max_block_uncompressed.max(uncompressed_size);
+
+                if let Some(job) = batcher.push(resolved, uncompressed_size, crc) {
+                    ordered.send(&job_tx, &result_rx, job)?;
+                }
+
+                block_start_position = resolver.position();
+                pending_tokens.clear();
+                pending_uncompressed_size = 0;
+                splitter.reset();
             }
 
             // Check for another gzip member
-            if !parser.read_trailer_and_check_next()? {
-                break; // No more members, we're done
+            let more_members = parser.read_trailer_and_check_next()?;
+            if let Some((crc, isize)) = parser.last_trailer() {
+                resolver.end_member(parsed_size, crc, isize)?;
+                if self.config.collect_member_stats {
+                    members.push(MemberInfo {
+                        crc32: crc,
+                        isize,
+                        uncompressed_bytes: parsed_size - member_start,
+                    });
+                }
+                members_processed += 1;
+                member_start = parsed_size;
             }
-            // Continue with next member - parser state has been reset
-        }
 
-        // Flush remaining tokens (must use send_job_and_drain to avoid deadlock —
-        // a blocking send here can deadlock if both channels are full)
-        if !pending_tokens.is_empty() {
-            let (resolved, crc, uncompressed_size) =
-                resolver.resolve_block(block_start_position, &pending_tokens);
-
-            let job =
-                EncodingJob { block_id: next_block_id, tokens: resolved, uncompressed_size, crc };
-            next_block_id += 1;
-
-            send_job_and_drain(
-                &job_tx,
-                &result_rx,
-                job,
-                &mut writer,
-                &mut pending_blocks,
-                &mut next_write_id,
-                &mut blocks_written,
-                &mut output_bytes,
-                build_index,
-                &mut index_entries,
-                &mut current_compressed_offset,
-                &mut current_uncompressed_offset,
-            )?;
-        }
-
-        // Drop job_tx to signal workers we're done
-        drop(job_tx);
+            // End the block where a work unit ends, or the input does, as
+            // `BlockProducer` does, so the block layout matches the other paths
+            let member_end = parser.member_end();
+            let unit_ends = ends_unit(last_member_end, member_end);
+            last_member_end = member_end;
+            if !more_members || unit_ends {
+                // Flush remaining tokens (must use OrderedWriter::send to avoid
+                // deadlock — a blocking send can deadlock if both channels are full)
+                let tail_blocks = match tail_merger {
+                    Some(ref mut merger) => merger.finish(std::mem::take(&mut pending_tokens)),
+                    None => vec![std::mem::take(&mut pending_tokens)],
+                };
+                for tokens in tail_blocks.iter().filter(|t| !t.is_empty()) {
+                    let (resolved, crc, uncompressed_size) =
+                        resolver.resolve_block(block_start_position, tokens)?;
+                    if let Some(ref mut coords) = coords {
+                        coords.add_block(resolver.last_block());
+                    }
+                    min_block_uncompressed = min_block_uncompressed.min(uncompressed_size);
+                    max_block_uncompressed = max_block_uncompressed.max(uncompressed_size);
 
-        // Drain remaining results
-        while blocks_written + (pending_blocks.len() as u64) < next_block_id {
-            match result_rx.recv() {
-                Ok(result) => {
-                    let block = result?;
-                    buffer_and_write_block(
-                        &mut writer,
-                        block,
-                        &mut pending_blocks,
-                        &mut next_write_id,
-                        &mut blocks_written,
-                        &mut output_bytes,
-                        build_index,
-                        &mut index_entries,
-                        &mut current_compressed_offset,
-                        &mut current_uncompressed_offset,
-                    )?;
+                    if let Some(job) = batcher.push(resolved, uncompressed_size, crc) {
+                        ordered.send(&job_tx, &result_rx, job)?;
+                    }
+                    block_start_position = resolver.position();
                 }
-                Err(_) => break,
+                if let Some(mut tokens) = tail_blocks.into_iter().max_by_key(|t| t.capacity()) {
+                    tokens.clear();
+                    pending_tokens = tokens;
+                }
+                pending_uncompressed_size = 0;
+                splitter.reset();
+            }
+            if !more_members {
+                break; // No more members, we're done
             }
+            debug!(
+                "gzip member {} starts before input byte {}",
+                parser.member_index(),
+                parser.bytes_consumed()
+            );
+            // Continue with next member - parser state has been reset
         }
 
-        // Write any remaining buffered blocks
-        while let Some(block) = pending_blocks.remove(&next_write_id) {
-            write_single_block(
-                &mut writer,
-                &block.data,
-                block.uncompressed_size,
-                &mut output_bytes,
-                build_index,
-                &mut index_entries,
-                &mut current_compressed_offset,
-                &mut current_uncompressed_offset,
-            )?;
-            blocks_written += 1;
-            next_write_id += 1;
+        // The last batch may be only partly full
+        if let Some(job) = batcher.flush() {
+            ordered.send(&job_tx, &result_rx, job)?;
         }
 
-        // Write EOF marker
-        writer.write_all(&BGZF_EOF)?;
-        output_bytes += 28;
+        // Drop job_tx to signal workers we're done
+        drop(job_tx);
 
-        writer.flush()?;
+        // Drain remaining results and write the EOF marker
+        let written = ordered.finish(&result_rx, batcher.jobs(), self.config.write_eof)?;
 
         let (refs_resolved, _refs_preserved) = resolver.stats();
 
+        let coordinate_index = coords.map(|c| c.finish(&written.index_entries));
+
         Ok(TranscodeStats {
-            input_bytes: parser.bytes_read(),
-            output_bytes,
-            blocks_written,
+            input_bytes: parser.bytes_consumed(),
+            uncompressed_bytes: parsed_size,
+            output_bytes: written.output_bytes,
+            blocks_written: written.blocks_written,
+            min_block_uncompressed: if batcher.jobs() > 0 { min_block_uncompressed } else { 0 },
+            max_block_uncompressed,
             boundary_refs_resolved: refs_resolved,
+            resolved_bytes: resolver.resolved_bytes(),
+            input_trailer_mismatch: resolver.trailer_mismatch(),
+            // Filled in once the workers are joined
+            recompressed_bytes_saved: 0,
             copied_directly: false,
-            index_entries: if build_index { Some(index_entries) } else { None },
+            input_was_stored: deflate_blocks > 0 && stored_blocks == deflate_blocks,
+            index_entries: self.config.build_index.then_some(written.index_entries),
+            coordinate_index,
+            // Worker utilization is added once the workers are joined
+            parallel: Some(ParallelStats {
+                peak_buffered_blocks: written.peak_buffered_blocks,
+                ..Default::default()
+            }),
+            members,
+            members_processed,
         })
     }
 }
@@ -339,6 +547,142 @@ mod tests {
         assert_eq!(output[13], b'C');
     }
 
+    #[test]
+    fn test_worker_panic_message_is_reported() {
+        use std::io::Write as IoWrite;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&vec![b'A'; 200_000]).unwrap();
+        let gzip_data = encoder.finish().unwrap();
+
+        let config = TranscodeConfig { num_threads: 2, block_size: 16384, ..Default::default() };
+        let mut transcoder = ParallelTranscoder::new(config);
+        transcoder.worker_panic = Some(("injected encoder failure", usize::MAX));
+
+        let err = transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("injected encoder failure"), "Unexpected error: {}", message);
+    }
+
+    #[test]
+    fn test_one_worker_panic_fails_instead_of_hanging() {
+        use std::io::Write as IoWrite;
+        use std::time::Duration;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&crate::testdata::generate_mixed_data(2_000_000)).unwrap();
+        let gzip_data = encoder.finish().unwrap();
+
+        // The other workers keep the result channel open, so the lost job must be reported
+        let (done_tx, done_rx) = crossbeam::channel::bounded(1);
+        std::thread::spawn(move || {
+            let config =
+                TranscodeConfig { num_threads: 4, block_size: 16384, ..Default::default() };
+            let mut transcoder = ParallelTranscoder::new(config);
+            transcoder.worker_panic = Some(("injected encoder failure", 1));
+            let _ = done_tx.send(transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()));
+        });
+        let result = done_rx.recv_timeout(Duration::from_secs(60)).expect("transcode hung");
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("injected encoder failure"), "Unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_failed_workers_leave_no_eof_block() {
+        use crate::bgzf::verify_bgzf;
+
+        // Few enough jobs to all fit in the channel, so results are only awaited
+        // once every job is sent
+        let gzip_data = spawned_gzip();
+        let config =
+            TranscodeConfig { num_threads: 4, max_jobs_ahead: 10_000, ..Default::default() };
+        let mut transcoder = ParallelTranscoder::new(config);
+        transcoder.worker_panic = Some(("injected encoder failure", 1));
+        let mut output = Vec::new();
+        assert!(transcoder.transcode(Cursor::new(&gzip_data), &mut output).is_err());
+        assert!(!verify_bgzf(&mut &output[..]).unwrap().has_eof_block);
+    }
+
+    fn spawned_gzip() -> Vec<u8> {
+        use std::io::Write as IoWrite;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&crate::testdata::generate_mixed_data(300_000)).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_with_spawner_matches_scoped_threads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let gzip_data = spawned_gzip();
+        let config = TranscodeConfig { num_threads: 3, block_size: 16384, ..Default::default() };
+        let mut expected = Vec::new();
+        ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut expected)
+            .unwrap();
+
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&spawned);
+        let mut transcoder = ParallelTranscoder::new(config).with_spawner(Arc::new(move |task| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(task);
+        }));
+        let mut output = Vec::new();
+        let stats = transcoder.transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(stats.output_bytes, output.len() as u64);
+        assert_eq!(spawned.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_with_spawner_transcodes_members_on_its_tasks() {
+        use std::io::Write as IoWrite;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let mut gzip_data = spawned_gzip();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"second member\n").unwrap();
+        gzip_data.extend(encoder.finish().unwrap());
+
+        let config = TranscodeConfig { num_threads: 3, block_size: 16384, ..Default::default() };
+        let mut expected = Vec::new();
+        ParallelTranscoder::new(config.clone()).transcode_slice(&gzip_data, &mut expected).unwrap();
+
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&spawned);
+        let mut transcoder = ParallelTranscoder::new(config).with_spawner(Arc::new(move |task| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(task);
+        }));
+        let mut output = Vec::new();
+        let stats = transcoder.transcode_slice(&gzip_data, &mut output).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(stats.members_processed, 2);
+        assert_eq!(spawned.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_with_spawner_reports_panics_and_dropped_tasks() {
+        use std::sync::Arc;
+
+        let gzip_data = spawned_gzip();
+        let config = TranscodeConfig { num_threads: 2, block_size: 16384, ..Default::default() };
+
+        let mut transcoder = ParallelTranscoder::new(config.clone())
+            .with_spawner(Arc::new(|task| drop(std::thread::spawn(task))));
+        transcoder.worker_panic = Some(("injected encoder failure", usize::MAX));
+        let err = transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("injected encoder failure"), "Unexpected error: {}", err);
+
+        // An executor that never runs the workers fails instead of hanging
+        let mut transcoder = ParallelTranscoder::new(config).with_spawner(Arc::new(drop));
+        let err = transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("dropped without running"), "Unexpected error: {}", err);
+    }
+
     #[test]
     fn test_effective_threads() {
         let config = TranscodeConfig { num_threads: 0, ..Default::default() };
@@ -9,8 +9,7 @@
 //! 5. Phase 3 (sequential): Feed all tokens through BoundaryResolver to resolve
 //!    cross-boundary references, then encode and emit BGZF blocks
 
-use std::collections::BTreeMap;
-use std::io::{BufWriter, Write};
+use std::io::Write;
 use std::sync::{Arc, Condvar, Mutex};
 
 use crossbeam::channel::{bounded, Receiver, Sender};
@@ -18,19 +17,23 @@ use crossbeam::channel::{bounded, Receiver, Sender};
 use super::block_scanner::scan_for_block;
 use super::boundary::BoundaryResolver;
 use super::encoding::{
-    buffer_and_write_block, encoding_worker, send_job_and_drain, write_single_block, EncodedBlock,
-    EncodingJob,
+    encoding_worker, join_workers, panic_message, EncodedBlock, EncodingJob, JobBatcher,
+    OrderedWriter, PanicReporter, WorkerHandle,
 };
-use super::single::{parse_gzip_header_size, SingleThreadedTranscoder};
-use super::splitter::{BlockSplitter, DefaultSplitter, FastqSplitter};
-use crate::bgzf::{GziEntry, BGZF_EOF};
+use super::members::{member_ranges, transcode_members};
+use super::output::BlockOutput;
+use super::single::{block_header_fields, parse_gzip_header_size, SingleThreadedTranscoder};
+use super::splitter::{splitter_for, TailMerger};
+use crate::bgzf::coords::CoordinateTracker;
 use crate::bits::{BitRead, SliceBitReader};
 use crate::deflate::parser::parse_dynamic_huffman_tables;
 use crate::deflate::tables::{DISTANCE_TABLE, LENGTH_TABLE};
 use crate::deflate::LZ77Token;
 use crate::error::{Error, Result};
+use crate::gzip::GzipHeader;
 use crate::huffman::HuffmanDecoder;
-use crate::{FormatProfile, TranscodeConfig, TranscodeStats};
+use crate::logging::debug;
+use crate::{MemberInfo, ParallelStats, TranscodeConfig, TranscodeStats};
 
 /// Minimum DEFLATE region size (in bytes) to justify parallelism.
 const MIN_REGION_BYTES: usize = 512 * 1024;
@@ -58,15 +61,21 @@ impl ParallelDecodeTranscoder {
 
     /// Transcode from a memory-mapped gzip byte slice to a writer.
     ///
-    /// Falls back to single-threaded for multi-member gzip files (detected by
-    /// checking for a valid gzip header after the first member's trailer).
+    /// Multi-member gzip files (detected by checking for a valid gzip header
+    /// after the first member's trailer) are transcoded a run of members per
+    /// thread, with the same blocks as a single thread would write, or
+    /// single-threaded when building a coordinate index.
     pub fn transcode_mmap<W: Write>(&mut self, data: &[u8], output: W) -> Result<TranscodeStats> {
+        self.config.validate()?;
         let header_size = parse_gzip_header_size(data)?;
-        let deflate_end = data.len().saturating_sub(8);
         let num_threads = self.config.effective_threads();
 
-        let region = deflate_end.saturating_sub(header_size);
+        let region = data.len().saturating_sub(header_size + 8);
         if region < self.min_region_bytes || num_threads <= 1 {
+            debug!(
+                "{} byte DEFLATE region on {} threads: decoding sequentially",
+                region, num_threads
+            );
             return self.fallback(data, output);
         }
 
@@ -74,7 +83,31 @@ impl ParallelDecodeTranscoder {
         // after the first member's DEFLATE stream + 8-byte trailer.
         // This is cheaper than scanning the whole file for 1f 8b magic bytes
         // and avoids false positives from DEFLATE-compressed data.
-        if is_multi_member(data, header_size) {
+        let member_end = first_member_end(data, header_size);
+        if member_end.is_some_and(|end| is_multi_member(data, end)) {
+            // Members are independent, so each work unit is decoded and encoded whole
+            // on one thread; coordinates are tracked across the stream, which they do not see
+            let members = member_ranges(data);
+            if members.len() > 1 && !self.config.build_coordinate_index() {
+                debug!("{} gzip members: transcoding them in parallel", members.len());
+                return transcode_members(&self.config, data, &members, num_threads, output);
+            }
+            debug!("multi-member gzip input: decoding sequentially");
+            return self.fallback(data, output);
+        }
+
+        // Only a member that ends the input is decoded in parallel; anything
+        // else is left for the sequential parser to accept or report
+        let Some(deflate_end) = member_end.filter(|&end| end == data.len()).map(|end| end - 8)
+        else {
+            debug!("first member does not end the input: decoding sequentially");
+            return self.fallback(data, output);
+        };
+
+        // Block scanning only finds Huffman blocks, so chunks of stored data would be
+        // skipped; stored blocks are cheap to decode sequentially anyway
+        if is_stored_only(data, header_size) {
+            debug!("input has only stored blocks: decoding sequentially");
             return self.fallback(data, output);
         }
 
@@ -90,45 +123,46 @@ impl ParallelDecodeTranscoder {
     }
 
     /// Main thread work for Phase 3: resolve boundaries, dispatch to workers, write output.
+    ///
+    /// The member's gzip trailer follows its DEFLATE data at `deflate_end`.
+    #[allow(clippy::too_many_arguments)]
     fn resolve_dispatch_write<W: Write>(
         &self,
         data: &[u8],
+        deflate_end: usize,
         chunk_tokens: impl IntoIterator<Item = Vec<LZ77Token>>,
+        num_threads: usize,
         job_tx: Sender<EncodingJob>,
         result_rx: Receiver<Result<EncodedBlock>>,
         output: W,
     ) -> Result<TranscodeStats> {
-        let mut writer = BufWriter::with_capacity(self.config.buffer_size, output);
+        let writer = BlockOutput::with_capacity(self.config.buffer_size, output)
+            .hold_below(self.config.single_write_below);
+        let mut ordered = OrderedWriter::new(writer, &self.config, num_threads);
         let mut resolver = BoundaryResolver::new();
+        resolver.set_tolerate_mismatch(self.config.tolerate_bad_input_trailer);
+        resolver.set_check_members(self.config.verify_input_crc);
 
         // Smart boundary splitting (matching single-threaded path)
         let use_smart = self.config.use_smart_boundaries();
-        let mut splitter: Box<dyn BlockSplitter> =
-            if use_smart && self.config.format == FormatProfile::Fastq {
-                Box::new(FastqSplitter::new())
-            } else {
-                Box::new(DefaultSplitter)
-            };
-        let max_block_size = if use_smart {
-            (self.config.block_size as f64 * 1.1) as usize
-        } else {
-            self.config.block_size
-        };
+        let mut splitter = splitter_for(&self.config);
+        // A token split where a record ends inside it, so a block can end there
+        let mut pieces: Vec<LZ77Token> = Vec::new();
+        let max_block_size = self.config.max_block_size();
+
+        // Optional look-ahead that holds the last full block to absorb a small trailing block
+        let mut tail_merger =
+            self.config.min_block_size.map(|min| TailMerger::new(min, max_block_size));
 
         let mut pending_tokens: Vec<LZ77Token> = Vec::with_capacity(32768);
         let mut pending_uncompressed_size: usize = 0;
         let mut block_start_position: u64 = 0;
-        let mut next_block_id: u64 = 0;
-
-        // Output ordering state
-        let build_index = self.config.build_index;
-        let mut blocks_written: u64 = 0;
-        let mut output_bytes: u64 = 0;
-        let mut index_entries: Vec<GziEntry> = Vec::new();
-        let mut current_compressed_offset: u64 = 0;
-        let mut current_uncompressed_offset: u64 = 0;
-        let mut pending_blocks: BTreeMap<u64, EncodedBlock> = BTreeMap::new();
-        let mut next_write_id: u64 = 0;
+        let mut batcher = JobBatcher::new(self.config.blocks_per_job);
+        let mut parsed_size: u64 = 0;
+
+        let mut min_block_uncompressed = u32::MAX;
+        let mut max_block_uncompressed: u32 = 0;
+        let mut coords = self.config.build_coordinate_index().then(CoordinateTracker::new);
 
         // Iterate all tokens from all chunks, accumulating into pending_tokens.
         // Use into_iter to take ownership (avoids clone).
@@ -138,138 +172,133 @@ impl ParallelDecodeTranscoder {
                     continue;
                 }
 
-                let token_size = token.uncompressed_size();
-                splitter.process_token(&token);
-
-                let should_emit = if use_smart {
-                    let near_target =
-                        pending_uncompressed_size + token_size >= self.config.block_size;
-                    let at_good_split = splitter.is_good_split_point();
-                    let exceeds_max = pending_uncompressed_size + token_size > max_block_size;
-                    !pending_tokens.is_empty() && ((near_target && at_good_split) || exceeds_max)
+                // Let the splitter cut the token where a block may need to end inside it
+                let reaches_target =
+                    pending_uncompressed_size + token.uncompressed_size() >= self.config.block_size;
+                if use_smart {
+                    splitter.split_token(token, reaches_target, &mut pieces);
                 } else {
-                    pending_uncompressed_size + token_size > self.config.block_size
-                        && !pending_tokens.is_empty()
-                };
+                    pieces.push(token);
+                }
+                for token in pieces.drain(..) {
+                    let token_size = token.uncompressed_size();
+
+                    let should_emit = if use_smart {
+                        let near_target =
+                            pending_uncompressed_size + token_size >= self.config.block_size;
+                        let at_good_split = splitter.is_good_split_point();
+                        let exceeds_max = pending_uncompressed_size + token_size > max_block_size;
+                        !pending_tokens.is_empty()
+                            && (splitter.must_split()
+                                || (near_target && at_good_split)
+                                || exceeds_max)
+                    } else {
+                        pending_uncompressed_size + token_size > self.config.block_size
+                            && !pending_tokens.is_empty()
+                    };
 
-                if should_emit {
-                    let (resolved, crc, uncompressed_size) =
-                        resolver.resolve_block(block_start_position, &pending_tokens);
+                    if should_emit {
+                        // With a tail merger, dispatch the previously held block instead
+                        if let Some(ref mut merger) = tail_merger {
+                            merger.hold(&mut pending_tokens);
+                        }
+                        if !pending_tokens.is_empty() {
+                            let (resolved, crc, uncompressed_size) =
+                                resolver.resolve_block(block_start_position, &pending_tokens)?;
+                            if let Some(ref mut coords) = coords {
+                                coords.add_block(resolver.last_block());
+                            }
+                            min_block_uncompressed = min_block_uncompressed.min(uncompressed_size);
+                            max_block_uncompressed = max_block_uncompressed.max(uncompressed_size);
+
+                            if let Some(job) = batcher.push(resolved, uncompressed_size, crc) {
+                                ordered.send(&job_tx, &result_rx, job)?;
+                            }
+
+                            block_start_position = resolver.position();
+                        }
+                        pending_tokens.clear();
+                        pending_uncompressed_size = 0;
+                        splitter.reset();
+                    }
 
-                    let job = EncodingJob {
-                        block_id: next_block_id,
-                        tokens: resolved,
-                        uncompressed_size,
-                        crc,
-                    };
-                    next_block_id += 1;
-
-                    send_job_and_drain(
-                        &job_tx,
-                        &result_rx,
-                        job,
-                        &mut writer,
-                        &mut pending_blocks,
-                        &mut next_write_id,
-                        &mut blocks_written,
-                        &mut output_bytes,
-                        build_index,
-                        &mut index_entries,
-                        &mut current_compressed_offset,
-                        &mut current_uncompressed_offset,
-                    )?;
-
-                    block_start_position = resolver.position();
-                    pending_tokens.clear();
-                    pending_uncompressed_size = 0;
-                    splitter.reset();
+                    pending_uncompressed_size += token_size;
+                    parsed_size += token_size as u64;
+                    // Update splitter after the split decision so it describes the pending block
+                    splitter.process_token(&token);
+                    pending_tokens.push(token); // moved, not cloned
                 }
-
-                pending_uncompressed_size += token_size;
-                pending_tokens.push(token); // moved, not cloned
             }
         }
 
-        // Flush remaining tokens (must use send_job_and_drain to avoid deadlock —
+        let trailer = &data[deflate_end..deflate_end + 8];
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let isize = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        resolver.end_member(parsed_size, crc, isize)?;
+
+        // Flush remaining tokens (must use OrderedWriter::send to avoid deadlock —
         // a blocking send here can deadlock if both channels are full and workers
         // are blocked on result_tx.send while the main thread blocks on job_tx.send)
-        if !pending_tokens.is_empty() {
+        let tail_blocks = match tail_merger {
+            Some(ref mut merger) => merger.finish(pending_tokens),
+            None => vec![pending_tokens],
+        };
+        let mut jobs = Vec::new();
+        for tokens in tail_blocks.iter().filter(|t| !t.is_empty()) {
             let (resolved, crc, uncompressed_size) =
-                resolver.resolve_block(block_start_position, &pending_tokens);
-            let job =
-                EncodingJob { block_id: next_block_id, tokens: resolved, uncompressed_size, crc };
-            next_block_id += 1;
-            send_job_and_drain(
-                &job_tx,
-                &result_rx,
-                job,
-                &mut writer,
-                &mut pending_blocks,
-                &mut next_write_id,
-                &mut blocks_written,
-                &mut output_bytes,
-                build_index,
-                &mut index_entries,
-                &mut current_compressed_offset,
-                &mut current_uncompressed_offset,
-            )?;
+                resolver.resolve_block(block_start_position, tokens)?;
+            if let Some(ref mut coords) = coords {
+                coords.add_block(resolver.last_block());
+            }
+            min_block_uncompressed = min_block_uncompressed.min(uncompressed_size);
+            max_block_uncompressed = max_block_uncompressed.max(uncompressed_size);
+            jobs.extend(batcher.push(resolved, uncompressed_size, crc));
+            block_start_position = resolver.position();
+        }
+        // The last batch may be only partly full
+        jobs.extend(batcher.flush());
+        for job in jobs {
+            ordered.send(&job_tx, &result_rx, job)?;
         }
 
         // Signal workers to stop
         drop(job_tx);
 
-        // Drain remaining results
-        while blocks_written + (pending_blocks.len() as u64) < next_block_id {
-            match result_rx.recv() {
-                Ok(result) => {
-                    let block = result?;
-                    buffer_and_write_block(
-                        &mut writer,
-                        block,
-                        &mut pending_blocks,
-                        &mut next_write_id,
-                        &mut blocks_written,
-                        &mut output_bytes,
-                        build_index,
-                        &mut index_entries,
-                        &mut current_compressed_offset,
-                        &mut current_uncompressed_offset,
-                    )?;
-                }
-                Err(_) => break,
-            }
-        }
-
-        // Write any remaining buffered blocks
-        while let Some(block) = pending_blocks.remove(&next_write_id) {
-            write_single_block(
-                &mut writer,
-                &block.data,
-                block.uncompressed_size,
-                &mut output_bytes,
-                build_index,
-                &mut index_entries,
-                &mut current_compressed_offset,
-                &mut current_uncompressed_offset,
-            )?;
-            blocks_written += 1;
-            next_write_id += 1;
-        }
-
-        // Write EOF
-        writer.write_all(&BGZF_EOF).map_err(Error::Io)?;
-        output_bytes += 28;
-        writer.flush().map_err(Error::Io)?;
+        // Drain remaining results and write EOF
+        let written = ordered.finish(&result_rx, batcher.jobs(), self.config.write_eof)?;
 
         let (resolved, _) = resolver.stats();
 
+        let coordinate_index = coords.map(|c| c.finish(&written.index_entries));
+
         Ok(TranscodeStats {
             input_bytes: data.len() as u64,
-            output_bytes,
-            blocks_written,
+            uncompressed_bytes: parsed_size,
+            output_bytes: written.output_bytes,
+            blocks_written: written.blocks_written,
+            min_block_uncompressed: if batcher.jobs() > 0 { min_block_uncompressed } else { 0 },
+            max_block_uncompressed,
             boundary_refs_resolved: resolved,
+            resolved_bytes: resolver.resolved_bytes(),
+            input_trailer_mismatch: resolver.trailer_mismatch(),
+            // Filled in once the workers are joined
+            recompressed_bytes_saved: 0,
             copied_directly: false,
-            index_entries: if build_index { Some(index_entries) } else { None },
+            // Stored-only input is decoded sequentially
+            input_was_stored: false,
+            index_entries: self.config.build_index.then_some(written.index_entries),
+            coordinate_index,
+            // Worker utilization is added once the workers are joined
+            parallel: Some(ParallelStats {
+                peak_buffered_blocks: written.peak_buffered_blocks,
+                ..Default::default()
+            }),
+            members: if self.config.collect_member_stats {
+                vec![MemberInfo { crc32: crc, isize, uncompressed_bytes: parsed_size }]
+            } else {
+                Vec::new()
+            },
+            members_processed: 1,
         })
     }
 
@@ -304,6 +333,15 @@ impl ParallelDecodeTranscoder {
         let encoding_threads = self.config.effective_threads();
         let channel_capacity = encoding_threads * 4;
         let use_fixed_huffman = self.config.use_fixed_huffman();
+        let split_subblocks = self.config.compression_level.use_subblock_splitting();
+        let recompress_chain = self.config.recompress_chain();
+        let extra_subfields = &self.config.extra_subfields;
+        let (gzip_header, _) = GzipHeader::parse_with_len_checked(
+            &mut &data[..header_size],
+            self.config.verify_input_crc,
+        )?;
+        let header_fields = block_header_fields(&self.config, &gzip_header);
+        let collect_stats = self.config.collect_parallel_stats;
 
         let (job_tx, job_rx): (Sender<EncodingJob>, Receiver<EncodingJob>) =
             bounded(channel_capacity);
@@ -344,12 +382,23 @@ impl ParallelDecodeTranscoder {
             }
 
             // Spawn Phase 3 encoding workers
+            let mut handles = Vec::with_capacity(encoding_threads);
             for _ in 0..encoding_threads {
                 let rx = job_rx.clone();
                 let tx = result_tx.clone();
-                scope.spawn(move |_| {
-                    encoding_worker(rx, tx, use_fixed_huffman);
-                });
+                handles.push(WorkerHandle::Scoped(scope.spawn(move |_| {
+                    let _reporter = PanicReporter(tx.clone());
+                    encoding_worker(
+                        rx,
+                        tx,
+                        use_fixed_huffman,
+                        split_subblocks,
+                        recompress_chain,
+                        extra_subfields,
+                        header_fields,
+                        collect_stats,
+                    )
+                })));
             }
             drop(job_rx);
             drop(result_tx);
@@ -372,10 +421,27 @@ impl ParallelDecodeTranscoder {
                 }
             });
 
-            self.resolve_dispatch_write(data, chunk_tokens_iter, job_tx, result_rx, output)
+            let result = self.resolve_dispatch_write(
+                data,
+                deflate_end,
+                chunk_tokens_iter,
+                encoding_threads,
+                job_tx,
+                result_rx,
+                output,
+            );
+
+            let (parallel, recompressed_bytes_saved) = join_workers(handles, collect_stats)?;
+            let mut stats = result?;
+            let peak_buffered_blocks = stats.parallel.take().map_or(0, |p| p.peak_buffered_blocks);
+            stats.parallel = parallel.map(|p| ParallelStats { peak_buffered_blocks, ..p });
+            stats.recompressed_bytes_saved = recompressed_bytes_saved;
+            Ok(stats)
         });
 
-        result.map_err(|_| Error::Internal("Phase 2/3 thread panicked".into()))?
+        result.map_err(|e| {
+            Error::Internal(format!("Phase 2/3 thread panicked: {}", panic_message(e.as_ref())))
+        })?
     }
 
     fn fallback<W: Write>(&self, data: &[u8], output: W) -> Result<TranscodeStats> {
@@ -384,13 +450,20 @@ impl ParallelDecodeTranscoder {
     }
 }
 
-/// Check if mmap'd data contains multiple gzip members by decoding the first
-/// member's DEFLATE stream to find its end, then checking for another header.
+/// Check if mmap'd data contains multiple gzip members: whether another gzip
+/// header follows the first member, which ends at `member_end`.
 ///
-/// This follows the actual DEFLATE structure rather than scanning for magic bytes
-/// (which can appear inside compressed data as false positives).
-fn is_multi_member(data: &[u8], header_size: usize) -> bool {
-    // Decode the first member to find where BFINAL is, then check after trailer
+/// Finding the end with [`first_member_end`] follows the actual DEFLATE
+/// structure rather than scanning for magic bytes (which can appear inside
+/// compressed data as false positives).
+fn is_multi_member(data: &[u8], member_end: usize) -> bool {
+    member_end + 10 <= data.len() && parse_gzip_header_size(&data[member_end..]).is_ok()
+}
+
+/// Where the first gzip member ends, just past its trailer, found by walking
+/// its DEFLATE blocks, or `None` if they do not parse or the trailer is cut off.
+fn first_member_end(data: &[u8], header_size: usize) -> Option<usize> {
+    // Decode the first member to find where BFINAL is
     let start_bit = header_size * 8;
     let end_bit = data.len() * 8;
 
@@ -404,16 +477,16 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
         let (cur_byte, cur_bit) = bits.bit_position();
         let cur_abs_bit = cur_byte * 8 + cur_bit as usize;
         if cur_abs_bit >= end_bit {
-            return false; // Ran out of data — single member
+            return None; // Ran out of data
         }
 
         let bfinal = match bits.read_bits(1) {
             Ok(v) => v != 0,
-            Err(_) => return false,
+            Err(_) => return None,
         };
         let btype = match bits.read_bits(2) {
             Ok(v) => v,
-            Err(_) => return false,
+            Err(_) => return None,
         };
 
         match btype {
@@ -422,19 +495,19 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
                 bits.align_to_byte();
                 let len = match bits.read_u16_le() {
                     Ok(v) => v,
-                    Err(_) => return false,
+                    Err(_) => return None,
                 };
                 let nlen = match bits.read_u16_le() {
                     Ok(v) => v,
-                    Err(_) => return false,
+                    Err(_) => return None,
                 };
                 if len != !nlen {
-                    return false;
+                    return None;
                 }
                 // Skip `len` bytes of stored data
                 for _ in 0..len {
                     if bits.read_bits(8).is_err() {
-                        return false;
+                        return None;
                     }
                 }
             }
@@ -445,7 +518,7 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
                 } else {
                     match parse_dynamic_huffman_tables(&mut bits) {
                         Ok((lit, dist)) => (lit, dist),
-                        Err(_) => return false,
+                        Err(_) => return None,
                     }
                 };
 
@@ -453,7 +526,7 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
                 loop {
                     let sym = match lit_decoder.decode(&mut bits) {
                         Ok(s) => s,
-                        Err(_) => return false,
+                        Err(_) => return None,
                     };
                     if sym == 256 {
                         break; // EOB
@@ -461,32 +534,32 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
                     if sym > 256 {
                         // Length code: skip extra bits + distance code + extra bits
                         if sym > 285 {
-                            return false;
+                            return None;
                         }
                         let len_idx = (sym - 257) as usize;
                         let (_, extra_bits) = LENGTH_TABLE[len_idx];
                         if extra_bits > 0 && bits.read_bits(extra_bits).is_err() {
-                            return false;
+                            return None;
                         }
                         let dist_dec = match &dist_decoder {
                             Some(d) => d,
-                            None => return false,
+                            None => return None,
                         };
                         let dist_sym = match dist_dec.decode(&mut bits) {
                             Ok(s) => s,
-                            Err(_) => return false,
+                            Err(_) => return None,
                         };
                         if dist_sym > 29 {
-                            return false;
+                            return None;
                         }
                         let (_, dist_extra) = DISTANCE_TABLE[dist_sym as usize];
                         if dist_extra > 0 && bits.read_bits(dist_extra).is_err() {
-                            return false;
+                            return None;
                         }
                     }
                 }
             }
-            _ => return false, // Reserved block type
+            _ => return None, // Reserved block type
         }
 
         if bfinal {
@@ -495,13 +568,31 @@ fn is_multi_member(data: &[u8], header_size: usize) -> bool {
             bits.align_to_byte();
             let (trailer_byte, _) = bits.bit_position();
             let after_trailer = trailer_byte + 8;
+            return (after_trailer <= data.len()).then_some(after_trailer);
+        }
+    }
+}
 
-            // Check if a valid gzip header follows
-            if after_trailer + 10 <= data.len() {
-                return parse_gzip_header_size(&data[after_trailer..]).is_ok();
-            }
-            return false; // No room for another member
+/// Check whether a single-member DEFLATE stream consists only of stored blocks.
+///
+/// Stored blocks are skipped by their LEN without decoding, and the walk stops
+/// at the first block of any other type.
+fn is_stored_only(data: &[u8], header_size: usize) -> bool {
+    // Stored blocks end byte-aligned, so every header here starts a byte:
+    // BFINAL and BTYPE in its low 3 bits, then LEN and NLEN
+    let mut pos = header_size;
+    loop {
+        let Some(&[header, len_lo, len_hi, nlen_lo, nlen_hi]) = data.get(pos..pos + 5) else {
+            return false;
+        };
+        let len = u16::from_le_bytes([len_lo, len_hi]);
+        if (header >> 1) & 0b11 != 0 || len != !u16::from_le_bytes([nlen_lo, nlen_hi]) {
+            return false;
+        }
+        if header & 1 == 1 {
+            return true;
         }
+        pos += 5 + len as usize;
     }
 }
 
@@ -774,11 +865,35 @@ mod tests {
         assert_eq!(st_dec, pd_dec);
     }
 
+    #[test]
+    fn test_trailer_is_read_where_the_member_ends() {
+        // A stray byte after the trailer, which the sequential parser accepts
+        let data = make_fastq(2000);
+        let mut gz = gzip_compress(&data);
+        gz.push(0);
+
+        let config = TranscodeConfig {
+            num_threads: 4,
+            verify_input_crc: true,
+            collect_member_stats: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let stats = ParallelDecodeTranscoder::new(config)
+            .with_min_region_bytes(0)
+            .transcode_mmap(&gz, &mut output)
+            .unwrap();
+        assert_eq!(gzip_decompress(&output), data);
+        assert_eq!(stats.members[0].isize, data.len() as u32);
+    }
+
     #[test]
     fn test_is_multi_member_single() {
         let gz = gzip_compress(b"hello world");
         let header_size = parse_gzip_header_size(&gz).unwrap();
-        assert!(!is_multi_member(&gz, header_size));
+        let member_end = first_member_end(&gz, header_size);
+        assert_eq!(member_end, Some(gz.len()));
+        assert!(!is_multi_member(&gz, gz.len()));
     }
 
     #[test]
@@ -789,29 +904,57 @@ mod tests {
         concat.extend_from_slice(&gz1);
         concat.extend_from_slice(&gz2);
         let header_size = parse_gzip_header_size(&concat).unwrap();
-        assert!(is_multi_member(&concat, header_size));
+        assert_eq!(first_member_end(&concat, header_size), Some(gz1.len()));
+        assert!(is_multi_member(&concat, gz1.len()));
+    }
+
+    #[test]
+    fn test_stored_only_roundtrip() {
+        // Stored blocks are invisible to the block scanner, so this must decode sequentially
+        use std::io::Write as IoWrite;
+        let data = make_fastq(5000);
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::none());
+        enc.write_all(&data).unwrap();
+        let stored = enc.finish().unwrap();
+        let header_size = parse_gzip_header_size(&stored).unwrap();
+        assert!(is_stored_only(&stored, header_size));
+        assert!(!is_stored_only(&gzip_compress(&data), header_size));
+        assert!(!is_stored_only(&stored[..stored.len() / 2], header_size));
+
+        let config = TranscodeConfig { num_threads: 4, ..Default::default() };
+        let mut transcoder = ParallelDecodeTranscoder::new(config).with_min_region_bytes(0);
+        let mut bgzf_output = Vec::new();
+        let stats = transcoder.transcode_mmap(&stored, &mut bgzf_output).unwrap();
+        assert!(stats.input_was_stored);
+        assert_eq!(gzip_decompress(&bgzf_output), data);
     }
 
     #[test]
     fn test_multi_member_roundtrip() {
-        // Multi-member should fall back to single-threaded and still produce correct output
+        // Members are transcoded on separate threads with the blocks one thread writes
         let data1 = make_fastq(500);
         let data2 = make_fastq(500);
         let mut concat_gz = Vec::new();
         concat_gz.extend_from_slice(&gzip_compress(&data1));
         concat_gz.extend_from_slice(&gzip_compress(&data2));
 
-        let config = TranscodeConfig { num_threads: 4, ..Default::default() };
-        let mut transcoder = ParallelDecodeTranscoder::new(config).with_min_region_bytes(0);
+        let config = TranscodeConfig { num_threads: 4, build_index: true, ..Default::default() };
+        let mut transcoder = ParallelDecodeTranscoder::new(config.clone()).with_min_region_bytes(0);
 
         let mut bgzf_output = Vec::new();
-        transcoder.transcode_mmap(&concat_gz, &mut bgzf_output).unwrap();
+        let stats = transcoder.transcode_mmap(&concat_gz, &mut bgzf_output).unwrap();
 
         let decompressed = gzip_decompress(&bgzf_output);
         let mut expected = Vec::new();
         expected.extend_from_slice(&data1);
         expected.extend_from_slice(&data2);
         assert_eq!(decompressed, expected);
+
+        let mut single = Vec::new();
+        let single_stats =
+            SingleThreadedTranscoder::new(config).transcode_slice(&concat_gz, &mut single).unwrap();
+        assert_eq!(bgzf_output, single);
+        assert_eq!(stats.index_entries, single_stats.index_entries);
     }
 
     #[test]
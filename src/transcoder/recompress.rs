@@ -0,0 +1,113 @@
+//! Recompression: re-running LZ77 over each block's bytes at levels 7-9.
+//!
+//! Token reuse keeps whatever matches the input's compressor found, so input
+//! written at a low level stays loosely compressed. A [`Recompressor`] instead
+//! runs a fresh hash-chain search over the block and keeps whichever token
+//! stream Huffman-codes smaller, so the output is never larger than reuse.
+
+use super::SlidingWindow;
+use crate::deflate::inflate::expand_tokens;
+use crate::deflate::{LZ77Token, Matcher};
+use crate::error::Result;
+use crate::huffman::HuffmanEncoder;
+
+/// Re-finds matches within single BGZF blocks.
+pub(super) struct Recompressor {
+    matcher: Matcher,
+    /// A block's bytes, rebuilt from its tokens when the caller does not have them
+    bytes: Vec<u8>,
+    window: SlidingWindow,
+}
+
+/// A block encoded by [`Recompressor`]: the tokens that won, their DEFLATE
+/// data, and the bytes saved over encoding the resolved tokens.
+pub(super) struct Recompressed {
+    pub tokens: Vec<LZ77Token>,
+    pub deflate_data: Vec<u8>,
+    pub saved: u64,
+}
+
+impl Recompressor {
+    /// Create a recompressor examining up to `max_chain` candidates per position
+    pub fn new(max_chain: usize) -> Self {
+        Self { matcher: Matcher::new(max_chain), bytes: Vec::new(), window: SlidingWindow::new() }
+    }
+
+    /// Encode a resolved block, rebuilding its bytes from `tokens`, which must
+    /// not refer outside the block (as after boundary resolution); a copy that
+    /// does is an error.
+    pub fn encode(
+        &mut self,
+        encoder: &mut HuffmanEncoder,
+        tokens: Vec<LZ77Token>,
+    ) -> Result<Recompressed> {
+        let mut bytes = std::mem::take(&mut self.bytes);
+        bytes.clear();
+        self.window.clear();
+        let result = expand_tokens(&tokens, &mut self.window, &mut bytes)
+            .and_then(|()| self.encode_bytes(encoder, tokens, &bytes));
+        self.bytes = bytes;
+        result
+    }
+
+    /// Encode a resolved block whose uncompressed bytes are `bytes`.
+    pub fn encode_bytes(
+        &mut self,
+        encoder: &mut HuffmanEncoder,
+        tokens: Vec<LZ77Token>,
+        bytes: &[u8],
+    ) -> Result<Recompressed> {
+        let reused = encoder.encode(&tokens, true)?;
+
+        // Blocks are independent, so matches must not reach into the previous one
+        self.matcher.reset();
+        let fresh_tokens = self.matcher.tokenize(bytes);
+        let fresh = encoder.encode(&fresh_tokens, true)?;
+
+        Ok(if fresh.len() < reused.len() {
+            let saved = (reused.len() - fresh.len()) as u64;
+            Recompressed { tokens: fresh_tokens, deflate_data: fresh, saved }
+        } else {
+            Recompressed { tokens, deflate_data: reused, saved: 0 }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deflate::inflate;
+
+    #[test]
+    fn test_recompress_beats_literal_tokens() {
+        let data = b"GATTACA CATTAGA GATTACA ".repeat(500);
+        let literals: Vec<LZ77Token> = data.iter().map(|&b| LZ77Token::Literal(b)).collect();
+        let mut encoder = HuffmanEncoder::new(false);
+        let mut recompressor = Recompressor::new(64);
+
+        let block = recompressor.encode(&mut encoder, literals.clone()).unwrap();
+        assert!(block.saved > 0);
+        assert_eq!(block.saved as usize + block.deflate_data.len(), {
+            encoder.encode(&literals, true).unwrap().len()
+        });
+        assert_eq!(inflate(&block.deflate_data[..]).unwrap(), data);
+
+        // Already well-matched tokens are kept
+        let again = recompressor.encode_bytes(&mut encoder, block.tokens.clone(), &data).unwrap();
+        assert_eq!(again.saved, 0);
+        assert_eq!(again.tokens, block.tokens);
+    }
+
+    #[test]
+    fn test_recompress_rejects_copy_outside_block() {
+        let tokens = vec![LZ77Token::Literal(b'A'), LZ77Token::Copy { length: 4, distance: 2 }];
+        let mut encoder = HuffmanEncoder::new(false);
+        let mut recompressor = Recompressor::new(64);
+        assert!(recompressor.encode(&mut encoder, tokens).is_err());
+
+        // The recompressor is still usable afterwards
+        let tokens = vec![LZ77Token::Literal(b'A'), LZ77Token::Copy { length: 4, distance: 1 }];
+        let block = recompressor.encode(&mut encoder, tokens).unwrap();
+        assert_eq!(inflate(&block.deflate_data[..]).unwrap(), b"AAAAA");
+    }
+}
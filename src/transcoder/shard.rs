@@ -0,0 +1,245 @@
+//! Splitting one gzip input into several independent BGZF files.
+
+use std::io::{self, Read, Write};
+
+use super::parallel::ParallelTranscoder;
+use super::single::SingleThreadedTranscoder;
+use crate::bgzf::{
+    block_size_from_bsize, is_eof_block, BGZF_EOF, BGZF_EOF_SIZE, BGZF_FOOTER_SIZE,
+    BGZF_HEADER_SIZE,
+};
+use crate::error::{Error, Result};
+use crate::{TranscodeConfig, Transcoder};
+
+/// Size of one shard written by [`shard_transcode`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    /// Data blocks in the shard (excludes its EOF block)
+    pub blocks: u64,
+    /// Bytes written to the shard, including its EOF block
+    pub compressed_bytes: u64,
+    /// Uncompressed bytes in the shard
+    pub uncompressed_bytes: u64,
+}
+
+/// Transcode `input` into BGZF files of about `shard_size` uncompressed bytes each.
+///
+/// `make_shard(i)` is called once per shard, in order, to open its writer. Whole
+/// blocks are routed to shards in stream order as they are written, so
+/// concatenating the shards' contents reproduces the input, and every shard ends
+/// with its own EOF block and is a complete BGZF file (there is always at least
+/// one, empty if the input is).
+///
+/// The input is read once, so it can be a pipe: a new shard is started once the
+/// current one holds at least `shard_size` bytes, leaving every shard but the
+/// last within one block over `shard_size`. To split an input of known
+/// uncompressed size into N shards, pass that size divided by N, rounded up.
+/// `build_index` is ignored, since block offsets would not match any one shard.
+pub fn shard_transcode<R, W, F>(
+    input: R,
+    make_shard: F,
+    shard_size: u64,
+    mut config: TranscodeConfig,
+) -> Result<Vec<ShardStats>>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(usize) -> W,
+{
+    if shard_size == 0 {
+        return Err(Error::InvalidConfig("shard_size must be at least 1".to_string()));
+    }
+    config.build_index = false;
+
+    let mut router = ShardRouter { make_shard, shard_size, shards: Vec::new(), buf: Vec::new() };
+    if config.effective_threads() == 1 {
+        SingleThreadedTranscoder::new(config).transcode(input, &mut router)?;
+    } else {
+        ParallelTranscoder::new(config).transcode(input, &mut router)?;
+    }
+    router.finish()
+}
+
+/// Transcoder output that splits the BGZF stream into blocks and routes them to shards
+struct ShardRouter<W, F> {
+    make_shard: F,
+    /// Uncompressed bytes after which a shard is complete
+    shard_size: u64,
+    /// Opened shards with their sizes; the last one is being filled
+    shards: Vec<(W, ShardStats)>,
+    /// Bytes of the block currently being received
+    buf: Vec<u8>,
+}
+
+impl<W: Write, F: FnMut(usize) -> W> ShardRouter<W, F> {
+    /// Route a complete data block to the current shard, first opening a new
+    /// shard if the current one is full.
+    fn route(&mut self, block: &[u8]) -> io::Result<()> {
+        let full = self
+            .shards
+            .last()
+            .map_or(true, |(_, stats)| stats.uncompressed_bytes >= self.shard_size);
+        if full {
+            self.open_shard();
+        }
+
+        let footer = &block[block.len() - 4..];
+        let isize = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]) as u64;
+        let (shard, stats) = self.shards.last_mut().expect("a shard is open");
+        shard.write_all(block)?;
+        stats.blocks += 1;
+        stats.compressed_bytes += block.len() as u64;
+        stats.uncompressed_bytes += isize;
+        Ok(())
+    }
+
+    fn open_shard(&mut self) {
+        let shard = (self.make_shard)(self.shards.len());
+        self.shards.push((shard, ShardStats::default()));
+    }
+
+    /// End every shard with an EOF block, opening one if the input had no data.
+    fn finish(mut self) -> Result<Vec<ShardStats>> {
+        if !self.buf.is_empty() {
+            return Err(Error::Internal("transcoder output ended mid-block".into()));
+        }
+        if self.shards.is_empty() {
+            self.open_shard();
+        }
+        let mut summaries = Vec::with_capacity(self.shards.len());
+        for (mut shard, mut stats) in self.shards {
+            shard.write_all(&BGZF_EOF)?;
+            shard.flush()?;
+            stats.compressed_bytes += BGZF_EOF_SIZE as u64;
+            summaries.push(stats);
+        }
+        Ok(summaries)
+    }
+}
+
+impl<W: Write, F: FnMut(usize) -> W> Write for ShardRouter<W, F> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        // The transcoders write BC as the first subfield, so BSIZE is at bytes 16-17
+        while self.buf.len() >= BGZF_HEADER_SIZE {
+            let block_size =
+                block_size_from_bsize(u16::from_le_bytes([self.buf[16], self.buf[17]]));
+            if block_size < BGZF_HEADER_SIZE + BGZF_FOOTER_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid BGZF block size"));
+            }
+            if self.buf.len() < block_size {
+                break;
+            }
+            let block: Vec<u8> = self.buf.drain(..block_size).collect();
+            // Each shard gets its own EOF block in `finish`
+            if !is_eof_block(&block) {
+                self.route(&block)?;
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn fastq(records: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut state = 3u32;
+        let mut next = |alphabet: &[u8]| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            alphabet[(state >> 16) as usize % alphabet.len()] as char
+        };
+        for i in 0..records {
+            let bases: String = (0..60).map(|_| next(b"ACGT")).collect();
+            let quals: String = (0..60).map(|_| next(b"#-7<AFJ")).collect();
+            writeln!(data, "@read{}\n{}\n+\n{}", i, bases, quals).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_shard_transcode_three_shards() {
+        let data = fastq(10_000);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(6));
+        encoder.write_all(&data).unwrap();
+        let gz = encoder.finish().unwrap();
+        let shard_size = (data.len() as u64 + 2) / 3;
+
+        for num_threads in [1, 4] {
+            let mut shards = vec![Vec::new(); 3];
+            let mut opened = Vec::new();
+            let stats = {
+                let mut slots = shards.iter_mut();
+                let config =
+                    TranscodeConfig { block_size: 16384, num_threads, ..Default::default() };
+                // A plain `Read` with no `Seek`, as from a pipe
+                shard_transcode(
+                    &gz[..],
+                    |i| {
+                        opened.push(i);
+                        slots.next().unwrap()
+                    },
+                    shard_size,
+                    config,
+                )
+                .unwrap()
+            };
+            assert_eq!(opened, [0, 1, 2]);
+            assert_eq!(stats.len(), 3);
+
+            let mut joined = Vec::new();
+            for (shard, stats) in shards.iter().zip(&stats) {
+                // Every shard is a complete BGZF file on its own
+                assert!(stats.blocks > 0, "{:?}", stats);
+                assert_eq!(stats.compressed_bytes, shard.len() as u64);
+                assert!(is_eof_block(&shard[shard.len() - BGZF_EOF_SIZE..]));
+                let mut decoded = Vec::new();
+                MultiGzDecoder::new(&shard[..]).read_to_end(&mut decoded).unwrap();
+                assert_eq!(decoded.len() as u64, stats.uncompressed_bytes);
+                joined.extend(decoded);
+            }
+            assert_eq!(joined, data, "{} threads", num_threads);
+
+            // Full shards are within one block over the target
+            for stats in &stats[..2] {
+                assert!(stats.uncompressed_bytes >= shard_size, "{:?}", stats);
+                assert!(stats.uncompressed_bytes - shard_size <= 16384, "{:?}", stats);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_transcode_small_input() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"@r\nACGT\n+\nIIII\n").unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let mut shards = vec![Vec::new(); 2];
+        let mut slots = shards.iter_mut();
+        let config = TranscodeConfig::default();
+        let stats = shard_transcode(&gz[..], |_| slots.next().unwrap(), 1 << 20, config).unwrap();
+        assert_eq!(stats.iter().map(|s| s.blocks).collect::<Vec<_>>(), [1]);
+        assert!(shards[1].is_empty());
+
+        // Empty input still makes one (empty) BGZF file
+        let gz = GzEncoder::new(Vec::new(), Compression::default()).finish().unwrap();
+        let mut shards = vec![Vec::new(); 2];
+        let mut slots = shards.iter_mut();
+        let config = TranscodeConfig::default();
+        let stats = shard_transcode(&gz[..], |_| slots.next().unwrap(), 1 << 20, config).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(shards[0], BGZF_EOF);
+
+        let config = TranscodeConfig::default();
+        assert!(shard_transcode(&gz[..], |_| Vec::new(), 0, config).is_err());
+    }
+}
@@ -1,15 +1,34 @@
 use super::boundary::BoundaryResolver;
-use super::splitter::{BlockSplitter, DefaultSplitter, FastqSplitter};
-use crate::bgzf::{BgzfBlockWriter, GziIndexBuilder};
-use crate::bits::BitRead;
+use super::context::TranscodeContext;
+use super::members::ends_unit;
+use super::output::{BlockOutput, BlockQueue, BlockSink};
+use super::recompress::Recompressor;
+use super::splitter::{splitter_for, BlockSplitter, TailMerger};
+use crate::bgzf::coords::CoordinateTracker;
+use crate::bgzf::writer::HeaderFields;
+use crate::bgzf::{
+    write_gzi, BgzfBlockWriter, GziIndexBuilder, BGZF_EOF_SIZE, BGZF_FOOTER_SIZE,
+    MAX_BGZF_BLOCK_SIZE,
+};
+use crate::bits::{BitRead, BitReader};
 use crate::deflate::{DeflateParser, LZ77Token};
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::gzip::GzipHeader;
+use crate::huffman::encoder::{encode_stored, stored_len};
 use crate::huffman::HuffmanEncoder;
-use crate::{FormatProfile, TranscodeConfig, TranscodeStats, Transcoder};
-use std::io::{BufReader, BufWriter, Read, Write};
+use crate::logging::{debug, trace};
+use crate::timing::{timed, Phase};
+use crate::{BlockProgress, TranscodeConfig, TranscodeStats, Transcoder};
+use std::borrow::BorrowMut;
+use std::io::{BufReader, Read, Write};
 
 /// Single-threaded transcoder implementation
+///
+/// Input is streamed: at any time it holds only the pending BGZF block's tokens,
+/// the current DEFLATE block's tokens, the 32KB back-reference window and the I/O
+/// buffers, so memory use does not grow with the input size (unless `build_index`
+/// is set, which keeps one index entry per block), apart from the stats' small
+/// entry per gzip member.
 pub struct SingleThreadedTranscoder {
     config: TranscodeConfig,
 }
@@ -22,196 +41,587 @@ impl SingleThreadedTranscoder {
     /// Transcode from a byte slice (e.g., mmap'd file) to a writer.
     /// Uses `SliceBitReader` for maximum parsing performance.
     pub fn transcode_slice<W: Write>(&mut self, data: &[u8], output: W) -> Result<TranscodeStats> {
-        let mut writer = BufWriter::with_capacity(self.config.buffer_size, output);
+        self.config.validate()?;
+        let mut writer = BlockOutput::with_capacity(self.config.buffer_size, output)
+            .hold_below(self.config.single_write_below);
 
         // Parse gzip header from the raw bytes
         let header_size = parse_gzip_header_size(data)?;
 
         // Create DEFLATE parser using slice-backed bit reader
-        let mut parser = DeflateParser::from_slice(data, header_size);
-        let mut bgzf_writer = BgzfBlockWriter::new(&mut writer);
+        let parser = DeflateParser::from_slice(data, header_size);
+        let mut bgzf_writer =
+            BgzfBlockWriter::with_extra_subfields(&mut writer, &self.config.extra_subfields);
+        let (gzip_header, _) = GzipHeader::parse_with_len_checked(
+            &mut &data[..header_size],
+            self.config.verify_input_crc,
+        )?;
+        bgzf_writer.set_header_fields(block_header_fields(&self.config, &gzip_header));
+
+        let stats = self.transcode_core(parser, &mut bgzf_writer, &mut TranscodeContext::new())?;
+
+        // Not `bgzf_writer.finish()`: its flush would write the blocks apart
+        // from the EOF block
+        writer.finish()?;
+        Ok(stats)
+    }
 
-        let stats = self.transcode_core(&mut parser, &mut bgzf_writer)?;
+    /// Transcode to `output` and write the matching GZI index to `index_writer`.
+    ///
+    /// Builds the index regardless of `config.build_index`; the returned stats
+    /// carry the same entries that were written.
+    pub fn transcode_with_index<R: Read, W: Write, I: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        index_writer: I,
+    ) -> Result<TranscodeStats> {
+        let build_index = std::mem::replace(&mut self.config.build_index, true);
+        let result = self.transcode(input, output);
+        self.config.build_index = build_index;
 
-        let _ = bgzf_writer.finish()?;
+        let stats = result?;
+        write_gzi(stats.index_entries.as_deref().unwrap_or_default(), index_writer)?;
         Ok(stats)
     }
 
+    /// Transcode `input` one block at a time, as the blocks are requested.
+    ///
+    /// The returned iterator yields each BGZF block (header and footer
+    /// included) in output order, ending with the EOF block when `write_eof`
+    /// is set, so the caller decides where each one goes. Fails up front if
+    /// the configuration is invalid or the gzip header cannot be read.
+    pub fn blocks<R: Read>(&self, input: R) -> Result<BlockIterator<R>> {
+        self.config.validate()?;
+        let mut reader = BufReader::with_capacity(self.config.buffer_size, input);
+        let (gzip_header, header_len) =
+            GzipHeader::parse_with_len_checked(&mut reader, self.config.verify_input_crc)?;
+
+        let parser = DeflateParser::with_input_offset(reader, header_len);
+        let mut writer = BgzfBlockWriter::with_extra_subfields(
+            BlockQueue::default(),
+            &self.config.extra_subfields,
+        );
+        writer.set_header_fields(block_header_fields(&self.config, &gzip_header));
+        let producer = BlockProducer::new(&self.config, parser, TranscodeContext::new());
+        Ok(BlockIterator { producer, writer, done: false })
+    }
+
     /// Core transcoding loop, generic over the bit reader type.
-    fn transcode_core<B: BitRead, W: Write>(
+    fn transcode_core<B: BitRead, S: BlockSink>(
         &self,
-        parser: &mut DeflateParser<B>,
-        bgzf_writer: &mut BgzfBlockWriter<W>,
+        parser: DeflateParser<B>,
+        bgzf_writer: &mut BgzfBlockWriter<S>,
+        context: &mut TranscodeContext,
     ) -> Result<TranscodeStats> {
-        let mut resolver = BoundaryResolver::new();
-        let mut encoder = HuffmanEncoder::new(self.config.use_fixed_huffman());
-
-        // Create splitter based on config
-        let use_smart = self.config.use_smart_boundaries();
-        let mut splitter: Box<dyn BlockSplitter> =
-            if use_smart && self.config.format == FormatProfile::Fastq {
-                Box::new(FastqSplitter::new())
-            } else {
-                Box::new(DefaultSplitter)
-            };
-
-        // Maximum block size with overshoot allowance for smart boundaries
-        let max_block_size = if use_smart {
-            (self.config.block_size as f64 * 1.1) as usize
-        } else {
-            self.config.block_size
-        };
+        let mut producer = BlockProducer::new(&self.config, parser, context);
+        while producer.step(bgzf_writer)? {}
+        Ok(producer.stats)
+    }
+}
 
-        // Accumulator for current BGZF block — larger initial capacity to reduce reallocs
-        let mut pending_tokens: Vec<LZ77Token> = Vec::with_capacity(32768);
-        let mut pending_uncompressed_size: usize = 0;
-        let mut block_start_position: u64 = 0;
+/// Iterator over the BGZF blocks of one gzip input, returned by
+/// [`SingleThreadedTranscoder::blocks`].
+///
+/// Input is read and parsed only as far as the next block needs, so memory use
+/// stays as bounded as with [`Transcoder::transcode`]. After an error, nothing
+/// more is yielded.
+pub struct BlockIterator<R: Read> {
+    producer: BlockProducer<BitReader<BufReader<R>>, TranscodeContext>,
+    writer: BgzfBlockWriter<BlockQueue>,
+    done: bool,
+}
 
-        // Optional index builder
-        let mut index_builder =
-            if self.config.build_index { Some(GziIndexBuilder::new()) } else { None };
+impl<R: Read> BlockIterator<R> {
+    /// Stats for the whole transcode, once every block has been yielded.
+    pub fn stats(&self) -> Option<&TranscodeStats> {
+        self.producer.stats()
+    }
+}
 
-        let mut stats = TranscodeStats::default();
+impl<R: Read> Iterator for BlockIterator<R> {
+    type Item = Result<Vec<u8>>;
 
-        // Main transcoding loop — handles multiple gzip members
+    fn next(&mut self) -> Option<Self::Item> {
         loop {
-            while let Some(deflate_block) = parser.parse_block()? {
-                for token in deflate_block.tokens {
-                    if matches!(token, LZ77Token::EndOfBlock) {
-                        continue;
-                    }
+            if let Some(block) = self.writer.get_mut().pop() {
+                return Some(Ok(block));
+            }
+            if self.done {
+                return None;
+            }
+            match self.producer.step(&mut self.writer) {
+                Ok(more) => self.done = !more,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
 
-                    let token_size = token.uncompressed_size();
-                    splitter.process_token(&token);
-
-                    let should_emit = if use_smart {
-                        let near_target =
-                            pending_uncompressed_size + token_size >= self.config.block_size;
-                        let at_good_split = splitter.is_good_split_point();
-                        let exceeds_max = pending_uncompressed_size + token_size > max_block_size;
-
-                        !pending_tokens.is_empty()
-                            && ((near_target && at_good_split) || exceeds_max)
-                    } else {
-                        pending_uncompressed_size + token_size > self.config.block_size
-                            && !pending_tokens.is_empty()
-                    };
-
-                    if should_emit {
-                        emit_block(
-                            &self.config,
-                            &mut resolver,
-                            &mut encoder,
-                            bgzf_writer,
-                            &pending_tokens,
-                            block_start_position,
-                            &mut stats,
-                            &mut index_builder,
-                        )?;
-
-                        block_start_position = resolver.position();
-                        pending_tokens.clear();
-                        pending_uncompressed_size = 0;
-                        splitter.reset();
-                    }
+/// State of the transcoding loop between DEFLATE blocks, so that its BGZF
+/// blocks can be written all at once or produced as they are requested.
+pub(super) struct BlockProducer<B: BitRead, C: BorrowMut<TranscodeContext>> {
+    config: TranscodeConfig,
+    parser: DeflateParser<B>,
+    context: C,
+    recompressor: Option<Recompressor>,
+    splitter: Box<dyn BlockSplitter>,
+    use_smart: bool,
+    /// Maximum block size with overshoot allowance for smart boundaries
+    max_block_size: usize,
+    /// Optional look-ahead that holds the last full block to absorb a small trailing block
+    tail_merger: Option<TailMerger>,
+    /// Accumulator for current BGZF block, borrowed from the context to reuse its capacity
+    pending_tokens: Vec<LZ77Token>,
+    pending_uncompressed_size: usize,
+    block_start_position: u64,
+    /// Uncompressed bytes parsed so far, where each member's trailer applies
+    parsed_size: u64,
+    /// `parsed_size` where the current member began
+    member_start: u64,
+    /// Offset of this input within a larger one it is a piece of
+    input_start: u64,
+    /// Offset, within the larger input, where the last member read ended
+    last_member_end: u64,
+    /// Fail unless the input ends where a work unit does, as a piece that
+    /// does not end the larger input must
+    whole_units: bool,
+    index_builder: Option<GziIndexBuilder>,
+    coords: Option<CoordinateTracker>,
+    stats: TranscodeStats,
+    deflate_blocks: u64,
+    stored_blocks: u64,
+    /// A token split where a record ends inside it, so a block can end there
+    pieces: Vec<LZ77Token>,
+    /// Whether the input has ended and every block has been written
+    finished: bool,
+}
 
-                    pending_tokens.push(token);
-                    pending_uncompressed_size += token_size;
-                }
+impl<B: BitRead, C: BorrowMut<TranscodeContext>> BlockProducer<B, C> {
+    pub(super) fn new(
+        config: &TranscodeConfig,
+        mut parser: DeflateParser<B>,
+        mut context: C,
+    ) -> Self {
+        parser.set_verify_header_crc(config.verify_input_crc);
+        let ctx = context.borrow_mut();
+        ctx.reset();
+        ctx.encoder.set_use_fixed(config.use_fixed_huffman());
+        ctx.encoder.set_split_subblocks(config.compression_level.use_subblock_splitting());
+        ctx.resolver.set_tolerate_mismatch(config.tolerate_bad_input_trailer);
+        ctx.resolver.set_check_members(config.verify_input_crc);
+        let pending_tokens = std::mem::take(&mut ctx.pending_tokens);
+
+        let use_smart = config.use_smart_boundaries();
+        let splitter = splitter_for(config);
+        let max_block_size = config.max_block_size();
+
+        Self {
+            config: config.clone(),
+            parser,
+            context,
+            recompressor: config.recompress_chain().map(Recompressor::new),
+            splitter,
+            use_smart,
+            max_block_size,
+            tail_merger: config.min_block_size.map(|min| TailMerger::new(min, max_block_size)),
+            pending_tokens,
+            pending_uncompressed_size: 0,
+            block_start_position: 0,
+            parsed_size: 0,
+            member_start: 0,
+            input_start: 0,
+            last_member_end: 0,
+            whole_units: false,
+            index_builder: config.build_index.then(GziIndexBuilder::new),
+            coords: config.build_coordinate_index().then(CoordinateTracker::new),
+            stats: TranscodeStats::default(),
+            deflate_blocks: 0,
+            stored_blocks: 0,
+            pieces: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Parse the next DEFLATE block, or handle the end of a member, writing the
+    /// BGZF blocks that completes. At the end of the input, writes the rest
+    /// (and the EOF block) and returns false.
+    pub(super) fn step<S: BlockSink>(
+        &mut self,
+        bgzf_writer: &mut BgzfBlockWriter<S>,
+    ) -> Result<bool> {
+        if self.finished {
+            return Ok(false);
+        }
+        if let Some(deflate_block) = timed(Phase::Parse, || self.parser.parse_block())? {
+            self.deflate_blocks += 1;
+            self.stored_blocks += (deflate_block.block_type == 0) as u64;
+            self.add_tokens(deflate_block.tokens, bgzf_writer)?;
+            return Ok(true);
+        }
+
+        // End the block at the member boundary so no block spans two members
+        if self.config.block_per_member && !self.pending_tokens.is_empty() {
+            self.emit_pending(bgzf_writer)?;
+        }
+
+        let more_members = self.parser.read_trailer_and_check_next()?;
+        if let Some((crc, isize)) = self.parser.last_trailer() {
+            self.context.borrow_mut().resolver.end_member(self.parsed_size, crc, isize)?;
+            let collect = self.config.collect_member_stats;
+            self.stats.add_member(collect, crc, isize, self.parsed_size - self.member_start);
+            self.member_start = self.parsed_size;
+        }
+        let member_end = self.input_start + self.parser.member_end();
+        let unit_ends = ends_unit(self.last_member_end, member_end);
+        self.last_member_end = member_end;
+        if more_members {
+            if unit_ends {
+                self.end_unit(bgzf_writer)?;
+            }
+            debug!(
+                "gzip member {} starts before input byte {}",
+                self.parser.member_index(),
+                self.parser.bytes_consumed()
+            );
+            return Ok(true);
+        }
+        if self.whole_units && !unit_ends {
+            return Err(Error::Internal(format!(
+                "gzip member ending at byte {} does not end a work unit",
+                member_end
+            )));
+        }
+
+        self.finish(bgzf_writer)?;
+        self.finished = true;
+        Ok(false)
+    }
+
+    /// Treat the input as the piece at `offset` of a larger input, so its
+    /// work units line up with the whole's. Unless the piece `ends_input`,
+    /// it must also end where a work unit does, or the last step fails.
+    pub(super) fn set_piece(&mut self, offset: u64, ends_input: bool) {
+        self.input_start = offset;
+        self.last_member_end = offset;
+        self.whole_units = !ends_input;
+    }
+
+    pub(super) fn parser_mut(&mut self) -> &mut DeflateParser<B> {
+        &mut self.parser
+    }
+
+    /// Stats for the whole transcode, once the last block has been written.
+    pub(super) fn stats(&self) -> Option<&TranscodeStats> {
+        self.finished.then_some(&self.stats)
+    }
+
+    /// Add one DEFLATE block's tokens to the pending block, emitting it
+    /// wherever the splitter ends it.
+    fn add_tokens<S: BlockSink>(
+        &mut self,
+        tokens: Vec<LZ77Token>,
+        bgzf_writer: &mut BgzfBlockWriter<S>,
+    ) -> Result<()> {
+        let mut pieces = std::mem::take(&mut self.pieces);
+        for token in tokens {
+            if matches!(token, LZ77Token::EndOfBlock) {
+                continue;
             }
 
-            stats.input_bytes = parser.bytes_read();
+            // Let the splitter cut the token where a block may need to end inside it
+            let reaches_target = self.pending_uncompressed_size + token.uncompressed_size()
+                >= self.config.block_size;
+            if self.use_smart {
+                self.splitter.split_token(token, reaches_target, &mut pieces);
+            } else {
+                pieces.push(token);
+            }
+            for token in pieces.drain(..) {
+                let token_size = token.uncompressed_size();
+
+                let should_emit = if self.use_smart {
+                    let near_target =
+                        self.pending_uncompressed_size + token_size >= self.config.block_size;
+                    let at_good_split = self.splitter.is_good_split_point();
+                    let exceeds_max =
+                        self.pending_uncompressed_size + token_size > self.max_block_size;
+
+                    !self.pending_tokens.is_empty()
+                        && (self.splitter.must_split()
+                            || (near_target && at_good_split)
+                            || exceeds_max)
+                } else {
+                    self.pending_uncompressed_size + token_size > self.config.block_size
+                        && !self.pending_tokens.is_empty()
+                };
+
+                if should_emit {
+                    // With a tail merger, emit the previously held block instead
+                    if let Some(ref mut merger) = self.tail_merger {
+                        merger.hold(&mut self.pending_tokens);
+                    }
+                    self.emit_pending(bgzf_writer)?;
+                }
 
-            if !parser.read_trailer_and_check_next()? {
-                break;
+                // Update splitter after the split decision so it describes the pending block
+                self.splitter.process_token(&token);
+                self.pending_tokens.push(token);
+                self.pending_uncompressed_size += token_size;
+                self.parsed_size += token_size as u64;
             }
         }
+        self.pieces = pieces;
+        Ok(())
+    }
 
-        // Flush remaining tokens
-        if !pending_tokens.is_empty() {
-            emit_block(
-                &self.config,
-                &mut resolver,
-                &mut encoder,
-                bgzf_writer,
-                &pending_tokens,
-                block_start_position,
-                &mut stats,
-                &mut index_builder,
-            )?;
+    /// Emit the pending tokens (if any) as a block and start a new one.
+    fn emit_pending<S: BlockSink>(&mut self, bgzf_writer: &mut BgzfBlockWriter<S>) -> Result<()> {
+        let tokens = std::mem::take(&mut self.pending_tokens);
+        if !tokens.is_empty() {
+            self.emit(&tokens, bgzf_writer)?;
         }
+        self.pending_tokens = tokens;
+        self.pending_tokens.clear();
+        self.pending_uncompressed_size = 0;
+        self.splitter.reset();
+        Ok(())
+    }
 
-        // Write EOF
-        bgzf_writer.write_eof()?;
-        stats.output_bytes += 28;
+    fn emit<S: BlockSink>(
+        &mut self,
+        tokens: &[LZ77Token],
+        bgzf_writer: &mut BgzfBlockWriter<S>,
+    ) -> Result<()> {
+        let context = self.context.borrow_mut();
+        emit_block(
+            &self.config,
+            &mut context.resolver,
+            &mut context.encoder,
+            self.recompressor.as_mut(),
+            bgzf_writer,
+            tokens,
+            self.block_start_position,
+            &mut self.stats,
+            &mut self.index_builder,
+            &mut self.coords,
+        )?;
+        self.block_start_position = context.resolver.position();
+        Ok(())
+    }
 
-        let (resolved, _preserved) = resolver.stats();
-        stats.boundary_refs_resolved = resolved;
-        stats.index_entries = index_builder.map(|b| b.entries().to_vec());
+    /// Write the pending tokens (plus any held block) as though the input
+    /// ended here, so that block layout does not depend on how the input is
+    /// divided into work units (see [`MEMBER_UNIT_BYTES`]).
+    fn end_unit<S: BlockSink>(&mut self, bgzf_writer: &mut BgzfBlockWriter<S>) -> Result<()> {
+        let pending_tokens = std::mem::take(&mut self.pending_tokens);
+        let tail_blocks = match self.tail_merger {
+            Some(ref mut merger) => merger.finish(pending_tokens),
+            None => vec![pending_tokens],
+        };
+        for tokens in tail_blocks.iter().filter(|t| !t.is_empty()) {
+            self.emit(tokens, bgzf_writer)?;
+        }
 
-        Ok(stats)
+        // Keep the largest token buffer for the next block
+        if let Some(mut tokens) = tail_blocks.into_iter().max_by_key(|t| t.capacity()) {
+            tokens.clear();
+            self.pending_tokens = tokens;
+        }
+        self.pending_uncompressed_size = 0;
+        self.splitter.reset();
+        Ok(())
+    }
+
+    /// Write the remaining blocks and the EOF block, and complete the stats.
+    fn finish<S: BlockSink>(&mut self, bgzf_writer: &mut BgzfBlockWriter<S>) -> Result<()> {
+        self.stats.input_bytes = self.parser.bytes_consumed();
+        self.stats.uncompressed_bytes = self.parsed_size;
+        self.stats.input_was_stored =
+            self.deflate_blocks > 0 && self.stored_blocks == self.deflate_blocks;
+
+        self.end_unit(bgzf_writer)?;
+
+        // Write EOF
+        if self.config.write_eof {
+            bgzf_writer.write_eof()?;
+            bgzf_writer.get_mut().end_block()?;
+            self.stats.output_bytes += BGZF_EOF_SIZE as u64;
+        }
+
+        let context = self.context.borrow_mut();
+        let (resolved, _preserved) = context.resolver.stats();
+        self.stats.boundary_refs_resolved = resolved;
+        self.stats.resolved_bytes = context.resolver.resolved_bytes();
+        self.stats.input_trailer_mismatch = context.resolver.trailer_mismatch();
+        self.stats.index_entries = self.index_builder.take().map(|b| b.entries().to_vec());
+        self.stats.coordinate_index = self
+            .coords
+            .take()
+            .map(|c| c.finish(self.stats.index_entries.as_deref().unwrap_or_default()));
+
+        // Hand the token buffer back to the context for the next transcode
+        context.pending_tokens = std::mem::take(&mut self.pending_tokens);
+        Ok(())
     }
 }
 
 impl Transcoder for SingleThreadedTranscoder {
     fn transcode<R: Read, W: Write>(&mut self, input: R, output: W) -> Result<TranscodeStats> {
+        self.transcode_with_context(input, output, &mut TranscodeContext::new())
+    }
+
+    fn transcode_with_context<R: Read, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        context: &mut TranscodeContext,
+    ) -> Result<TranscodeStats> {
+        self.config.validate()?;
         let mut reader = BufReader::with_capacity(self.config.buffer_size, input);
-        let mut writer = BufWriter::with_capacity(self.config.buffer_size, output);
+        let mut writer = BlockOutput::with_capacity(self.config.buffer_size, output)
+            .hold_below(self.config.single_write_below);
 
         // Parse first gzip header
-        let _gzip_header = GzipHeader::parse(&mut reader)?;
+        let (gzip_header, header_len) =
+            GzipHeader::parse_with_len_checked(&mut reader, self.config.verify_input_crc)?;
 
-        let mut parser = DeflateParser::new(&mut reader);
-        let mut bgzf_writer = BgzfBlockWriter::new(&mut writer);
+        let parser = DeflateParser::with_input_offset(&mut reader, header_len);
+        let mut bgzf_writer =
+            BgzfBlockWriter::with_extra_subfields(&mut writer, &self.config.extra_subfields);
+        bgzf_writer.set_header_fields(block_header_fields(&self.config, &gzip_header));
 
-        let stats = self.transcode_core(&mut parser, &mut bgzf_writer)?;
+        let stats = self.transcode_core(parser, &mut bgzf_writer, context)?;
 
-        let _ = bgzf_writer.finish()?;
+        // Not `bgzf_writer.finish()`: its flush would write the blocks apart
+        // from the EOF block
+        writer.finish()?;
         Ok(stats)
     }
 }
 
-/// Emit a single BGZF block from pending tokens.
+/// Emit a BGZF block from pending tokens.
 /// Uses fused resolve+encode for fixed Huffman (one pass, no intermediate Vec).
+/// Output over the 64KB BGZF limit is re-encoded with `encode_fitting`, which may
+/// emit several blocks.
 #[allow(clippy::too_many_arguments)]
-fn emit_block<W: Write>(
+fn emit_block<S: BlockSink>(
     config: &TranscodeConfig,
     resolver: &mut BoundaryResolver,
     encoder: &mut HuffmanEncoder,
-    bgzf_writer: &mut BgzfBlockWriter<W>,
+    recompressor: Option<&mut Recompressor>,
+    bgzf_writer: &mut BgzfBlockWriter<S>,
     tokens: &[LZ77Token],
     block_start: u64,
     stats: &mut TranscodeStats,
     index_builder: &mut Option<GziIndexBuilder>,
+    coords: &mut Option<CoordinateTracker>,
 ) -> Result<()> {
-    let (deflate_data, crc, uncompressed_size) = if config.use_fixed_huffman() {
+    let max_deflate = MAX_BGZF_BLOCK_SIZE - bgzf_writer.header_size() - BGZF_FOOTER_SIZE;
+
+    let blocks = if config.use_fixed_huffman() {
         // Fused path: resolve + encode in one pass (no intermediate token Vec)
-        resolver.resolve_and_encode_fixed(block_start, tokens, encoder)
+        let mut block = timed(Phase::Encode, || {
+            resolver.resolve_and_encode_fixed(block_start, tokens, encoder)
+        })?;
+        // Match `HuffmanEncoder::encode`, which stores data that fixed codes expand
+        if stored_len(block.2 as usize) < block.0.len() {
+            block.0 = encode_stored(resolver.last_block(), true);
+        }
+        if block.0.len() <= max_deflate {
+            vec![block]
+        } else {
+            // No resolved tokens to re-encode; the decoded bytes stand in as literals
+            debug!("{} byte block over the BGZF limit; re-encoding to fit", block.0.len());
+            let literals: Vec<LZ77Token> =
+                resolver.last_block().iter().map(|&b| LZ77Token::Literal(b)).collect();
+            encoder.encode_fitting(&literals, max_deflate)?
+        }
     } else {
         // Two-pass path: resolve first, then encode (dynamic Huffman needs frequency pass)
-        let (resolved, crc, uncompressed_size) = resolver.resolve_block(block_start, tokens);
-        let deflate_data = encoder.encode(&resolved, true)?;
-        (deflate_data, crc, uncompressed_size)
+        let (resolved, crc, uncompressed_size) =
+            timed(Phase::Resolve, || resolver.resolve_block(block_start, tokens))?;
+        let (resolved, deflate_data) = match recompressor {
+            Some(recompressor) => {
+                let recompressed = timed(Phase::Encode, || {
+                    recompressor.encode_bytes(encoder, resolved, resolver.last_block())
+                })?;
+                stats.recompressed_bytes_saved += recompressed.saved;
+                (recompressed.tokens, recompressed.deflate_data)
+            }
+            None => {
+                let deflate_data = timed(Phase::Encode, || encoder.encode(&resolved, true))?;
+                (resolved, deflate_data)
+            }
+        };
+        if deflate_data.len() <= max_deflate {
+            vec![(deflate_data, crc, uncompressed_size)]
+        } else {
+            debug!("{} byte block over the BGZF limit; re-encoding to fit", deflate_data.len());
+            encoder.encode_fitting(&resolved, max_deflate)?
+        }
     };
+    if let Some(coords) = coords {
+        coords.add_block(resolver.last_block());
+    }
 
-    bgzf_writer.write_block_with_crc(&deflate_data, crc, uncompressed_size)?;
+    let mut cumulative_in = block_start;
+    for (deflate_data, crc, uncompressed_size) in blocks {
+        bgzf_writer.write_block_with_crc(&deflate_data, crc, uncompressed_size)?;
+        bgzf_writer.get_mut().end_block()?;
+
+        let compressed_block_size =
+            (bgzf_writer.header_size() + deflate_data.len() + BGZF_FOOTER_SIZE) as u64;
+        trace!(
+            "BGZF block at offset {}: {} bytes compressed, {} uncompressed",
+            stats.output_bytes,
+            compressed_block_size,
+            uncompressed_size
+        );
 
-    let compressed_block_size = (18 + deflate_data.len() + 8) as u64;
+        if let Some(ref mut builder) = index_builder {
+            builder.add_block(compressed_block_size, uncompressed_size as u64);
+        }
 
-    if let Some(ref mut builder) = index_builder {
-        builder.add_block(compressed_block_size, uncompressed_size as u64);
+        if stats.blocks_written == 0 || uncompressed_size < stats.min_block_uncompressed {
+            stats.min_block_uncompressed = uncompressed_size;
+        }
+        stats.max_block_uncompressed = stats.max_block_uncompressed.max(uncompressed_size);
+        stats.blocks_written += 1;
+        stats.output_bytes += compressed_block_size;
+
+        cumulative_in += uncompressed_size as u64;
+        if let Some(on_block) = &config.on_block {
+            on_block.call(BlockProgress {
+                block_id: stats.blocks_written - 1,
+                compressed_size: compressed_block_size as u32,
+                uncompressed_size,
+                cumulative_in,
+                cumulative_out: stats.output_bytes,
+            });
+        }
     }
 
-    stats.blocks_written += 1;
-    stats.output_bytes += compressed_block_size;
-
     Ok(())
 }
 
+/// Header fields for output data blocks, taken from the input's first gzip
+/// header as `config` asks
+pub(crate) fn block_header_fields(config: &TranscodeConfig, header: &GzipHeader) -> HeaderFields {
+    let mut fields =
+        HeaderFields { text: config.preserve_ftext && header.is_text(), ..Default::default() };
+    if config.preserve_mtime {
+        fields.mtime = header.mtime;
+        fields.os = header.os;
+    }
+    fields
+}
+
 /// Parse a gzip header from raw bytes and return the byte offset where DEFLATE data starts.
 pub fn parse_gzip_header_size(data: &[u8]) -> Result<usize> {
-    use crate::error::Error;
-
     if data.len() < 10 {
         return Err(Error::UnexpectedEof);
     }
@@ -222,7 +632,7 @@ pub fn parse_gzip_header_size(data: &[u8]) -> Result<usize> {
     }
 
     if data[2] != 8 {
-        return Err(Error::UnsupportedCompressionMethod(data[2]));
+        return Err(Error::UnsupportedCompressionMethod { method: data[2], member: 0, offset: 0 });
     }
 
     let flags = data[3];
@@ -378,4 +788,87 @@ mod tests {
             "Slice and stream transcoding should produce identical output"
         );
     }
+
+    #[test]
+    fn test_blocks_match_transcode() {
+        use std::io::Write as IoWrite;
+
+        let mut test_data = Vec::new();
+        for i in 0..20_000 {
+            test_data.extend_from_slice(format!("Line {} ABCDEFGHIJKLMNOP\n", i).as_bytes());
+        }
+        let mut gzip_data = Vec::new();
+        for chunk in test_data.chunks(200_000) {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(chunk).unwrap();
+            gzip_data.extend(encoder.finish().unwrap());
+        }
+
+        let config = TranscodeConfig { build_index: true, ..Default::default() };
+        let mut output = Vec::new();
+        let expected_stats = SingleThreadedTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut output)
+            .unwrap();
+
+        let mut blocks =
+            SingleThreadedTranscoder::new(config).blocks(Cursor::new(&gzip_data)).unwrap();
+        let mut yielded = Vec::new();
+        for block in blocks.by_ref() {
+            let block = block.unwrap();
+            assert_eq!(&block[12..14], b"BC");
+            yielded.push(block);
+        }
+        assert_eq!(yielded.concat(), output);
+        assert_eq!(yielded.last().unwrap(), &crate::bgzf::BGZF_EOF);
+        assert_eq!(yielded.len() as u64, expected_stats.blocks_written + 1);
+
+        let stats = blocks.stats().unwrap();
+        assert_eq!(stats.output_bytes, output.len() as u64);
+        assert_eq!(stats.input_bytes, gzip_data.len() as u64);
+        assert_eq!(stats.index_entries, expected_stats.index_entries);
+    }
+
+    #[test]
+    fn test_blocks_stop_after_error() {
+        use std::io::Write as IoWrite;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[b'A'; 10_000]).unwrap();
+        let mut gzip_data = encoder.finish().unwrap();
+        gzip_data.truncate(gzip_data.len() - 4);
+
+        let transcoder = SingleThreadedTranscoder::new(TranscodeConfig::default());
+        let mut blocks = transcoder.blocks(Cursor::new(&gzip_data)).unwrap();
+        assert!(blocks.by_ref().any(|block| block.is_err()));
+        assert!(blocks.next().is_none());
+        assert!(blocks.stats().is_none());
+
+        assert!(transcoder.blocks(Cursor::new(b"not gzip")).is_err());
+    }
+
+    #[test]
+    fn test_member_over_4gib_does_not_wrap() {
+        use std::io::Write as IoWrite;
+
+        let data = b"ACGT\n".repeat(1000);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        let gzip_data = encoder.finish().unwrap();
+
+        // Pretend the member already decoded to 4 GiB, which its ISIZE wraps back to 0
+        let config = TranscodeConfig { collect_member_stats: true, ..Default::default() };
+        let transcoder = SingleThreadedTranscoder::new(config);
+        let mut blocks = transcoder.blocks(Cursor::new(&gzip_data)).unwrap();
+        blocks.producer.parsed_size = 1 << 32;
+        for block in blocks.by_ref() {
+            block.unwrap();
+        }
+
+        let stats = blocks.stats().unwrap();
+        let expected = (1u64 << 32) + data.len() as u64;
+        assert_eq!(stats.uncompressed_bytes, expected);
+        assert_eq!(stats.members[0].uncompressed_bytes, expected);
+        assert_eq!(stats.members[0].isize, data.len() as u32);
+    }
 }
@@ -1,4 +1,6 @@
+use super::window::SlidingWindow;
 use crate::deflate::tokens::LZ77Token;
+use crate::{FormatProfile, TranscodeConfig};
 
 /// Trait for determining optimal BGZF block split points.
 ///
@@ -19,6 +21,44 @@ pub trait BlockSplitter {
 
     /// Reset state for a new block.
     fn reset(&mut self);
+
+    /// Whether the current position must end a block, whatever its size.
+    ///
+    /// Used for structural boundaries such as the end of a file header.
+    fn must_split(&self) -> bool {
+        false
+    }
+
+    /// Append `token` to `out`, split into pieces wherever a block may need to
+    /// end inside it. Called for every token, before `process_token`.
+    ///
+    /// `near_target` is whether the pending block reaches the target size with
+    /// this token, which is when ordinary good split points matter; points where
+    /// [`must_split`](Self::must_split) holds apply regardless. Only splitters
+    /// that see the bytes a Copy produces can find such points; the default
+    /// appends the token whole.
+    fn split_token(&mut self, token: LZ77Token, near_target: bool, out: &mut Vec<LZ77Token>) {
+        let _ = near_target;
+        out.push(token);
+    }
+}
+
+/// The splitter for `config`: record-aware for formats that have one when smart
+/// boundaries are on, [`DefaultSplitter`] otherwise. FASTQ uses
+/// [`FastqByteSplitter`], which also finds record ends produced by Copy tokens.
+pub(crate) fn splitter_for(config: &TranscodeConfig) -> Box<dyn BlockSplitter> {
+    if !config.use_smart_boundaries() {
+        return Box::new(DefaultSplitter);
+    }
+    match config.format {
+        FormatProfile::Fastq => Box::new(FastqByteSplitter::new()),
+        FormatProfile::Vcf => Box::new(VcfSplitter::new()),
+        FormatProfile::Lines { per_record } => Box::new(LineSplitter::new(per_record)),
+        FormatProfile::FixedRecords { record_size } => {
+            Box::new(FixedRecordSplitter::new(record_size))
+        }
+        _ => Box::new(DefaultSplitter),
+    }
 }
 
 /// Default splitter that considers every position a good split point.
@@ -119,30 +159,57 @@ impl BlockSplitter for FastqSplitter {
     }
 }
 
-/// FASTQ-aware splitter that uses the uncompressed data from boundary resolution.
+/// Line-aware splitter that works on the uncompressed bytes.
 ///
-/// This is more accurate than FastqSplitter because it sees the actual
-/// uncompressed bytes after Copy tokens are resolved.
-pub struct FastqByteSplitter {
-    /// Count of newlines seen (mod 4)
-    newline_count: u8,
+/// Every `lines_per_record`-th newline ends a record, and the position right
+/// after it is a good split point; with one line per record, any line end is.
+/// Suits line-oriented text such as VCF, BED, or TSV, where tabix-style tools
+/// want blocks that do not cut lines.
+///
+/// Unlike a token-only splitter it sees the bytes that Copy tokens produce, so
+/// record ends inside back-references are found too. As a [`BlockSplitter`] it
+/// expands each token against its own copy of the last 32KB of output; bytes
+/// can also be fed directly with `process_bytes`.
+pub struct LineSplitter {
+    /// Lines making up one record
+    lines_per_record: usize,
+    /// Count of newlines seen (mod `lines_per_record`)
+    newline_count: usize,
     /// Bytes processed since last record boundary
     bytes_since_record_end: usize,
     /// Whether we're at a record boundary
     at_record_boundary: bool,
+    /// Recent bytes, for expanding Copy tokens
+    window: SlidingWindow,
+    /// Bytes of the Copy token being processed, reused between tokens
+    copied: Vec<u8>,
 }
 
-impl FastqByteSplitter {
-    pub fn new() -> Self {
-        Self { newline_count: 0, bytes_since_record_end: 0, at_record_boundary: true }
+impl LineSplitter {
+    /// Create a splitter treating every `lines_per_record` lines as one record.
+    ///
+    /// # Panics
+    ///
+    /// If `lines_per_record` is zero.
+    pub fn new(lines_per_record: usize) -> Self {
+        assert!(lines_per_record > 0, "lines_per_record must be at least 1");
+        Self {
+            lines_per_record,
+            newline_count: 0,
+            bytes_since_record_end: 0,
+            at_record_boundary: true,
+            window: SlidingWindow::new(),
+            copied: Vec::new(),
+        }
     }
 
     /// Process raw bytes (called with uncompressed data)
     pub fn process_bytes(&mut self, bytes: &[u8]) {
+        self.window.push_bytes(bytes);
         for &byte in bytes {
             self.bytes_since_record_end += 1;
             if byte == b'\n' {
-                self.newline_count = (self.newline_count + 1) % 4;
+                self.newline_count = (self.newline_count + 1) % self.lines_per_record;
                 if self.newline_count == 0 {
                     self.at_record_boundary = true;
                     self.bytes_since_record_end = 0;
@@ -166,12 +233,341 @@ impl FastqByteSplitter {
     }
 }
 
+impl BlockSplitter for LineSplitter {
+    fn process_token(&mut self, token: &LZ77Token) {
+        match *token {
+            LZ77Token::Literal(byte) => self.process_bytes(&[byte]),
+            LZ77Token::Copy { length, distance } => {
+                let mut copied = std::mem::take(&mut self.copied);
+                copied.clear();
+                if self.window.copy_to_vec(distance, length, &mut copied).is_ok() {
+                    self.process_bytes(&copied);
+                } else {
+                    // Reaches before the start of the stream; boundary resolution
+                    // reports the error, so only keep the byte count right
+                    self.bytes_since_record_end += length as usize;
+                    self.at_record_boundary = false;
+                }
+                self.copied = copied;
+            }
+            LZ77Token::EndOfBlock => {}
+        }
+    }
+
+    fn is_good_split_point(&self) -> bool {
+        LineSplitter::is_good_split_point(self)
+    }
+
+    fn bytes_since_last_good_split(&self) -> usize {
+        LineSplitter::bytes_since_last_good_split(self)
+    }
+
+    fn reset(&mut self) {
+        // As with FastqSplitter, records and the window span blocks
+        self.bytes_since_record_end = 0;
+    }
+
+    fn split_token(&mut self, token: LZ77Token, near_target: bool, out: &mut Vec<LZ77Token>) {
+        let (LZ77Token::Copy { length, distance }, true) = (token, near_target) else {
+            out.push(token);
+            return;
+        };
+        let mut copied = std::mem::take(&mut self.copied);
+        copied.clear();
+        if self.window.copy_to_vec(distance, length, &mut copied).is_err() {
+            out.push(token);
+            self.copied = copied;
+            return;
+        }
+
+        // Cut after each record end; a copy continues at the same distance, and
+        // pieces too short for a DEFLATE copy become literals
+        let mut newline_count = self.newline_count;
+        let mut start = 0;
+        for (i, &byte) in copied.iter().enumerate() {
+            if byte == b'\n' {
+                newline_count = (newline_count + 1) % self.lines_per_record;
+                if newline_count == 0 && i + 1 < copied.len() {
+                    push_copy_piece(&copied[start..i + 1], distance, out);
+                    start = i + 1;
+                }
+            }
+        }
+        push_copy_piece(&copied[start..], distance, out);
+        self.copied = copied;
+    }
+}
+
+/// FASTQ-aware splitter that works on the uncompressed bytes: a
+/// [`LineSplitter`] with four-line records.
+///
+/// This is more accurate than FastqSplitter because it sees the bytes that Copy
+/// tokens produce, so record ends inside back-references are found too.
+pub struct FastqByteSplitter {
+    lines: LineSplitter,
+}
+
+impl FastqByteSplitter {
+    pub fn new() -> Self {
+        Self { lines: LineSplitter::new(4) }
+    }
+
+    /// Process raw bytes (called with uncompressed data)
+    pub fn process_bytes(&mut self, bytes: &[u8]) {
+        self.lines.process_bytes(bytes);
+    }
+
+    /// Check if at a good split point
+    pub fn is_good_split_point(&self) -> bool {
+        self.lines.is_good_split_point()
+    }
+
+    /// Bytes since last good split
+    pub fn bytes_since_last_good_split(&self) -> usize {
+        self.lines.bytes_since_last_good_split()
+    }
+}
+
 impl Default for FastqByteSplitter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+impl BlockSplitter for FastqByteSplitter {
+    fn process_token(&mut self, token: &LZ77Token) {
+        self.lines.process_token(token);
+    }
+
+    fn is_good_split_point(&self) -> bool {
+        self.lines.is_good_split_point()
+    }
+
+    fn bytes_since_last_good_split(&self) -> usize {
+        self.lines.bytes_since_last_good_split()
+    }
+
+    fn reset(&mut self) {
+        self.lines.reset();
+    }
+
+    fn split_token(&mut self, token: LZ77Token, near_target: bool, out: &mut Vec<LZ77Token>) {
+        self.lines.split_token(token, near_target, out);
+    }
+}
+
+/// VCF-aware splitter that gives the header its own leading block(s).
+///
+/// The position before the first data line (the first line not starting with
+/// `#`) must end a block, so tools can read the `##` meta lines and the
+/// `#CHROM` line without touching the body. A header larger than a block is
+/// split at line boundaries like any other text. Everywhere else this behaves
+/// as a [`LineSplitter`] with one line per record.
+///
+/// The header's end is found by `split_token`, which must see every token
+/// before it is processed.
+pub struct VcfSplitter {
+    lines: LineSplitter,
+    /// Uncompressed bytes processed
+    position: u64,
+    /// Where the first data line starts, once seen
+    header_end: Option<u64>,
+}
+
+impl VcfSplitter {
+    pub fn new() -> Self {
+        Self { lines: LineSplitter::new(1), position: 0, header_end: None }
+    }
+}
+
+impl Default for VcfSplitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockSplitter for VcfSplitter {
+    fn process_token(&mut self, token: &LZ77Token) {
+        self.lines.process_token(token);
+        self.position += token.uncompressed_size() as u64;
+    }
+
+    fn is_good_split_point(&self) -> bool {
+        self.lines.is_good_split_point()
+    }
+
+    fn bytes_since_last_good_split(&self) -> usize {
+        self.lines.bytes_since_last_good_split()
+    }
+
+    fn reset(&mut self) {
+        self.lines.reset();
+    }
+
+    fn must_split(&self) -> bool {
+        self.header_end == Some(self.position)
+    }
+
+    fn split_token(&mut self, token: LZ77Token, near_target: bool, out: &mut Vec<LZ77Token>) {
+        // Every header line starts with '#', so a copy from a header-only window
+        // cannot hold a newline followed by anything else: the first data line
+        // starts with the token that brings a non-'#' byte to a line start
+        if self.header_end.is_none() && self.lines.is_good_split_point() {
+            let first = match token {
+                LZ77Token::Literal(byte) => Some(byte),
+                LZ77Token::Copy { distance, .. } => {
+                    self.lines.window.get(distance, 1).ok().map(|bytes| bytes[0])
+                }
+                LZ77Token::EndOfBlock => None,
+            };
+            if first.is_some_and(|byte| byte != b'#') {
+                self.header_end = Some(self.position);
+            }
+        }
+        self.lines.split_token(token, near_target, out);
+    }
+}
+
+/// Splitter for fixed-width binary records: good split points are where the
+/// uncompressed byte count is a multiple of `record_size`.
+///
+/// Only token sizes are needed, so unlike [`LineSplitter`] no window is kept.
+/// Copies are cut at record ends too, except within two bytes of either end of
+/// the copy, where a piece would be too short to stay a copy.
+pub struct FixedRecordSplitter {
+    record_size: usize,
+    /// Bytes into the current record
+    record_offset: usize,
+    /// Bytes processed since last record boundary
+    bytes_since_record_end: usize,
+}
+
+impl FixedRecordSplitter {
+    /// Create a splitter for records of `record_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `record_size` is zero.
+    pub fn new(record_size: usize) -> Self {
+        assert!(record_size > 0, "record_size must be at least 1");
+        Self { record_size, record_offset: 0, bytes_since_record_end: 0 }
+    }
+}
+
+impl BlockSplitter for FixedRecordSplitter {
+    fn process_token(&mut self, token: &LZ77Token) {
+        let size = token.uncompressed_size();
+        if size >= self.record_size - self.record_offset {
+            // A record ends within the token
+            self.record_offset = (self.record_offset + size) % self.record_size;
+            self.bytes_since_record_end = self.record_offset;
+        } else {
+            self.record_offset += size;
+            self.bytes_since_record_end += size;
+        }
+    }
+
+    fn is_good_split_point(&self) -> bool {
+        self.record_offset == 0
+    }
+
+    fn bytes_since_last_good_split(&self) -> usize {
+        self.bytes_since_record_end
+    }
+
+    fn reset(&mut self) {
+        self.bytes_since_record_end = 0;
+    }
+
+    fn split_token(&mut self, token: LZ77Token, near_target: bool, out: &mut Vec<LZ77Token>) {
+        let (LZ77Token::Copy { length, distance }, true) = (token, near_target) else {
+            out.push(token);
+            return;
+        };
+        // Cut at each record end; a copy continues at the same distance
+        let length = length as usize;
+        let mut start = 0;
+        let mut end = self.record_size - self.record_offset;
+        while end < length {
+            if end - start >= 3 && length - end >= 3 {
+                out.push(LZ77Token::Copy { length: (end - start) as u16, distance });
+                start = end;
+            }
+            end += self.record_size;
+        }
+        out.push(LZ77Token::Copy { length: (length - start) as u16, distance });
+    }
+}
+
+/// Append part of a Copy token's output: as a Copy at `distance` when long
+/// enough to encode, otherwise as literals.
+fn push_copy_piece(bytes: &[u8], distance: u16, out: &mut Vec<LZ77Token>) {
+    if bytes.len() >= 3 {
+        out.push(LZ77Token::Copy { length: bytes.len() as u16, distance });
+    } else {
+        out.extend(bytes.iter().map(|&b| LZ77Token::Literal(b)));
+    }
+}
+
+/// Holds back the most recent full block so a small trailing block can be merged into it.
+///
+/// Blocks are handed to [`TailMerger::hold`] in order, and each call releases the previously
+/// held block for emission. At end of stream, [`TailMerger::finish`] combines the held block
+/// with the leftover tokens when the leftover is smaller than `min_block_size`, splitting the
+/// combined tokens in half if they would exceed `max_block_size`. The rebalanced split ignores
+/// record boundaries, so the last one or two blocks trade alignment and exact size targets for
+/// avoiding a tiny final block.
+pub(crate) struct TailMerger {
+    min_block_size: usize,
+    max_block_size: usize,
+    held: Vec<LZ77Token>,
+}
+
+impl TailMerger {
+    pub(crate) fn new(min_block_size: usize, max_block_size: usize) -> Self {
+        Self { min_block_size, max_block_size, held: Vec::new() }
+    }
+
+    /// Hold `block` and leave the previously held block (possibly empty) in its place.
+    ///
+    /// Swapping buffers keeps both allocations alive across blocks.
+    pub(crate) fn hold(&mut self, block: &mut Vec<LZ77Token>) {
+        std::mem::swap(&mut self.held, block);
+    }
+
+    /// Release the held block and the trailing tokens as the final blocks to emit.
+    pub(crate) fn finish(&mut self, trailing: Vec<LZ77Token>) -> Vec<Vec<LZ77Token>> {
+        let held = std::mem::take(&mut self.held);
+        let trailing_size = tokens_size(&trailing);
+        if held.is_empty() || trailing.is_empty() || trailing_size >= self.min_block_size {
+            return [held, trailing].into_iter().filter(|b| !b.is_empty()).collect();
+        }
+
+        let mut combined = held;
+        combined.extend(trailing);
+        let total = tokens_size(&combined);
+        if total <= self.max_block_size {
+            return vec![combined];
+        }
+
+        // Too large for one block: split at the first token boundary past the midpoint
+        let mut size = 0;
+        let split = combined
+            .iter()
+            .position(|t| {
+                size += t.uncompressed_size();
+                size >= total / 2
+            })
+            .map_or(combined.len(), |i| i + 1);
+        let second = combined.split_off(split);
+        vec![combined, second]
+    }
+}
+
+fn tokens_size(tokens: &[LZ77Token]) -> usize {
+    tokens.iter().map(|t| t.uncompressed_size()).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +630,189 @@ mod tests {
         assert!(!splitter.is_good_split_point());
         assert!(splitter.bytes_since_last_good_split() > 0);
     }
+
+    #[test]
+    fn test_fastq_byte_splitter_sees_copied_newlines() {
+        let record = b"@r\nACGT\n+\nIIII\n";
+        let mut splitter = FastqByteSplitter::new();
+        for &b in record {
+            splitter.process_token(&LZ77Token::Literal(b));
+        }
+        assert!(splitter.is_good_split_point());
+
+        // A Copy repeating the whole record ends at a record boundary
+        let copy = LZ77Token::Copy { length: record.len() as u16, distance: record.len() as u16 };
+        splitter.process_token(&copy);
+        assert!(splitter.is_good_split_point());
+
+        // One ending mid-record does not; the token-only splitter cannot tell either apart
+        let copy = LZ77Token::Copy { length: 5, distance: record.len() as u16 };
+        splitter.process_token(&copy);
+        assert!(!splitter.is_good_split_point());
+        assert_eq!(splitter.bytes_since_last_good_split(), 5);
+    }
+
+    #[test]
+    fn test_fastq_byte_splitter_split_token() {
+        let record = b"@r\nACGT\n+\nIIII\n";
+        let mut splitter = FastqByteSplitter::new();
+        splitter.process_bytes(record);
+
+        // Two records' worth cut after the first, at the same distance
+        let mut pieces = Vec::new();
+        splitter.split_token(LZ77Token::Copy { length: 30, distance: 15 }, true, &mut pieces);
+        assert_eq!(
+            pieces,
+            [
+                LZ77Token::Copy { length: 15, distance: 15 },
+                LZ77Token::Copy { length: 15, distance: 15 }
+            ]
+        );
+
+        // Pieces too short to copy become literals; the piece with no record end stays whole
+        pieces.clear();
+        splitter.split_token(LZ77Token::Copy { length: 17, distance: 15 }, true, &mut pieces);
+        assert_eq!(
+            pieces,
+            [
+                LZ77Token::Copy { length: 15, distance: 15 },
+                LZ77Token::Literal(b'@'),
+                LZ77Token::Literal(b'r'),
+            ]
+        );
+        pieces.clear();
+        splitter.split_token(LZ77Token::Copy { length: 10, distance: 15 }, true, &mut pieces);
+        assert_eq!(pieces, [LZ77Token::Copy { length: 10, distance: 15 }]);
+
+        // Other splitters keep tokens whole
+        pieces.clear();
+        FastqSplitter::new().split_token(
+            LZ77Token::Copy { length: 32, distance: 16 },
+            true,
+            &mut pieces,
+        );
+        assert_eq!(pieces.len(), 1);
+    }
+
+    #[test]
+    fn test_line_splitter() {
+        let mut splitter = LineSplitter::new(1);
+        splitter.process_bytes(b"chr1\t100\n");
+        assert!(splitter.is_good_split_point());
+        splitter.process_bytes(b"chr1\t2");
+        assert!(!splitter.is_good_split_point());
+        assert_eq!(splitter.bytes_since_last_good_split(), 6);
+
+        // Grouped lines: only every second newline ends a record, also inside copies
+        let mut splitter = LineSplitter::new(2);
+        splitter.process_bytes(b"a\nb\n");
+        assert!(splitter.is_good_split_point());
+        splitter.process_token(&LZ77Token::Copy { length: 2, distance: 4 });
+        assert!(!splitter.is_good_split_point());
+        let mut pieces = Vec::new();
+        splitter.split_token(LZ77Token::Copy { length: 6, distance: 4 }, true, &mut pieces);
+        // "b\na\nb\n": the record ends after "b\n", which is too short to stay a copy
+        assert_eq!(
+            pieces,
+            [
+                LZ77Token::Literal(b'b'),
+                LZ77Token::Literal(b'\n'),
+                LZ77Token::Copy { length: 4, distance: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vcf_splitter_ends_block_after_header() {
+        let mut splitter = VcfSplitter::new();
+        let mut pieces = Vec::new();
+        for &byte in b"##fileformat=VCFv4.2\n#CHROM\tPOS\n" {
+            splitter.split_token(LZ77Token::Literal(byte), false, &mut pieces);
+            assert!(!splitter.must_split());
+            splitter.process_token(&pieces.pop().unwrap());
+        }
+
+        // A copy of "POS\n" starts the first data line: the block ends before it
+        let copy = LZ77Token::Copy { length: 4, distance: 4 };
+        splitter.split_token(copy, false, &mut pieces);
+        assert_eq!(pieces, [copy]);
+        assert!(splitter.must_split());
+        splitter.reset();
+        splitter.process_token(&copy);
+        assert!(!splitter.must_split());
+
+        // Later lines starting with '#' or any byte do not end blocks early
+        splitter.split_token(LZ77Token::Literal(b'#'), false, &mut pieces);
+        assert!(!splitter.must_split());
+    }
+
+    #[test]
+    fn test_fixed_record_splitter() {
+        let mut splitter = FixedRecordSplitter::new(100);
+        assert!(splitter.is_good_split_point());
+        splitter.process_token(&LZ77Token::Copy { length: 90, distance: 1 });
+        assert!(!splitter.is_good_split_point());
+
+        // Cut at 10 and 110 bytes in; the 2 bytes after 210 stay with the last piece
+        let mut pieces = Vec::new();
+        splitter.split_token(LZ77Token::Copy { length: 212, distance: 5 }, true, &mut pieces);
+        let lengths: Vec<usize> = pieces.iter().map(|t| t.uncompressed_size()).collect();
+        assert_eq!(lengths, [10, 100, 102]);
+        splitter.process_token(&pieces[0]);
+        assert!(splitter.is_good_split_point());
+        splitter.process_token(&pieces[1]);
+        assert!(splitter.is_good_split_point());
+        splitter.process_token(&pieces[2]);
+        assert_eq!(splitter.bytes_since_last_good_split(), 2);
+
+        // Away from the target size, copies stay whole
+        splitter.split_token(LZ77Token::Copy { length: 200, distance: 5 }, false, &mut pieces);
+        assert_eq!(pieces.last(), Some(&LZ77Token::Copy { length: 200, distance: 5 }));
+    }
+
+    fn literals(n: usize) -> Vec<LZ77Token> {
+        vec![LZ77Token::Literal(b'A'); n]
+    }
+
+    #[test]
+    fn test_tail_merger_releases_previous_block() {
+        let mut merger = TailMerger::new(100, 1000);
+
+        let mut block = literals(900);
+        merger.hold(&mut block);
+        assert!(block.is_empty());
+
+        let mut block = literals(800);
+        merger.hold(&mut block);
+        assert_eq!(block.len(), 900);
+
+        let tail = merger.finish(literals(500));
+        assert_eq!(tail.iter().map(Vec::len).collect::<Vec<_>>(), vec![800, 500]);
+    }
+
+    #[test]
+    fn test_tail_merger_merges_small_trailing_block() {
+        let mut merger = TailMerger::new(100, 1000);
+        merger.hold(&mut literals(900));
+
+        let tail = merger.finish(literals(50));
+        assert_eq!(tail.iter().map(Vec::len).collect::<Vec<_>>(), vec![950]);
+    }
+
+    #[test]
+    fn test_tail_merger_rebalances_oversized_merge() {
+        let mut merger = TailMerger::new(100, 1000);
+        merger.hold(&mut literals(1000));
+
+        let tail = merger.finish(literals(10));
+        assert_eq!(tail.iter().map(Vec::len).collect::<Vec<_>>(), vec![505, 505]);
+    }
+
+    #[test]
+    fn test_tail_merger_without_held_block() {
+        let mut merger = TailMerger::new(100, 1000);
+        let tail = merger.finish(literals(10));
+        assert_eq!(tail.iter().map(Vec::len).collect::<Vec<_>>(), vec![10]);
+        assert!(merger.finish(Vec::new()).is_empty());
+    }
 }
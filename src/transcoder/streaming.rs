@@ -0,0 +1,329 @@
+//! Push-based transcoding of gzip input that arrives in chunks.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::context::TranscodeContext;
+use super::output::BlockQueue;
+use super::single::{block_header_fields, BlockProducer};
+use crate::bgzf::BgzfBlockWriter;
+use crate::bits::BitReader;
+use crate::deflate::DeflateParser;
+use crate::error::{Error, Result};
+use crate::gzip::GzipHeader;
+use crate::{TranscodeConfig, TranscodeStats};
+
+/// Transcoder that is handed input as it arrives instead of reading it, for
+/// callers (e.g. async servers) that receive gzip data in arbitrary chunks
+/// and have no blocking `Read` to give [`SingleThreadedTranscoder`].
+///
+/// Each [`feed`](Self::feed) returns the BGZF blocks the input so far
+/// completes, and [`finish`](Self::finish) returns the rest and the EOF block.
+/// Concatenated, they equal what [`SingleThreadedTranscoder`] writes for the
+/// same input and config.
+///
+/// Input is kept from the start of the DEFLATE block being parsed. A block
+/// that runs out of input is parsed again from its start once the input
+/// after it has doubled, so small chunks do not make parsing quadratic.
+///
+/// [`SingleThreadedTranscoder`]: crate::SingleThreadedTranscoder
+pub struct StreamingTranscoder {
+    config: TranscodeConfig,
+    state: State,
+}
+
+enum State {
+    /// Input so far, not yet holding the whole gzip header
+    Header(Vec<u8>),
+    Transcoding(Box<Active>),
+    /// An error was returned; no further input is accepted
+    Failed,
+}
+
+struct Active {
+    producer: BlockProducer<BitReader<ChunkSource>, TranscodeContext>,
+    writer: BgzfBlockWriter<BlockQueue>,
+    /// Input end offset to wait for before parsing again after running out
+    retry_at: u64,
+}
+
+impl StreamingTranscoder {
+    pub fn new(config: TranscodeConfig) -> Self {
+        Self { config, state: State::Header(Vec::new()) }
+    }
+
+    /// Add the next chunk of gzip input, returning the BGZF blocks it completes
+    /// (possibly none).
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match &mut self.state {
+            State::Header(input) => {
+                input.extend_from_slice(bytes);
+                let input = std::mem::take(input);
+                self.start(input, false)?;
+            }
+            State::Transcoding(active) => {
+                if active.producer.stats().is_some() {
+                    return Err(Error::Internal("StreamingTranscoder fed after finish".into()));
+                }
+                active.producer.parser_mut().get_mut().get_mut().push(bytes);
+            }
+            State::Failed => {
+                return Err(Error::Internal("StreamingTranscoder fed after an error".into()));
+            }
+        }
+        self.run()
+    }
+
+    /// End the input, returning the remaining BGZF blocks and the EOF block
+    /// (with `write_eof`).
+    ///
+    /// Fails if the input ends inside a gzip member.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        match &mut self.state {
+            State::Header(input) => {
+                let input = std::mem::take(input);
+                self.start(input, true)?;
+            }
+            State::Transcoding(active) => {
+                active.producer.parser_mut().get_mut().get_mut().close();
+            }
+            State::Failed => {
+                return Err(Error::Internal("StreamingTranscoder finished after an error".into()));
+            }
+        }
+        let output = self.run()?;
+        match &self.state {
+            State::Transcoding(active) if active.producer.stats().is_some() => Ok(output),
+            _ => Err(Error::Internal("StreamingTranscoder did not reach the end of input".into())),
+        }
+    }
+
+    /// Stats for the whole transcode, once [`finish`](Self::finish) has succeeded.
+    pub fn stats(&self) -> Option<&TranscodeStats> {
+        match &self.state {
+            State::Transcoding(active) => active.producer.stats(),
+            _ => None,
+        }
+    }
+
+    /// Start transcoding once `input` holds the whole gzip header; until then,
+    /// keep waiting for more unless the input has ended.
+    fn start(&mut self, input: Vec<u8>, closed: bool) -> Result<()> {
+        let verify_crc = self.config.verify_input_crc;
+        let result = self
+            .config
+            .validate()
+            .and_then(|()| GzipHeader::parse_with_len_checked(&mut &input[..], verify_crc));
+        let (gzip_header, header_len) = match result {
+            Ok(parsed) => parsed,
+            Err(Error::UnexpectedEof) if !closed => {
+                self.state = State::Header(input);
+                return Ok(());
+            }
+            Err(e) => {
+                self.state = State::Failed;
+                return Err(e);
+            }
+        };
+
+        let mut source = ChunkSource::new(input);
+        if closed {
+            source.close();
+        }
+        let parser = DeflateParser::at_bit_offset(source, header_len * 8)?;
+        let mut writer = BgzfBlockWriter::with_extra_subfields(
+            BlockQueue::default(),
+            &self.config.extra_subfields,
+        );
+        writer.set_header_fields(block_header_fields(&self.config, &gzip_header));
+        let producer = BlockProducer::new(&self.config, parser, TranscodeContext::new());
+        self.state = State::Transcoding(Box::new(Active { producer, writer, retry_at: 0 }));
+        Ok(())
+    }
+
+    /// Parse as far as the input allows, returning the blocks completed.
+    fn run(&mut self) -> Result<Vec<u8>> {
+        let State::Transcoding(active) = &mut self.state else {
+            return Ok(Vec::new());
+        };
+        if let Err(e) = active.run() {
+            self.state = State::Failed;
+            return Err(e);
+        }
+
+        let mut output = Vec::new();
+        while let Some(block) = active.writer.get_mut().pop() {
+            output.extend_from_slice(&block);
+        }
+        Ok(output)
+    }
+}
+
+impl Active {
+    fn run(&mut self) -> Result<()> {
+        let source = self.producer.parser_mut().get_mut().get_mut();
+        if !source.closed && source.end() < self.retry_at {
+            return Ok(());
+        }
+
+        loop {
+            let boundary = self.producer.parser_mut().bit_position();
+            match self.producer.step(&mut self.writer) {
+                Ok(true) => {
+                    let parser = self.producer.parser_mut();
+                    let consumed = parser.bit_position() / 8;
+                    parser.get_mut().get_mut().discard_before(consumed);
+                }
+                Ok(false) => return Ok(()),
+                Err(Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => {
+                    let parser = self.producer.parser_mut();
+                    parser.rewind(boundary)?;
+                    let end = parser.get_mut().get_mut().end();
+                    self.retry_at = 2 * end - boundary / 8;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Input received so far, read by the parser at absolute input offsets.
+///
+/// Reading past the end fails with `WouldBlock` until the input is closed,
+/// and then reports end of input.
+struct ChunkSource {
+    data: Vec<u8>,
+    /// Input offset of `data[0]`
+    base: u64,
+    /// Input offset before which data is no longer needed
+    discard: u64,
+    pos: u64,
+    closed: bool,
+}
+
+impl ChunkSource {
+    fn new(data: Vec<u8>) -> Self {
+        Self { data, base: 0, discard: 0, pos: 0, closed: false }
+    }
+
+    /// Offset of the end of the input received so far
+    fn end(&self) -> u64 {
+        self.base + self.data.len() as u64
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        // Drop the discarded prefix here, once per chunk rather than per block
+        let discard = (self.discard - self.base) as usize;
+        self.data.drain(..discard);
+        self.base = self.discard;
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Mark the input before `offset` as no longer needed.
+    fn discard_before(&mut self, offset: u64) {
+        self.discard = offset.clamp(self.base, self.end());
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+    }
+}
+
+impl Read for ChunkSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = &self.data[(self.pos - self.base) as usize..];
+        if available.is_empty() && !buf.is_empty() && !self.closed {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for ChunkSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) if offset >= self.base && offset <= self.end() => {
+                self.pos = offset;
+                Ok(offset)
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek outside retained input")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testdata::{generate_fastq_data, generate_mixed_data, generate_random_data};
+    use crate::{SingleThreadedTranscoder, Transcoder};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_chunked_feed_matches_transcode() {
+        let gzip_data =
+            [gzip(&generate_fastq_data(2_000, 100)), gzip(&generate_mixed_data(200_000))].concat();
+        let config = TranscodeConfig { build_index: true, ..Default::default() };
+        let mut expected = Vec::new();
+        let expected_stats = SingleThreadedTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut expected)
+            .unwrap();
+
+        for chunk_size in [1, 7, 4096, 100_000, gzip_data.len()] {
+            let mut transcoder = StreamingTranscoder::new(config.clone());
+            let mut output = Vec::new();
+            for chunk in gzip_data.chunks(chunk_size) {
+                output.extend(transcoder.feed(chunk).unwrap());
+            }
+            output.extend(transcoder.finish().unwrap());
+            assert_eq!(output, expected, "chunk size {}", chunk_size);
+
+            let stats = transcoder.stats().unwrap();
+            assert_eq!(stats.input_bytes, gzip_data.len() as u64);
+            assert_eq!(stats.index_entries, expected_stats.index_entries);
+        }
+    }
+
+    #[test]
+    fn test_blocks_are_returned_as_input_arrives() {
+        let gzip_data = gzip(&generate_random_data(500_000, 7));
+        let mut transcoder = StreamingTranscoder::new(TranscodeConfig::default());
+        let half = transcoder.feed(&gzip_data[..gzip_data.len() / 2]).unwrap();
+        assert!(!half.is_empty());
+        assert!(crate::verify_bgzf(&mut Cursor::new(&half)).is_ok());
+    }
+
+    #[test]
+    fn test_truncated_input_fails_at_finish() {
+        let gzip_data = gzip(&generate_mixed_data(50_000));
+        let mut transcoder = StreamingTranscoder::new(TranscodeConfig::default());
+        transcoder.feed(&gzip_data[..gzip_data.len() - 4]).unwrap();
+        assert!(transcoder.finish().is_err());
+        assert!(transcoder.feed(b"more").is_err());
+        assert!(transcoder.stats().is_none());
+
+        // Before the header is complete
+        let mut transcoder = StreamingTranscoder::new(TranscodeConfig::default());
+        assert!(transcoder.feed(&gzip_data[..5]).unwrap().is_empty());
+        assert!(matches!(transcoder.finish(), Err(Error::UnexpectedEof)));
+    }
+
+    #[test]
+    fn test_feed_after_finish_fails() {
+        let gzip_data = gzip(b"hello\n");
+        let mut transcoder = StreamingTranscoder::new(TranscodeConfig::default());
+        transcoder.feed(&gzip_data).unwrap();
+        transcoder.finish().unwrap();
+        assert!(transcoder.feed(&gzip_data).is_err());
+    }
+}
@@ -1,40 +1,57 @@
-/// 32KB circular buffer for LZ77 sliding window
-pub struct SlidingWindow {
-    buffer: Box<[u8; 32768]>,
-    /// Next write position (0-32767)
+use crate::error::{Error, Result};
+
+/// DEFLATE window size (32KB); DEFLATE64 would use 65536
+pub const DEFAULT_WINDOW_SIZE: usize = 32768;
+
+/// Circular buffer for the LZ77 sliding window.
+///
+/// `N` is the window size and must be a power of two; it defaults to the 32KB
+/// DEFLATE window. Smaller sizes are mainly useful for exercising wrap-around in tests.
+pub struct SlidingWindow<const N: usize = DEFAULT_WINDOW_SIZE> {
+    buffer: Box<[u8; N]>,
+    /// Next write position (0..N)
     write_pos: usize,
     /// Total bytes ever written
     total_written: u64,
 }
 
 impl SlidingWindow {
+    /// Create a standard 32KB DEFLATE window
     pub fn new() -> Self {
-        Self { buffer: Box::new([0u8; 32768]), write_pos: 0, total_written: 0 }
+        Self::default()
     }
+}
+
+impl<const N: usize> SlidingWindow<N> {
+    /// Mask for wrapping positions; evaluating it rejects non-power-of-two sizes at compile time
+    const MASK: usize = {
+        assert!(N.is_power_of_two(), "SlidingWindow size must be a power of two");
+        N - 1
+    };
 
     /// Add a single byte to the window
     #[inline(always)]
     pub fn push_byte(&mut self, byte: u8) {
-        // Safety: write_pos is always masked to 0x7FFF, so always < 32768
+        // Safety: write_pos is always masked to N - 1, so always < N
         unsafe { *self.buffer.get_unchecked_mut(self.write_pos) = byte };
-        self.write_pos = (self.write_pos + 1) & 0x7FFF;
+        self.write_pos = (self.write_pos + 1) & Self::MASK;
         self.total_written += 1;
     }
 
     /// Add multiple bytes to the window efficiently using bulk copy.
     ///
-    /// Handles inputs of any length, including those larger than the 32KB window.
+    /// Handles inputs of any length, including those larger than the window.
     pub fn push_bytes(&mut self, bytes: &[u8]) {
         if bytes.is_empty() {
             return;
         }
         let mut offset = 0;
         while offset < bytes.len() {
-            let remaining = 32768 - self.write_pos;
+            let remaining = N - self.write_pos;
             let chunk_len = remaining.min(bytes.len() - offset);
             self.buffer[self.write_pos..self.write_pos + chunk_len]
                 .copy_from_slice(&bytes[offset..offset + chunk_len]);
-            self.write_pos = (self.write_pos + chunk_len) & 0x7FFF;
+            self.write_pos = (self.write_pos + chunk_len) & Self::MASK;
             offset += chunk_len;
         }
         self.total_written += bytes.len() as u64;
@@ -44,37 +61,44 @@ impl SlidingWindow {
     ///
     /// Note: distance=1 means the most recently written byte.
     /// Length can exceed distance (run-length encoding case).
-    pub fn get(&self, distance: u16, length: u16) -> Vec<u8> {
+    pub fn get(&self, distance: u16, length: u16) -> Result<Vec<u8>> {
         let mut result = Vec::with_capacity(length as usize);
-        self.copy_to_vec(distance, length, &mut result);
-        result
+        self.copy_to_vec(distance, length, &mut result)?;
+        Ok(result)
+    }
+
+    /// Reject a distance of zero or one reaching past the bytes written so far.
+    ///
+    /// Checked in release builds too: clamping instead would silently read stale bytes.
+    #[inline]
+    fn check_distance(&self, distance: u16) -> Result<()> {
+        let available = self.available();
+        if distance == 0 || distance as usize > available {
+            return Err(Error::InvalidBackReference { distance, available });
+        }
+        Ok(())
     }
 
     /// Copy `length` bytes from `distance` bytes back into a pre-allocated Vec.
     /// This avoids allocation when the caller can reuse a buffer.
     #[inline]
-    pub fn copy_to_vec(&self, distance: u16, length: u16, out: &mut Vec<u8>) {
-        debug_assert!((1..=32768).contains(&distance));
+    pub fn copy_to_vec(&self, distance: u16, length: u16, out: &mut Vec<u8>) -> Result<()> {
+        self.check_distance(distance)?;
 
         let dist = distance as usize;
         let len = length as usize;
-        let available = self.total_written.min(32768) as usize;
-        debug_assert!(
-            dist <= available,
-            "invalid back-reference: distance {dist} exceeds available window {available}"
-        );
-        let start = (self.write_pos + 32768 - dist) & 0x7FFF;
+        let start = (self.write_pos + N - dist) & Self::MASK;
 
         if dist >= len {
             // Fast path: non-RLE, source doesn't overlap destination
             out.reserve(len);
             let end = start + len;
-            if end <= 32768 {
+            if end <= N {
                 out.extend_from_slice(&self.buffer[start..end]);
             } else {
                 // Wraps around circular buffer
                 out.extend_from_slice(&self.buffer[start..]);
-                out.extend_from_slice(&self.buffer[..end & 0x7FFF]);
+                out.extend_from_slice(&self.buffer[..end & Self::MASK]);
             }
         } else {
             // RLE case: distance < length, must handle overlap byte-by-byte
@@ -82,9 +106,9 @@ impl SlidingWindow {
             let mut read_pos = start;
             for i in 0..len {
                 if i < dist {
-                    // Safety: read_pos is always masked to 0x7FFF, so always < 32768
+                    // Safety: read_pos is always masked to N - 1, so always < N
                     out.push(unsafe { *self.buffer.get_unchecked(read_pos) });
-                    read_pos = (read_pos + 1) & 0x7FFF;
+                    read_pos = (read_pos + 1) & Self::MASK;
                 } else {
                     // RLE: copy from earlier in output
                     let rle_idx = start_len + i - dist;
@@ -92,28 +116,23 @@ impl SlidingWindow {
                 }
             }
         }
+        Ok(())
     }
 
     /// Process each byte from `distance` bytes back, calling the provided closure.
     /// This avoids allocation entirely for cases where we just need to iterate.
     #[inline]
-    pub fn for_each_byte<F: FnMut(u8)>(&self, distance: u16, length: u16, mut f: F) {
-        debug_assert!((1..=32768).contains(&distance));
+    pub fn for_each_byte<F: FnMut(u8)>(&self, distance: u16, length: u16, mut f: F) -> Result<()> {
+        self.check_distance(distance)?;
 
-        let available = self.total_written.min(32768) as usize;
-        debug_assert!(
-            (distance as usize) <= available,
-            "invalid back-reference: distance {} exceeds available window {available}",
-            distance
-        );
-        let start = (self.write_pos + 32768 - distance as usize) & 0x7FFF;
+        let start = (self.write_pos + N - distance as usize) & Self::MASK;
 
         if length <= distance {
             // Simple case: no RLE, just read from buffer
             let mut read_pos = start;
             for _ in 0..length {
                 f(self.buffer[read_pos]);
-                read_pos = (read_pos + 1) & 0x7FFF;
+                read_pos = (read_pos + 1) & Self::MASK;
             }
         } else {
             // RLE case: need to track what we've "produced"
@@ -125,7 +144,7 @@ impl SlidingWindow {
             let mut read_pos = start;
             for _ in 0..dist {
                 pattern.push(self.buffer[read_pos]);
-                read_pos = (read_pos + 1) & 0x7FFF;
+                read_pos = (read_pos + 1) & Self::MASK;
             }
 
             // Now emit the pattern repeatedly
@@ -133,11 +152,12 @@ impl SlidingWindow {
                 f(pattern[i % dist]);
             }
         }
+        Ok(())
     }
 
     /// Get available window size
     pub fn available(&self) -> usize {
-        self.total_written.min(32768) as usize
+        self.total_written.min(N as u64) as usize
     }
 
     /// Get total bytes written
@@ -152,9 +172,11 @@ impl SlidingWindow {
     }
 }
 
-impl Default for SlidingWindow {
+impl<const N: usize> Default for SlidingWindow<N> {
     fn default() -> Self {
-        Self::new()
+        // Force the power-of-two check even if no method is ever called
+        let _ = Self::MASK;
+        Self { buffer: Box::new([0u8; N]), write_pos: 0, total_written: 0 }
     }
 }
 
@@ -169,10 +191,10 @@ mod tests {
         window.push_byte(b'B');
         window.push_byte(b'C');
 
-        assert_eq!(window.get(1, 1), vec![b'C']);
-        assert_eq!(window.get(2, 1), vec![b'B']);
-        assert_eq!(window.get(3, 1), vec![b'A']);
-        assert_eq!(window.get(3, 3), vec![b'A', b'B', b'C']);
+        assert_eq!(window.get(1, 1).unwrap(), vec![b'C']);
+        assert_eq!(window.get(2, 1).unwrap(), vec![b'B']);
+        assert_eq!(window.get(3, 1).unwrap(), vec![b'A']);
+        assert_eq!(window.get(3, 3).unwrap(), vec![b'A', b'B', b'C']);
     }
 
     #[test]
@@ -181,7 +203,7 @@ mod tests {
         window.push_byte(b'A');
 
         // RLE case: distance=1, length=5 -> "AAAAA"
-        assert_eq!(window.get(1, 5), vec![b'A', b'A', b'A', b'A', b'A']);
+        assert_eq!(window.get(1, 5).unwrap(), vec![b'A', b'A', b'A', b'A', b'A']);
     }
 
     #[test]
@@ -191,14 +213,14 @@ mod tests {
         window.push_byte(b'B');
 
         // RLE case: distance=2, length=6 -> "ABABAB"
-        assert_eq!(window.get(2, 6), vec![b'A', b'B', b'A', b'B', b'A', b'B']);
+        assert_eq!(window.get(2, 6).unwrap(), vec![b'A', b'B', b'A', b'B', b'A', b'B']);
     }
 
     #[test]
     fn test_push_bytes_bulk() {
         let mut window = SlidingWindow::new();
         window.push_bytes(b"ABCDEFGH");
-        assert_eq!(window.get(8, 8), b"ABCDEFGH");
+        assert_eq!(window.get(8, 8).unwrap(), b"ABCDEFGH");
         assert_eq!(window.total_written(), 8);
     }
 
@@ -211,7 +233,7 @@ mod tests {
         }
         // Push 4 bytes that wrap around
         window.push_bytes(&[0xAA, 0xBB, 0xCC, 0xDD]);
-        assert_eq!(window.get(4, 4), vec![0xAA, 0xBB, 0xCC, 0xDD]);
+        assert_eq!(window.get(4, 4).unwrap(), vec![0xAA, 0xBB, 0xCC, 0xDD]);
         assert_eq!(window.total_written(), 32770);
     }
 
@@ -220,7 +242,7 @@ mod tests {
         let mut window = SlidingWindow::new();
         window.push_bytes(b"ABCDEFGH");
         let mut out = Vec::new();
-        window.copy_to_vec(8, 4, &mut out);
+        window.copy_to_vec(8, 4, &mut out).unwrap();
         assert_eq!(out, b"ABCD");
     }
 
@@ -232,7 +254,7 @@ mod tests {
         }
         window.push_bytes(&[0xAA, 0xBB]);
         let mut out = Vec::new();
-        window.copy_to_vec(2, 2, &mut out);
+        window.copy_to_vec(2, 2, &mut out).unwrap();
         assert_eq!(out, vec![0xAA, 0xBB]);
     }
 
@@ -245,7 +267,7 @@ mod tests {
         assert_eq!(window.total_written(), 40000);
         assert_eq!(window.available(), 32768);
         // Most recent byte should be the last in data
-        assert_eq!(window.get(1, 1), vec![(39999 & 0xFF) as u8]);
+        assert_eq!(window.get(1, 1).unwrap(), vec![(39999 & 0xFF) as u8]);
     }
 
     #[test]
@@ -261,6 +283,91 @@ mod tests {
         assert_eq!(window.total_written(), 40000);
 
         // Most recent byte should be (39999 & 0xFF) = 63
-        assert_eq!(window.get(1, 1), vec![63]);
+        assert_eq!(window.get(1, 1).unwrap(), vec![63]);
+    }
+
+    #[test]
+    fn test_copy_distance_equals_available() {
+        let mut window = SlidingWindow::new();
+        window.push_bytes(b"XYZ");
+        assert_eq!(window.get(3, 3).unwrap(), b"XYZ");
+        // RLE at the full available distance
+        assert_eq!(window.get(3, 7).unwrap(), b"XYZXYZX");
+
+        let mut seen = Vec::new();
+        window.for_each_byte(3, 5, |b| seen.push(b)).unwrap();
+        assert_eq!(seen, b"XYZXY");
+    }
+
+    #[test]
+    fn test_copy_max_distance_wraps() {
+        let mut window = SlidingWindow::new();
+        // Write past the window so the oldest retained byte sits mid-buffer
+        let data: Vec<u8> = (0..40000u32).map(|i| (i % 251) as u8).collect();
+        window.push_bytes(&data);
+
+        let oldest = &data[data.len() - 32768..];
+        assert_eq!(window.get(32768, 258).unwrap(), &oldest[..258]);
+
+        let mut seen = Vec::new();
+        window.for_each_byte(32768, 258, |b| seen.push(b)).unwrap();
+        assert_eq!(seen, &oldest[..258]);
+    }
+
+    #[test]
+    fn test_copy_distance_exceeds_available() {
+        let mut window = SlidingWindow::new();
+        window.push_bytes(b"AB");
+
+        assert!(matches!(
+            window.get(3, 1),
+            Err(Error::InvalidBackReference { distance: 3, available: 2 })
+        ));
+        assert!(window.for_each_byte(3, 1, |_| {}).is_err());
+        assert!(window.get(0, 1).is_err());
+    }
+
+    #[test]
+    fn test_tiny_window_wrap() {
+        let mut window = SlidingWindow::<8>::default();
+        window.push_bytes(b"0123456789AB");
+
+        assert_eq!(window.available(), 8);
+        assert_eq!(window.total_written(), 12);
+        // Oldest retained byte is '4'; a full-window copy crosses the wrap point
+        assert_eq!(window.get(8, 8).unwrap(), b"456789AB");
+        assert_eq!(window.get(6, 3).unwrap(), b"678");
+        assert!(window.get(9, 1).is_err());
+    }
+
+    #[test]
+    fn test_tiny_window_rle_across_wrap() {
+        let mut window = SlidingWindow::<8>::default();
+        for &b in b"abcdefgXY" {
+            window.push_byte(b);
+        }
+        // "XY" straddles the end of the buffer (positions 7 and 0)
+        assert_eq!(window.get(2, 7).unwrap(), b"XYXYXYX");
+
+        let mut seen = Vec::new();
+        window.for_each_byte(3, 7, |b| seen.push(b)).unwrap();
+        assert_eq!(seen, b"gXYgXYg");
+    }
+
+    #[test]
+    fn test_tiny_window_push_bytes_matches_push_byte() {
+        let data: Vec<u8> = (0..37u8).collect();
+        let mut bulk = SlidingWindow::<8>::default();
+        let mut single = SlidingWindow::<8>::default();
+        for chunk in data.chunks(5) {
+            bulk.push_bytes(chunk);
+        }
+        for &b in &data {
+            single.push_byte(b);
+        }
+        for distance in 1..=8 {
+            assert_eq!(bulk.get(distance, 8).unwrap(), single.get(distance, 8).unwrap());
+        }
+        assert_eq!(bulk.get(8, 8).unwrap(), &data[29..]);
     }
 }
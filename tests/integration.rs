@@ -9,80 +9,23 @@ use flate2::read::MultiGzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 
+use rebgzf::bgzf::{block_size_from_bsize, bsize_from_block_size};
+use rebgzf::gzip::GzipHeader;
+use rebgzf::testdata::{
+    generate_all_bytes, generate_fastq_data, generate_mixed_data, generate_random_data,
+    generate_repetitive_data, CountingWriter,
+};
 use rebgzf::{
-    is_bgzf, validate_bgzf_streaming, validate_bgzf_strict, verify_bgzf, ParallelTranscoder,
-    SingleThreadedTranscoder, TranscodeConfig, Transcoder,
+    is_bgzf, validate_bgzf_detailed, validate_bgzf_streaming, validate_bgzf_streaming_detailed,
+    validate_bgzf_strict, verify_bgzf, verify_bgzf_tee, verify_bgzf_with, verify_bgzf_with_options,
+    BlockError, BlockErrorKind, CompressionLevel, FormatProfile, MemberInfo, ParallelTranscoder,
+    SingleThreadedTranscoder, TranscodeConfig, TranscodeContext, Transcoder, VerifyOptions,
 };
 
 // ============================================================================
 // Test Data Generators
 // ============================================================================
 
-/// Generate random data using a simple PRNG
-fn generate_random_data(size: usize, seed: u64) -> Vec<u8> {
-    let mut data = Vec::with_capacity(size);
-    let mut state = seed;
-    for _ in 0..size {
-        // Simple xorshift PRNG
-        state ^= state << 13;
-        state ^= state >> 7;
-        state ^= state << 17;
-        data.push((state & 0xFF) as u8);
-    }
-    data
-}
-
-/// Generate highly repetitive data (good compression)
-fn generate_repetitive_data(size: usize) -> Vec<u8> {
-    let pattern = b"AAAAAAAAAAAAAAAA";
-    pattern.iter().cycle().take(size).copied().collect()
-}
-
-/// Generate data with mixed patterns (moderate compression)
-fn generate_mixed_data(size: usize) -> Vec<u8> {
-    let mut data = Vec::with_capacity(size);
-    let patterns = [
-        b"ACGTACGTACGTACGT".as_slice(),
-        b"NNNNNNNNNNNNNNNN".as_slice(),
-        b"ATATATATATATATAT".as_slice(),
-    ];
-
-    let mut pattern_idx = 0;
-    while data.len() < size {
-        let pattern = patterns[pattern_idx % patterns.len()];
-        let remaining = size - data.len();
-        let chunk_size = remaining.min(pattern.len());
-        data.extend_from_slice(&pattern[..chunk_size]);
-        pattern_idx += 1;
-    }
-    data
-}
-
-/// Generate FASTQ-formatted data
-fn generate_fastq_data(num_reads: usize, read_length: usize) -> Vec<u8> {
-    let mut data = Vec::new();
-    let bases = [b'A', b'C', b'G', b'T'];
-
-    for i in 0..num_reads {
-        // Header
-        writeln!(data, "@read_{}", i).unwrap();
-
-        // Sequence - use deterministic pattern based on read number
-        for j in 0..read_length {
-            data.push(bases[(i + j) % 4]);
-        }
-        data.push(b'\n');
-
-        // Plus line
-        data.extend_from_slice(b"+\n");
-
-        // Quality scores
-        data.resize(data.len() + read_length, b'I'); // High quality
-        data.push(b'\n');
-    }
-    data
-}
-
 /// Compress data to gzip format
 fn compress_to_gzip(data: &[u8]) -> Vec<u8> {
     let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
@@ -90,6 +33,13 @@ fn compress_to_gzip(data: &[u8]) -> Vec<u8> {
     encoder.finish().unwrap()
 }
 
+/// Compress data to gzip using only stored (uncompressed) DEFLATE blocks
+fn compress_to_gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::none());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
 /// Decompress gzip/BGZF data to verify contents
 /// Uses MultiGzDecoder to handle BGZF's concatenated gzip blocks
 fn decompress_gzip(data: &[u8]) -> Vec<u8> {
@@ -139,7 +89,7 @@ fn parse_bgzf_blocks(data: &[u8]) -> Vec<(usize, u32)> {
         }
 
         // Get BSIZE
-        let bsize = u16::from_le_bytes([data[pos + 16], data[pos + 17]]) as usize + 1;
+        let bsize = block_size_from_bsize(u16::from_le_bytes([data[pos + 16], data[pos + 17]]));
 
         if pos + bsize > data.len() {
             break;
@@ -253,22 +203,33 @@ fn test_single_thread_highly_compressible() {
 
 #[test]
 fn test_single_thread_incompressible() {
-    // Random data is incompressible and may expand during encoding
-    // Use smaller block size to avoid exceeding BGZF max block size
-    let data = generate_random_data(50_000, 99999);
+    // Random data is incompressible; stored blocks keep it from expanding
+    // beyond block framing at any level
+    let data = generate_random_data(200_000, 99999);
     let gzip_data = compress_to_gzip(&data);
 
-    let config = TranscodeConfig {
-        block_size: 32768, // Smaller to accommodate potential expansion
-        ..Default::default()
-    };
-    let mut transcoder = SingleThreadedTranscoder::new(config);
-    let mut output = Vec::new();
-
-    transcoder.transcode(Cursor::new(&gzip_data), &mut output).unwrap();
-
-    assert!(verify_bgzf_format(&output));
-    assert_eq!(decompress_gzip(&output), data);
+    for level in [1, 6, 9] {
+        let config = TranscodeConfig {
+            compression_level: CompressionLevel::from_level(level),
+            ..Default::default()
+        };
+        let mut transcoder = SingleThreadedTranscoder::new(config);
+        let mut output = Vec::new();
+
+        let stats = transcoder.transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+
+        assert!(verify_bgzf_format(&output));
+        assert_eq!(decompress_gzip(&output), data);
+        // 18-byte header, 5-byte stored header, 8-byte footer per block, then EOF
+        let overhead = stats.blocks_written * (18 + 5 + 8) + 28;
+        assert!(
+            output.len() as u64 <= data.len() as u64 + overhead,
+            "level {}: {} bytes from {}",
+            level,
+            output.len(),
+            data.len()
+        );
+    }
 }
 
 #[test]
@@ -286,6 +247,285 @@ fn test_single_thread_fastq_data() {
     assert_eq!(decompress_gzip(&output), data);
 }
 
+/// Count BGZF data blocks whose uncompressed content ends on a FASTQ record boundary.
+fn count_record_aligned_blocks(bgzf: &[u8]) -> (usize, usize) {
+    let mut pos = 0;
+    let mut blocks = Vec::new();
+    for (bsize, isize) in parse_bgzf_blocks(bgzf) {
+        if isize > 0 {
+            blocks.push(decompress_gzip(&bgzf[pos..pos + bsize]));
+        }
+        pos += bsize;
+    }
+    // A block is aligned when the following block starts a new record
+    let aligned = blocks.windows(2).filter(|w| w[0].ends_with(b"\n") && w[1][0] == b'@').count();
+    (aligned, blocks.len() - 1)
+}
+
+#[test]
+fn test_fastq_boundaries_found_inside_copies() {
+    // Compressed FASTQ: repeated sequence and quality runs become Copy tokens,
+    // many of which span the newline that ends a record
+    let data = generate_fastq_data(2000, 150);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config =
+        TranscodeConfig { block_size: 8192, format: FormatProfile::Fastq, ..Default::default() };
+    let mut output = Vec::new();
+    SingleThreadedTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+    assert_eq!(decompress_gzip(&output), data);
+
+    let (aligned, total) = count_record_aligned_blocks(&output);
+    assert!(total > 0);
+    assert!(
+        aligned * 10 >= total * 9,
+        "Only {}/{} blocks end after a quality line",
+        aligned,
+        total
+    );
+}
+
+#[test]
+fn test_lines_format_ends_blocks_on_newlines() {
+    // Tab-separated rows of varying length, gzip-compressed so many newlines
+    // fall inside Copy tokens
+    let data: Vec<u8> = (0..20_000u32)
+        .map(|i| format!("chr{}\t{}\t{}\tgene{}\n", i % 3 + 1, i * 37, i * 37 + 90, i % 50))
+        .flat_map(String::into_bytes)
+        .collect();
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::Lines { per_record: 1 },
+            num_threads,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+        assert_eq!(decompress_gzip(&output), data);
+
+        let mut offset = 0;
+        let blocks = parse_bgzf_blocks(&output);
+        assert!(blocks.len() > 10);
+        for (_, isize) in &blocks[..blocks.len() - 2] {
+            offset += *isize as usize;
+            assert_eq!(
+                data[offset - 1],
+                b'\n',
+                "{} threads, block ending at {}",
+                num_threads,
+                offset
+            );
+        }
+    }
+
+    let config =
+        TranscodeConfig { format: FormatProfile::Lines { per_record: 0 }, ..Default::default() };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_vcf_header_ends_a_block() {
+    // A header larger than one block, then sorted records
+    let mut data: Vec<u8> = (0..300u32)
+        .map(|i| format!("##INFO=<ID=X{},Number=1,Type=Integer,Description=\"x\">\n", i))
+        .flat_map(String::into_bytes)
+        .collect();
+    data.extend_from_slice(b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n");
+    let header_len = data.len();
+    for i in 0..20_000u32 {
+        data.extend_from_slice(
+            format!("chr1\t{}\t.\tA\tG\t50\tPASS\tX{}=1\n", i * 10, i % 300).as_bytes(),
+        );
+    }
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::Vcf,
+            num_threads,
+            ..Default::default()
+        };
+        let mut streamed = Vec::new();
+        ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut streamed)
+            .unwrap();
+        let mut mapped = Vec::new();
+        rebgzf::ParallelDecodeTranscoder::new(config)
+            .transcode_mmap(&gzip_data, &mut mapped)
+            .unwrap();
+
+        for output in [streamed, mapped] {
+            assert_eq!(decompress_gzip(&output), data);
+            let ends: Vec<usize> = parse_bgzf_blocks(&output)
+                .iter()
+                .scan(0, |offset, (_, isize)| {
+                    *offset += *isize as usize;
+                    Some(*offset)
+                })
+                .collect();
+            assert!(ends.contains(&header_len), "{} threads", num_threads);
+            for &end in &ends[..ends.len() - 2] {
+                assert_eq!(
+                    data[end - 1],
+                    b'\n',
+                    "{} threads, block ending at {}",
+                    num_threads,
+                    end
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_fixed_records_align_blocks() {
+    // 100-byte records sharing long runs, so matches span record ends
+    let data: Vec<u8> = (0..5_000u32)
+        .flat_map(|i| {
+            let mut record = i.to_le_bytes().to_vec();
+            record.extend((0..96u32).map(|j| (j * (i % 7 + 1)) as u8));
+            record
+        })
+        .collect();
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::FixedRecords { record_size: 100 },
+            num_threads,
+            ..Default::default()
+        };
+        let mut streamed = Vec::new();
+        ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut streamed)
+            .unwrap();
+        let mut mapped = Vec::new();
+        rebgzf::ParallelDecodeTranscoder::new(config)
+            .transcode_mmap(&gzip_data, &mut mapped)
+            .unwrap();
+
+        for output in [streamed, mapped] {
+            assert_eq!(decompress_gzip(&output), data);
+            let blocks = parse_bgzf_blocks(&output);
+            assert!(blocks.len() > 10);
+            // The last two are the final data block and the EOF block
+            for (_, isize) in &blocks[..blocks.len() - 2] {
+                assert_eq!(isize % 100, 0, "{} threads", num_threads);
+            }
+        }
+    }
+
+    let config = TranscodeConfig {
+        format: FormatProfile::FixedRecords { record_size: 0 },
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_boundary_overshoot_improves_alignment() {
+    let data = generate_fastq_data(2000, 150);
+    // Stored blocks yield only literal tokens, so the splitter sees every newline
+    let gzip_data = compress_to_gzip_stored(&data);
+
+    let transcode_with = |boundary_overshoot: f64| {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::Fastq,
+            boundary_overshoot,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut output)
+            .unwrap();
+        assert_eq!(decompress_gzip(&output), data);
+        count_record_aligned_blocks(&output)
+    };
+
+    let (tight_aligned, tight_total) = transcode_with(0.0);
+    let (loose_aligned, loose_total) = transcode_with(0.3);
+
+    assert!(loose_total > 0);
+    assert!(
+        loose_aligned * tight_total > tight_aligned * loose_total,
+        "Larger overshoot should align more blocks: {}/{} vs {}/{}",
+        loose_aligned,
+        loose_total,
+        tight_aligned,
+        tight_total
+    );
+}
+
+#[test]
+fn test_boundary_overshoot_out_of_range() {
+    let gzip_data = compress_to_gzip(b"Hello");
+
+    for boundary_overshoot in [-0.1, 0.6, f64::NAN] {
+        let config = TranscodeConfig { boundary_overshoot, ..Default::default() };
+        let result = SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut Vec::new());
+        assert!(
+            matches!(result, Err(rebgzf::Error::InvalidConfig(_))),
+            "Overshoot {} should be rejected",
+            boundary_overshoot
+        );
+    }
+}
+
+#[test]
+fn test_min_block_size_merges_trailing_block() {
+    let data = generate_mixed_data(200_000);
+    let gzip_data = compress_to_gzip(&data);
+    let min_block_size = 8192;
+
+    // Without a minimum the trailing block is small
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut output = Vec::new();
+    SingleThreadedTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+    let sizes: Vec<u32> = parse_bgzf_blocks(&output).iter().map(|&(_, s)| s).collect();
+    assert!((sizes[sizes.len() - 2] as usize) < min_block_size);
+
+    for threads in [1, 4] {
+        let config = TranscodeConfig {
+            block_size: 16384,
+            min_block_size: Some(min_block_size),
+            num_threads: threads,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+
+        assert_eq!(decompress_gzip(&output), data);
+        let blocks = parse_bgzf_blocks(&output);
+        let data_blocks = &blocks[..blocks.len() - 1];
+        for (i, &(_, isize)) in data_blocks.iter().enumerate() {
+            assert!(
+                isize as usize >= min_block_size && isize as usize <= 16384,
+                "Block {} has {} bytes with {} threads",
+                i,
+                isize,
+                threads
+            );
+        }
+    }
+}
+
+#[test]
+fn test_min_block_size_too_large() {
+    let gzip_data = compress_to_gzip(b"Hello");
+    let config =
+        TranscodeConfig { block_size: 16384, min_block_size: Some(10_000), ..Default::default() };
+    let result =
+        SingleThreadedTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut Vec::new());
+    assert!(matches!(result, Err(rebgzf::Error::InvalidConfig(_))));
+}
+
 #[test]
 fn test_single_thread_custom_block_size() {
     let data = generate_random_data(100_000, 11111);
@@ -304,6 +544,86 @@ fn test_single_thread_custom_block_size() {
     assert!(stats.blocks_written >= 6);
 }
 
+#[test]
+fn test_transcode_with_shared_context() {
+    let inputs = [
+        compress_to_gzip(&generate_mixed_data(150_000)),
+        compress_to_gzip(&generate_fastq_data(500, 100)),
+        compress_to_gzip(b"short"),
+    ];
+
+    for level in [1, 6] {
+        let config = TranscodeConfig {
+            block_size: 16384,
+            compression_level: CompressionLevel::from_level(level),
+            ..Default::default()
+        };
+        let mut context = TranscodeContext::new();
+
+        for input in &inputs {
+            let mut expected = Vec::new();
+            SingleThreadedTranscoder::new(config.clone())
+                .transcode(Cursor::new(input), &mut expected)
+                .unwrap();
+
+            let mut shared = Vec::new();
+            let stats = SingleThreadedTranscoder::new(config.clone())
+                .transcode_with_context(Cursor::new(input), &mut shared, &mut context)
+                .unwrap();
+            assert_eq!(shared, expected, "level {level}: shared context changed output");
+            assert_eq!(stats.output_bytes, shared.len() as u64);
+
+            // A failed transcode must not leak state into the next one
+            let truncated = &input[..input.len() / 2];
+            let _ = SingleThreadedTranscoder::new(config.clone()).transcode_with_context(
+                Cursor::new(truncated),
+                &mut Vec::new(),
+                &mut context,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_config_builder() {
+    let config = TranscodeConfig::builder()
+        .block_size(16384)
+        .compression_level(CompressionLevel::Level6)
+        .threads(2)
+        .format(FormatProfile::Fastq)
+        .index(true)
+        .build()
+        .unwrap();
+    assert_eq!(config.block_size, 16384);
+    assert_eq!(config.compression_level, CompressionLevel::Level6);
+    assert_eq!(config.num_threads, 2);
+    assert_eq!(config.format, FormatProfile::Fastq);
+    assert!(config.build_index);
+
+    let data = generate_fastq_data(500, 100);
+    let mut output = Vec::new();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(compress_to_gzip(&data)), &mut output)
+        .unwrap();
+    assert_eq!(decompress_gzip(&output), data);
+    assert!(stats.index_entries.is_some());
+
+    // Unset fields match the defaults
+    let default = TranscodeConfig::default();
+    let built = TranscodeConfig::builder().build().unwrap();
+    assert_eq!(built.block_size, default.block_size);
+    assert_eq!(built.compression_level, default.compression_level);
+    assert!(!built.build_index);
+
+    // Block sizes BGZF cannot hold, and anything validate() rejects, fail
+    let result = TranscodeConfig::builder().block_size(0).build();
+    assert!(matches!(result, Err(rebgzf::Error::InvalidConfig(_))));
+    let result = TranscodeConfig::builder().block_size(70_000).build();
+    assert!(matches!(result, Err(rebgzf::Error::BgzfBlockTooLarge { .. })));
+    let result = TranscodeConfig::builder().format(FormatProfile::Lines { per_record: 0 }).build();
+    assert!(result.is_err());
+}
+
 // ============================================================================
 // Parallel Transcoder Tests
 // ============================================================================
@@ -375,6 +695,42 @@ fn test_parallel_matches_single_threaded() {
     assert_eq!(decompress_gzip(&output_parallel), data);
 }
 
+#[test]
+fn test_parallel_fastq_boundaries_match_single_threaded() {
+    let data = generate_fastq_data(2000, 150);
+    let gzip_data = compress_to_gzip_stored(&data);
+
+    let transcode_with = |num_threads: usize| {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::Fastq,
+            num_threads,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+        assert_eq!(decompress_gzip(&output), data);
+        output
+    };
+
+    let single = transcode_with(1);
+    let (aligned, total) = count_record_aligned_blocks(&single);
+    assert!(aligned * 10 >= total * 9, "Only {}/{} blocks end at a record", aligned, total);
+
+    let single_sizes: Vec<u32> = parse_bgzf_blocks(&single).iter().map(|&(_, s)| s).collect();
+    for threads in [2, 4] {
+        let parallel = transcode_with(threads);
+        let parallel_sizes: Vec<u32> =
+            parse_bgzf_blocks(&parallel).iter().map(|&(_, s)| s).collect();
+        assert_eq!(
+            parallel_sizes, single_sizes,
+            "Block boundaries differ with {} threads",
+            threads
+        );
+        assert_eq!(count_record_aligned_blocks(&parallel), (aligned, total));
+    }
+}
+
 #[test]
 fn test_parallel_auto_threads() {
     let data = generate_random_data(100_000, 33333);
@@ -393,6 +749,494 @@ fn test_parallel_auto_threads() {
     assert_eq!(decompress_gzip(&output), data);
 }
 
+#[test]
+fn test_parallel_stats_account_for_every_block() {
+    let data = generate_mixed_data(500_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [2, 4] {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            num_threads,
+            collect_parallel_stats: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let stats = ParallelTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut output)
+            .unwrap();
+        assert_eq!(decompress_gzip(&output), data);
+
+        let parallel = stats.parallel.expect("parallel stats requested");
+        assert_eq!(parallel.worker_blocks.len(), num_threads);
+        assert_eq!(parallel.worker_idle.len(), num_threads);
+        assert_eq!(parallel.worker_blocks.iter().sum::<u64>(), stats.blocks_written);
+        assert!(parallel.peak_buffered_blocks < 16 * num_threads);
+    }
+
+    // Off by default
+    let config = TranscodeConfig { num_threads: 2, ..Default::default() };
+    let stats = ParallelTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+        .unwrap();
+    assert!(stats.parallel.is_none());
+}
+
+#[test]
+fn test_blocks_per_job_preserves_output() {
+    let data = generate_fastq_data(5_000, 100);
+    let gzip_data = compress_to_gzip(&data);
+
+    let transcode = |blocks_per_job: usize| {
+        let config = TranscodeConfig {
+            block_size: 4096,
+            num_threads: 4,
+            blocks_per_job,
+            build_index: true,
+            ..Default::default()
+        };
+        let mut streamed = Vec::new();
+        let stats = ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&gzip_data), &mut streamed)
+            .unwrap();
+        let mut mapped = Vec::new();
+        let mapped_stats = rebgzf::ParallelDecodeTranscoder::new(config)
+            .transcode_mmap(&gzip_data, &mut mapped)
+            .unwrap();
+        assert_eq!(mapped, streamed, "blocks_per_job {}", blocks_per_job);
+        assert_eq!(mapped_stats.index_entries, stats.index_entries);
+        (streamed, stats)
+    };
+
+    let (expected, expected_stats) = transcode(1);
+    assert!(expected_stats.blocks_written > 16, "test input should span many blocks");
+    // 3 leaves a partly filled final batch
+    for blocks_per_job in [3, 8] {
+        let (output, stats) = transcode(blocks_per_job);
+        assert_eq!(output, expected, "blocks_per_job {}", blocks_per_job);
+        assert_eq!(stats.blocks_written, expected_stats.blocks_written);
+        assert_eq!(stats.index_entries, expected_stats.index_entries);
+    }
+    assert_eq!(decompress_gzip(&expected), data);
+
+    let config = TranscodeConfig { num_threads: 2, blocks_per_job: 0, ..Default::default() };
+    assert!(ParallelTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), Vec::new())
+        .is_err());
+}
+
+#[test]
+fn test_extra_subfields_written_after_bc() {
+    let data = generate_mixed_data(200_000);
+    let gzip_data = compress_to_gzip(&data);
+    let extra = (b'X', b'Y', b"rebgzf".to_vec());
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig {
+            num_threads,
+            extra_subfields: vec![extra.clone()],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output).unwrap();
+
+        assert!(is_bgzf(&mut Cursor::new(&output)).unwrap());
+        let verification = verify_bgzf(&mut Cursor::new(&output)).unwrap();
+        assert!(verification.is_valid_bgzf && verification.crc_valid, "{:?}", verification);
+        assert_eq!(decompress_gzip(&output), data);
+
+        // BC stays first and its BSIZE accounts for the extra subfield
+        let header = GzipHeader::parse(&mut Cursor::new(&output)).unwrap();
+        let bsize = bsize_from_block_size(parse_bgzf_blocks(&output)[0].0).unwrap();
+        assert_eq!(
+            header.subfields(),
+            vec![(b'B', b'C', bsize.to_le_bytes().to_vec()), extra.clone()]
+        );
+    }
+}
+
+#[test]
+fn test_extra_subfields_reject_bc() {
+    let config =
+        TranscodeConfig { extra_subfields: vec![(b'B', b'C', vec![0, 0])], ..Default::default() };
+    let mut output = Vec::new();
+    let result = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(compress_to_gzip(b"Hello")), &mut output);
+    assert!(matches!(result, Err(rebgzf::Error::InvalidConfig(_))));
+}
+
+#[test]
+fn test_preserve_ftext() {
+    let data = generate_fastq_data(2000, 150);
+    let mut gzip_data = compress_to_gzip(&data);
+    gzip_data[3] |= 0x01; // FTEXT
+
+    for num_threads in [1, 2] {
+        let transcode = |preserve_ftext: bool| {
+            let config = TranscodeConfig { num_threads, preserve_ftext, ..Default::default() };
+            let mut output = Vec::new();
+            ParallelTranscoder::new(config)
+                .transcode(Cursor::new(&gzip_data), &mut output)
+                .unwrap();
+            output
+        };
+
+        // Every data block carries FEXTRA and FTEXT and the output is still BGZF
+        let output = transcode(true);
+        assert!(is_bgzf(&mut Cursor::new(&output)).unwrap());
+        assert!(validate_bgzf_strict(&mut Cursor::new(&output)).unwrap().is_valid_bgzf);
+        assert!(verify_bgzf(&mut Cursor::new(&output)).unwrap().has_eof_block);
+        assert_eq!(decompress_gzip(&output), data);
+        let blocks = parse_bgzf_blocks(&output);
+        let mut offset = 0;
+        for (block_size, _) in &blocks[..blocks.len() - 1] {
+            assert_eq!(output[offset + 3], 0x05, "{} threads", num_threads);
+            offset += block_size;
+        }
+        assert!(GzipHeader::parse(&mut Cursor::new(&output)).unwrap().is_text());
+
+        // Off by default
+        assert_eq!(transcode(false)[3], 0x04, "{} threads", num_threads);
+    }
+}
+
+#[test]
+fn test_preserve_mtime() {
+    let data = generate_fastq_data(2000, 150);
+    let mut gzip_data = compress_to_gzip(&data);
+    gzip_data[4..8].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+    gzip_data[9] = 3; // Unix
+
+    for num_threads in [1, 2] {
+        let transcode = |preserve_mtime: bool| {
+            let config = TranscodeConfig { num_threads, preserve_mtime, ..Default::default() };
+            let mut streamed = Vec::new();
+            ParallelTranscoder::new(config.clone())
+                .transcode(Cursor::new(&gzip_data), &mut streamed)
+                .unwrap();
+            let mut mapped = Vec::new();
+            rebgzf::ParallelDecodeTranscoder::new(config)
+                .transcode_mmap(&gzip_data, &mut mapped)
+                .unwrap();
+            [streamed, mapped]
+        };
+
+        // Every data block carries the input's MTIME and OS; the EOF block is canonical
+        for output in transcode(true) {
+            assert!(verify_bgzf(&mut Cursor::new(&output)).unwrap().has_eof_block);
+            assert_eq!(decompress_gzip(&output), data);
+            let blocks = parse_bgzf_blocks(&output);
+            assert!(blocks.len() > 2);
+            let mut offset = 0;
+            for (block_size, _) in &blocks[..blocks.len() - 1] {
+                let header = GzipHeader::parse(&mut &output[offset..]).unwrap();
+                assert_eq!(
+                    (header.mtime, header.os),
+                    (1_700_000_000, 3),
+                    "{} threads",
+                    num_threads
+                );
+                offset += block_size;
+            }
+        }
+
+        // Off by default
+        for output in transcode(false) {
+            let header = GzipHeader::parse(&mut Cursor::new(&output)).unwrap();
+            assert_eq!((header.mtime, header.os), (0, 0xff), "{} threads", num_threads);
+        }
+    }
+}
+
+#[test]
+fn test_concatenate_parts_without_eof() {
+    let parts = [generate_fastq_data(1000, 150), generate_mixed_data(100_000)];
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig { num_threads, write_eof: false, ..Default::default() };
+        let mut combined = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            let gzip_data = compress_to_gzip(part);
+            let mut output = Vec::new();
+            let stats = if i == 0 {
+                ParallelTranscoder::new(config.clone())
+                    .transcode(Cursor::new(&gzip_data), &mut output)
+                    .unwrap()
+            } else {
+                rebgzf::ParallelDecodeTranscoder::new(config.clone())
+                    .transcode_mmap(&gzip_data, &mut output)
+                    .unwrap()
+            };
+            assert_eq!(stats.output_bytes, output.len() as u64);
+            assert!(!verify_bgzf(&mut Cursor::new(&output)).unwrap().has_eof_block);
+            combined.extend_from_slice(&output);
+        }
+        combined.extend_from_slice(&rebgzf::bgzf::BGZF_EOF);
+
+        let verification = verify_bgzf(&mut Cursor::new(&combined)).unwrap();
+        assert!(verification.crc_valid && verification.has_eof_block, "{} threads", num_threads);
+        assert_eq!(decompress_gzip(&combined), parts.concat());
+    }
+}
+
+#[test]
+fn test_verify_input_crc() {
+    // Two small members (the streaming paths) and one large member that the
+    // mmap path decodes in parallel
+    let fastq = generate_fastq_data(2000, 150);
+    let members = [compress_to_gzip(&fastq[..100_000]), compress_to_gzip(&fastq[100_000..])];
+    let multi = members.concat();
+    let large = compress_to_gzip(&generate_random_data(1_500_000, 7));
+
+    let transcode = |input: &[u8], verify_input_crc: bool| {
+        let config = TranscodeConfig { num_threads: 4, verify_input_crc, ..Default::default() };
+        let mut output = Vec::new();
+        let streamed = ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(input), &mut output)
+            .map(|_| ());
+        let single =
+            SingleThreadedTranscoder::new(TranscodeConfig { num_threads: 1, ..config.clone() })
+                .transcode_slice(input, &mut output)
+                .map(|_| ());
+        let mapped = rebgzf::ParallelDecodeTranscoder::new(config)
+            .transcode_mmap(input, &mut output)
+            .map(|_| ());
+        [streamed, single, mapped]
+    };
+
+    for input in [&multi, &large] {
+        assert!(transcode(input, true).iter().all(|r| r.is_ok()));
+
+        // The first member's CRC32, then the last member's ISIZE
+        let mut bad_crc = input.clone();
+        let crc_offset = if input == &multi { members[0].len() - 8 } else { input.len() - 8 };
+        bad_crc[crc_offset] ^= 0x01;
+        let mut bad_size = input.clone();
+        let last = bad_size.len() - 1;
+        bad_size[last] ^= 0x01;
+
+        for result in transcode(&bad_crc, true) {
+            assert!(matches!(result, Err(rebgzf::Error::Crc32Mismatch { .. })), "{:?}", result);
+        }
+        for result in transcode(&bad_size, true) {
+            assert!(matches!(result, Err(rebgzf::Error::SizeMismatch { .. })), "{:?}", result);
+        }
+        // Trailers go unchecked by default
+        assert!(transcode(&bad_crc, false).iter().all(|r| r.is_ok()));
+    }
+}
+
+#[test]
+fn test_tolerate_bad_input_trailer() {
+    // As in test_verify_input_crc: streaming, member-parallel and parallel-decode paths
+    let fastq = generate_fastq_data(2000, 150);
+    let multi = [compress_to_gzip(&fastq[..100_000]), compress_to_gzip(&fastq[100_000..])].concat();
+    let random = generate_random_data(1_500_000, 7);
+    let large = compress_to_gzip(&random);
+
+    let config = TranscodeConfig {
+        num_threads: 4,
+        verify_input_crc: true,
+        tolerate_bad_input_trailer: true,
+        ..Default::default()
+    };
+    let transcode = |input: &[u8]| {
+        let mut outputs = [Vec::new(), Vec::new(), Vec::new()];
+        let stats = [
+            ParallelTranscoder::new(config.clone())
+                .transcode(Cursor::new(input), &mut outputs[0])
+                .unwrap(),
+            SingleThreadedTranscoder::new(TranscodeConfig { num_threads: 1, ..config.clone() })
+                .transcode_slice(input, &mut outputs[1])
+                .unwrap(),
+            rebgzf::ParallelDecodeTranscoder::new(config.clone())
+                .transcode_mmap(input, &mut outputs[2])
+                .unwrap(),
+        ];
+        (outputs, stats.map(|s| s.input_trailer_mismatch))
+    };
+
+    for (input, expected) in [(&multi, &fastq), (&large, &random)] {
+        let (_, mismatches) = transcode(input);
+        assert_eq!(mismatches, [false; 3]);
+
+        // A wrong ISIZE on the last member is flagged, and the output is still right
+        let mut bad_size = input.clone();
+        let last = bad_size.len() - 1;
+        bad_size[last] ^= 0x01;
+        let (outputs, mismatches) = transcode(&bad_size);
+        assert_eq!(mismatches, [true; 3]);
+        for output in &outputs {
+            assert!(decompress_gzip(output) == *expected);
+        }
+    }
+}
+
+#[test]
+fn test_verify_input_header_crc() {
+    // A member whose header carries FNAME and FHCRC (the CRC16 of the bytes before it)
+    let member = |data: &[u8], corrupt_header_crc: bool| {
+        let mut bytes = vec![0x1f, 0x8b, 0x08, 0x0a, 0, 0, 0, 0, 0, 0x03];
+        bytes.extend_from_slice(b"reads.fastq\0");
+        let header_crc = crc32fast::hash(&bytes) as u16 ^ corrupt_header_crc as u16;
+        bytes.extend_from_slice(&header_crc.to_le_bytes());
+        let mut encoder = flate2::write::DeflateEncoder::new(bytes, Compression::default());
+        encoder.write_all(data).unwrap();
+        let mut bytes = encoder.finish().unwrap();
+        bytes.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes
+    };
+    let fastq = generate_fastq_data(500, 100);
+    let (first, second) = fastq.split_at(fastq.len() / 2);
+
+    let transcode = |input: &[u8], verify_input_crc: bool| {
+        let config = TranscodeConfig { num_threads: 4, verify_input_crc, ..Default::default() };
+        let single = TranscodeConfig { num_threads: 1, ..config.clone() };
+        let mut output = Vec::new();
+        [
+            ParallelTranscoder::new(config.clone())
+                .transcode(Cursor::new(input), &mut output)
+                .map(|_| ()),
+            SingleThreadedTranscoder::new(single.clone())
+                .transcode(Cursor::new(input), &mut output)
+                .map(|_| ()),
+            SingleThreadedTranscoder::new(single).transcode_slice(input, &mut output).map(|_| ()),
+            rebgzf::ParallelDecodeTranscoder::new(config)
+                .transcode_mmap(input, &mut output)
+                .map(|_| ()),
+        ]
+    };
+
+    let good = [member(first, false), member(second, false)].concat();
+    assert!(transcode(&good, true).iter().all(|r| r.is_ok()));
+    assert_eq!(decompress_gzip(&good), fastq);
+
+    // A bad header CRC on the first member, then on a following one
+    for bad in [
+        [member(first, true), member(second, false)].concat(),
+        [member(first, false), member(second, true)].concat(),
+    ] {
+        for result in transcode(&bad, true) {
+            assert!(
+                matches!(result, Err(rebgzf::Error::GzipHeaderCrcMismatch { .. })),
+                "{:?}",
+                result
+            );
+        }
+        // Header CRCs go unchecked by default
+        assert!(transcode(&bad, false).iter().all(|r| r.is_ok()));
+    }
+}
+
+#[test]
+fn test_incompressible_blocks_fit_bgzf_limit() {
+    // Random data expands past the 64KB BGZF limit at full block size with Huffman codes,
+    // so those blocks fall back to stored (or split) encoding
+    let data = generate_random_data(200_000, 44444);
+    let gzip_data = compress_to_gzip(&data);
+
+    for (num_threads, level) in
+        [(1, 1), (2, 1), (1, 6), (2, 6)].map(|(t, l)| (t, CompressionLevel::from_level(l)))
+    {
+        let config = TranscodeConfig {
+            num_threads,
+            compression_level: level,
+            extra_subfields: vec![(b'X', b'Y', vec![0; 256])],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let stats = ParallelTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut output)
+            .unwrap();
+
+        let blocks = parse_bgzf_blocks(&output);
+        assert_eq!(blocks.len() as u64, stats.blocks_written + 1);
+        assert!(blocks.iter().all(|&(bsize, _)| bsize <= 65536));
+        let verification = verify_bgzf(&mut Cursor::new(&output)).unwrap();
+        assert!(verification.is_valid_bgzf && verification.crc_valid, "{:?}", verification);
+        assert_eq!(decompress_gzip(&output), data, "{} threads, {:?}", num_threads, level);
+    }
+}
+
+#[test]
+fn test_input_was_stored() {
+    // Large enough that the stored input passes the mmap path's parallel threshold
+    let data = generate_fastq_data(5000, 150);
+    let stored = compress_to_gzip_stored(&data);
+    let compressed = compress_to_gzip(&data);
+
+    for (gzip_data, expected) in [(&stored, true), (&compressed, false)] {
+        for num_threads in [1, 4] {
+            let config = TranscodeConfig { num_threads, ..Default::default() };
+            let mut output = Vec::new();
+            let stats = ParallelTranscoder::new(config.clone())
+                .transcode(Cursor::new(gzip_data), &mut output)
+                .unwrap();
+            assert_eq!(stats.input_was_stored, expected, "{} threads", num_threads);
+            assert_eq!(decompress_gzip(&output), data);
+
+            let mut output = Vec::new();
+            let stats = rebgzf::ParallelDecodeTranscoder::new(config)
+                .transcode_mmap(gzip_data, &mut output)
+                .unwrap();
+            assert_eq!(stats.input_was_stored, expected, "mmap, {} threads", num_threads);
+            assert_eq!(decompress_gzip(&output), data);
+        }
+    }
+}
+
+#[test]
+fn test_vcf_coordinate_index_is_monotonic() {
+    let mut vcf = b"##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n".to_vec();
+    for chrom in ["chr1", "chr2", "chrX"] {
+        for i in 0..3000u64 {
+            let info = "DP=".to_string() + &(i * 7 % 50).to_string();
+            writeln!(vcf, "{}\t{}\t.\tA\tG\t50\tPASS\t{}", chrom, 1000 + i * 37, info).unwrap();
+        }
+    }
+    let gzip_data = compress_to_gzip(&vcf);
+    let chrom_rank = |chrom: &str| ["chr1", "chr2", "chrX"].iter().position(|&c| c == chrom);
+
+    for num_threads in [1, 4] {
+        let config = TranscodeConfig {
+            block_size: 8192,
+            format: FormatProfile::Vcf,
+            build_index: true,
+            num_threads,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let stats = ParallelTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut output)
+            .unwrap();
+        let coordinates = stats.coordinate_index.unwrap();
+        let entries = stats.index_entries.unwrap();
+        // The header gets a block of its own, which has no data line
+        assert_eq!(coordinates.len() + 1, entries.len(), "{} threads", num_threads);
+
+        // Sorted input gives sorted block coordinates
+        let keys: Vec<_> = coordinates.iter().map(|c| (chrom_rank(&c.chrom), c.pos)).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]), "{} threads", num_threads);
+
+        // Each is the first data line starting in its block
+        let data_lines: Vec<usize> =
+            (0..vcf.len()).filter(|&i| (i == 0 || vcf[i - 1] == b'\n') && vcf[i] != b'#').collect();
+        for c in &coordinates {
+            let first = data_lines.iter().find(|&&i| i >= c.uncompressed_offset as usize).unwrap();
+            let expected = format!("{}\t{}\t", c.chrom, c.pos);
+            assert!(vcf[*first..].starts_with(expected.as_bytes()), "{:?}", c);
+        }
+    }
+
+    // Only the VCF profile builds it
+    let config = TranscodeConfig { build_index: true, ..Default::default() };
+    let mut output = Vec::new();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut output)
+        .unwrap();
+    assert!(stats.coordinate_index.is_none());
+}
+
 // ============================================================================
 // BGZF Detection Tests
 // ============================================================================
@@ -460,6 +1304,23 @@ fn test_validate_bgzf_strict_valid() {
     assert!(validation.total_uncompressed_size.is_some());
 }
 
+#[test]
+fn test_count_blocks_matches_strict_validation() {
+    let data = generate_mixed_data(300_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut bgzf_data)
+        .unwrap();
+
+    let count = rebgzf::bgzf::count_blocks(Cursor::new(&bgzf_data)).unwrap();
+    let validation = validate_bgzf_strict(&mut Cursor::new(&bgzf_data)).unwrap();
+    assert!(count > 2);
+    assert_eq!(Some(count), validation.block_count);
+}
+
 #[test]
 fn test_validate_bgzf_strict_invalid() {
     let gzip_data = compress_to_gzip(b"Hello");
@@ -531,12 +1392,7 @@ fn test_roundtrip_property_fastq_data() {
 #[test]
 fn test_roundtrip_property_all_bytes() {
     // Test that all byte values survive the round-trip
-    let mut data = Vec::with_capacity(256 * 10);
-    for _ in 0..10 {
-        for b in 0..=255u8 {
-            data.push(b);
-        }
-    }
+    let data = generate_all_bytes(10);
 
     let gzip_data = compress_to_gzip(&data);
 
@@ -588,48 +1444,309 @@ fn test_roundtrip_property_compression_levels() {
     }
 }
 
+#[test]
+fn test_roundtrip_subblock_splitting() {
+    // Alternating regions with different statistics inside each BGZF block, so
+    // levels 7-9 emit several DEFLATE blocks per BGZF block
+    let mut data = Vec::new();
+    for i in 0..8 {
+        if i % 2 == 0 {
+            data.extend(generate_fastq_data(100, 150));
+        } else {
+            data.extend(generate_random_data(20_000, i));
+        }
+    }
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig {
+            compression_level: CompressionLevel::Level9,
+            num_threads,
+            ..Default::default()
+        };
+        let mut bgzf_data = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
+
+        let verification = verify_bgzf(&mut Cursor::new(&bgzf_data)).unwrap();
+        assert!(verification.is_valid_bgzf && verification.crc_valid, "{:?}", verification);
+        assert_eq!(decompress_gzip(&bgzf_data), data);
+    }
+}
+
+#[test]
+fn test_recompress_level1_input() {
+    // Level-1 gzip finds few matches; recompression should find more
+    let data = generate_fastq_data(3000, 150);
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(&data).unwrap();
+    let gzip_data = encoder.finish().unwrap();
+
+    for num_threads in [1, 4] {
+        let transcode = |recompress: bool| {
+            let config = TranscodeConfig {
+                compression_level: CompressionLevel::Level8,
+                num_threads,
+                recompress,
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            let stats = ParallelTranscoder::new(config)
+                .transcode(Cursor::new(&gzip_data), &mut output)
+                .unwrap();
+            (output, stats)
+        };
+        let (reused, reused_stats) = transcode(false);
+        let (recompressed, stats) = transcode(true);
+
+        assert_eq!(decompress_gzip(&recompressed), data);
+        assert!(validate_bgzf_strict(&mut Cursor::new(&recompressed)).unwrap().is_valid_bgzf);
+        assert_eq!(reused_stats.recompressed_bytes_saved, 0);
+        assert!(stats.recompressed_bytes_saved > 0, "{} threads", num_threads);
+        assert_eq!(
+            reused.len() as u64 - recompressed.len() as u64,
+            stats.recompressed_bytes_saved,
+            "{} threads",
+            num_threads
+        );
+    }
+
+    // Only levels 7-9 recompress
+    let config = TranscodeConfig { recompress: true, ..Default::default() };
+    let result =
+        SingleThreadedTranscoder::new(config).transcode(Cursor::new(&gzip_data), Vec::new());
+    assert!(matches!(result, Err(rebgzf::Error::InvalidConfig(_))));
+}
+
+#[test]
+fn test_suggest_level_from_bgzf() {
+    let to_bgzf = |data: &[u8], compression_level| {
+        let config = TranscodeConfig { block_size: 32768, compression_level, ..Default::default() };
+        let mut bgzf_data = Vec::new();
+        SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(compress_to_gzip(data)), &mut bgzf_data)
+            .unwrap();
+        bgzf_data
+    };
+
+    let compressible = to_bgzf(&generate_fastq_data(2000, 150), CompressionLevel::Level6);
+    let incompressible = to_bgzf(&generate_random_data(200_000, 55555), CompressionLevel::Level1);
+
+    let mut reader = Cursor::new(&compressible);
+    let high = CompressionLevel::suggest_from_bgzf(&mut reader).unwrap();
+    assert_eq!(reader.position(), 0, "reader should be rewound");
+    let low = CompressionLevel::suggest_from_bgzf(Cursor::new(&incompressible)).unwrap();
+    assert!(high.level() > low.level(), "{:?} vs {:?}", high, low);
+    assert_eq!(low, CompressionLevel::Level1);
+
+    // No data blocks: fall back to the default
+    let mut empty = Vec::new();
+    rebgzf::bgzf::write_empty(&mut empty).unwrap();
+    let level = CompressionLevel::suggest_from_bgzf(Cursor::new(&empty)).unwrap();
+    assert_eq!(level, CompressionLevel::default());
+}
+
+#[test]
+fn test_default_block_size_follows_level() {
+    let config = TranscodeConfig::default();
+    assert_eq!(config.block_size, config.compression_level.default_block_size());
+
+    for level in 1..=9 {
+        let block_size = CompressionLevel::from_level(level).default_block_size();
+        assert!(block_size > 0 && block_size <= rebgzf::bgzf::BGZF_BLOCK_SIZE);
+    }
+}
+
 // ============================================================================
 // BGZF Verification Tests (Deep validation with CRC check)
 // ============================================================================
 
 #[test]
-fn test_verify_bgzf_valid() {
-    // Use compressible data
-    let data = generate_mixed_data(100_000);
+fn test_verify_bgzf_valid() {
+    // Use compressible data
+    let data = generate_mixed_data(100_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    // Transcode to BGZF
+    let config = TranscodeConfig::default();
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+    let mut bgzf_data = Vec::new();
+    transcoder.transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
+
+    // Deep verification
+    let mut cursor = Cursor::new(&bgzf_data);
+    let verification = verify_bgzf(&mut cursor).unwrap();
+
+    assert!(verification.is_valid_bgzf, "Should be valid BGZF");
+    assert!(verification.crc_valid, "CRC32 checksums should match");
+    assert!(verification.isize_valid, "ISIZE values should match");
+    assert!(verification.block_count >= 2, "Should have data + EOF blocks");
+    assert_eq!(
+        verification.uncompressed_size as usize,
+        data.len(),
+        "Uncompressed size should match original data"
+    );
+    assert!(verification.first_error.is_none(), "Should have no errors");
+    assert!(verification.has_eof_block, "Should end with the EOF block");
+
+    // Dropping the EOF block is still structurally valid, but is reported
+    let truncated = &bgzf_data[..bgzf_data.len() - rebgzf::bgzf::BGZF_EOF_SIZE];
+    let verification = verify_bgzf(&mut Cursor::new(truncated)).unwrap();
+    assert!(!verification.has_eof_block);
+}
+
+#[test]
+fn test_verify_bgzf_invalid_not_bgzf() {
+    // Plain gzip is not valid BGZF
+    let gzip_data = compress_to_gzip(b"Hello");
+
+    let mut cursor = Cursor::new(&gzip_data);
+    let verification = verify_bgzf(&mut cursor).unwrap();
+
+    assert!(!verification.is_valid_bgzf, "Plain gzip should not be valid BGZF");
+    assert!(verification.first_error.is_some(), "Should have error message");
+}
+
+#[test]
+fn test_verify_bgzf_reports_first_error_offset() {
+    let data = generate_mixed_data(100_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut bgzf_data)
+        .unwrap();
+
+    let blocks: Vec<_> =
+        rebgzf::bgzf::iter_blocks(Cursor::new(&bgzf_data)).map(|b| b.unwrap()).collect();
+    assert!(blocks.len() > 3);
+    let bad = blocks[2];
+
+    // Flip a byte in the middle of block 2's DEFLATE payload
+    bgzf_data[(bad.compressed_offset + bad.compressed_size as u64 / 2) as usize] ^= 0x55;
+
+    let verification = verify_bgzf(&mut Cursor::new(&bgzf_data)).unwrap();
+    assert_eq!(verification.first_error_block, Some(2));
+    assert_eq!(verification.first_error_offset, Some(bad.compressed_offset));
+    assert_eq!(&bgzf_data[bad.compressed_offset as usize..][..2], &[0x1f, 0x8b]);
+
+    let clean = verify_bgzf(&mut Cursor::new(&bgzf_data[..bad.compressed_offset as usize]));
+    assert_eq!(clean.unwrap().first_error_offset, None);
+}
+
+#[test]
+fn test_verify_bgzf_collects_all_errors() {
+    let gzip_data = compress_to_gzip(&generate_mixed_data(100_000));
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut bgzf_data)
+        .unwrap();
+
+    let blocks: Vec<_> =
+        rebgzf::bgzf::iter_blocks(Cursor::new(&bgzf_data)).map(|b| b.unwrap()).collect();
+    assert!(blocks.len() > 4);
+    let footer =
+        |i: usize| (blocks[i].compressed_offset + blocks[i].compressed_size as u64) as usize - 8;
+
+    // Block 1 gets a wrong CRC32, block 3 an ISIZE one larger than its data
+    bgzf_data[footer(1)] ^= 0xFF;
+    let isize_at = footer(3) + 4;
+    let isize = u32::from_le_bytes(bgzf_data[isize_at..isize_at + 4].try_into().unwrap());
+    bgzf_data[isize_at..isize_at + 4].copy_from_slice(&(isize + 1).to_le_bytes());
+
+    let options = VerifyOptions { collect_all_errors: true };
+    let verification =
+        verify_bgzf_with_options(&mut Cursor::new(&bgzf_data), &options, |_| true).unwrap();
+    let expected = [
+        BlockError {
+            block_index: 1,
+            offset: blocks[1].compressed_offset,
+            kind: BlockErrorKind::Crc,
+        },
+        BlockError {
+            block_index: 3,
+            offset: blocks[3].compressed_offset,
+            kind: BlockErrorKind::Isize,
+        },
+    ];
+    assert_eq!(verification.errors, expected);
+    assert_eq!(verification.first_error_block, Some(1));
+    assert!(!verification.crc_valid && !verification.isize_valid);
+    assert!(verification.has_eof_block);
+
+    // Off by default: the first error is still described, but nothing is listed
+    let verification = verify_bgzf(&mut Cursor::new(&bgzf_data)).unwrap();
+    assert_eq!(verification.first_error_block, Some(1));
+    assert!(verification.errors.is_empty());
+}
+
+#[test]
+fn test_verify_bgzf_with_callback() {
+    let data = generate_mixed_data(200_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+    let mut bgzf_data = Vec::new();
+    transcoder.transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
+
+    // Every block, including EOF, is reported in order
+    let mut blocks = Vec::new();
+    let verification = verify_bgzf_with(&mut Cursor::new(&bgzf_data), |block| {
+        blocks.push(*block);
+        true
+    })
+    .unwrap();
+    assert!(verification.is_valid_bgzf && verification.crc_valid);
+    assert_eq!(blocks.len() as u64, verification.block_count);
+    assert_eq!(blocks.iter().map(|b| b.uncompressed_size as u64).sum::<u64>(), data.len() as u64);
+    assert_eq!(blocks.last().unwrap().uncompressed_size, 0);
+    let listed: Vec<_> =
+        rebgzf::bgzf::iter_blocks(Cursor::new(&bgzf_data)).map(|b| b.unwrap()).collect();
+    assert_eq!(blocks, listed);
+
+    // Returning false stops verification after that block
+    let mut calls = 0;
+    let partial = verify_bgzf_with(&mut Cursor::new(&bgzf_data), |_| {
+        calls += 1;
+        calls < 3
+    })
+    .unwrap();
+    assert_eq!(calls, 3);
+    assert_eq!(partial.block_count, 3);
+    assert!(!partial.has_eof_block);
+}
+
+#[test]
+fn test_verify_bgzf_tee_copies_input() {
+    let data = generate_mixed_data(200_000);
     let gzip_data = compress_to_gzip(&data);
 
-    // Transcode to BGZF
     let config = TranscodeConfig::default();
     let mut transcoder = SingleThreadedTranscoder::new(config);
     let mut bgzf_data = Vec::new();
     transcoder.transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
 
-    // Deep verification
-    let mut cursor = Cursor::new(&bgzf_data);
-    let verification = verify_bgzf(&mut cursor).unwrap();
+    let mut teed = Vec::new();
+    let verification = verify_bgzf_tee(&mut Cursor::new(&bgzf_data), &mut teed).unwrap();
+    let expected = verify_bgzf(&mut Cursor::new(&bgzf_data)).unwrap();
 
-    assert!(verification.is_valid_bgzf, "Should be valid BGZF");
-    assert!(verification.crc_valid, "CRC32 checksums should match");
-    assert!(verification.isize_valid, "ISIZE values should match");
-    assert!(verification.block_count >= 2, "Should have data + EOF blocks");
-    assert_eq!(
-        verification.uncompressed_size as usize,
-        data.len(),
-        "Uncompressed size should match original data"
-    );
-    assert!(verification.first_error.is_none(), "Should have no errors");
+    assert_eq!(teed, bgzf_data, "Teed output should be byte-identical to input");
+    assert!(verification.is_valid_bgzf && verification.crc_valid && verification.isize_valid);
+    assert_eq!(verification.block_count, expected.block_count);
+    assert_eq!(verification.uncompressed_size, expected.uncompressed_size);
 }
 
 #[test]
-fn test_verify_bgzf_invalid_not_bgzf() {
-    // Plain gzip is not valid BGZF
+fn test_verify_bgzf_tee_copies_invalid_input() {
     let gzip_data = compress_to_gzip(b"Hello");
 
-    let mut cursor = Cursor::new(&gzip_data);
-    let verification = verify_bgzf(&mut cursor).unwrap();
+    let mut teed = Vec::new();
+    let verification = verify_bgzf_tee(&mut Cursor::new(&gzip_data), &mut teed).unwrap();
 
     assert!(!verification.is_valid_bgzf, "Plain gzip should not be valid BGZF");
-    assert!(verification.first_error.is_some(), "Should have error message");
+    assert_eq!(teed, gzip_data, "Input should be copied even when verification fails");
 }
 
 // ============================================================================
@@ -688,6 +1805,33 @@ fn test_bgzf_total_uncompressed_matches() {
 // Edge Cases
 // ============================================================================
 
+#[test]
+fn test_block_size_over_bgzf_limit_is_rejected() {
+    // Random data barely compresses, so 70000-byte blocks would not fit in BGZF
+    let gzip_data = compress_to_gzip(&generate_random_data(200_000, 7));
+    let config = TranscodeConfig { block_size: 70_000, ..Default::default() };
+
+    let mut output = Vec::new();
+    let single = SingleThreadedTranscoder::new(config.clone())
+        .transcode(Cursor::new(&gzip_data), &mut output);
+    let parallel = ParallelTranscoder::new(TranscodeConfig { num_threads: 2, ..config.clone() })
+        .transcode(Cursor::new(&gzip_data), &mut output);
+    let mapped =
+        rebgzf::ParallelDecodeTranscoder::new(config).transcode_mmap(&gzip_data, &mut output);
+    for result in [single, parallel, mapped] {
+        assert!(
+            matches!(result, Err(rebgzf::Error::BgzfBlockTooLarge { size: 70_000, max: 65536 })),
+            "{:?}",
+            result
+        );
+    }
+    assert!(output.is_empty());
+
+    // Overshoot past a full-size block is capped at the BGZF limit
+    let config = TranscodeConfig { format: FormatProfile::Fastq, ..Default::default() };
+    assert_eq!(config.max_block_size(), rebgzf::bgzf::BGZF_MAX_BLOCK_SIZE);
+}
+
 #[test]
 fn test_data_at_block_boundary() {
     // Create compressible data at block size boundary
@@ -753,6 +1897,114 @@ fn test_single_byte_input() {
     assert_eq!(decompress_gzip(&output), data);
 }
 
+/// Writer that fails once `limit` bytes have been accepted
+struct FailingWriter {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let room = self.limit - self.data.len();
+        if room == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "injected failure"));
+        }
+        let n = buf.len().min(room);
+        self.data.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_failed_transcode_output_lacks_eof() {
+    // Random bases: enough entropy that the input spans many DEFLATE blocks
+    let data: Vec<u8> =
+        generate_random_data(1_000_000, 7).iter().map(|b| b"ACGT"[*b as usize % 4]).collect();
+    let gzip_data = compress_to_gzip(&data);
+    let truncated = &gzip_data[..gzip_data.len() / 2];
+
+    for num_threads in [1, 4] {
+        let config = TranscodeConfig { num_threads, buffer_size: 4096, ..Default::default() };
+        let transcode = |input: &[u8], output: &mut dyn Write| {
+            if num_threads == 1 {
+                SingleThreadedTranscoder::new(config.clone()).transcode(Cursor::new(input), output)
+            } else {
+                ParallelTranscoder::new(config.clone()).transcode(Cursor::new(input), output)
+            }
+        };
+
+        // Output error partway through
+        let mut complete = Vec::new();
+        transcode(&gzip_data, &mut complete).unwrap();
+        let mut output = FailingWriter { data: Vec::new(), limit: complete.len() / 2 };
+        assert!(transcode(&gzip_data, &mut output).is_err());
+        let verification = verify_bgzf(&mut Cursor::new(&output.data)).unwrap();
+        assert!(!verification.has_eof_block, "{} threads", num_threads);
+
+        // Input error partway through: the output holds whole blocks but no EOF block
+        let mut output = Vec::new();
+        assert!(transcode(truncated, &mut output).is_err());
+        let blocks: Vec<_> =
+            rebgzf::bgzf::iter_blocks(Cursor::new(&output)).collect::<Result<_, _>>().unwrap();
+        let block_bytes: u64 = blocks.iter().map(|b| b.compressed_size as u64).sum();
+        assert_eq!(block_bytes, output.len() as u64, "{} threads", num_threads);
+        assert!(blocks.iter().all(|b| b.uncompressed_size > 0), "{} threads", num_threads);
+        if num_threads == 1 {
+            // The serial path writes each block as soon as it is encoded
+            assert!(!blocks.is_empty());
+        }
+    }
+}
+
+#[test]
+fn test_single_write_below() {
+    // Random bases, so the output spans many buffers
+    let data: Vec<u8> =
+        generate_random_data(200_000, 11).iter().map(|b| b"ACGT"[*b as usize % 4]).collect();
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 4] {
+        let transcode = |single_write_below: usize| {
+            let config = TranscodeConfig {
+                num_threads,
+                block_size: 16384,
+                buffer_size: 4096,
+                single_write_below,
+                ..Default::default()
+            };
+            let mut output = CountingWriter::default();
+            if num_threads == 1 {
+                SingleThreadedTranscoder::new(config)
+                    .transcode(Cursor::new(&gzip_data), &mut output)
+                    .unwrap();
+            } else {
+                ParallelTranscoder::new(config)
+                    .transcode(Cursor::new(&gzip_data), &mut output)
+                    .unwrap();
+            }
+            output
+        };
+
+        let streamed = transcode(0);
+        assert!(streamed.writes > 2, "{} threads", num_threads);
+
+        // The whole output, EOF block included, goes out in one write
+        let coalesced = transcode(1 << 20);
+        assert_eq!(coalesced.writes, 1, "{} threads", num_threads);
+        assert_eq!(coalesced.data, streamed.data, "{} threads", num_threads);
+        assert!(verify_bgzf(&mut Cursor::new(&coalesced.data)).unwrap().has_eof_block);
+
+        // Outputs past the threshold stream as before
+        let large = transcode(streamed.data.len() / 2);
+        assert!(large.writes > 2, "{} threads", num_threads);
+        assert_eq!(large.data, streamed.data, "{} threads", num_threads);
+    }
+}
+
 // ============================================================================
 // Stats Verification
 // ============================================================================
@@ -769,9 +2021,12 @@ fn test_stats_accuracy() {
 
     let stats = transcoder.transcode(Cursor::new(&gzip_data), &mut output).unwrap();
 
-    // Input bytes should be approximately the compressed input size (may differ slightly)
-    assert!(stats.input_bytes > 0);
-    assert!(stats.input_bytes <= gzip_data.len() as u64 + 100); // Allow small tolerance
+    // Input bytes count exactly the gzip stream, header through trailer
+    assert_eq!(stats.input_bytes, gzip_data.len() as u64);
+    let slice_stats = SingleThreadedTranscoder::new(TranscodeConfig::default())
+        .transcode_slice(&gzip_data, &mut Vec::new())
+        .unwrap();
+    assert_eq!(slice_stats.input_bytes, gzip_data.len() as u64);
 
     // Output bytes should match actual output size
     assert_eq!(stats.output_bytes, output.len() as u64);
@@ -782,6 +2037,126 @@ fn test_stats_accuracy() {
     assert_eq!(stats.blocks_written, data_blocks as u64);
 }
 
+#[test]
+fn test_on_block_reports_blocks_in_output_order() {
+    use rebgzf::{BlockCallback, BlockProgress};
+    use std::sync::{Arc, Mutex};
+
+    let data = generate_fastq_data(2000, 150);
+    let gzip_data = compress_to_gzip(&data);
+
+    for (num_threads, mmap) in [(1, false), (4, false), (4, true)] {
+        let progress = Arc::new(Mutex::new(Vec::<BlockProgress>::new()));
+        let sink = Arc::clone(&progress);
+        let config = TranscodeConfig {
+            block_size: 8192,
+            num_threads,
+            on_block: Some(BlockCallback::new(move |p| sink.lock().unwrap().push(p))),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        if mmap {
+            rebgzf::ParallelDecodeTranscoder::new(config)
+                .transcode_mmap(&gzip_data, &mut output)
+                .unwrap();
+        } else {
+            ParallelTranscoder::new(config)
+                .transcode(Cursor::new(&gzip_data), &mut output)
+                .unwrap();
+        }
+
+        // One call per data block, in order, matching the blocks on disk
+        let progress = progress.lock().unwrap();
+        let blocks = parse_bgzf_blocks(&output);
+        assert_eq!(progress.len(), blocks.len() - 1, "{} threads", num_threads);
+        let (mut cumulative_in, mut cumulative_out) = (0, 0);
+        for (i, (p, &(bsize, isize))) in progress.iter().zip(&blocks).enumerate() {
+            cumulative_in += isize as u64;
+            cumulative_out += bsize as u64;
+            let expected = BlockProgress {
+                block_id: i as u64,
+                compressed_size: bsize as u32,
+                uncompressed_size: isize,
+                cumulative_in,
+                cumulative_out,
+            };
+            assert_eq!(*p, expected, "{} threads, mmap {}", num_threads, mmap);
+        }
+        assert_eq!(cumulative_in, data.len() as u64);
+    }
+}
+
+#[test]
+fn test_stats_block_size_range() {
+    let data = generate_mixed_data(200_000);
+    let gzip_data = compress_to_gzip(&data);
+    let block_size = 16384;
+
+    let config = TranscodeConfig { block_size, ..Default::default() };
+    let mut output = Vec::new();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut output)
+        .unwrap();
+
+    let sizes: Vec<u32> =
+        parse_bgzf_blocks(&output).iter().map(|&(_, isize)| isize).filter(|&s| s > 0).collect();
+    assert_eq!(stats.min_block_uncompressed, *sizes.iter().min().unwrap());
+    assert_eq!(stats.max_block_uncompressed, *sizes.iter().max().unwrap());
+    assert!(stats.max_block_uncompressed as usize <= block_size);
+
+    for threads in [2, 4] {
+        let config = TranscodeConfig { block_size, num_threads: threads, ..Default::default() };
+        let parallel = ParallelTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+            .unwrap();
+        assert_eq!(parallel.min_block_uncompressed, stats.min_block_uncompressed);
+        assert_eq!(parallel.max_block_uncompressed, stats.max_block_uncompressed);
+    }
+}
+
+#[test]
+fn test_stats_block_size_range_smart_boundaries() {
+    let data = generate_fastq_data(2000, 150);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config =
+        TranscodeConfig { block_size: 8192, format: FormatProfile::Fastq, ..Default::default() };
+    let max_block_size = config.max_block_size();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+        .unwrap();
+
+    assert!(stats.min_block_uncompressed > 0);
+    assert!(stats.min_block_uncompressed <= stats.max_block_uncompressed);
+    assert!(stats.max_block_uncompressed as usize <= max_block_size);
+}
+
+#[test]
+fn test_stats_resolved_bytes() {
+    let data = generate_mixed_data(200_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let transcode = |block_size: usize| {
+        let config = TranscodeConfig { block_size, ..Default::default() };
+        SingleThreadedTranscoder::new(config)
+            .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+            .unwrap()
+    };
+
+    let small = transcode(4096);
+    let large = transcode(65280);
+    assert!(small.resolved_bytes > 0);
+    assert!(small.resolved_bytes >= small.boundary_refs_resolved * 3); // min copy length is 3
+    assert!(large.resolved_bytes * 10 < small.resolved_bytes);
+
+    // The parallel transcoder resolves the same references
+    let config = TranscodeConfig { block_size: 4096, num_threads: 2, ..Default::default() };
+    let parallel = ParallelTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+        .unwrap();
+    assert_eq!(parallel.resolved_bytes, small.resolved_bytes);
+}
+
 // ============================================================================
 // Compression Level Input Tests
 // ============================================================================
@@ -854,8 +2229,8 @@ fn test_concatenated_gzip_single_threaded() {
     let expected: Vec<u8> = [data1, data2, data3].concat();
     assert_eq!(decompress_gzip(&output), expected);
 
-    // Verify we processed more than just the first member
-    assert!(stats.input_bytes > gzip1.len() as u64);
+    // Every member was consumed, trailers included
+    assert_eq!(stats.input_bytes, concat_gzip.len() as u64);
 }
 
 #[test]
@@ -884,8 +2259,335 @@ fn test_concatenated_gzip_parallel() {
     let expected: Vec<u8> = [data1, data2].concat();
     assert_eq!(decompress_gzip(&output), expected);
 
-    // Verify we processed both members
-    assert!(stats.input_bytes > gzip1.len() as u64);
+    // Both members were consumed, trailers included
+    assert_eq!(stats.input_bytes, concat_gzip.len() as u64);
+}
+
+#[test]
+#[cfg(feature = "mmap")]
+fn test_transcode_mmap_concatenated_members() {
+    use rebgzf::BlockCallback;
+    use std::sync::{Arc, Mutex};
+
+    let members =
+        [generate_fastq_data(2_000, 100), generate_mixed_data(150_000), b"tail\n".to_vec()];
+    let concat_gzip: Vec<u8> = members.iter().flat_map(|m| compress_to_gzip(m)).collect();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("concat.gz");
+    std::fs::write(&path, &concat_gzip).unwrap();
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&progress);
+    let config = TranscodeConfig {
+        num_threads: 3,
+        build_index: true,
+        on_block: Some(BlockCallback::new(move |p| seen.lock().unwrap().push(p))),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    let stats = ParallelTranscoder::new(config).transcode_mmap(&path, &mut output).unwrap();
+
+    assert_eq!(decompress_gzip(&output), members.concat());
+    assert_eq!(stats.input_bytes, concat_gzip.len() as u64);
+    assert_eq!(stats.output_bytes, output.len() as u64);
+    let verification = verify_bgzf(&mut Cursor::new(&output)).unwrap();
+    assert!(verification.crc_valid && verification.has_eof_block, "{:?}", verification);
+
+    // The index and progress follow output order
+    let blocks = parse_bgzf_blocks(&output);
+    let mut offset = 0u64;
+    let mut uncompressed = 0u64;
+    let index = stats.index_entries.unwrap();
+    for (i, &(size, isize)) in blocks.iter().filter(|&&(_, isize)| isize > 0).enumerate() {
+        assert_eq!(index[i].compressed_offset, offset);
+        assert_eq!(index[i].uncompressed_offset, uncompressed);
+        offset += size as u64;
+        uncompressed += isize as u64;
+    }
+    assert_eq!(index.len() as u64, stats.blocks_written);
+    let progress = progress.lock().unwrap();
+    assert_eq!(progress.len() as u64, stats.blocks_written);
+    assert!(progress.iter().enumerate().all(|(i, p)| p.block_id == i as u64));
+    assert_eq!(progress.last().unwrap().cumulative_in, uncompressed);
+
+    // Same bytes as the slice API, and a single-member file still round-trips
+    let mut from_slice = Vec::new();
+    ParallelTranscoder::new(TranscodeConfig { num_threads: 3, ..Default::default() })
+        .transcode_slice(&concat_gzip, &mut from_slice)
+        .unwrap();
+    assert_eq!(from_slice, output);
+    let mut single = Vec::new();
+    ParallelTranscoder::new(TranscodeConfig { num_threads: 3, ..Default::default() })
+        .transcode_slice(&compress_to_gzip(&members[0]), &mut single)
+        .unwrap();
+    assert_eq!(decompress_gzip(&single), members[0]);
+}
+
+#[test]
+fn test_wrong_input_trailer_still_transcodes() {
+    // Input trailers are skipped, not checked: output CRCs and sizes are recomputed
+    let data = generate_fastq_data(1_000, 100);
+    let mut gzip_data = compress_to_gzip(&data);
+    let isize_start = gzip_data.len() - 4;
+    gzip_data[isize_start..].copy_from_slice(&12345u32.to_le_bytes());
+    gzip_data[isize_start - 4] ^= 0xFF;
+    // A second member follows the bad trailer
+    gzip_data.extend(compress_to_gzip(b"@tail\nACGT\n+\nIIII\n"));
+    let mut expected = data.clone();
+    expected.extend_from_slice(b"@tail\nACGT\n+\nIIII\n");
+
+    for num_threads in [1, 4] {
+        let config = TranscodeConfig { num_threads, ..Default::default() };
+        let mut output = Vec::new();
+        if num_threads == 1 {
+            SingleThreadedTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output)
+        } else {
+            ParallelTranscoder::new(config).transcode(Cursor::new(&gzip_data), &mut output)
+        }
+        .unwrap();
+        assert_eq!(decompress_gzip(&output), expected, "{} threads", num_threads);
+        let verification = verify_bgzf(&mut Cursor::new(&output)).unwrap();
+        assert!(verification.crc_valid && verification.isize_valid, "{:?}", verification);
+    }
+}
+
+#[test]
+fn test_concatenated_gzip_bad_method_names_member() {
+    let gzip1 = compress_to_gzip(&generate_mixed_data(10_000));
+    let mut gzip2 = compress_to_gzip(b"second member");
+    gzip2[2] = 7; // Not DEFLATE
+
+    let gzip1_len = gzip1.len();
+    let mut concat_gzip = gzip1;
+    concat_gzip.extend_from_slice(&gzip2);
+
+    let config = TranscodeConfig::default();
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+    let err = transcoder.transcode(Cursor::new(&concat_gzip), &mut Vec::new()).unwrap_err();
+    assert!(err.to_string().contains("gzip member 1"), "Unexpected message: {}", err);
+
+    match err {
+        rebgzf::Error::UnsupportedCompressionMethod { method, member, offset } => {
+            assert_eq!(method, 7);
+            assert_eq!(member, 1);
+            assert_eq!(offset, gzip1_len as u64);
+        }
+        other => panic!("Expected UnsupportedCompressionMethod, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_block_per_member() {
+    let members = [b"first\n".repeat(100), b"second\n".repeat(200), b"third\n".repeat(50)];
+    let concat_gzip: Vec<u8> = members.iter().flat_map(|m| compress_to_gzip(m)).collect();
+
+    // Member ends in uncompressed coordinates
+    let member_ends: Vec<u64> = members
+        .iter()
+        .scan(0u64, |end, m| {
+            *end += m.len() as u64;
+            Some(*end)
+        })
+        .collect();
+
+    for num_threads in [1, 2] {
+        let config = TranscodeConfig { num_threads, block_per_member: true, ..Default::default() };
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(Cursor::new(&concat_gzip), &mut output).unwrap();
+        assert_eq!(decompress_gzip(&output), members.concat());
+
+        // One data block per member, each ending exactly at a member end
+        let block_ends: Vec<u64> = parse_bgzf_blocks(&output)
+            .iter()
+            .filter(|&&(_, isize)| isize > 0)
+            .scan(0u64, |end, &(_, isize)| {
+                *end += isize as u64;
+                Some(*end)
+            })
+            .collect();
+        assert_eq!(block_ends, member_ends);
+    }
+
+    // Without the option the three small members share one block
+    let mut output = Vec::new();
+    SingleThreadedTranscoder::new(TranscodeConfig::default())
+        .transcode(Cursor::new(&concat_gzip), &mut output)
+        .unwrap();
+    assert_eq!(parse_bgzf_blocks(&output).len(), 2); // data + EOF
+}
+
+#[test]
+fn test_stats_report_member_trailers() {
+    let members = [b"first\n".repeat(100), generate_mixed_data(300_000), b"third\n".repeat(50)];
+    let concat_gzip: Vec<u8> = members.iter().flat_map(|m| compress_to_gzip(m)).collect();
+    let expected: Vec<MemberInfo> = members
+        .iter()
+        .map(|m| MemberInfo {
+            crc32: crc32fast::hash(m),
+            isize: m.len() as u32,
+            uncompressed_bytes: m.len() as u64,
+        })
+        .collect();
+
+    for num_threads in [1, 2] {
+        // Off by default: members are counted but not listed
+        let config = TranscodeConfig { num_threads, ..Default::default() };
+        let stats = ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&concat_gzip), &mut Vec::new())
+            .unwrap();
+        assert_eq!(stats.members_processed, 3);
+        assert!(stats.members.is_empty());
+        let stats = ParallelTranscoder::new(config).transcode_slice(&concat_gzip, &mut Vec::new());
+        assert_eq!(stats.unwrap().members_processed, 3);
+
+        let config =
+            TranscodeConfig { num_threads, collect_member_stats: true, ..Default::default() };
+        let stats = ParallelTranscoder::new(config.clone())
+            .transcode(Cursor::new(&concat_gzip), &mut Vec::new())
+            .unwrap();
+        assert_eq!(stats.members_processed, 3);
+        assert_eq!(stats.members, expected);
+
+        // Member-parallel path
+        let stats =
+            ParallelTranscoder::new(config).transcode_slice(&concat_gzip, &mut Vec::new()).unwrap();
+        assert_eq!(stats.members_processed, 3);
+        assert_eq!(stats.members, expected);
+        assert_eq!(stats.uncompressed_bytes, members.concat().len() as u64);
+    }
+}
+
+#[test]
+fn test_member_parallel_matches_single_threaded() {
+    // Large and tiny members, over enough input for several work units
+    let members: Vec<Vec<u8>> = (0..12)
+        .map(|i| match i % 3 {
+            0 => [generate_fastq_data(500 * i, 100), generate_random_data(600_000, i as u64 + 1)]
+                .concat(),
+            1 => generate_mixed_data(50_000 + 10_000 * i),
+            _ => format!("tiny member {}\n", i).into_bytes(),
+        })
+        .collect();
+    let concat_gzip: Vec<u8> = members.iter().flat_map(|m| compress_to_gzip(m)).collect();
+    assert!(concat_gzip.len() > 1 << 21);
+
+    let config = TranscodeConfig { num_threads: 1, ..Default::default() };
+    let mut expected = Vec::new();
+    SingleThreadedTranscoder::new(config).transcode_slice(&concat_gzip, &mut expected).unwrap();
+    assert_eq!(decompress_gzip(&expected), members.concat());
+
+    for num_threads in [1, 2, 4] {
+        let config = TranscodeConfig { num_threads, ..Default::default() };
+        let mut output = Vec::new();
+        let stats = rebgzf::ParallelDecodeTranscoder::new(config.clone())
+            .transcode_mmap(&concat_gzip, &mut output)
+            .unwrap();
+        assert!(output == expected, "{} threads, mmap", num_threads);
+        assert_eq!(stats.input_bytes, concat_gzip.len() as u64);
+        assert_eq!(stats.blocks_written, parse_bgzf_blocks(&output).len() as u64 - 1);
+
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config.clone()).transcode_slice(&concat_gzip, &mut output).unwrap();
+        assert!(output == expected, "{} threads, slice", num_threads);
+
+        let mut output = Vec::new();
+        ParallelTranscoder::new(config).transcode(&concat_gzip[..], &mut output).unwrap();
+        assert!(output == expected, "{} threads, stream", num_threads);
+    }
+}
+
+#[test]
+fn test_block_per_member_rejects_min_block_size() {
+    let config = TranscodeConfig {
+        block_per_member: true,
+        min_block_size: Some(1024),
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+// ============================================================================
+// Output Portability Tests
+// ============================================================================
+
+// Three FASTQ records compressed by zlib at level 9 with mtime 0.
+const INPUT_GZIP: [u8; 84] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xa5, 0x8e, 0xb1, 0x09, 0x00, 0x20,
+    0x10, 0x03, 0xfb, 0xac, 0x62, 0xa5, 0x2e, 0xe0, 0xf3, 0x85, 0xda, 0xff, 0x02, 0x82, 0x2e, 0xe0,
+    0xfe, 0x85, 0x88, 0x7c, 0x21, 0x3c, 0x36, 0x06, 0x52, 0x24, 0x90, 0x23, 0x69, 0x8e, 0xd6, 0x3d,
+    0x88, 0xb3, 0x6c, 0x8b, 0x64, 0x62, 0xa6, 0x13, 0xb4, 0x80, 0x43, 0x7d, 0x0b, 0x69, 0x63, 0x02,
+    0xcc, 0xb9, 0x16, 0x17, 0xa7, 0xa8, 0x0c, 0x4e, 0xfc, 0xbe, 0xb3, 0x00, 0x45, 0x52, 0x07, 0xc5,
+    0xd6, 0x00, 0x00, 0x00,
+];
+
+// Expected BGZF bytes for `INPUT_GZIP` at 64-byte blocks. All multi-byte fields are
+// little-endian by spec, so these must match on every architecture; a change here
+// means the output format changed (or native-endian bytes leaked in).
+const EXPECTED_BGZF_FIXED: [u8; 334] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x36, 0x00, 0x73, 0x28, 0x4a, 0x4d, 0x4c, 0x31, 0xe4, 0x72, 0x74, 0x76, 0x0f, 0x01, 0xe1, 0x90,
+    0x10, 0x77, 0x47, 0x67, 0x67, 0x47, 0x08, 0x07, 0x26, 0xc0, 0xa5, 0xcd, 0xe5, 0x09, 0x00, 0x1f,
+    0x4c, 0xbe, 0xe4, 0x28, 0x00, 0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00, 0x56, 0x00, 0xf3, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4,
+    0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4,
+    0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xe4, 0x72, 0x28, 0x4a, 0x4d, 0x4c, 0x31, 0xe2, 0x0a,
+    0x09, 0x71, 0x77, 0x74, 0x76, 0x76, 0x74, 0x0f, 0x71, 0x74, 0x76, 0x0f, 0x71, 0x74, 0x76, 0x0f,
+    0x09, 0x09, 0x71, 0x77, 0x04, 0x00, 0xba, 0x0b, 0x74, 0x66, 0x3b, 0x00, 0x00, 0x00, 0x1f, 0x8b,
+    0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00, 0x46, 0x00,
+    0x73, 0x76, 0x76, 0x74, 0x0f, 0x71, 0x74, 0x76, 0x77, 0xe4, 0xd2, 0xe6, 0xf2, 0xf4, 0xf4, 0xf4,
+    0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0x80, 0x01, 0x4f, 0x4f, 0x4f, 0x4f, 0x4f, 0x4f, 0x4f, 0x4f,
+    0x4f, 0x4f, 0x4f, 0x4f, 0x4f, 0x2e, 0x87, 0xa2, 0xd4, 0xc4, 0x14, 0x63, 0x00, 0x0f, 0x24, 0xec,
+    0xde, 0x32, 0x00, 0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06,
+    0x00, 0x42, 0x43, 0x02, 0x00, 0x5c, 0x00, 0xe3, 0x72, 0x74, 0x76, 0x0f, 0x71, 0x74, 0x76, 0x0f,
+    0x09, 0x09, 0x71, 0x77, 0x74, 0x76, 0x76, 0x74, 0x0f, 0x71, 0x74, 0x76, 0x0f, 0x71, 0x74, 0x76,
+    0x0f, 0x09, 0x09, 0x71, 0x77, 0xe4, 0xd2, 0xe6, 0xf2, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4,
+    0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4,
+    0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xf4, 0xe4, 0x02, 0x00, 0x55, 0x75, 0xff, 0xf5, 0x41, 0x00,
+    0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+    0x02, 0x00, 0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const EXPECTED_BGZF_DYNAMIC: [u8; 288] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x36, 0x00, 0x73, 0x28, 0x4a, 0x4d, 0x4c, 0x31, 0xe4, 0x72, 0x74, 0x76, 0x0f, 0x01, 0xe1, 0x90,
+    0x10, 0x77, 0x47, 0x67, 0x67, 0x47, 0x08, 0x07, 0x26, 0xc0, 0xa5, 0xcd, 0xe5, 0x09, 0x00, 0x1f,
+    0x4c, 0xbe, 0xe4, 0x28, 0x00, 0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00, 0x46, 0x00, 0x05, 0xc0, 0x31, 0x0d, 0xc0, 0x30, 0x0c,
+    0x04, 0xc0, 0x3d, 0x70, 0x8a, 0xa0, 0x96, 0x07, 0xeb, 0xf6, 0x27, 0x50, 0xa9, 0x21, 0x10, 0xfe,
+    0x43, 0x0e, 0x00, 0x00, 0x00, 0x58, 0xef, 0xd9, 0xdf, 0xff, 0xac, 0x64, 0xaa, 0xbb, 0x26, 0xd5,
+    0x93, 0xea, 0x49, 0x32, 0x75, 0x01, 0xba, 0x0b, 0x74, 0x66, 0x3b, 0x00, 0x00, 0x00, 0x1f, 0x8b,
+    0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00, 0x43, 0x00,
+    0x35, 0xc0, 0xa1, 0x11, 0xc0, 0x20, 0x0c, 0x00, 0x40, 0x9f, 0x55, 0x2a, 0xbb, 0x40, 0x73, 0x11,
+    0xf4, 0x3d, 0x0b, 0x70, 0x07, 0x0b, 0xb0, 0xbf, 0x40, 0xf1, 0x55, 0xd9, 0x7a, 0x56, 0xcb, 0x78,
+    0x02, 0xc0, 0x7f, 0x01, 0x80, 0xf8, 0xf6, 0x1a, 0xf3, 0x3d, 0x0f, 0x24, 0xec, 0xde, 0x32, 0x00,
+    0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+    0x02, 0x00, 0x41, 0x00, 0x05, 0xc0, 0x31, 0x0d, 0xc0, 0x40, 0x10, 0x03, 0xb0, 0xfd, 0xa9, 0x14,
+    0x45, 0x74, 0x43, 0xe4, 0x3d, 0xfc, 0xb1, 0xd4, 0x2f, 0xd7, 0xe5, 0xba, 0xad, 0xb9, 0x4b, 0x97,
+    0xeb, 0x72, 0xdd, 0xd6, 0xbc, 0xef, 0x01, 0x00, 0x00, 0x00, 0xde, 0x0f, 0x55, 0x75, 0xff, 0xf5,
+    0x41, 0x00, 0x00, 0x00, 0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00,
+    0x42, 0x43, 0x02, 0x00, 0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn test_output_is_byte_exact() {
+    for (level, expected) in [
+        (CompressionLevel::Level1, &EXPECTED_BGZF_FIXED[..]),
+        (CompressionLevel::Level6, &EXPECTED_BGZF_DYNAMIC[..]),
+    ] {
+        for num_threads in [1, 2] {
+            let config = TranscodeConfig {
+                block_size: 64,
+                compression_level: level,
+                num_threads,
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            ParallelTranscoder::new(config)
+                .transcode(Cursor::new(&INPUT_GZIP), &mut output)
+                .unwrap();
+            assert_eq!(output, expected, "{:?} with {} threads", level, num_threads);
+        }
+        assert_eq!(decompress_gzip(expected), decompress_gzip(&INPUT_GZIP));
+    }
 }
 
 // ============================================================================
@@ -943,6 +2645,120 @@ fn test_cli_check_gzip() {
     std::fs::remove_file(&gzip_path).ok();
 }
 
+#[test]
+#[ignore] // Run with --ignored flag when binary is available
+fn test_cli_index_bgzf_input_without_transcoding() {
+    let data = generate_mixed_data(300_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    // Odd block size so a re-transcode would change the block layout
+    let config = TranscodeConfig { block_size: 20_000, build_index: true, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut bgzf_data)
+        .unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let input_path = temp_dir.path().join("input.bgzf");
+    let output_path = temp_dir.path().join("output.bgzf");
+    let index_path = temp_dir.path().join("output.gzi");
+    std::fs::write(&input_path, &bgzf_data).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--bin", "rebgzf", "--", "-i"])
+        .arg(&input_path)
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--index")
+        .arg(&index_path)
+        .output()
+        .expect("Failed to run CLI");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(std::fs::read(&output_path).unwrap(), bgzf_data);
+    assert_eq!(
+        std::fs::read(&index_path).unwrap(),
+        serialize_gzi_entries(&stats.index_entries.unwrap())
+    );
+}
+
+#[test]
+#[ignore] // Run with --ignored flag when binary is available
+fn test_cli_stats_verbose_block_histogram() {
+    // A full block followed by a shorter trailing block gives two distinct buckets
+    let data = generate_mixed_data(100_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let mut transcoder = SingleThreadedTranscoder::new(TranscodeConfig::default());
+    let mut bgzf_data = Vec::new();
+    transcoder.transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
+
+    let temp_dir = std::env::temp_dir();
+    let bgzf_path = temp_dir.join("test_cli_stats_histogram.bgzf");
+    std::fs::write(&bgzf_path, &bgzf_data).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--bin", "rebgzf", "--", "--stats", "--verbose", "-i"])
+        .arg(&bgzf_path)
+        .output()
+        .expect("Failed to run CLI");
+
+    assert!(output.status.success(), "CLI should succeed in stats mode");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Block size distribution"), "missing histogram: {}", stderr);
+    let populated = stderr
+        .lines()
+        .filter(|line| line.contains("KiB:") && line.trim_end().ends_with('#'))
+        .count();
+    assert_eq!(populated, 2, "expected two populated buckets: {}", stderr);
+    assert!(stderr.contains("empty/EOF:"), "missing EOF count: {}", stderr);
+
+    std::fs::remove_file(&bgzf_path).ok();
+}
+
+#[test]
+#[ignore] // Run with --ignored flag when binary is available
+fn test_cli_block_range_matches_full_decompress() {
+    let data = generate_mixed_data(40_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { block_size: 8192, ..Default::default() };
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+    let mut bgzf_data = Vec::new();
+    transcoder.transcode(Cursor::new(&gzip_data), &mut bgzf_data).unwrap();
+
+    // Uncompressed start of each block, to slice the full decompress
+    let mut starts = vec![0u64];
+    for block in rebgzf::bgzf::iter_blocks(Cursor::new(&bgzf_data)) {
+        let block = block.unwrap();
+        starts.push(starts.last().unwrap() + block.uncompressed_size as u64);
+    }
+    assert!(starts.len() > 5, "need at least four data blocks");
+
+    let temp_dir = std::env::temp_dir();
+    let bgzf_path = temp_dir.join("test_cli_block_range.bgzf");
+    let out_path = temp_dir.join("test_cli_block_range.out");
+    std::fs::write(&bgzf_path, &bgzf_data).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--bin", "rebgzf", "--", "--block-range", "1:4", "-i"])
+        .arg(&bgzf_path)
+        .arg("-o")
+        .arg(&out_path)
+        .output()
+        .expect("Failed to run CLI");
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let full = decompress_gzip(&bgzf_data);
+    let expected = &full[starts[1] as usize..starts[4] as usize];
+    assert_eq!(std::fs::read(&out_path).unwrap(), expected);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("{} bytes uncompressed", expected.len())), "{}", stderr);
+
+    std::fs::remove_file(&bgzf_path).ok();
+    std::fs::remove_file(&out_path).ok();
+}
+
 // ============================================================================
 // Index Generation Tests
 // ============================================================================
@@ -1015,6 +2831,112 @@ fn test_index_generation_parallel() {
     }
 }
 
+/// Serialize index entries in GZI layout (count, then offset pairs) for byte-level comparison.
+fn serialize_gzi_entries(entries: &[rebgzf::GziEntry]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + entries.len() * 16);
+    bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    for entry in entries {
+        bytes.extend_from_slice(&entry.compressed_offset.to_le_bytes());
+        bytes.extend_from_slice(&entry.uncompressed_offset.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn test_index_parallel_matches_single_thread() {
+    let data = generate_mixed_data(500_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { build_index: true, ..Default::default() };
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+    let stats = transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()).unwrap();
+    let expected = stats.index_entries.unwrap();
+    assert!(expected.len() > 1, "Test input should span several blocks");
+    let expected_bytes = serialize_gzi_entries(&expected);
+
+    for threads in [2, 4, 8] {
+        let config =
+            TranscodeConfig { build_index: true, num_threads: threads, ..Default::default() };
+        let mut transcoder = ParallelTranscoder::new(config);
+        let stats = transcoder.transcode(Cursor::new(&gzip_data), &mut Vec::new()).unwrap();
+        let entries = stats.index_entries.unwrap();
+
+        assert!(
+            entries.windows(2).all(|w| w[0].compressed_offset < w[1].compressed_offset
+                && w[0].uncompressed_offset < w[1].uncompressed_offset),
+            "Index offsets not strictly increasing with {} threads",
+            threads
+        );
+        assert_eq!(entries, expected, "Index entries differ with {} threads", threads);
+        assert_eq!(
+            serialize_gzi_entries(&entries),
+            expected_bytes,
+            "Serialized index differs with {} threads",
+            threads
+        );
+    }
+}
+
+#[test]
+fn test_transcode_with_index_matches_output() {
+    let data = generate_mixed_data(500_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    for num_threads in [1, 4] {
+        // build_index is off; transcode_with_index turns it on for the call
+        let config = TranscodeConfig { num_threads, ..Default::default() };
+        let mut output = Vec::new();
+        let mut index = Vec::new();
+        let stats = ParallelTranscoder::new(config)
+            .transcode_with_index(Cursor::new(&gzip_data), &mut output, &mut index)
+            .unwrap();
+        assert_eq!(decompress_gzip(&output), data);
+
+        // The index describes every data block of the output, in GZI layout
+        let mut expected = Vec::new();
+        let mut entry = rebgzf::GziEntry { compressed_offset: 0, uncompressed_offset: 0 };
+        for (bsize, isize) in parse_bgzf_blocks(&output).into_iter().filter(|&(_, i)| i > 0) {
+            expected.push(entry);
+            entry.compressed_offset += bsize as u64;
+            entry.uncompressed_offset += isize as u64;
+        }
+        assert!(expected.len() > 1, "Test input should span several blocks");
+        assert_eq!(index, serialize_gzi_entries(&expected), "{} threads", num_threads);
+        assert_eq!(stats.index_entries.unwrap(), expected);
+    }
+
+    let mut index = Vec::new();
+    SingleThreadedTranscoder::new(TranscodeConfig::default())
+        .transcode_with_index(Cursor::new(&gzip_data), &mut Vec::new(), &mut index)
+        .unwrap();
+    assert_eq!(&index[..8], &(index.len() as u64 / 16).to_le_bytes());
+}
+
+#[test]
+fn test_copy_with_index_matches_transcode_index() {
+    let data = generate_mixed_data(500_000);
+    let gzip_data = compress_to_gzip(&data);
+
+    let config = TranscodeConfig { build_index: true, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut bgzf_data)
+        .unwrap();
+    let expected = stats.index_entries.unwrap();
+    assert!(expected.len() > 1, "Test input should span several blocks");
+
+    // Indexing the BGZF output directly gives the index built during transcoding
+    let mut copy = Vec::new();
+    let copy_stats =
+        rebgzf::bgzf::copy_with_index(&mut Cursor::new(&bgzf_data), &mut copy).unwrap();
+    assert_eq!(copy, bgzf_data);
+    assert!(copy_stats.copied_directly);
+    assert_eq!(copy_stats.blocks_written, stats.blocks_written);
+    assert_eq!(copy_stats.output_bytes, bgzf_data.len() as u64);
+    assert_eq!(copy_stats.index_entries.unwrap(), expected);
+    assert_eq!(rebgzf::bgzf::index_bgzf(Cursor::new(&bgzf_data)).unwrap(), expected);
+}
+
 #[test]
 fn test_index_disabled_by_default() {
     let data = generate_mixed_data(100_000);
@@ -1030,6 +2952,50 @@ fn test_index_disabled_by_default() {
     assert!(stats.index_entries.is_none());
 }
 
+// ============================================================================
+// File Conversion Tests
+// ============================================================================
+
+#[test]
+fn test_convert_gzip_file_to_bgzf() {
+    let data = generate_fastq_data(5_000, 100);
+    let temp_dir = tempfile::tempdir().unwrap();
+    let input_path = temp_dir.path().join("reads.fastq.gz");
+    std::fs::write(&input_path, compress_to_gzip(&data)).unwrap();
+
+    for num_threads in [1, 4] {
+        let output_path = temp_dir.path().join(format!("reads.{num_threads}.bgz"));
+        let config = TranscodeConfig { num_threads, build_index: true, ..Default::default() };
+        let stats = rebgzf::convert(&input_path, &output_path, config).unwrap();
+        assert!(!stats.copied_directly);
+
+        let bgzf_data = std::fs::read(&output_path).unwrap();
+        assert!(verify_bgzf_format(&bgzf_data));
+        assert_eq!(decompress_gzip(&bgzf_data), data, "{} threads", num_threads);
+        assert_eq!(stats.output_bytes, bgzf_data.len() as u64);
+
+        // The index lands beside the output and describes its blocks
+        let index = std::fs::read(rebgzf::index_path(&output_path)).unwrap();
+        let expected = rebgzf::bgzf::index_bgzf(Cursor::new(&bgzf_data)).unwrap();
+        assert_eq!(&index[..8], &(expected.len() as u64).to_le_bytes());
+        assert_eq!(stats.index_entries.unwrap(), expected);
+    }
+
+    // BGZF input is copied as-is unless transcoding is forced
+    let bgzf_path = temp_dir.path().join("reads.1.bgz");
+    let copy_path = temp_dir.path().join("copy.bgz");
+    for strict_bgzf_check in [false, true] {
+        let config = TranscodeConfig { strict_bgzf_check, ..Default::default() };
+        let stats = rebgzf::convert(&bgzf_path, &copy_path, config).unwrap();
+        assert!(stats.copied_directly);
+        assert_eq!(std::fs::read(&copy_path).unwrap(), std::fs::read(&bgzf_path).unwrap());
+    }
+    let config = TranscodeConfig { force_transcode: true, ..Default::default() };
+    let stats = rebgzf::convert(&bgzf_path, &copy_path, config).unwrap();
+    assert!(!stats.copied_directly);
+    assert_eq!(decompress_gzip(&std::fs::read(&copy_path).unwrap()), data);
+}
+
 // ============================================================================
 // Streaming Validation Tests
 // ============================================================================
@@ -1079,6 +3045,32 @@ fn test_streaming_validation_matches_strict() {
     assert_eq!(streaming.total_uncompressed_size, strict.total_uncompressed_size);
 }
 
+#[test]
+fn test_streaming_detailed_validation_matches_seekable() {
+    let data = generate_fastq_data(3000, 150);
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let mut bgzf_data = Vec::new();
+    SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(compress_to_gzip(&data)), &mut bgzf_data)
+        .unwrap();
+
+    // A byte slice reader cannot seek, like a pipe
+    let streaming = validate_bgzf_streaming_detailed(&mut &bgzf_data[..]).unwrap();
+    let seekable = validate_bgzf_detailed(&mut Cursor::new(&bgzf_data)).unwrap();
+    assert!(streaming.is_valid_bgzf && streaming.has_eof_block);
+    let blocks = streaming.blocks.unwrap();
+    assert_eq!(blocks, seekable.blocks.unwrap());
+
+    let sizes: Vec<(u32, u32)> =
+        blocks.iter().map(|b| (b.compressed_size, b.uncompressed_size)).collect();
+    let expected: Vec<(u32, u32)> = parse_bgzf_blocks(&bgzf_data)
+        .iter()
+        .map(|&(compressed, isize)| (compressed as u32, isize))
+        .collect();
+    assert_eq!(sizes, expected);
+    assert_eq!(blocks.iter().map(|b| b.compressed_size as usize).sum::<usize>(), bgzf_data.len());
+}
+
 #[test]
 fn test_streaming_validation_invalid() {
     let gzip_data = compress_to_gzip(b"Hello, World!");
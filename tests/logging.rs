@@ -0,0 +1,63 @@
+//! Logging tests, run with `--features log`.
+//!
+//! Kept in a separate test binary because it installs a process-wide logger.
+
+#![cfg(feature = "log")]
+
+use std::io::{Cursor, Write};
+use std::sync::Mutex;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use rebgzf::{SingleThreadedTranscoder, TranscodeConfig, Transcoder};
+
+/// Logger that keeps every record's level, target and message
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger { records: Mutex::new(Vec::new()) };
+
+#[test]
+fn test_transcode_emits_block_records() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let data: Vec<u8> = (0..200_000u32).map(|i| b"ACGT"[(i * 7 % 13 % 4) as usize]).collect();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let gzip_data = encoder.finish().unwrap();
+
+    let config = TranscodeConfig { block_size: 16384, ..Default::default() };
+    let stats = SingleThreadedTranscoder::new(config)
+        .transcode(Cursor::new(&gzip_data), &mut Vec::new())
+        .unwrap();
+    assert!(stats.blocks_written > 1);
+
+    let records = LOGGER.records.lock().unwrap();
+    let block_records = records
+        .iter()
+        .filter(|(level, target, message)| {
+            *level >= Level::Debug && target.starts_with("rebgzf") && message.contains("BGZF block")
+        })
+        .count();
+    assert_eq!(block_records as u64, stats.blocks_written, "{:?}", records);
+}
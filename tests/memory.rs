@@ -0,0 +1,118 @@
+//! Memory-bound tests for streaming transcoding.
+//!
+//! These live in their own test binary because they install a counting global
+//! allocator, which would otherwise see allocations from unrelated tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use rebgzf::{SingleThreadedTranscoder, TranscodeConfig, Transcoder};
+
+/// Global allocator that tracks live and peak heap usage.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(now, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Reader that yields `member` and then repeats it `remaining` more times, producing an
+/// arbitrarily long multi-member stream without holding it in memory.
+struct RepeatedMember {
+    member: Vec<u8>,
+    pos: usize,
+    remaining: usize,
+}
+
+impl Read for RepeatedMember {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.member.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.remaining -= 1;
+            self.pos = 0;
+        }
+        let n = buf.len().min(self.member.len() - self.pos);
+        buf[..n].copy_from_slice(&self.member[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Writer that discards output, counting bytes written.
+struct CountingSink(u64);
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_streaming_transcode_memory_is_bounded() {
+    const MEMBER_SIZE: usize = 1 << 20;
+    const MEMBERS: usize = 200;
+
+    // One 1 MiB member of random sequence-like data, repeated to make a 200 MiB stream
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let data: Vec<u8> = (0..MEMBER_SIZE)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if i % 101 == 100 {
+                b'\n'
+            } else {
+                b"ACGT"[(state >> 32) as usize % 4]
+            }
+        })
+        .collect();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let member = encoder.finish().unwrap();
+    drop(data);
+
+    let input = RepeatedMember { member, pos: 0, remaining: MEMBERS - 1 };
+    let mut output = CountingSink(0);
+    let config = TranscodeConfig { num_threads: 1, ..Default::default() };
+    let mut transcoder = SingleThreadedTranscoder::new(config);
+
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let stats = transcoder.transcode(input, &mut output).unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    // Blocks end on token boundaries, so there are at least as many as full-size blocks need
+    assert!(stats.blocks_written >= (MEMBERS * MEMBER_SIZE / 65280) as u64);
+    assert!(output.0 > 0);
+    // The working set is a block of tokens, the window and I/O buffers: well under
+    // 4 MiB regardless of the 200 MiB input
+    assert!(peak < 4 << 20, "peak heap usage {peak} bytes while streaming");
+}